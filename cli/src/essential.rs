@@ -408,6 +408,53 @@ pub async fn update_configmap(config_struct: MetadataConfigFile) -> Result<(), C
     }
 }
 
+// docs:
+//
+// This is a function used to store a single classifier setting (e.g. the enforcement mode) in the configmap
+//
+// The patch uses a dedicated field manager so the key is not removed when the blocklist is patched
+// by the update_configmap function
+//
+// Returns an error if something fails
+
+pub async fn update_config_value(key: &str, value: &str) -> Result<(), CliError> {
+    match connect_to_client().await {
+        Ok(client) => {
+            let namespace = "cortexflow";
+            let name = "cortexbrain-client-config";
+            let api: Api<ConfigMap> = Api::namespaced(client, namespace);
+
+            let patch = Patch::Apply(json!({
+                "apiVersion": "v1",
+                "kind": "ConfigMap",
+                "data": {
+                    key: value
+                }
+            }));
+
+            let patch_params = PatchParams::apply(&format!("cortexbrain-{}", key)).force();
+            match api.patch(name, &patch_params, &patch).await {
+                Ok(_) => {
+                    println!("Map updated successfully");
+                }
+                Err(e) => {
+                    return Err(CliError::BaseError {
+                        reason: format!("An error occured during the patching process: {}", e),
+                    });
+                }
+            }
+
+            Ok(())
+        }
+        Err(e) => Err(CliError::ClientError(kube::Error::Api(ErrorResponse {
+            status: "failed".to_string(),
+            message: "Failed to connect to kubernetes client".to_string(),
+            reason: e.to_string(),
+            code: 404,
+        }))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::essential::extract_version_from_output;
@@ -17,17 +17,20 @@ use crate::essential::{info, update_cli};
 use crate::install::{InstallArgs, InstallCommands, install_cortexflow, install_simple_example};
 use crate::logs::{LogsArgs, logs_command};
 use crate::monitoring::{
-    MonitorArgs, MonitorCommands, list_features, monitor_dropped_packets, monitor_identity_events,
+    MonitorArgs, MonitorCommands, list_features, monitor_audit_events, monitor_classifier_counters,
+    monitor_classifier_drops, monitor_dropped_packets, monitor_flows, monitor_identity_events,
     monitor_latency_metrics, monitor_tracked_veth,
 };
 use crate::policies::{
-    PoliciesArgs, PoliciesCommands, check_blocklist, create_blocklist, remove_ip,
+    PoliciesArgs, PoliciesCommands, RateLimitCommands, check_blocklist, create_blocklist,
+    create_rule, get_failure_policy, get_mode, list_rate_limits, list_rules, parse_ttl, remove_ip,
+    remove_rate_limit, remove_rule, set_failure_policy, set_mode, set_rate_limit,
 };
 use crate::service::{ServiceArgs, ServiceCommands, describe_service, list_services};
 use crate::status::{StatusArgs, status_command};
 use crate::uninstall::uninstall;
 
-use crate::essential::update_config_metadata;
+use crate::essential::{update_config_metadata, update_config_value};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -127,13 +130,25 @@ async fn args_parser() -> Result<(), CliError> {
             MonitorCommands::Veth => {
                 let _ = monitor_tracked_veth().await?;
             }
+            MonitorCommands::Audit => {
+                let _ = monitor_audit_events().await?;
+            }
+            MonitorCommands::Drops => {
+                let _ = monitor_classifier_drops().await?;
+            }
+            MonitorCommands::Counters => {
+                let _ = monitor_classifier_counters().await?;
+            }
+            MonitorCommands::Flows => {
+                let _ = monitor_flows().await?;
+            }
         },
         Some(Commands::Policies(policies_args)) => {
             match policies_args.policy_cmd {
                 PoliciesCommands::CheckBlocklist => {
                     let _ = check_blocklist().await?;
                 }
-                PoliciesCommands::CreateBlocklist => {
+                PoliciesCommands::CreateBlocklist { ttl } => {
                     // pass the ip as a monitoring flag
                     match policies_args.flags {
                         None => {
@@ -143,11 +158,22 @@ async fn args_parser() -> Result<(), CliError> {
                         }
                         Some(ip) => {
                             println!("inserted ip: {} ", ip);
+                            let ttl_seconds = match ttl.as_deref().map(parse_ttl).transpose() {
+                                Ok(ttl_seconds) => ttl_seconds.unwrap_or(0),
+                                Err(e) => {
+                                    return Err(CliError::BaseError {
+                                        reason: e.to_string(),
+                                    });
+                                }
+                            };
                             //insert the ip in the blocklist
-                            match create_blocklist(&ip).await {
+                            match create_blocklist(&ip, ttl_seconds).await {
                                 Ok(_) => {
                                     //update the config metadata
-                                    let _ = update_config_metadata(&ip, "add").await?;
+                                    //temporary entries are not saved: they would be restored after the ttl
+                                    if ttl_seconds == 0 {
+                                        let _ = update_config_metadata(&ip, "add").await?;
+                                    }
                                 }
                                 Err(e) => {
                                     return Err(CliError::BaseError {
@@ -179,6 +205,42 @@ async fn args_parser() -> Result<(), CliError> {
                         }
                     }
                 },
+                PoliciesCommands::CreateRule(rule_args) => {
+                    let _ = create_rule(&rule_args).await?;
+                }
+                PoliciesCommands::ListRules => {
+                    let _ = list_rules().await?;
+                }
+                PoliciesCommands::RemoveRule { id } => {
+                    let _ = remove_rule(id).await?;
+                }
+                PoliciesCommands::SetMode { mode } => {
+                    let _ = set_mode(&mode).await?;
+                    // persist the mode so it survives an agent restart
+                    let _ = update_config_value("mode", &mode).await?;
+                }
+                PoliciesCommands::GetMode => {
+                    let _ = get_mode().await?;
+                }
+                PoliciesCommands::SetFailurePolicy { policy } => {
+                    let _ = set_failure_policy(&policy).await?;
+                    // persist the policy so it survives an agent restart
+                    let _ = update_config_value("failure_policy", &policy).await?;
+                }
+                PoliciesCommands::GetFailurePolicy => {
+                    let _ = get_failure_policy().await?;
+                }
+                PoliciesCommands::RateLimit { rate_limit_cmd } => match rate_limit_cmd {
+                    RateLimitCommands::Set(rate_limit_args) => {
+                        let _ = set_rate_limit(&rate_limit_args).await?;
+                    }
+                    RateLimitCommands::List => {
+                        let _ = list_rate_limits().await?;
+                    }
+                    RateLimitCommands::Remove { cidr } => {
+                        let _ = remove_rate_limit(&cidr).await?;
+                    }
+                },
             }
         }
         None => {
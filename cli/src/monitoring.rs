@@ -9,8 +9,10 @@ use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
 
 use agent_api::client::{connect_to_client, connect_to_server_reflection};
 use agent_api::requests::{
-    get_all_features, send_active_connection_request, send_dropped_packets_request,
-    send_latency_metrics_request, send_tracked_veth_request, send_veth_tracked_hashmap_req,
+    get_all_features, send_active_connection_request, send_audit_events_request,
+    send_classifier_counters_request, send_classifier_drop_events_request,
+    send_dropped_packets_request, send_flows_request, send_latency_metrics_request,
+    send_tracked_veth_request, send_veth_tracked_hashmap_req,
 };
 
 use crate::errors::CliError;
@@ -41,6 +43,26 @@ pub enum MonitorCommands {
         about = "Monitor tracked veth interfaces from the identity service"
     )]
     Veth,
+    #[command(
+        name = "audit",
+        about = "Monitor the packets that would have been dropped by the classifier in audit mode"
+    )]
+    Audit,
+    #[command(
+        name = "drops",
+        about = "Monitor the packets dropped by the classifier and the drop reason"
+    )]
+    Drops,
+    #[command(
+        name = "counters",
+        about = "Show how many packets hit each classifier path (drop reasons and fail-open passes)"
+    )]
+    Counters,
+    #[command(
+        name = "flows",
+        about = "Show the packet and byte counters of the flows seen by the classifier"
+    )]
+    Flows,
 }
 
 // cfcli monitor <args>
@@ -119,21 +141,23 @@ pub async fn monitor_identity_events() -> Result<(), CliError> {
                 Ok(response) => {
                     let resp = response.into_inner();
                     if resp.events.is_empty() {
-                        println!("{} No events found", "=====>".blue().bold());
+                        println!("{} No active connections found", "=====>".blue().bold());
                     } else {
                         println!(
-                            "{} Found {} events",
+                            "{} Found {} active connections",
                             "=====>".blue().bold(),
                             resp.events.len()
                         );
                         for (i, ev) in resp.events.iter().enumerate() {
                             println!(
-                                "{} Event[{}] id: {}  src: {}  dst: {}",
+                                "{} Connection[{}] id: {}  src: {}  dst: {}  direction: {}  established_ns: {}",
                                 "=====>".blue().bold(),
                                 i,
                                 ev.event_id,
                                 ev.src_ip_port,
-                                ev.dst_ip_port
+                                ev.dst_ip_port,
+                                ev.direction,
+                                ev.established_ns
                             );
                         }
                     }
@@ -321,6 +345,210 @@ pub async fn monitor_tracked_veth() -> Result<(), CliError> {
                 for veth in veth_response.veths.iter() {
                     println!("{} {:?}", "=====>".blue().bold(), &veth);
                 }
+                if !veth_response.xdp_modes.is_empty() {
+                    println!("{} {}", "=====>".blue().bold(), "XDP blocklist modes:");
+                    for (iface, mode) in veth_response.xdp_modes.iter() {
+                        println!("{} {} {}", "=====>".blue().bold(), iface, mode);
+                    }
+                }
+                Ok(())
+            }
+            Err(e) => {
+                return Err(CliError::AgentError(
+                    tonic_reflection::server::Error::InvalidFileDescriptorSet(e.to_string()),
+                ));
+            }
+        },
+        Err(e) => {
+            return Err(CliError::ClientError(kube::Error::Api(ErrorResponse {
+                status: "failed".to_string(),
+                message: "Failed to connect to kubernetes client".to_string(),
+                reason: e.to_string(),
+                code: 404,
+            })));
+        }
+    }
+}
+
+pub async fn monitor_audit_events() -> Result<(), CliError> {
+    println!(
+        "{} {}",
+        "=====>".blue().bold(),
+        "Connecting to cortexflow Client".white()
+    );
+    match connect_to_client().await {
+        Ok(client) => match send_audit_events_request(client).await {
+            Ok(response) => {
+                let audit_response = response.into_inner();
+                if audit_response.events.is_empty() {
+                    println!("{} No audit events found", "=====>".blue().bold());
+                    return Ok(());
+                }
+                println!(
+                    "{} Found {} audit events",
+                    "=====>".blue().bold(),
+                    audit_response.events.len()
+                );
+                for event in audit_response.events.iter() {
+                    println!(
+                        "{} [{}] would drop {} -> {} protocol: {} reason: {} rule: {} ifindex: {}",
+                        "=====>".blue().bold(),
+                        event.timestamp,
+                        event.src_ip_port,
+                        event.dst_ip_port,
+                        event.protocol,
+                        event.reason.yellow(),
+                        event.rule_id,
+                        event.ifindex
+                    );
+                }
+                Ok(())
+            }
+            Err(e) => {
+                return Err(CliError::AgentError(
+                    tonic_reflection::server::Error::InvalidFileDescriptorSet(e.to_string()),
+                ));
+            }
+        },
+        Err(e) => {
+            return Err(CliError::ClientError(kube::Error::Api(ErrorResponse {
+                status: "failed".to_string(),
+                message: "Failed to connect to kubernetes client".to_string(),
+                reason: e.to_string(),
+                code: 404,
+            })));
+        }
+    }
+}
+
+pub async fn monitor_classifier_drops() -> Result<(), CliError> {
+    println!(
+        "{} {}",
+        "=====>".blue().bold(),
+        "Connecting to cortexflow Client".white()
+    );
+    match connect_to_client().await {
+        Ok(client) => match send_classifier_drop_events_request(client).await {
+            Ok(response) => {
+                let drops_response = response.into_inner();
+                if drops_response.events.is_empty() {
+                    println!("{} No drop events found", "=====>".blue().bold());
+                    return Ok(());
+                }
+                println!(
+                    "{} Found {} drop events",
+                    "=====>".blue().bold(),
+                    drops_response.events.len()
+                );
+                for event in drops_response.events.iter() {
+                    if event.src_ip_port.is_empty() {
+                        // parse errors and truncated headers do not carry the 5-tuple
+                        println!(
+                            "{} [{}] dropped packet reason: {} ifindex: {}",
+                            "=====>".blue().bold(),
+                            event.timestamp,
+                            event.reason.red(),
+                            event.ifindex
+                        );
+                        continue;
+                    }
+                    println!(
+                        "{} [{}] dropped {} -> {} protocol: {} reason: {} rule: {} ifindex: {}",
+                        "=====>".blue().bold(),
+                        event.timestamp,
+                        event.src_ip_port,
+                        event.dst_ip_port,
+                        event.protocol,
+                        event.reason.red(),
+                        event.rule_id,
+                        event.ifindex
+                    );
+                }
+                Ok(())
+            }
+            Err(e) => {
+                return Err(CliError::AgentError(
+                    tonic_reflection::server::Error::InvalidFileDescriptorSet(e.to_string()),
+                ));
+            }
+        },
+        Err(e) => {
+            return Err(CliError::ClientError(kube::Error::Api(ErrorResponse {
+                status: "failed".to_string(),
+                message: "Failed to connect to kubernetes client".to_string(),
+                reason: e.to_string(),
+                code: 404,
+            })));
+        }
+    }
+}
+
+pub async fn monitor_classifier_counters() -> Result<(), CliError> {
+    println!(
+        "{} {}",
+        "=====>".blue().bold(),
+        "Connecting to cortexflow Client".white()
+    );
+    match connect_to_client().await {
+        Ok(client) => match send_classifier_counters_request(client).await {
+            Ok(response) => {
+                let mut counters: Vec<(String, u64)> =
+                    response.into_inner().counters.into_iter().collect();
+                counters.sort();
+                for (name, value) in counters.iter() {
+                    println!("{} {}: {}", "=====>".blue().bold(), name, value);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                return Err(CliError::AgentError(
+                    tonic_reflection::server::Error::InvalidFileDescriptorSet(e.to_string()),
+                ));
+            }
+        },
+        Err(e) => {
+            return Err(CliError::ClientError(kube::Error::Api(ErrorResponse {
+                status: "failed".to_string(),
+                message: "Failed to connect to kubernetes client".to_string(),
+                reason: e.to_string(),
+                code: 404,
+            })));
+        }
+    }
+}
+
+pub async fn monitor_flows() -> Result<(), CliError> {
+    println!(
+        "{} {}",
+        "=====>".blue().bold(),
+        "Connecting to cortexflow Client".white()
+    );
+    match connect_to_client().await {
+        Ok(client) => match send_flows_request(client).await {
+            Ok(response) => {
+                let flows_response = response.into_inner();
+                if flows_response.flows.is_empty() {
+                    println!("{} No flows found", "=====>".blue().bold());
+                    return Ok(());
+                }
+                println!(
+                    "{} Found {} flows",
+                    "=====>".blue().bold(),
+                    flows_response.flows.len()
+                );
+                for flow in flows_response.flows.iter() {
+                    println!(
+                        "{} {} -> {} protocol: {} direction: {} packets: {} bytes: {} ifindex: {}",
+                        "=====>".blue().bold(),
+                        flow.src_ip_port,
+                        flow.dst_ip_port,
+                        flow.protocol,
+                        flow.direction,
+                        flow.packets,
+                        flow.bytes,
+                        flow.ifindex
+                    );
+                }
                 Ok(())
             }
             Err(e) => {
@@ -4,19 +4,132 @@ use colored::Colorize;
 use agent_api::requests::send_check_blocklist_request;
 use agent_api::requests::send_create_blocklist_request;
 use agent_api::requests::remove_ip_from_blocklist_request;
-use anyhow::Error;
+use agent_api::requests::{
+    send_add_filter_rule_request, send_list_filter_rules_request, send_rm_filter_rule_request,
+};
+use agent_api::requests::{ send_get_classifier_mode_request, send_set_classifier_mode_request };
+use agent_api::requests::{ send_get_failure_policy_request, send_set_failure_policy_request };
+use agent_api::requests::{
+    send_list_rate_limits_request, send_rm_rate_limit_request, send_set_rate_limit_request,
+};
+use agent_api::agent::BlocklistEntry;
+use agent_api::agent::FilterRule;
+use agent_api::agent::RateLimit;
+use cortexbrain_common::map_handlers::parse_cidr;
+use anyhow::{ Error, anyhow };
 use clap::{ Args, Parser, Subcommand };
 use agent_api::client::{ connect_to_client, connect_to_server_reflection };
 
 //policies subcommands
 #[derive(Subcommand, Debug, Clone)]
 pub enum PoliciesCommands {
-    #[command(name = "create-blocklist", about = "Create a blocklist to filter ips")]
-    CreateBlocklist,
+    #[command(
+        name = "create-blocklist",
+        about = "Create a blocklist to filter ips. Accepts single ips or CIDR ranges (e.g. 10.0.0.0/8)"
+    )]
+    CreateBlocklist {
+        #[arg(
+            long,
+            help = "Remove the entry after the given time (e.g. 90s, 30m, 2h, 1d). Temporary entries are not saved in the configmap"
+        )]
+        ttl: Option<String>,
+    },
     #[command(name = "check-blocklist", about = "Check current ip blocklist")]
     CheckBlocklist,
-    #[command(name="remove-ip",about ="Remove an ip from the blocklist")]
-    RemoveIpFromBlocklist
+    #[command(
+        name = "remove-ip",
+        about = "Remove an ip or a CIDR range from the blocklist"
+    )]
+    RemoveIpFromBlocklist,
+    #[command(
+        name = "create-rule",
+        about = "Create a L4 filtering rule (src CIDR, dst CIDR, protocol, dst port range, action)"
+    )]
+    CreateRule(RuleArgs),
+    #[command(name = "list-rules", about = "List the active L4 filtering rules")]
+    ListRules,
+    #[command(name = "remove-rule", about = "Remove a L4 filtering rule by id")]
+    RemoveRule {
+        #[arg(long, help = "Rule id returned by create-rule or list-rules")]
+        id: u32,
+    },
+    #[command(
+        name = "set-mode",
+        about = "Set the classifier enforcement mode. In audit mode the packets are logged instead of dropped"
+    )]
+    SetMode {
+        #[arg(long, help = "enforce or audit")]
+        mode: String,
+    },
+    #[command(name = "get-mode", about = "Return the classifier enforcement mode")]
+    GetMode,
+    #[command(
+        name = "set-failure-policy",
+        about = "Set what the classifier does with the packets it cannot parse (open: pass, closed: drop)"
+    )]
+    SetFailurePolicy {
+        #[arg(long, help = "open (default) or closed")]
+        policy: String,
+    },
+    #[command(name = "get-failure-policy", about = "Return the classifier failure policy")]
+    GetFailurePolicy,
+    #[command(
+        name = "rate-limit",
+        about = "Manage the per source rate limits (token bucket) enforced by the classifier"
+    )]
+    RateLimit {
+        #[command(subcommand)]
+        rate_limit_cmd: RateLimitCommands,
+    },
+}
+
+// cfcli policy rate-limit subcommands
+#[derive(Subcommand, Debug, Clone)]
+pub enum RateLimitCommands {
+    #[command(
+        name = "set",
+        about = "Create or update the rate limit of an ip or a CIDR range"
+    )]
+    Set(RateLimitArgs),
+    #[command(name = "list", about = "List the active rate limits")]
+    List,
+    #[command(name = "remove", about = "Remove the rate limit of an ip or a CIDR range")]
+    Remove {
+        #[arg(long, help = "Ip or CIDR range used in rate-limit set")]
+        cidr: String,
+    },
+}
+
+// cfcli policy rate-limit set <args>
+#[derive(Args, Debug, Clone)]
+pub struct RateLimitArgs {
+    #[arg(long, help = "Source ip or CIDR range to rate limit")]
+    pub cidr: String,
+    #[arg(long, help = "Allowed packets per second")]
+    pub rate: u32,
+    #[arg(long, default_value_t = 0, help = "Bucket size in packets (default: the rate)")]
+    pub burst: u32,
+    #[arg(
+        long,
+        default_value = "source",
+        help = "source (one bucket for every address in the range) or cidr (one bucket for the whole range)"
+    )]
+    pub scope: String,
+}
+
+// cfcli policy create-rule <args>
+#[derive(Args, Debug, Clone)]
+pub struct RuleArgs {
+    #[arg(long, help = "Source ip or CIDR range (default: any)")]
+    pub src: Option<String>,
+    #[arg(long, help = "Destination ip or CIDR range (default: any)")]
+    pub dst: Option<String>,
+    #[arg(long, default_value = "any", help = "tcp, udp, icmp, icmpv6, a protocol number or any")]
+    pub protocol: String,
+    #[arg(long, help = "Destination port or port range (e.g. 6379 or 8000-8080). Default: any")]
+    pub port: Option<String>,
+    #[arg(long, default_value = "drop", help = "drop, allow or audit (log without dropping)")]
+    pub action: String,
 }
 
 // cfcli policies <args>
@@ -28,15 +141,57 @@ pub struct PoliciesArgs {
     pub flags: Option<String>,
 }
 
-pub async fn create_blocklist(ip: &str) -> Result<(), Error> {
+// parses a blocklist entry ttl ("90", "90s", "30m", "2h", "1d") and returns the ttl in seconds
+pub fn parse_ttl(input: &str) -> Result<u64, Error> {
+    let input = input.trim();
+    let (value, multiplier) = match input.char_indices().last() {
+        Some((idx, 's')) => (&input[..idx], 1),
+        Some((idx, 'm')) => (&input[..idx], 60),
+        Some((idx, 'h')) => (&input[..idx], 3600),
+        Some((idx, 'd')) => (&input[..idx], 86400),
+        _ => (input, 1),
+    };
+    match value.parse::<u64>() {
+        Ok(value) if value > 0 => Ok(value * multiplier),
+        _ => Err(anyhow!("Invalid ttl {:?}. Expected a duration like 90s, 30m, 2h or 1d", input)),
+    }
+}
+
+// returns who is adding the blocklist entry (the current user)
+fn blocklist_author() -> String {
+    std::env::var("USER").unwrap_or_else(|_| "cfcli".to_string())
+}
+
+fn print_blocklist_entries(entries: &[BlocklistEntry]) {
+    if entries.is_empty() {
+        println!("{} {}", "=====>".blue().bold(), "The blocklist is empty".yellow());
+        return;
+    }
+    for entry in entries {
+        let expires_at =
+            if entry.expires_at.is_empty() { "never" } else { entry.expires_at.as_str() };
+        println!(
+            "{} [{}] ip: {} added by: {} added at: {} expires at: {}",
+            "=====>".blue().bold(),
+            entry.rule_id,
+            entry.ip,
+            entry.added_by,
+            entry.added_at,
+            expires_at
+        );
+    }
+}
+
+pub async fn create_blocklist(ip: &str, ttl_seconds: u64) -> Result<(), Error> {
+    parse_cidr(ip)?;
     println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
 
     match connect_to_client().await {
         Ok(client) => {
             println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
-            match send_create_blocklist_request(client, ip).await {
+            match send_create_blocklist_request(client, ip, ttl_seconds, &blocklist_author()).await {
                 Ok(response) => {
-                    println!("{:?}", response.into_inner().events);
+                    print_blocklist_entries(&response.into_inner().entries);
                 }
                 Err(e) => {
                     println!(
@@ -70,7 +225,7 @@ pub async fn check_blocklist() -> Result<(), Error> {
             println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
             match send_check_blocklist_request(client).await {
                 Ok(response) => {
-                    println!("{:?}", response.into_inner().events);
+                    print_blocklist_entries(&response.into_inner().entries);
                 }
                 Err(e) => {
                     println!(
@@ -96,6 +251,7 @@ pub async fn check_blocklist() -> Result<(), Error> {
     Ok(())
 }
 pub async fn remove_ip(ip:&str) -> Result<(), Error> {
+    parse_cidr(ip)?;
     println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
     match connect_to_client().await {
         Ok(client) => {
@@ -127,3 +283,450 @@ pub async fn remove_ip(ip:&str) -> Result<(), Error> {
     }
     Ok(())
 }
+
+// parses a destination port or a port range ("6379", "8000-8080")
+fn parse_port_range(input: &str) -> Result<(u32, u32), Error> {
+    let (start, end) = match input.split_once('-') {
+        Some((start, end)) => (start, end),
+        None => (input, input),
+    };
+    let start = start
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| anyhow!("Invalid port {:?}. Reason: {}", input, e))?;
+    let end = end
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| anyhow!("Invalid port {:?}. Reason: {}", input, e))?;
+    if start > end {
+        return Err(anyhow!("Invalid port range {:?}", input));
+    }
+    Ok((start as u32, end as u32))
+}
+
+fn print_rules(rules: &[FilterRule]) {
+    if rules.is_empty() {
+        println!("{} {}", "=====>".blue().bold(), "No filtering rules found".yellow());
+        return;
+    }
+    for rule in rules {
+        println!(
+            "{} id: {} src: {} dst: {} protocol: {} ports: {}-{} action: {}",
+            "=====>".blue().bold(),
+            rule.rule_id,
+            rule.src_cidr,
+            rule.dst_cidr,
+            rule.protocol,
+            rule.dst_port_start,
+            rule.dst_port_end,
+            rule.action
+        );
+    }
+}
+
+pub async fn create_rule(args: &RuleArgs) -> Result<(), Error> {
+    if let Some(src) = &args.src {
+        parse_cidr(src)?;
+    }
+    if let Some(dst) = &args.dst {
+        parse_cidr(dst)?;
+    }
+    let (dst_port_start, dst_port_end) = match &args.port {
+        Some(port) => parse_port_range(port)?,
+        None => (0, u16::MAX as u32),
+    };
+    let rule = FilterRule {
+        rule_id: 0,
+        src_cidr: args.src.clone().unwrap_or_default(),
+        dst_cidr: args.dst.clone().unwrap_or_default(),
+        protocol: args.protocol.clone(),
+        dst_port_start,
+        dst_port_end,
+        action: args.action.clone(),
+    };
+
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_add_filter_rule_request(client, rule).await {
+                Ok(response) => {
+                    let response = response.into_inner();
+                    println!("{} {}", "=====>".blue().bold(), response.status.green());
+                    print_rules(&response.rules);
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_rules() -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_list_filter_rules_request(client).await {
+                Ok(response) => {
+                    print_rules(&response.into_inner().rules);
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn remove_rule(rule_id: u32) -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_rm_filter_rule_request(client, rule_id).await {
+                Ok(response) => {
+                    let response = response.into_inner();
+                    println!("{} {}", "=====>".blue().bold(), response.status.green());
+                    print_rules(&response.rules);
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn set_mode(mode: &str) -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_set_classifier_mode_request(client, mode).await {
+                Ok(response) => {
+                    println!(
+                        "{} {} {}",
+                        "=====>".blue().bold(),
+                        "Classifier mode:".white(),
+                        response.into_inner().mode.green()
+                    );
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_mode() -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_get_classifier_mode_request(client).await {
+                Ok(response) => {
+                    println!(
+                        "{} {} {}",
+                        "=====>".blue().bold(),
+                        "Classifier mode:".white(),
+                        response.into_inner().mode.green()
+                    );
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn set_failure_policy(policy: &str) -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_set_failure_policy_request(client, policy).await {
+                Ok(response) => {
+                    println!(
+                        "{} {} {}",
+                        "=====>".blue().bold(),
+                        "Classifier failure policy:".white(),
+                        response.into_inner().policy.green()
+                    );
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_failure_policy() -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_get_failure_policy_request(client).await {
+                Ok(response) => {
+                    println!(
+                        "{} {} {}",
+                        "=====>".blue().bold(),
+                        "Classifier failure policy:".white(),
+                        response.into_inner().policy.green()
+                    );
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+fn print_rate_limits(limits: &[RateLimit]) {
+    if limits.is_empty() {
+        println!("{} {}", "=====>".blue().bold(), "No rate limits found".yellow());
+        return;
+    }
+    for limit in limits {
+        println!(
+            "{} cidr: {} rate: {} pkt/s burst: {} scope: {}",
+            "=====>".blue().bold(),
+            limit.cidr,
+            limit.rate,
+            limit.burst,
+            limit.scope
+        );
+    }
+}
+
+pub async fn set_rate_limit(args: &RateLimitArgs) -> Result<(), Error> {
+    parse_cidr(&args.cidr)?;
+    if args.rate == 0 {
+        return Err(anyhow!("The rate must be greater than 0 packets per second"));
+    }
+    let limit = RateLimit {
+        cidr: args.cidr.clone(),
+        rate: args.rate,
+        burst: args.burst,
+        scope: args.scope.clone(),
+    };
+
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_set_rate_limit_request(client, limit).await {
+                Ok(response) => {
+                    let response = response.into_inner();
+                    println!("{} {}", "=====>".blue().bold(), response.status.green());
+                    print_rate_limits(&response.limits);
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_rate_limits() -> Result<(), Error> {
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_list_rate_limits_request(client).await {
+                Ok(response) => {
+                    print_rate_limits(&response.into_inner().limits);
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn remove_rate_limit(cidr: &str) -> Result<(), Error> {
+    parse_cidr(cidr)?;
+    println!("{} {}", "=====>".blue().bold(), "Connecting to cortexflow Client".white());
+    match connect_to_client().await {
+        Ok(client) => {
+            println!("{} {}", "=====>".blue().bold(), "Connected to CortexFlow Client".green());
+            match send_rm_rate_limit_request(client, cidr).await {
+                Ok(response) => {
+                    let response = response.into_inner();
+                    println!("{} {}", "=====>".blue().bold(), response.status.green());
+                    print_rate_limits(&response.limits);
+                }
+                Err(e) => {
+                    println!(
+                        "{} {} {} {}",
+                        "=====>".blue().bold(),
+                        "An error occured".red(),
+                        "Error:",
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            println!(
+                "{} {}",
+                "=====>".blue().bold(),
+                "Failed to connect to CortexFlow Client".red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
@@ -6,20 +6,27 @@ pub struct RequestActiveConnections {
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct ConnectionEvent {
+    /// kernel socket address (hex), unique while the connection is open
     #[prost(string, tag = "1")]
     pub event_id: ::prost::alloc::string::String,
-    /// e.g., "192.168.1.1:8080" (src_ip:src_port)
+    /// local end, e.g., "192.168.1.1:8080" (src_ip:src_port)
     #[prost(string, tag = "2")]
     pub src_ip_port: ::prost::alloc::string::String,
-    /// e.g., "10.0.0.1:80" (dst_ip:dst_port)
+    /// remote end, e.g., "10.0.0.1:80" (dst_ip:dst_port)
     #[prost(string, tag = "3")]
     pub dst_ip_port: ::prost::alloc::string::String,
+    /// egress (opened by the node) or ingress (accepted)
+    #[prost(string, tag = "4")]
+    pub direction: ::prost::alloc::string::String,
+    /// nanoseconds since boot
+    #[prost(uint64, tag = "5")]
+    pub established_ns: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ActiveConnectionResponse {
     #[prost(string, tag = "1")]
     pub status: ::prost::alloc::string::String,
-    /// List of connection events
+    /// Established TCP connections, oldest first
     #[prost(message, repeated, tag = "2")]
     pub events: ::prost::alloc::vec::Vec<ConnectionEvent>,
 }
@@ -161,11 +168,41 @@ pub struct VethHashMapResponse {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
+    /// interface name -> blocklist enforcement mode (native, generic, tc, none)
+    #[prost(map = "string, string", tag = "3")]
+    pub xdp_modes: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct AddIpToBlocklistRequest {
+    /// single address ("10.0.0.1") or CIDR range ("10.0.0.0/8"). Both ipv4 and ipv6 are accepted
     #[prost(string, optional, tag = "1")]
     pub ip: ::core::option::Option<::prost::alloc::string::String>,
+    /// the entry is removed automatically after ttl_seconds. 0 keeps it until it is removed
+    #[prost(uint64, tag = "2")]
+    pub ttl_seconds: u64,
+    /// who added the entry (e.g. the cli user)
+    #[prost(string, tag = "3")]
+    pub added_by: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct BlocklistEntry {
+    /// single address or CIDR range
+    #[prost(string, tag = "1")]
+    pub ip: ::prost::alloc::string::String,
+    /// assigned by the agent
+    #[prost(uint32, tag = "2")]
+    pub rule_id: u32,
+    #[prost(string, tag = "3")]
+    pub added_by: ::prost::alloc::string::String,
+    /// RFC 3339 timestamp
+    #[prost(string, tag = "4")]
+    pub added_at: ::prost::alloc::string::String,
+    /// RFC 3339 timestamp. Empty if the entry never expires
+    #[prost(string, tag = "5")]
+    pub expires_at: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BlocklistResponse {
@@ -176,9 +213,12 @@ pub struct BlocklistResponse {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
+    #[prost(message, repeated, tag = "3")]
+    pub entries: ::prost::alloc::vec::Vec<BlocklistEntry>,
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct RmIpFromBlocklistRequest {
+    /// single address or CIDR range. Must match the inserted entry
     #[prost(string, tag = "1")]
     pub ip: ::prost::alloc::string::String,
 }
@@ -192,6 +232,199 @@ pub struct RmIpFromBlocklistResponse {
         ::prost::alloc::string::String,
     >,
 }
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FilterRule {
+    /// assigned by the agent
+    #[prost(uint32, tag = "1")]
+    pub rule_id: u32,
+    /// single address or CIDR range. Empty or "any" matches every address
+    #[prost(string, tag = "2")]
+    pub src_cidr: ::prost::alloc::string::String,
+    /// single address or CIDR range. Empty or "any" matches every address
+    #[prost(string, tag = "3")]
+    pub dst_cidr: ::prost::alloc::string::String,
+    /// tcp, udp, icmp, icmpv6, a protocol number or "any"
+    #[prost(string, tag = "4")]
+    pub protocol: ::prost::alloc::string::String,
+    /// first destination port of the range (inclusive)
+    #[prost(uint32, tag = "5")]
+    pub dst_port_start: u32,
+    /// last destination port of the range (inclusive)
+    #[prost(uint32, tag = "6")]
+    pub dst_port_end: u32,
+    /// drop, allow or audit
+    #[prost(string, tag = "7")]
+    pub action: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct AddFilterRuleRequest {
+    #[prost(message, optional, tag = "1")]
+    pub rule: ::core::option::Option<FilterRule>,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RmFilterRuleRequest {
+    #[prost(uint32, tag = "1")]
+    pub rule_id: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilterRulesResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub rules: ::prost::alloc::vec::Vec<FilterRule>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RateLimit {
+    /// single address or CIDR range
+    #[prost(string, tag = "1")]
+    pub cidr: ::prost::alloc::string::String,
+    /// packets per second
+    #[prost(uint32, tag = "2")]
+    pub rate: u32,
+    /// bucket size in packets. 0 defaults to rate
+    #[prost(uint32, tag = "3")]
+    pub burst: u32,
+    /// source (one bucket for every address in the range) or cidr (one bucket for the whole range)
+    #[prost(string, tag = "4")]
+    pub scope: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetRateLimitRequest {
+    #[prost(message, optional, tag = "1")]
+    pub limit: ::core::option::Option<RateLimit>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RmRateLimitRequest {
+    /// single address or CIDR range. Must match the inserted entry
+    #[prost(string, tag = "1")]
+    pub cidr: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RateLimitsResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub limits: ::prost::alloc::vec::Vec<RateLimit>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetClassifierModeRequest {
+    /// enforce or audit
+    #[prost(string, tag = "1")]
+    pub mode: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ClassifierModeResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub mode: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetClassifierFailurePolicyRequest {
+    /// open or closed
+    #[prost(string, tag = "1")]
+    pub policy: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ClassifierFailurePolicyResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub policy: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassifierCountersResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    /// counter name (blocklist, rule, parse_error, truncated_header, fail_open, xdp_blocklist, rate_limit) -> total packets
+    #[prost(map = "string, uint64", tag = "2")]
+    pub counters: ::std::collections::HashMap<::prost::alloc::string::String, u64>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct AuditEvent {
+    #[prost(string, tag = "1")]
+    pub src_ip_port: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub dst_ip_port: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub protocol: ::prost::alloc::string::String,
+    /// blocklist, rule or rate_limit
+    #[prost(string, tag = "4")]
+    pub reason: ::prost::alloc::string::String,
+    /// 0 for blocklist and rate limit hits
+    #[prost(uint32, tag = "5")]
+    pub rule_id: u32,
+    #[prost(uint32, tag = "6")]
+    pub ifindex: u32,
+    #[prost(string, tag = "7")]
+    pub timestamp: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuditEventsResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub events: ::prost::alloc::vec::Vec<AuditEvent>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ClassifierDropEvent {
+    /// empty for parse errors and truncated headers
+    #[prost(string, tag = "1")]
+    pub src_ip_port: ::prost::alloc::string::String,
+    /// empty for parse errors and truncated headers
+    #[prost(string, tag = "2")]
+    pub dst_ip_port: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub protocol: ::prost::alloc::string::String,
+    /// blocklist, rule, parse_error, truncated_header or rate_limit
+    #[prost(string, tag = "4")]
+    pub reason: ::prost::alloc::string::String,
+    /// 0 if the reason is not rule
+    #[prost(uint32, tag = "5")]
+    pub rule_id: u32,
+    #[prost(uint32, tag = "6")]
+    pub ifindex: u32,
+    #[prost(string, tag = "7")]
+    pub timestamp: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassifierDropEventsResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub events: ::prost::alloc::vec::Vec<ClassifierDropEvent>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Flow {
+    #[prost(string, tag = "1")]
+    pub src_ip_port: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub dst_ip_port: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub protocol: ::prost::alloc::string::String,
+    /// ingress or egress
+    #[prost(string, tag = "4")]
+    pub direction: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "5")]
+    pub packets: u64,
+    #[prost(uint64, tag = "6")]
+    pub bytes: u64,
+    /// nanoseconds since boot
+    #[prost(uint64, tag = "7")]
+    pub first_seen_ns: u64,
+    /// nanoseconds since boot
+    #[prost(uint64, tag = "8")]
+    pub last_seen_ns: u64,
+    #[prost(uint32, tag = "9")]
+    pub ifindex: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlowsResponse {
+    #[prost(string, tag = "1")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub flows: ::prost::alloc::vec::Vec<Flow>,
+}
 /// Generated client implementations.
 pub mod agent_client {
     #![allow(
@@ -480,178 +713,1034 @@ pub mod agent_client {
                 .insert(GrpcMethod::new("agent.Agent", "GetTrackedVethFromHashMap"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod agent_server {
-    #![allow(
-        unused_variables,
-        dead_code,
-        missing_docs,
-        clippy::wildcard_imports,
-        clippy::let_unit_value,
-    )]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with AgentServer.
-    #[async_trait]
-    pub trait Agent: std::marker::Send + std::marker::Sync + 'static {
-        /// active connections endpoint
-        async fn active_connections(
-            &self,
-            request: tonic::Request<super::RequestActiveConnections>,
-        ) -> std::result::Result<
-            tonic::Response<super::ActiveConnectionResponse>,
-            tonic::Status,
-        >;
-        /// create blocklist endpoint
-        async fn add_ip_to_blocklist(
-            &self,
-            request: tonic::Request<super::AddIpToBlocklistRequest>,
+        /// L4 filtering rules endpoints
+        pub async fn add_filter_rule(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AddFilterRuleRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::BlocklistResponse>,
+            tonic::Response<super::FilterRulesResponse>,
             tonic::Status,
-        >;
-        async fn check_blocklist(
-            &self,
-            request: tonic::Request<()>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/AddFilterRule",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("agent.Agent", "AddFilterRule"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_filter_rules(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
         ) -> std::result::Result<
-            tonic::Response<super::BlocklistResponse>,
+            tonic::Response<super::FilterRulesResponse>,
             tonic::Status,
-        >;
-        /// remove ip from blocklist endpoint
-        async fn rm_ip_from_blocklist(
-            &self,
-            request: tonic::Request<super::RmIpFromBlocklistRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/ListFilterRules",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "ListFilterRules"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn rm_filter_rule(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RmFilterRuleRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::RmIpFromBlocklistResponse>,
+            tonic::Response<super::FilterRulesResponse>,
             tonic::Status,
-        >;
-        /// metrics data endpoint
-        async fn get_latency_metrics(
-            &self,
-            request: tonic::Request<()>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/agent.Agent/RmFilterRule");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("agent.Agent", "RmFilterRule"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// per source token bucket rate limits endpoints
+        pub async fn set_rate_limit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetRateLimitRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::LatencyMetricsResponse>,
+            tonic::Response<super::RateLimitsResponse>,
             tonic::Status,
-        >;
-        /// dropped packets endpoint
-        async fn get_dropped_packets_metrics(
-            &self,
-            request: tonic::Request<()>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/agent.Agent/SetRateLimit");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("agent.Agent", "SetRateLimit"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_rate_limits(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
         ) -> std::result::Result<
-            tonic::Response<super::DroppedPacketsResponse>,
+            tonic::Response<super::RateLimitsResponse>,
             tonic::Status,
-        >;
-        /// TODO: can i combine this 2 endpoints?
-        /// active veth info endpoint
-        async fn get_tracked_veth(
-            &self,
-            request: tonic::Request<()>,
-        ) -> std::result::Result<tonic::Response<super::VethResponse>, tonic::Status>;
-        /// get tracked veth from blocklist
-        async fn get_tracked_veth_from_hash_map(
-            &self,
-            request: tonic::Request<()>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/ListRateLimits",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "ListRateLimits"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn rm_rate_limit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RmRateLimitRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::VethHashMapResponse>,
+            tonic::Response<super::RateLimitsResponse>,
             tonic::Status,
-        >;
-    }
-    #[derive(Debug)]
-    pub struct AgentServer<T> {
-        inner: Arc<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    impl<T> AgentServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/agent.Agent/RmRateLimit");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("agent.Agent", "RmRateLimit"));
+            self.inner.unary(req, path, codec).await
         }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
+        /// classifier enforcement mode endpoints (enforce or audit)
+        pub async fn set_classifier_mode(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetClassifierModeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierModeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/SetClassifierMode",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "SetClassifierMode"));
+            self.inner.unary(req, path, codec).await
         }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
+        pub async fn get_classifier_mode(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierModeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/GetClassifierMode",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "GetClassifierMode"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
+        /// classifier failure policy endpoints (fail-open or fail-closed on parse errors)
+        pub async fn set_classifier_failure_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetClassifierFailurePolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierFailurePolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/SetClassifierFailurePolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "SetClassifierFailurePolicy"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        pub async fn get_classifier_failure_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierFailurePolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/GetClassifierFailurePolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "GetClassifierFailurePolicy"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
+        /// per reason classifier counters
+        pub async fn get_classifier_counters(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierCountersResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/GetClassifierCounters",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "GetClassifierCounters"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
+        /// audit (would-have-dropped) events endpoint
+        pub async fn get_audit_events(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::AuditEventsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/GetAuditEvents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "GetAuditEvents"));
+            self.inner.unary(req, path, codec).await
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for AgentServer<T>
-    where
-        T: Agent,
-        B: Body + std::marker::Send + 'static,
-        B::Error: Into<StdError> + std::marker::Send + 'static,
-    {
-        type Response = http::Response<tonic::body::Body>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
+        /// packets dropped by the identity classifier
+        pub async fn get_classifier_drop_events(
             &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
+            request: impl tonic::IntoRequest<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierDropEventsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/agent.Agent/GetClassifierDropEvents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("agent.Agent", "GetClassifierDropEvents"));
+            self.inner.unary(req, path, codec).await
         }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            match req.uri().path() {
-                "/agent.Agent/ActiveConnections" => {
+        /// per-flow packet and byte counters from the flow_table map
+        pub async fn get_flows(
+            &mut self,
+            request: impl tonic::IntoRequest<()>,
+        ) -> std::result::Result<tonic::Response<super::FlowsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/agent.Agent/GetFlows");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("agent.Agent", "GetFlows"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod agent_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with AgentServer.
+    #[async_trait]
+    pub trait Agent: std::marker::Send + std::marker::Sync + 'static {
+        /// active connections endpoint
+        async fn active_connections(
+            &self,
+            request: tonic::Request<super::RequestActiveConnections>,
+        ) -> std::result::Result<
+            tonic::Response<super::ActiveConnectionResponse>,
+            tonic::Status,
+        >;
+        /// create blocklist endpoint
+        async fn add_ip_to_blocklist(
+            &self,
+            request: tonic::Request<super::AddIpToBlocklistRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BlocklistResponse>,
+            tonic::Status,
+        >;
+        async fn check_blocklist(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::BlocklistResponse>,
+            tonic::Status,
+        >;
+        /// remove ip from blocklist endpoint
+        async fn rm_ip_from_blocklist(
+            &self,
+            request: tonic::Request<super::RmIpFromBlocklistRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RmIpFromBlocklistResponse>,
+            tonic::Status,
+        >;
+        /// metrics data endpoint
+        async fn get_latency_metrics(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::LatencyMetricsResponse>,
+            tonic::Status,
+        >;
+        /// dropped packets endpoint
+        async fn get_dropped_packets_metrics(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::DroppedPacketsResponse>,
+            tonic::Status,
+        >;
+        /// TODO: can i combine this 2 endpoints?
+        /// active veth info endpoint
+        async fn get_tracked_veth(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<tonic::Response<super::VethResponse>, tonic::Status>;
+        /// get tracked veth from blocklist
+        async fn get_tracked_veth_from_hash_map(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::VethHashMapResponse>,
+            tonic::Status,
+        >;
+        /// L4 filtering rules endpoints
+        async fn add_filter_rule(
+            &self,
+            request: tonic::Request<super::AddFilterRuleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FilterRulesResponse>,
+            tonic::Status,
+        >;
+        async fn list_filter_rules(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::FilterRulesResponse>,
+            tonic::Status,
+        >;
+        async fn rm_filter_rule(
+            &self,
+            request: tonic::Request<super::RmFilterRuleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FilterRulesResponse>,
+            tonic::Status,
+        >;
+        /// per source token bucket rate limits endpoints
+        async fn set_rate_limit(
+            &self,
+            request: tonic::Request<super::SetRateLimitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RateLimitsResponse>,
+            tonic::Status,
+        >;
+        async fn list_rate_limits(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::RateLimitsResponse>,
+            tonic::Status,
+        >;
+        async fn rm_rate_limit(
+            &self,
+            request: tonic::Request<super::RmRateLimitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RateLimitsResponse>,
+            tonic::Status,
+        >;
+        /// classifier enforcement mode endpoints (enforce or audit)
+        async fn set_classifier_mode(
+            &self,
+            request: tonic::Request<super::SetClassifierModeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierModeResponse>,
+            tonic::Status,
+        >;
+        async fn get_classifier_mode(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierModeResponse>,
+            tonic::Status,
+        >;
+        /// classifier failure policy endpoints (fail-open or fail-closed on parse errors)
+        async fn set_classifier_failure_policy(
+            &self,
+            request: tonic::Request<super::SetClassifierFailurePolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierFailurePolicyResponse>,
+            tonic::Status,
+        >;
+        async fn get_classifier_failure_policy(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierFailurePolicyResponse>,
+            tonic::Status,
+        >;
+        /// per reason classifier counters
+        async fn get_classifier_counters(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierCountersResponse>,
+            tonic::Status,
+        >;
+        /// audit (would-have-dropped) events endpoint
+        async fn get_audit_events(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::AuditEventsResponse>,
+            tonic::Status,
+        >;
+        /// packets dropped by the identity classifier
+        async fn get_classifier_drop_events(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<super::ClassifierDropEventsResponse>,
+            tonic::Status,
+        >;
+        /// per-flow packet and byte counters from the flow_table map
+        async fn get_flows(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<tonic::Response<super::FlowsResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct AgentServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> AgentServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for AgentServer<T>
+    where
+        T: Agent,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/agent.Agent/ActiveConnections" => {
+                    #[allow(non_camel_case_types)]
+                    struct ActiveConnectionsSvc<T: Agent>(pub Arc<T>);
+                    impl<
+                        T: Agent,
+                    > tonic::server::UnaryService<super::RequestActiveConnections>
+                    for ActiveConnectionsSvc<T> {
+                        type Response = super::ActiveConnectionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RequestActiveConnections>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::active_connections(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ActiveConnectionsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/AddIpToBlocklist" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddIpToBlocklistSvc<T: Agent>(pub Arc<T>);
+                    impl<
+                        T: Agent,
+                    > tonic::server::UnaryService<super::AddIpToBlocklistRequest>
+                    for AddIpToBlocklistSvc<T> {
+                        type Response = super::BlocklistResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AddIpToBlocklistRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::add_ip_to_blocklist(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = AddIpToBlocklistSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/CheckBlocklist" => {
+                    #[allow(non_camel_case_types)]
+                    struct CheckBlocklistSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for CheckBlocklistSvc<T> {
+                        type Response = super::BlocklistResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::check_blocklist(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = CheckBlocklistSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/RmIpFromBlocklist" => {
+                    #[allow(non_camel_case_types)]
+                    struct RmIpFromBlocklistSvc<T: Agent>(pub Arc<T>);
+                    impl<
+                        T: Agent,
+                    > tonic::server::UnaryService<super::RmIpFromBlocklistRequest>
+                    for RmIpFromBlocklistSvc<T> {
+                        type Response = super::RmIpFromBlocklistResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RmIpFromBlocklistRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::rm_ip_from_blocklist(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RmIpFromBlocklistSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetLatencyMetrics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLatencyMetricsSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for GetLatencyMetricsSvc<T> {
+                        type Response = super::LatencyMetricsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::get_latency_metrics(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetLatencyMetricsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetDroppedPacketsMetrics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDroppedPacketsMetricsSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for GetDroppedPacketsMetricsSvc<T> {
+                        type Response = super::DroppedPacketsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::get_dropped_packets_metrics(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetDroppedPacketsMetricsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetTrackedVeth" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTrackedVethSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for GetTrackedVethSvc<T> {
+                        type Response = super::VethResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::get_tracked_veth(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetTrackedVethSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetTrackedVethFromHashMap" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTrackedVethFromHashMapSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for GetTrackedVethFromHashMapSvc<T> {
+                        type Response = super::VethHashMapResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::get_tracked_veth_from_hash_map(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetTrackedVethFromHashMapSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/AddFilterRule" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddFilterRuleSvc<T: Agent>(pub Arc<T>);
+                    impl<
+                        T: Agent,
+                    > tonic::server::UnaryService<super::AddFilterRuleRequest>
+                    for AddFilterRuleSvc<T> {
+                        type Response = super::FilterRulesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AddFilterRuleRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::add_filter_rule(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = AddFilterRuleSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/ListFilterRules" => {
                     #[allow(non_camel_case_types)]
-                    struct ActiveConnectionsSvc<T: Agent>(pub Arc<T>);
+                    struct ListFilterRulesSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for ListFilterRulesSvc<T> {
+                        type Response = super::FilterRulesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::list_filter_rules(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListFilterRulesSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/RmFilterRule" => {
+                    #[allow(non_camel_case_types)]
+                    struct RmFilterRuleSvc<T: Agent>(pub Arc<T>);
                     impl<
                         T: Agent,
-                    > tonic::server::UnaryService<super::RequestActiveConnections>
-                    for ActiveConnectionsSvc<T> {
-                        type Response = super::ActiveConnectionResponse;
+                    > tonic::server::UnaryService<super::RmFilterRuleRequest>
+                    for RmFilterRuleSvc<T> {
+                        type Response = super::FilterRulesResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::RequestActiveConnections>,
+                            request: tonic::Request<super::RmFilterRuleRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::active_connections(&inner, request).await
+                                <T as Agent>::rm_filter_rule(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -662,7 +1751,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = ActiveConnectionsSvc(inner);
+                        let method = RmFilterRuleSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -678,25 +1767,25 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/AddIpToBlocklist" => {
+                "/agent.Agent/SetRateLimit" => {
                     #[allow(non_camel_case_types)]
-                    struct AddIpToBlocklistSvc<T: Agent>(pub Arc<T>);
+                    struct SetRateLimitSvc<T: Agent>(pub Arc<T>);
                     impl<
                         T: Agent,
-                    > tonic::server::UnaryService<super::AddIpToBlocklistRequest>
-                    for AddIpToBlocklistSvc<T> {
-                        type Response = super::BlocklistResponse;
+                    > tonic::server::UnaryService<super::SetRateLimitRequest>
+                    for SetRateLimitSvc<T> {
+                        type Response = super::RateLimitsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::AddIpToBlocklistRequest>,
+                            request: tonic::Request<super::SetRateLimitRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::add_ip_to_blocklist(&inner, request).await
+                                <T as Agent>::set_rate_limit(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -707,7 +1796,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = AddIpToBlocklistSvc(inner);
+                        let method = SetRateLimitSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -723,12 +1812,12 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/CheckBlocklist" => {
+                "/agent.Agent/ListRateLimits" => {
                     #[allow(non_camel_case_types)]
-                    struct CheckBlocklistSvc<T: Agent>(pub Arc<T>);
+                    struct ListRateLimitsSvc<T: Agent>(pub Arc<T>);
                     impl<T: Agent> tonic::server::UnaryService<()>
-                    for CheckBlocklistSvc<T> {
-                        type Response = super::BlocklistResponse;
+                    for ListRateLimitsSvc<T> {
+                        type Response = super::RateLimitsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -736,7 +1825,7 @@ pub mod agent_server {
                         fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::check_blocklist(&inner, request).await
+                                <T as Agent>::list_rate_limits(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -747,7 +1836,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = CheckBlocklistSvc(inner);
+                        let method = ListRateLimitsSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -763,25 +1852,25 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/RmIpFromBlocklist" => {
+                "/agent.Agent/RmRateLimit" => {
                     #[allow(non_camel_case_types)]
-                    struct RmIpFromBlocklistSvc<T: Agent>(pub Arc<T>);
+                    struct RmRateLimitSvc<T: Agent>(pub Arc<T>);
                     impl<
                         T: Agent,
-                    > tonic::server::UnaryService<super::RmIpFromBlocklistRequest>
-                    for RmIpFromBlocklistSvc<T> {
-                        type Response = super::RmIpFromBlocklistResponse;
+                    > tonic::server::UnaryService<super::RmRateLimitRequest>
+                    for RmRateLimitSvc<T> {
+                        type Response = super::RateLimitsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::RmIpFromBlocklistRequest>,
+                            request: tonic::Request<super::RmRateLimitRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::rm_ip_from_blocklist(&inner, request).await
+                                <T as Agent>::rm_rate_limit(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -792,7 +1881,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = RmIpFromBlocklistSvc(inner);
+                        let method = RmRateLimitSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -808,12 +1897,57 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/GetLatencyMetrics" => {
+                "/agent.Agent/SetClassifierMode" => {
                     #[allow(non_camel_case_types)]
-                    struct GetLatencyMetricsSvc<T: Agent>(pub Arc<T>);
+                    struct SetClassifierModeSvc<T: Agent>(pub Arc<T>);
+                    impl<
+                        T: Agent,
+                    > tonic::server::UnaryService<super::SetClassifierModeRequest>
+                    for SetClassifierModeSvc<T> {
+                        type Response = super::ClassifierModeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetClassifierModeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::set_classifier_mode(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetClassifierModeSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetClassifierMode" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClassifierModeSvc<T: Agent>(pub Arc<T>);
                     impl<T: Agent> tonic::server::UnaryService<()>
-                    for GetLatencyMetricsSvc<T> {
-                        type Response = super::LatencyMetricsResponse;
+                    for GetClassifierModeSvc<T> {
+                        type Response = super::ClassifierModeResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -821,7 +1955,7 @@ pub mod agent_server {
                         fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::get_latency_metrics(&inner, request).await
+                                <T as Agent>::get_classifier_mode(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -832,7 +1966,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = GetLatencyMetricsSvc(inner);
+                        let method = GetClassifierModeSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -848,12 +1982,61 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/GetDroppedPacketsMetrics" => {
+                "/agent.Agent/SetClassifierFailurePolicy" => {
                     #[allow(non_camel_case_types)]
-                    struct GetDroppedPacketsMetricsSvc<T: Agent>(pub Arc<T>);
+                    struct SetClassifierFailurePolicySvc<T: Agent>(pub Arc<T>);
+                    impl<
+                        T: Agent,
+                    > tonic::server::UnaryService<
+                        super::SetClassifierFailurePolicyRequest,
+                    > for SetClassifierFailurePolicySvc<T> {
+                        type Response = super::ClassifierFailurePolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::SetClassifierFailurePolicyRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::set_classifier_failure_policy(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetClassifierFailurePolicySvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetClassifierFailurePolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClassifierFailurePolicySvc<T: Agent>(pub Arc<T>);
                     impl<T: Agent> tonic::server::UnaryService<()>
-                    for GetDroppedPacketsMetricsSvc<T> {
-                        type Response = super::DroppedPacketsResponse;
+                    for GetClassifierFailurePolicySvc<T> {
+                        type Response = super::ClassifierFailurePolicyResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -861,7 +2044,7 @@ pub mod agent_server {
                         fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::get_dropped_packets_metrics(&inner, request)
+                                <T as Agent>::get_classifier_failure_policy(&inner, request)
                                     .await
                             };
                             Box::pin(fut)
@@ -873,7 +2056,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = GetDroppedPacketsMetricsSvc(inner);
+                        let method = GetClassifierFailurePolicySvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -889,12 +2072,12 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/GetTrackedVeth" => {
+                "/agent.Agent/GetClassifierCounters" => {
                     #[allow(non_camel_case_types)]
-                    struct GetTrackedVethSvc<T: Agent>(pub Arc<T>);
+                    struct GetClassifierCountersSvc<T: Agent>(pub Arc<T>);
                     impl<T: Agent> tonic::server::UnaryService<()>
-                    for GetTrackedVethSvc<T> {
-                        type Response = super::VethResponse;
+                    for GetClassifierCountersSvc<T> {
+                        type Response = super::ClassifierCountersResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -902,7 +2085,7 @@ pub mod agent_server {
                         fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::get_tracked_veth(&inner, request).await
+                                <T as Agent>::get_classifier_counters(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -913,7 +2096,7 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = GetTrackedVethSvc(inner);
+                        let method = GetClassifierCountersSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -929,12 +2112,12 @@ pub mod agent_server {
                     };
                     Box::pin(fut)
                 }
-                "/agent.Agent/GetTrackedVethFromHashMap" => {
+                "/agent.Agent/GetAuditEvents" => {
                     #[allow(non_camel_case_types)]
-                    struct GetTrackedVethFromHashMapSvc<T: Agent>(pub Arc<T>);
+                    struct GetAuditEventsSvc<T: Agent>(pub Arc<T>);
                     impl<T: Agent> tonic::server::UnaryService<()>
-                    for GetTrackedVethFromHashMapSvc<T> {
-                        type Response = super::VethHashMapResponse;
+                    for GetAuditEventsSvc<T> {
+                        type Response = super::AuditEventsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -942,10 +2125,47 @@ pub mod agent_server {
                         fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Agent>::get_tracked_veth_from_hash_map(
-                                        &inner,
-                                        request,
-                                    )
+                                <T as Agent>::get_audit_events(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetAuditEventsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetClassifierDropEvents" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClassifierDropEventsSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()>
+                    for GetClassifierDropEventsSvc<T> {
+                        type Response = super::ClassifierDropEventsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::get_classifier_drop_events(&inner, request)
                                     .await
                             };
                             Box::pin(fut)
@@ -957,7 +2177,46 @@ pub mod agent_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = GetTrackedVethFromHashMapSvc(inner);
+                        let method = GetClassifierDropEventsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/agent.Agent/GetFlows" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetFlowsSvc<T: Agent>(pub Arc<T>);
+                    impl<T: Agent> tonic::server::UnaryService<()> for GetFlowsSvc<T> {
+                        type Response = super::FlowsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Agent>::get_flows(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetFlowsSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
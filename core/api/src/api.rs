@@ -1,14 +1,11 @@
-use anyhow::Context;
-use anyhow::anyhow;
-use aya::maps::perf::PerfEventArrayBuffer;
 use chrono::Local;
 use cortexbrain_common::buffer_type::IpProtocols;
 use cortexbrain_common::buffer_type::PacketLossMetrics;
-use cortexbrain_common::buffer_type::PacketLog;
 use cortexbrain_common::buffer_type::TimeStampMetrics;
-use cortexbrain_common::formatters::{format_ipv4, format_ipv6};
-use cortexbrain_common::map_handlers::load_perf_event_array_from_mapdata;
-use std::str::FromStr;
+use cortexbrain_common::connections::ConnectionTable;
+use cortexbrain_common::event_reader::{EventSource, spawn_event_reader};
+use cortexbrain_common::formatters::{format_ip_port, format_ipv4, format_ipv6};
+use cortexbrain_common::map_handlers::{BlocklistMaps, from_pin_with_retry};
 use std::sync::Mutex;
 use tonic::{Request, Response, Status};
 use tracing::info;
@@ -19,7 +16,7 @@ use tonic::async_trait;
 
 use aya::maps::HashMap as ayaHashMap;
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio::task;
 
 use crate::agent::{
@@ -31,31 +28,52 @@ use cortexbrain_common::buffer_type::VethLog;
 
 // *  contains agent api configuration
 use crate::agent::{
-    ActiveConnectionResponse, AddIpToBlocklistRequest, BlocklistResponse, RequestActiveConnections,
-    RmIpFromBlocklistRequest, RmIpFromBlocklistResponse, VethHashMapResponse, VethResponse,
+    ActiveConnectionResponse, AddFilterRuleRequest, AddIpToBlocklistRequest, AuditEvent,
+    AuditEventsResponse, BlocklistEntry, BlocklistResponse, ClassifierCountersResponse,
+    ClassifierDropEvent, ClassifierDropEventsResponse, ClassifierFailurePolicyResponse,
+    ClassifierModeResponse, FilterRule, FilterRulesResponse, Flow, FlowsResponse, RateLimit,
+    RateLimitsResponse, RequestActiveConnections, RmFilterRuleRequest, RmIpFromBlocklistRequest,
+    RmIpFromBlocklistResponse, RmRateLimitRequest, SetClassifierFailurePolicyRequest,
+    SetClassifierModeRequest, SetRateLimitRequest, VethHashMapResponse, VethResponse,
     agent_server::Agent,
 };
-use crate::constants::PIN_BLOCKLIST_MAP_PATH;
+use cortexbrain_common::buffer_type::AuditEvent as AuditEventLog;
+use cortexbrain_common::buffer_type::{
+    DROP_REASON_PARSE_ERROR, DROP_REASON_TRUNCATED_HEADER, DropEvent as DropEventLog,
+};
+use cortexbrain_common::classifier_config::{
+    ClassifierConfigMap, ClassifierCountersMap, format_failure_policy, format_mode,
+    parse_failure_policy, parse_mode,
+};
+use cortexbrain_common::filter_rules::{
+    FilterRule as KernelFilterRule, FilterRulesMap, format_action, format_protocol, parse_action,
+    parse_protocol,
+};
+use cortexbrain_common::flows::FlowTable;
+use cortexbrain_common::rate_limits::{
+    RateLimit as KernelRateLimit, RateLimitMaps, format_scope, parse_scope,
+};
 
+use crate::constants::{CLASSIFIER_EVENTS_CAPACITY, EVENT_MAP_RETRY_INTERVAL};
 use crate::helpers::comm_to_string;
 use aya::maps::Map;
-use std::net::Ipv4Addr;
 use tracing::warn;
 
 use cortexbrain_common::buffer_type::BufferSize;
-use cortexbrain_common::buffer_type::fill_buffers;
 
 pub struct AgentApi {
     //* event_rx is an istance of a mpsc receiver.
     //* is used to receive the data from the transmitter (tx)
-    active_connection_event_rx: Mutex<mpsc::Receiver<Result<Vec<ConnectionEvent>, Status>>>,
-    pub(crate) active_connection_event_tx: mpsc::Sender<Result<Vec<ConnectionEvent>, Status>>,
     latency_metrics_rx: Mutex<mpsc::Receiver<Result<Vec<LatencyMetric>, Status>>>,
     pub(crate) latency_metrics_tx: mpsc::Sender<Result<Vec<LatencyMetric>, Status>>,
     dropped_packet_metrics_rx: Mutex<mpsc::Receiver<Result<Vec<DroppedPacketMetric>, Status>>>,
     pub(crate) dropped_packet_metrics_tx: mpsc::Sender<Result<Vec<DroppedPacketMetric>, Status>>,
     tracked_veth_rx: Mutex<mpsc::Receiver<Result<Vec<VethEvent>, Status>>>,
     pub(crate) tracked_veth_tx: mpsc::Sender<Result<Vec<VethEvent>, Status>>,
+    // the audit and drop events are broadcast: the oldest events are dropped when no client polls them,
+    // so the audit_events and drop_events readers never stall
+    audit_events_rx: Mutex<broadcast::Receiver<AuditEvent>>,
+    classifier_drops_rx: Mutex<broadcast::Receiver<ClassifierDropEvent>>,
 }
 
 //initialize a default trait for AgentApi. Loads a name and a bpf istance.
@@ -67,198 +85,150 @@ impl Default for AgentApi {
         //
 
         // TODO: in the future will be better to not use .unwrap()
-        let active_connection_events_array =
-            load_perf_event_array_from_mapdata("/sys/fs/bpf/maps/events_map").unwrap();
-        let network_metrics_events_array =
-            load_perf_event_array_from_mapdata("/sys/fs/bpf/trace_maps/net_metrics").unwrap();
-        let time_stamp_events_array =
-            load_perf_event_array_from_mapdata("/sys/fs/bpf/trace_maps/time_stamp_events").unwrap();
-        let tracked_veth_events_array =
-            load_perf_event_array_from_mapdata("/sys/fs/bpf/maps/veth_identity_map").unwrap();
+        // the maps are ring buffers or perf event arrays depending on how the eBPF programs were built
+        let network_metrics_events =
+            EventSource::from_pin("/sys/fs/bpf/trace_maps/net_metrics").unwrap();
+        let time_stamp_events =
+            EventSource::from_pin("/sys/fs/bpf/trace_maps/time_stamp_events").unwrap();
+        let tracked_veth_events =
+            EventSource::from_pin("/sys/fs/bpf/maps/veth_identity_map").unwrap();
 
         //
         // init a mpsc channels with TX (transmission) and RX(Receiver) components
         //
 
-        let (conn_tx, conn_rx) = mpsc::channel(1024);
         let (lat_tx, lat_rx) = mpsc::channel(2048);
         let (drop_tx, drop_rx) = mpsc::channel(2048);
         let (veth_tx, tracked_veth_rx) = mpsc::channel(1024);
+        let (audit_tx, audit_rx) = broadcast::channel(CLASSIFIER_EVENTS_CAPACITY);
+        let (classifier_drops_tx, classifier_drops_rx) =
+            broadcast::channel(CLASSIFIER_EVENTS_CAPACITY);
 
         // init the API to send the events from the agent to the CLI
         let api = AgentApi {
-            active_connection_event_rx: conn_rx.into(),
-            active_connection_event_tx: conn_tx.clone(),
             latency_metrics_rx: Mutex::new(lat_rx),
             latency_metrics_tx: lat_tx.clone(),
             dropped_packet_metrics_rx: Mutex::new(drop_rx),
             dropped_packet_metrics_tx: drop_tx.clone(),
             tracked_veth_rx: Mutex::new(tracked_veth_rx),
             tracked_veth_tx: veth_tx.clone(),
+            audit_events_rx: Mutex::new(audit_rx),
+            classifier_drops_rx: Mutex::new(classifier_drops_rx),
         };
 
         // init map manager
         //let map_manager = map_manager(maps)?
 
         // init the buffers
-        let mut net_events_buffers = BufferSize::TcpEvents.set_buffer();
-        let mut net_metrics_buffers = BufferSize::NetworkMetricsEvents.set_buffer();
-        let mut ts_metrics_buffers = BufferSize::TimeMetricsEvents.set_buffer();
-        let mut veth_metrics_buffers = BufferSize::VethEvents.set_buffer();
-
-        // init the Vec of Buffers
-
-        let mut net_events_vec_buffer = Vec::<PerfEventArrayBuffer<MapData>>::new();
-        let mut net_metrics_vec_buffer = Vec::<PerfEventArrayBuffer<MapData>>::new();
-        let mut ts_events_vec_buffer = Vec::<PerfEventArrayBuffer<MapData>>::new();
-        let mut veth_events_vec_buffer = Vec::<PerfEventArrayBuffer<MapData>>::new();
-
-        // fill the Vec of Buffers
-
-        net_events_vec_buffer = fill_buffers(net_events_vec_buffer, active_connection_events_array);
-        net_metrics_vec_buffer = fill_buffers(net_metrics_vec_buffer, network_metrics_events_array);
-
-        ts_events_vec_buffer = fill_buffers(ts_events_vec_buffer, time_stamp_events_array);
-
-        veth_events_vec_buffer = fill_buffers(veth_events_vec_buffer, tracked_veth_events_array);
+        let net_metrics_buffers = BufferSize::NetworkMetricsEvents.set_buffer();
+        let ts_metrics_buffers = BufferSize::TimeMetricsEvents.set_buffer();
+        let veth_metrics_buffers = BufferSize::VethEvents.set_buffer();
+        let audit_events_buffers = BufferSize::AuditEvents.set_buffer();
+        let classifier_drops_buffers = BufferSize::DropEvents.set_buffer();
 
         // For network metrics
 
-        //spawn an event readers
         task::spawn(async move {
-            info!("Starting event listener");
+            info!("Starting network metrics listener");
+
             //send the data through a mpsc channel
-            loop {
-                for buf in net_events_vec_buffer.iter_mut() {
-                    match buf.read_events(&mut net_events_buffers) {
-                        Ok(events) => {
-                            //read the events, this function is similar to the one used in identity/helpers.rs/display_events
-                            if events.read > 0 {
-                                for i in 0..events.read {
-                                    let data = &net_events_buffers[i];
-                                    if data.len() >= std::mem::size_of::<PacketLog>() {
-                                        let pl: PacketLog =
-                                            unsafe { std::ptr::read(data.as_ptr() as *const _) };
-                                        let src = Ipv4Addr::from(u32::from_be(pl.src_ip));
-                                        let dst = Ipv4Addr::from(u32::from_be(pl.dst_ip));
-                                        let src_port = u16::from_be(pl.src_port as u16);
-                                        let dst_port = u16::from_be(pl.dst_port as u16);
-                                        let event_id = pl.pid;
-
-                                        match IpProtocols::try_from(pl.proto) {
-                                            Ok(proto) => {
-                                                info!(
-                                                    "Event Id: {} Protocol: {:?} SRC: {}:{} -> DST: {}:{}",
-                                                    event_id, proto, src, src_port, dst, dst_port
-                                                );
-                                                info!("creating vector for the aggregated data");
-                                                let mut evt = Vec::new();
-                                                // insert event in the vector
-                                                info!("Inserting events into the vector");
-                                                //TODO: use a Arc<str> or Box<str> type instead of String type.
-                                                //The data doesn't need to implement any .copy() or .clone() trait
-                                                // using an Arc<str> type will also waste less resources
-                                                evt.push(ConnectionEvent {
-                                                    event_id: event_id.to_string(),
-                                                    src_ip_port: format!("{}:{}", src, src_port),
-                                                    dst_ip_port: format!("{}:{}", dst, dst_port),
-                                                });
-                                                info!("sending events to the MPSC channel");
-                                                let _ = conn_tx.send(Ok(evt)).await;
-                                            }
-                                            Err(_) => {
-                                                info!(
-                                                    "Event Id: {} Protocol: Unknown ({})",
-                                                    event_id, pl.proto
-                                                );
-                                            }
-                                        };
-                                    } else {
-                                        warn!(
-                                            "Received packet data too small: {} bytes",
-                                            data.len()
-                                        );
-                                    }
-                                }
-                            } else if events.lost > 0 {
-                                info!("[Agent/API] Lost {} events", events.lost);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Error while reading events: {}", e);
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            let mut rx = spawn_event_reader(network_metrics_events, net_metrics_buffers);
+            while let Some(batch) = rx.recv().await {
+                for data in batch.iter() {
+                    if data.len() >= std::mem::size_of::<PacketLossMetrics>() {
+                        let nm: PacketLossMetrics =
+                            unsafe { std::ptr::read(data.as_ptr() as *const _) };
+
+                        let dropped_packet_metrics = DroppedPacketMetric {
+                            tgid: nm.tgid,
+                            process_name: comm_to_string(&nm.comm),
+                            sk_drops: nm.sk_drops,
+                            sk_err: nm.sk_err,
+                            sk_err_soft: nm.sk_err_soft,
+                            sk_backlog_len: nm.sk_backlog_len as u32,
+                            sk_wmem_queued: nm.sk_write_memory_queued,
+                            sk_rcvbuf: nm.sk_receive_buffer_size,
+                            sk_ack_backlog: nm.sk_ack_backlog,
+                            timestamp_us: nm.ts_us,
+                        };
+
+                        if dropped_packet_metrics.sk_drops > 0 {
+                            let mut evt = Vec::new();
+                            info!(
+                                "Dropped Packet Metric - tgid: {}, process_name: {}, sk_drops: {}, sk_err: {}, sk_err_soft: {}, sk_backlog_len: {}, sk_wmem_queued: {}, sk_rcvbuf: {}, sk_ack_backlog: {}, timestamp_us: {}",
+                                dropped_packet_metrics.tgid,
+                                dropped_packet_metrics.process_name,
+                                dropped_packet_metrics.sk_drops,
+                                dropped_packet_metrics.sk_err,
+                                dropped_packet_metrics.sk_err_soft,
+                                dropped_packet_metrics.sk_backlog_len,
+                                dropped_packet_metrics.sk_wmem_queued,
+                                dropped_packet_metrics.sk_rcvbuf,
+                                dropped_packet_metrics.sk_ack_backlog,
+                                dropped_packet_metrics.timestamp_us
+                            );
+                            evt.push(dropped_packet_metrics.clone());
+                            let _ = drop_tx.send(Ok(evt)).await;
                         }
+                    } else {
+                        warn!(
+                            "Received network metrics data too small: {} bytes",
+                            data.len()
+                        );
                     }
                 }
-                // small delay to avoid cpu congestion
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             }
         });
 
         task::spawn(async move {
-            info!("Starting network metrics listener");
+            info!("Starting time stamp events listener");
 
             //send the data through a mpsc channel
-            loop {
-                for buf in net_metrics_vec_buffer.iter_mut() {
-                    match buf.read_events(&mut net_metrics_buffers) {
-                        Ok(events) => {
-                            //read the events, this function is similar to the one used in identity/helpers.rs/display_events
-                            if events.read > 0 {
-                                for i in 0..events.read {
-                                    let data = &net_metrics_buffers[i];
-                                    if data.len() >= std::mem::size_of::<PacketLossMetrics>() {
-                                        let nm: PacketLossMetrics =
-                                            unsafe { std::ptr::read(data.as_ptr() as *const _) };
-
-                                        let dropped_packet_metrics = DroppedPacketMetric {
-                                            tgid: nm.tgid,
-                                            process_name: comm_to_string(&nm.comm),
-                                            sk_drops: nm.sk_drops,
-                                            sk_err: nm.sk_err,
-                                            sk_err_soft: nm.sk_err_soft,
-                                            sk_backlog_len: nm.sk_backlog_len as u32,
-                                            sk_wmem_queued: nm.sk_write_memory_queued,
-                                            sk_rcvbuf: nm.sk_receive_buffer_size,
-                                            sk_ack_backlog: nm.sk_ack_backlog,
-                                            timestamp_us: nm.ts_us,
-                                        };
-
-                                        if dropped_packet_metrics.sk_drops > 0 {
-                                            let mut evt = Vec::new();
-                                            info!(
-                                                "Dropped Packet Metric - tgid: {}, process_name: {}, sk_drops: {}, sk_err: {}, sk_err_soft: {}, sk_backlog_len: {}, sk_wmem_queued: {}, sk_rcvbuf: {}, sk_ack_backlog: {}, timestamp_us: {}",
-                                                dropped_packet_metrics.tgid,
-                                                dropped_packet_metrics.process_name,
-                                                dropped_packet_metrics.sk_drops,
-                                                dropped_packet_metrics.sk_err,
-                                                dropped_packet_metrics.sk_err_soft,
-                                                dropped_packet_metrics.sk_backlog_len,
-                                                dropped_packet_metrics.sk_wmem_queued,
-                                                dropped_packet_metrics.sk_rcvbuf,
-                                                dropped_packet_metrics.sk_ack_backlog,
-                                                dropped_packet_metrics.timestamp_us
-                                            );
-                                            evt.push(dropped_packet_metrics.clone());
-                                            let _ = drop_tx.send(Ok(evt)).await;
-                                        }
-                                    } else {
-                                        warn!(
-                                            "Received network metrics data too small: {} bytes",
-                                            data.len()
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Errore nella lettura network metrics eventi: {}", e);
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        }
+            let mut rx = spawn_event_reader(time_stamp_events, ts_metrics_buffers);
+            while let Some(batch) = rx.recv().await {
+                for data in batch.iter() {
+                    if data.len() >= std::mem::size_of::<TimeStampMetrics>() {
+                        let tsm: TimeStampMetrics =
+                            unsafe { std::ptr::read(data.as_ptr() as *const _) };
+                        let saddr_v6 = tsm.saddr_v6;
+                        let daddr_v6 = tsm.daddr_v6;
+                        let latency_metric = LatencyMetric {
+                            delta_us: tsm.delta_us,
+                            timestamp_us: tsm.ts_us,
+                            tgid: tsm.tgid,
+                            process_name: comm_to_string(&tsm.comm),
+                            local_port: tsm.lport as u32,
+                            remote_port: tsm.dport_be as u32,
+                            address_family: tsm.af as u32,
+                            src_address_v4: format_ipv4(tsm.saddr_v4),
+                            dst_address_v4: format_ipv4(tsm.daddr_v4),
+                            src_address_v6: format_ipv6(&saddr_v6),
+                            dst_address_v6: format_ipv6(&daddr_v6),
+                        };
+                        info!(
+                            "Latency Metric - tgid: {}, process_name: {}, delta_us: {}, timestamp_us: {}, local_port: {}, remote_port: {}, address_family: {}, src_address_v4: {}, dst_address_v4: {}, src_address_v6: {}, dst_address_v6: {}",
+                            latency_metric.tgid,
+                            latency_metric.process_name,
+                            latency_metric.delta_us,
+                            latency_metric.timestamp_us,
+                            latency_metric.local_port,
+                            latency_metric.remote_port,
+                            latency_metric.address_family,
+                            latency_metric.src_address_v4,
+                            latency_metric.dst_address_v4,
+                            latency_metric.src_address_v6,
+                            latency_metric.dst_address_v6
+                        );
+                        let mut evt = Vec::new();
+                        evt.push(latency_metric.clone());
+                        let _ = lat_tx.send(Ok(evt)).await;
+                    } else {
+                        warn!(
+                            "Received time stamp metrics data too small: {} bytes",
+                            data.len()
+                        );
                     }
                 }
-                // small delay to avoid cpu congestion
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             }
         });
 
@@ -266,131 +236,151 @@ impl Default for AgentApi {
             info!("Starting time stamp events listener");
 
             //send the data through a mpsc channel
-            loop {
-                for buf in ts_events_vec_buffer.iter_mut() {
-                    match buf.read_events(&mut ts_metrics_buffers) {
-                        Ok(events) => {
-                            //read the events, this function is similar to the one used in identity/helpers.rs/display_events
-                            if events.read > 0 {
-                                for i in 0..events.read {
-                                    let data = &ts_metrics_buffers[i];
-                                    if data.len() >= std::mem::size_of::<TimeStampMetrics>() {
-                                        let tsm: TimeStampMetrics =
-                                            unsafe { std::ptr::read(data.as_ptr() as *const _) };
-                                        let saddr_v6 = tsm.saddr_v6;
-                                        let daddr_v6 = tsm.daddr_v6;
-                                        let latency_metric = LatencyMetric {
-                                            delta_us: tsm.delta_us,
-                                            timestamp_us: tsm.ts_us,
-                                            tgid: tsm.tgid,
-                                            process_name: comm_to_string(&tsm.comm),
-                                            local_port: tsm.lport as u32,
-                                            remote_port: tsm.dport_be as u32,
-                                            address_family: tsm.af as u32,
-                                            src_address_v4: format_ipv4(tsm.saddr_v4),
-                                            dst_address_v4: format_ipv4(tsm.daddr_v4),
-                                            src_address_v6: format_ipv6(&saddr_v6),
-                                            dst_address_v6: format_ipv6(&daddr_v6),
-                                        };
-                                        info!(
-                                            "Latency Metric - tgid: {}, process_name: {}, delta_us: {}, timestamp_us: {}, local_port: {}, remote_port: {}, address_family: {}, src_address_v4: {}, dst_address_v4: {}, src_address_v6: {}, dst_address_v6: {}",
-                                            latency_metric.tgid,
-                                            latency_metric.process_name,
-                                            latency_metric.delta_us,
-                                            latency_metric.timestamp_us,
-                                            latency_metric.local_port,
-                                            latency_metric.remote_port,
-                                            latency_metric.address_family,
-                                            latency_metric.src_address_v4,
-                                            latency_metric.dst_address_v4,
-                                            latency_metric.src_address_v6,
-                                            latency_metric.dst_address_v6
-                                        );
-                                        let mut evt = Vec::new();
-                                        evt.push(latency_metric.clone());
-                                        let _ = lat_tx.send(Ok(evt)).await;
-                                    } else {
-                                        warn!(
-                                            "Received time stamp metrics data too small: {} bytes",
-                                            data.len()
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Errore nella lettura time stamp eventi: {}", e);
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        }
+            let mut rx = spawn_event_reader(tracked_veth_events, veth_metrics_buffers);
+            while let Some(batch) = rx.recv().await {
+                for data in batch.iter() {
+                    info!("Found veth events {}", batch.len());
+                    if data.len() >= std::mem::size_of::<VethLog>() {
+                        let veth: VethLog = unsafe { std::ptr::read(data.as_ptr() as *const _) };
+                        let veth_event = VethEvent {
+                            name: String::from_utf8_lossy(unsafe {
+                                std::slice::from_raw_parts(
+                                    veth.name.as_ptr() as *const u8,
+                                    veth.name.len() * std::mem::size_of::<u32>(),
+                                )
+                            })
+                            .trim_end_matches('\0')
+                            .to_string(),
+                            state: veth.state,
+                            dev_addr: String::from_utf8_lossy(unsafe {
+                                std::slice::from_raw_parts(
+                                    veth.dev_addr.as_ptr() as *const u8,
+                                    veth.dev_addr.len() * std::mem::size_of::<u32>(),
+                                )
+                            })
+                            .trim_end_matches('\0')
+                            .to_string(),
+                            event_type: veth.event_type.into(),
+                            netns: veth.netns,
+                            pid: veth.pid,
+                        };
+                        info!(
+                            "Veth Event - name: {}, state: {}, dev_addr: {}, event_type: {}, netns: {}, pid: {}",
+                            veth_event.name,
+                            veth_event.state,
+                            veth_event.dev_addr,
+                            veth_event.event_type,
+                            veth_event.netns,
+                            veth_event.pid
+                        );
+                        let mut evt = Vec::new();
+                        evt.push(veth_event.clone());
+                        let _ = veth_tx.send(Ok(evt)).await;
+                    } else {
+                        warn!(
+                            "Received time stamp metrics data too small: {} bytes",
+                            data.len()
+                        );
                     }
                 }
             }
         });
 
         task::spawn(async move {
-            info!("Starting time stamp events listener");
+            info!("Starting audit events listener");
+
+            // the map is pinned by the identity service: retry until it is available
+            let audit_events =
+                from_pin_with_retry("audit_events", EVENT_MAP_RETRY_INTERVAL, || {
+                    EventSource::from_pin("/sys/fs/bpf/maps/audit_events")
+                })
+                .await;
+
+            //send the data through a broadcast channel
+            let mut rx = spawn_event_reader(audit_events, audit_events_buffers);
+            while let Some(batch) = rx.recv().await {
+                for data in batch.iter() {
+                    if data.len() >= std::mem::size_of::<AuditEventLog>() {
+                        let audit: AuditEventLog =
+                            unsafe { std::ptr::read_unaligned(data.as_ptr() as *const _) };
+                        let (src, dst) = audit.addresses();
+                        let protocol = match IpProtocols::try_from(audit.proto) {
+                            Ok(proto) => format!("{:?}", proto),
+                            Err(_) => audit.proto.to_string(),
+                        };
+                        let audit_event = AuditEvent {
+                            src_ip_port: format_ip_port(audit.af, &src, audit.src_port),
+                            dst_ip_port: format_ip_port(audit.af, &dst, audit.dst_port),
+                            protocol,
+                            reason: audit.reason_name().to_string(),
+                            rule_id: audit.rule_id,
+                            ifindex: audit.ifindex,
+                            timestamp: Local::now().to_string(),
+                        };
+                        warn!(
+                            "[AUDIT] Would drop packet. Reason: {} Rule: {} Ifindex: {} Protocol: {} SRC: {} -> DST: {}",
+                            audit_event.reason,
+                            audit_event.rule_id,
+                            audit_event.ifindex,
+                            audit_event.protocol,
+                            audit_event.src_ip_port,
+                            audit_event.dst_ip_port
+                        );
+                        // the send fails only when there are no receivers
+                        let _ = audit_tx.send(audit_event);
+                    } else {
+                        warn!("Received audit event data too small: {} bytes", data.len());
+                    }
+                }
+            }
+        });
 
-            //send the data through a mpsc channel
-            loop {
-                for buf in veth_events_vec_buffer.iter_mut() {
-                    match buf.read_events(&mut veth_metrics_buffers) {
-                        Ok(events) => {
-                            //read the events, this function is similar to the one used in identity/helpers.rs/display_events
-                            if events.read > 0 {
-                                for i in 0..events.read {
-                                    info!("Found veth events {}", events.read);
-                                    let data = &veth_metrics_buffers[i];
-                                    if data.len() >= std::mem::size_of::<VethLog>() {
-                                        let veth: VethLog =
-                                            unsafe { std::ptr::read(data.as_ptr() as *const _) };
-                                        let veth_event = VethEvent {
-                                            name: String::from_utf8_lossy(unsafe {
-                                                std::slice::from_raw_parts(
-                                                    veth.name.as_ptr() as *const u8,
-                                                    veth.name.len() * std::mem::size_of::<u32>(),
-                                                )
-                                            })
-                                            .trim_end_matches('\0')
-                                            .to_string(),
-                                            state: veth.state,
-                                            dev_addr: String::from_utf8_lossy(unsafe {
-                                                std::slice::from_raw_parts(
-                                                    veth.dev_addr.as_ptr() as *const u8,
-                                                    veth.dev_addr.len()
-                                                        * std::mem::size_of::<u32>(),
-                                                )
-                                            })
-                                            .trim_end_matches('\0')
-                                            .to_string(),
-                                            event_type: veth.event_type.into(),
-                                            netns: veth.netns,
-                                            pid: veth.pid,
-                                        };
-                                        info!(
-                                            "Veth Event - name: {}, state: {}, dev_addr: {}, event_type: {}, netns: {}, pid: {}",
-                                            veth_event.name,
-                                            veth_event.state,
-                                            veth_event.dev_addr,
-                                            veth_event.event_type,
-                                            veth_event.netns,
-                                            veth_event.pid
-                                        );
-                                        let mut evt = Vec::new();
-                                        evt.push(veth_event.clone());
-                                        let _ = veth_tx.send(Ok(evt)).await;
-                                    } else {
-                                        warn!(
-                                            "Received time stamp metrics data too small: {} bytes",
-                                            data.len()
-                                        );
-                                    }
-                                }
+        task::spawn(async move {
+            info!("Starting classifier drop events listener");
+
+            // the map is pinned by the identity service: retry until it is available
+            let classifier_drops =
+                from_pin_with_retry("drop_events", EVENT_MAP_RETRY_INTERVAL, || {
+                    EventSource::from_pin("/sys/fs/bpf/maps/drop_events")
+                })
+                .await;
+
+            //send the data through a broadcast channel
+            let mut rx = spawn_event_reader(classifier_drops, classifier_drops_buffers);
+            while let Some(batch) = rx.recv().await {
+                for data in batch.iter() {
+                    if data.len() >= std::mem::size_of::<DropEventLog>() {
+                        let drop: DropEventLog =
+                            unsafe { std::ptr::read_unaligned(data.as_ptr() as *const _) };
+                        let protocol = match IpProtocols::try_from(drop.proto) {
+                            Ok(proto) => format!("{:?}", proto),
+                            Err(_) => drop.proto.to_string(),
+                        };
+                        // unparsable packets do not carry the 5-tuple
+                        let (src_ip_port, dst_ip_port) = match drop.reason {
+                            DROP_REASON_PARSE_ERROR | DROP_REASON_TRUNCATED_HEADER => {
+                                (String::new(), String::new())
                             }
-                        }
-                        Err(e) => {
-                            eprintln!("Errore nella lettura time stamp eventi: {}", e);
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        }
+                            _ => {
+                                let (src, dst) = drop.addresses();
+                                (
+                                    format_ip_port(drop.af, &src, drop.src_port),
+                                    format_ip_port(drop.af, &dst, drop.dst_port),
+                                )
+                            }
+                        };
+                        // the send fails only when there are no receivers
+                        let _ = classifier_drops_tx.send(ClassifierDropEvent {
+                            src_ip_port,
+                            dst_ip_port,
+                            protocol,
+                            reason: drop.reason_name().to_string(),
+                            rule_id: drop.rule_id,
+                            ifindex: drop.ifindex,
+                            timestamp: Local::now().to_string(),
+                        });
+                    } else {
+                        warn!("Received drop event data too small: {} bytes", data.len());
                     }
                 }
             }
@@ -405,7 +395,8 @@ impl Default for AgentApi {
 #[async_trait]
 impl Agent for AgentApi {
     // * read the incoming active_connections requests and returns a response with the
-    // * active connections. The data are transformed and sent to the api with a mpsc channel
+    // * established tcp connections. The connections are read from the tcp_connections map, kept
+    // * by the conntracker tcp_state_tracer (entries are removed when the connection is closed)
     async fn active_connections(
         &self,
         request: Request<RequestActiveConnections>,
@@ -413,26 +404,28 @@ impl Agent for AgentApi {
         //read request
         let _req = request.into_inner();
 
-        //create the hashmap to process events from the mpsc channel queue
-        let mut aggregated_events: Vec<ConnectionEvent> = Vec::new();
+        let snapshot = ConnectionTable::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open tcp_connections map: {}", e)))?
+            .snapshot()
+            .map_err(|e| Status::internal(format!("cannot read tcp_connections map: {}", e)))?;
 
-        //aggregate events
-        while let Ok(evt) = self.active_connection_event_rx.lock().unwrap().try_recv() {
-            if let Ok(vec) = evt {
-                aggregated_events.extend(vec);
-            }
-        }
+        let connections: Vec<ConnectionEvent> = snapshot
+            .iter()
+            .map(|(skaddr, connection)| ConnectionEvent {
+                event_id: format!("{:x}", skaddr),
+                src_ip_port: connection.src(),
+                dst_ip_port: connection.dst(),
+                direction: connection.direction_name().to_string(),
+                established_ns: connection.established_ns,
+            })
+            .collect();
 
-        //log response for debugging
-        info!(
-            "DEBUGGING RESPONSE FROM ACTIVE CONNECTION REQUEST: {:?}",
-            aggregated_events
-        );
+        info!("Found {} active connections", connections.len());
 
         //return response
         Ok(Response::new(ActiveConnectionResponse {
             status: "success".to_string(),
-            events: aggregated_events,
+            events: connections,
         }))
     }
 
@@ -444,49 +437,48 @@ impl Agent for AgentApi {
         //read request
         let req = request.into_inner();
 
-        //open blocklist map
-        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/blocklist_map")
-            .expect("cannot open blocklist_map Mapdata");
-        let blocklist_mapdata = Map::HashMap(mapdata); //load mapdata
-        let mut blocklist_map: ayaHashMap<MapData, [u8; 4], [u8; 4]> =
-            ayaHashMap::try_from(blocklist_mapdata).unwrap();
+        //open blocklist maps (ipv4 and ipv6 LPM tries)
+        let mut blocklist = BlocklistMaps::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open blocklist maps: {}", e)))?;
 
         if req.ip.is_none() {
             // log blocklist event
             info!("IP field in request is none");
-            info!("CURRENT BLOCKLIST: {:?}", blocklist_map);
         } else {
-            // add ip to the blocklist
-            // log blocklist event
-            let _datetime = Local::now().to_string();
+            // add ip or CIDR range to the blocklist
+            // the insertion time and the ttl are stored in the map value and read by the blocklist reaper
             let ip = req.ip.unwrap();
-            //convert ip from string to [u8;4] type and insert into the bpf map
-            let u8_4_ip = Ipv4Addr::from_str(&ip).unwrap().octets();
-            //TODO: convert datetime in a kernel compatible format
-            blocklist_map
-                .insert(u8_4_ip, u8_4_ip, 0)
-                .map_err(|e| anyhow!("Cannot insert address in the blocklist. Reason: {}", e))
-                .unwrap();
-            info!("CURRENT BLOCKLIST: {:?}", blocklist_map);
+            let added_by = if req.added_by.is_empty() {
+                "agent-api"
+            } else {
+                req.added_by.as_str()
+            };
+            let rule_id = blocklist
+                .insert(&ip, req.ttl_seconds, added_by)
+                .map_err(|e| {
+                    Status::invalid_argument(format!(
+                        "Cannot insert address in the blocklist. Reason: {}",
+                        e
+                    ))
+                })?;
+            info!(
+                "Inserted {} in the blocklist (id: {}, added by: {}, ttl: {}s)",
+                ip, rule_id, added_by, req.ttl_seconds
+            );
         }
-        let _path = std::env::var(PIN_BLOCKLIST_MAP_PATH)
-            .context("Blocklist map path not found!")
-            .unwrap();
 
-        //convert the maps with a buffer to match the protobuffer types
-        let mut converted_blocklist_map: HashMap<String, String> = HashMap::new();
-        for item in blocklist_map.iter() {
-            let (k, _v) = item.unwrap();
-            // convert keys and values from [u8;4] to String
-            let key = Ipv4Addr::from(k).to_string();
-            let value = Ipv4Addr::from(k).to_string();
-            converted_blocklist_map.insert(key, value);
-        }
+        //convert the maps to match the protobuffer types
+        let converted_blocklist_map = blocklist
+            .entries()
+            .map_err(|e| Status::internal(format!("cannot read blocklist maps: {}", e)))?;
+        info!("CURRENT BLOCKLIST: {:?}", converted_blocklist_map);
+        let entries = blocklist_entries_from_maps(&blocklist)?;
 
         //save ip into the blocklist
         Ok(Response::new(BlocklistResponse {
             status: "success".to_string(),
             events: converted_blocklist_map,
+            entries,
         }))
     }
 
@@ -495,27 +487,20 @@ impl Agent for AgentApi {
         _request: Request<()>,
     ) -> Result<Response<BlocklistResponse>, Status> {
         info!("Returning blocklist hashmap");
-        //open blocklist map
-        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/blocklist_map")
-            .expect("cannot open blocklist_map Mapdata");
-        let blocklist_mapdata = Map::HashMap(mapdata); //load mapdata
+        //open blocklist maps
+        let blocklist = BlocklistMaps::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open blocklist maps: {}", e)))?;
 
-        let blocklist_map: ayaHashMap<MapData, [u8; 4], [u8; 4]> =
-            ayaHashMap::try_from(blocklist_mapdata).unwrap();
+        //convert the maps to match the protobuffer types
+        let converted_blocklist_map = blocklist
+            .entries()
+            .map_err(|e| Status::internal(format!("cannot read blocklist maps: {}", e)))?;
+        let entries = blocklist_entries_from_maps(&blocklist)?;
 
-        //convert the maps with a buffer to match the protobuffer types
-
-        let mut converted_blocklist_map: HashMap<String, String> = HashMap::new();
-        for item in blocklist_map.iter() {
-            let (k, _v) = item.unwrap();
-            // convert keys and values from [u8;4] to String
-            let key = Ipv4Addr::from(k).to_string();
-            let value = Ipv4Addr::from(k).to_string();
-            converted_blocklist_map.insert(key, value);
-        }
         Ok(Response::new(BlocklistResponse {
             status: "success".to_string(),
             events: converted_blocklist_map,
+            entries,
         }))
     }
     async fn rm_ip_from_blocklist(
@@ -525,26 +510,22 @@ impl Agent for AgentApi {
         //read request
         let req = request.into_inner();
         info!("Removing ip from blocklist map");
-        //open blocklist map
-        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/blocklist_map")
-            .expect("cannot open blocklist_map Mapdata");
-        let blocklist_mapdata = Map::HashMap(mapdata); //load mapdata
-        let mut blocklist_map: ayaHashMap<MapData, [u8; 4], [u8; 4]> =
-            ayaHashMap::try_from(blocklist_mapdata).unwrap();
-        //remove the address
-        let ip_to_remove = req.ip;
-        let u8_4_ip_to_remove = Ipv4Addr::from_str(&ip_to_remove).unwrap().octets();
-        let _ = blocklist_map.remove(&u8_4_ip_to_remove);
-
-        //convert the maps with a buffer to match the protobuffer types
-        let mut converted_blocklist_map: HashMap<String, String> = HashMap::new();
-        for item in blocklist_map.iter() {
-            let (k, v) = item.unwrap();
-            // convert keys and values from [u8;4] to String
-            let key = Ipv4Addr::from(k).to_string();
-            let value = Ipv4Addr::from(k).to_string();
-            converted_blocklist_map.insert(key, value);
-        }
+        //open blocklist maps
+        let mut blocklist = BlocklistMaps::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open blocklist maps: {}", e)))?;
+        //remove the address or the CIDR range
+        blocklist.remove(&req.ip).map_err(|e| {
+            warn!("Cannot remove {} from the blocklist. Reason: {}", req.ip, e);
+            Status::not_found(format!(
+                "Cannot remove {} from the blocklist. Reason: {}",
+                req.ip, e
+            ))
+        })?;
+
+        //convert the maps to match the protobuffer types
+        let converted_blocklist_map = blocklist
+            .entries()
+            .map_err(|e| Status::internal(format!("cannot read blocklist maps: {}", e)))?;
 
         Ok(Response::new(RmIpFromBlocklistResponse {
             status: "Ip removed from blocklist".to_string(),
@@ -704,9 +685,413 @@ impl Agent for AgentApi {
             let value = String::from_utf8(v.to_vec()).unwrap();
             converted_tracked_veth_map.insert(key, value);
         }
+
+        // the xdp_mode map is empty when the XDP fast path is disabled
+        let mut xdp_modes: HashMap<String, String> = HashMap::new();
+        if let Ok(mapdata) = MapData::from_pin("/sys/fs/bpf/maps/xdp_mode") {
+            let xdp_mode_map: ayaHashMap<MapData, [u8; 16], [u8; 8]> =
+                ayaHashMap::try_from(Map::HashMap(mapdata))
+                    .map_err(|e| Status::internal(format!("Cannot open xdp_mode map: {}", e)))?;
+            for item in xdp_mode_map.iter() {
+                let (k, v) =
+                    item.map_err(|e| Status::internal(format!("Cannot read xdp_mode map: {}", e)))?;
+                let key = String::from_utf8_lossy(&k)
+                    .trim_end_matches('\0')
+                    .to_string();
+                let value = String::from_utf8_lossy(&v)
+                    .trim_end_matches('\0')
+                    .to_string();
+                xdp_modes.insert(key, value);
+            }
+        }
+
         Ok(Response::new(VethHashMapResponse {
             status: "success".to_string(),
             veths: converted_tracked_veth_map,
+            xdp_modes,
+        }))
+    }
+
+    async fn add_filter_rule(
+        &self,
+        request: Request<AddFilterRuleRequest>,
+    ) -> Result<Response<FilterRulesResponse>, Status> {
+        let req = request.into_inner();
+        let rule = req
+            .rule
+            .ok_or_else(|| Status::invalid_argument("rule field in request is none"))?;
+
+        // convert the protobuffer rule in the kernel layout
+        let kernel_rule = rule_from_proto(&rule)
+            .map_err(|e| Status::invalid_argument(format!("Invalid filter rule. Reason: {}", e)))?;
+
+        //open filter rules map
+        let mut rules_map = FilterRulesMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open filter rules map: {}", e)))?;
+        let rule_id = rules_map.add(kernel_rule).map_err(|e| {
+            Status::resource_exhausted(format!("Cannot insert filter rule. Reason: {}", e))
+        })?;
+        info!("Inserted filter rule {}", rule_id);
+
+        let rules = list_filter_rules_from_map(&rules_map)?;
+        Ok(Response::new(FilterRulesResponse {
+            status: format!("Rule {} added", rule_id),
+            rules,
+        }))
+    }
+
+    async fn list_filter_rules(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<FilterRulesResponse>, Status> {
+        info!("Returning filter rules");
+        let rules_map = FilterRulesMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open filter rules map: {}", e)))?;
+
+        let rules = list_filter_rules_from_map(&rules_map)?;
+        Ok(Response::new(FilterRulesResponse {
+            status: "success".to_string(),
+            rules,
+        }))
+    }
+
+    async fn rm_filter_rule(
+        &self,
+        request: Request<RmFilterRuleRequest>,
+    ) -> Result<Response<FilterRulesResponse>, Status> {
+        let req = request.into_inner();
+        info!("Removing filter rule {}", req.rule_id);
+        let mut rules_map = FilterRulesMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open filter rules map: {}", e)))?;
+        rules_map
+            .remove(req.rule_id)
+            .map_err(|e| Status::not_found(e.to_string()))?;
+
+        let rules = list_filter_rules_from_map(&rules_map)?;
+        Ok(Response::new(FilterRulesResponse {
+            status: format!("Rule {} removed", req.rule_id),
+            rules,
+        }))
+    }
+
+    async fn set_rate_limit(
+        &self,
+        request: Request<SetRateLimitRequest>,
+    ) -> Result<Response<RateLimitsResponse>, Status> {
+        let req = request.into_inner();
+        let limit = req
+            .limit
+            .ok_or_else(|| Status::invalid_argument("limit field in request is none"))?;
+
+        // convert the protobuffer rate limit in the kernel layout
+        let kernel_limit = parse_scope(&limit.scope)
+            .and_then(|scope| KernelRateLimit::new(&limit.cidr, limit.rate, limit.burst, scope))
+            .map_err(|e| Status::invalid_argument(format!("Invalid rate limit. Reason: {}", e)))?;
+
+        let mut rate_limits = RateLimitMaps::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open rate limits maps: {}", e)))?;
+        rate_limits.insert(kernel_limit).map_err(|e| {
+            Status::resource_exhausted(format!("Cannot insert rate limit. Reason: {}", e))
+        })?;
+        info!(
+            "Rate limit set for {}: {} packets/s (burst {})",
+            kernel_limit.cidr(),
+            kernel_limit.rate,
+            kernel_limit.burst
+        );
+
+        let limits = list_rate_limits_from_maps(&rate_limits)?;
+        Ok(Response::new(RateLimitsResponse {
+            status: format!("Rate limit for {} set", kernel_limit.cidr()),
+            limits,
+        }))
+    }
+
+    async fn list_rate_limits(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<RateLimitsResponse>, Status> {
+        info!("Returning rate limits");
+        let rate_limits = RateLimitMaps::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open rate limits maps: {}", e)))?;
+
+        let limits = list_rate_limits_from_maps(&rate_limits)?;
+        Ok(Response::new(RateLimitsResponse {
+            status: "success".to_string(),
+            limits,
+        }))
+    }
+
+    async fn rm_rate_limit(
+        &self,
+        request: Request<RmRateLimitRequest>,
+    ) -> Result<Response<RateLimitsResponse>, Status> {
+        let req = request.into_inner();
+        info!("Removing rate limit for {}", req.cidr);
+        let mut rate_limits = RateLimitMaps::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open rate limits maps: {}", e)))?;
+        rate_limits
+            .remove(&req.cidr)
+            .map_err(|e| Status::not_found(e.to_string()))?;
+
+        let limits = list_rate_limits_from_maps(&rate_limits)?;
+        Ok(Response::new(RateLimitsResponse {
+            status: format!("Rate limit for {} removed", req.cidr),
+            limits,
+        }))
+    }
+
+    async fn set_classifier_mode(
+        &self,
+        request: Request<SetClassifierModeRequest>,
+    ) -> Result<Response<ClassifierModeResponse>, Status> {
+        let req = request.into_inner();
+        let mode = parse_mode(&req.mode).map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let mut config = ClassifierConfigMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open classifier config map: {}", e)))?;
+        config
+            .set_mode(mode)
+            .map_err(|e| Status::internal(format!("cannot set enforcement mode: {}", e)))?;
+        info!("Classifier enforcement mode set to {}", format_mode(mode));
+
+        Ok(Response::new(ClassifierModeResponse {
+            status: "success".to_string(),
+            mode: format_mode(mode),
+        }))
+    }
+
+    async fn get_classifier_mode(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<ClassifierModeResponse>, Status> {
+        let config = ClassifierConfigMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open classifier config map: {}", e)))?;
+        let mode = config
+            .mode()
+            .map_err(|e| Status::internal(format!("cannot read enforcement mode: {}", e)))?;
+
+        Ok(Response::new(ClassifierModeResponse {
+            status: "success".to_string(),
+            mode: format_mode(mode),
+        }))
+    }
+
+    async fn set_classifier_failure_policy(
+        &self,
+        request: Request<SetClassifierFailurePolicyRequest>,
+    ) -> Result<Response<ClassifierFailurePolicyResponse>, Status> {
+        let req = request.into_inner();
+        let policy = parse_failure_policy(&req.policy)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let mut config = ClassifierConfigMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open classifier config map: {}", e)))?;
+        config
+            .set_failure_policy(policy)
+            .map_err(|e| Status::internal(format!("cannot set failure policy: {}", e)))?;
+        info!(
+            "Classifier failure policy set to {}",
+            format_failure_policy(policy)
+        );
+
+        Ok(Response::new(ClassifierFailurePolicyResponse {
+            status: "success".to_string(),
+            policy: format_failure_policy(policy),
+        }))
+    }
+
+    async fn get_classifier_failure_policy(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<ClassifierFailurePolicyResponse>, Status> {
+        let config = ClassifierConfigMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open classifier config map: {}", e)))?;
+        let policy = config
+            .failure_policy()
+            .map_err(|e| Status::internal(format!("cannot read failure policy: {}", e)))?;
+
+        Ok(Response::new(ClassifierFailurePolicyResponse {
+            status: "success".to_string(),
+            policy: format_failure_policy(policy),
         }))
     }
+
+    async fn get_classifier_counters(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<ClassifierCountersResponse>, Status> {
+        let counters = ClassifierCountersMap::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open classifier counters map: {}", e)))?
+            .totals()
+            .map_err(|e| Status::internal(format!("cannot read classifier counters: {}", e)))?;
+
+        Ok(Response::new(ClassifierCountersResponse {
+            status: "success".to_string(),
+            counters,
+        }))
+    }
+
+    async fn get_flows(&self, _request: Request<()>) -> Result<Response<FlowsResponse>, Status> {
+        info!("Getting flows");
+        let snapshot = FlowTable::from_pin()
+            .map_err(|e| Status::internal(format!("cannot open flow_table map: {}", e)))?
+            .snapshot()
+            .map_err(|e| Status::internal(format!("cannot read flow_table map: {}", e)))?;
+
+        let mut flows: Vec<Flow> = snapshot
+            .iter()
+            .map(|(key, stats)| Flow {
+                src_ip_port: key.src(),
+                dst_ip_port: key.dst(),
+                protocol: format_protocol(key.proto),
+                direction: key.direction_name().to_string(),
+                packets: stats.packets,
+                bytes: stats.bytes,
+                first_seen_ns: stats.first_seen_ns,
+                last_seen_ns: stats.last_seen_ns,
+                ifindex: stats.ifindex,
+            })
+            .collect();
+        // most active flows first
+        flows.sort_by_key(|flow| std::cmp::Reverse(flow.bytes));
+
+        Ok(Response::new(FlowsResponse {
+            status: "success".to_string(),
+            flows,
+        }))
+    }
+
+    async fn get_audit_events(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<AuditEventsResponse>, Status> {
+        info!("Getting audit events");
+        let mut aggregated_audit_events: Vec<AuditEvent> = Vec::new();
+
+        let mut audit_events_rx = self.audit_events_rx.lock().unwrap();
+        loop {
+            match audit_events_rx.try_recv() {
+                Ok(evt) => aggregated_audit_events.push(evt),
+                // the oldest events were overwritten since the previous request
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    warn!("{} audit events were dropped before being read", skipped);
+                }
+                Err(_) => break,
+            }
+        }
+
+        Ok(Response::new(AuditEventsResponse {
+            status: "success".to_string(),
+            events: aggregated_audit_events,
+        }))
+    }
+
+    async fn get_classifier_drop_events(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<ClassifierDropEventsResponse>, Status> {
+        info!("Getting classifier drop events");
+        let mut aggregated_drop_events: Vec<ClassifierDropEvent> = Vec::new();
+
+        let mut classifier_drops_rx = self.classifier_drops_rx.lock().unwrap();
+        loop {
+            match classifier_drops_rx.try_recv() {
+                Ok(evt) => aggregated_drop_events.push(evt),
+                // the oldest events were overwritten since the previous request
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    warn!("{} drop events were dropped before being read", skipped);
+                }
+                Err(_) => break,
+            }
+        }
+
+        Ok(Response::new(ClassifierDropEventsResponse {
+            status: "success".to_string(),
+            events: aggregated_drop_events,
+        }))
+    }
+}
+
+// converts a protobuffer FilterRule in the layout used by the identity_classifier
+fn rule_from_proto(rule: &FilterRule) -> Result<KernelFilterRule, anyhow::Error> {
+    let dst_port_start = u16::try_from(rule.dst_port_start)?;
+    // an empty range means every port
+    let dst_port_end = if rule.dst_port_start == 0 && rule.dst_port_end == 0 {
+        u16::MAX
+    } else {
+        u16::try_from(rule.dst_port_end)?
+    };
+    KernelFilterRule::new(
+        0,
+        Some(rule.src_cidr.as_str()),
+        Some(rule.dst_cidr.as_str()),
+        parse_protocol(&rule.protocol)?,
+        dst_port_start,
+        dst_port_end,
+        parse_action(&rule.action)?,
+    )
+}
+
+// reads the active rules and converts them to match the protobuffer types
+fn list_filter_rules_from_map(rules_map: &FilterRulesMap) -> Result<Vec<FilterRule>, Status> {
+    let rules = rules_map
+        .list()
+        .map_err(|e| Status::internal(format!("cannot read filter rules map: {}", e)))?;
+    Ok(rules
+        .iter()
+        .map(|rule| FilterRule {
+            rule_id: rule.rule_id,
+            src_cidr: rule.src_cidr(),
+            dst_cidr: rule.dst_cidr(),
+            protocol: format_protocol(rule.proto),
+            dst_port_start: rule.dst_port_start as u32,
+            dst_port_end: rule.dst_port_end as u32,
+            action: format_action(rule.action),
+        })
+        .collect())
+}
+
+// reads the rate limits and converts them to match the protobuffer types
+fn list_rate_limits_from_maps(rate_limits: &RateLimitMaps) -> Result<Vec<RateLimit>, Status> {
+    let limits = rate_limits
+        .list()
+        .map_err(|e| Status::internal(format!("cannot read rate limits maps: {}", e)))?;
+    Ok(limits
+        .iter()
+        .map(|limit| RateLimit {
+            cidr: limit.cidr(),
+            rate: limit.rate,
+            burst: limit.burst,
+            scope: format_scope(limit.scope),
+        })
+        .collect())
+}
+
+// reads the blocklist entries and converts them to match the protobuffer types
+fn blocklist_entries_from_maps(blocklist: &BlocklistMaps) -> Result<Vec<BlocklistEntry>, Status> {
+    let mut entries: Vec<BlocklistEntry> = blocklist
+        .list()
+        .map_err(|e| Status::internal(format!("cannot read blocklist maps: {}", e)))?
+        .into_iter()
+        .map(|(ip, entry)| BlocklistEntry {
+            ip,
+            rule_id: entry.rule_id,
+            added_by: entry.added_by(),
+            added_at: format_unix_timestamp(entry.added_at),
+            expires_at: entry
+                .expires_at()
+                .map(format_unix_timestamp)
+                .unwrap_or_default(),
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.rule_id);
+    Ok(entries)
+}
+
+// formats a unix timestamp (seconds) as a RFC 3339 local time
+fn format_unix_timestamp(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0)
+        .map(|time| time.with_timezone(&Local).to_rfc3339())
+        .unwrap_or_default()
 }
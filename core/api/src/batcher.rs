@@ -9,7 +9,7 @@ use tokio::sync::mpsc;
 use tonic::{Status, async_trait};
 
 use crate::{
-    agent::{ConnectionEvent, DroppedPacketMetric, LatencyMetric, VethEvent},
+    agent::{DroppedPacketMetric, LatencyMetric, VethEvent},
     api::AgentApi,
 };
 
@@ -17,18 +17,6 @@ use crate::{
 // using the send_map function
 #[async_trait]
 pub trait EventSender: Send + Sync + 'static {
-    async fn send_active_connection_event(&self, event: Vec<ConnectionEvent>);
-    async fn send_active_connection_event_map(
-        &self,
-        map: Vec<ConnectionEvent>,
-        tx: mpsc::Sender<Result<Vec<ConnectionEvent>, Status>>,
-    ) {
-        let status = Status::new(tonic::Code::Ok, "success");
-        let event = Ok(map);
-
-        let _ = tx.send(event).await;
-    }
-
     async fn send_latency_metrics_event(&self, event: Vec<LatencyMetric>);
     async fn send_latency_metrics_event_map(
         &self,
@@ -66,11 +54,6 @@ pub trait EventSender: Send + Sync + 'static {
 // send event function. takes an HashMap and send that using mpsc event_tx
 #[async_trait]
 impl EventSender for AgentApi {
-    async fn send_active_connection_event(&self, event: Vec<ConnectionEvent>) {
-        self.send_active_connection_event_map(event, self.active_connection_event_tx.clone())
-            .await;
-    }
-
     async fn send_latency_metrics_event(&self, event: Vec<LatencyMetric>) {
         self.send_latency_metrics_event_map(event, self.latency_metrics_tx.clone())
             .await;
@@ -1,2 +1,6 @@
 pub const PIN_BLOCKLIST_MAP_PATH: &str = "PIN_BLOCKLIST_MAP_PATH";
 pub const TASK_COMM_LEN: usize = 16;
+// number of classifier events (audit and drop events) kept for the polling clients. The oldest events are dropped first
+pub const CLASSIFIER_EVENTS_CAPACITY: usize = 1024;
+// interval between two attempts to open an event map pinned by the identity service
+pub const EVENT_MAP_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
@@ -7,13 +7,29 @@ use tonic_reflection::pb::v1::{
 };
 
 use crate::agent::ActiveConnectionResponse;
+use crate::agent::AddFilterRuleRequest;
 use crate::agent::AddIpToBlocklistRequest;
+use crate::agent::AuditEventsResponse;
 use crate::agent::BlocklistResponse;
+use crate::agent::ClassifierCountersResponse;
+use crate::agent::ClassifierDropEventsResponse;
+use crate::agent::ClassifierFailurePolicyResponse;
+use crate::agent::ClassifierModeResponse;
 use crate::agent::DroppedPacketsResponse;
+use crate::agent::FilterRule;
+use crate::agent::FilterRulesResponse;
+use crate::agent::FlowsResponse;
 use crate::agent::LatencyMetricsResponse;
+use crate::agent::RateLimit;
+use crate::agent::RateLimitsResponse;
 use crate::agent::RequestActiveConnections;
+use crate::agent::RmFilterRuleRequest;
 use crate::agent::RmIpFromBlocklistRequest;
 use crate::agent::RmIpFromBlocklistResponse;
+use crate::agent::RmRateLimitRequest;
+use crate::agent::SetClassifierFailurePolicyRequest;
+use crate::agent::SetClassifierModeRequest;
+use crate::agent::SetRateLimitRequest;
 use crate::agent::VethHashMapResponse;
 use crate::agent::VethResponse;
 use crate::agent::agent_client::AgentClient;
@@ -48,9 +64,15 @@ pub async fn get_all_features(
 pub async fn send_create_blocklist_request(
     mut client: AgentClient<Channel>,
     ip: &str,
+    ttl_seconds: u64,
+    added_by: &str,
 ) -> Result<Response<BlocklistResponse>, Error> {
     let ip = Some(ip.to_string());
-    let request = Request::new(AddIpToBlocklistRequest { ip });
+    let request = Request::new(AddIpToBlocklistRequest {
+        ip,
+        ttl_seconds,
+        added_by: added_by.to_string(),
+    });
     let response = client.add_ip_to_blocklist(request).await?;
     Ok(response)
 }
@@ -110,3 +132,139 @@ pub async fn send_veth_tracked_hashmap_req(
     let response = client.get_tracked_veth_from_hash_map(request).await?;
     Ok(response)
 }
+
+#[cfg(feature = "client")]
+pub async fn send_add_filter_rule_request(
+    mut client: AgentClient<Channel>,
+    rule: FilterRule,
+) -> Result<Response<FilterRulesResponse>, Error> {
+    let request = Request::new(AddFilterRuleRequest { rule: Some(rule) });
+    let response = client.add_filter_rule(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_list_filter_rules_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<FilterRulesResponse>, Error> {
+    let request = Request::new(());
+    let response = client.list_filter_rules(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_rm_filter_rule_request(
+    mut client: AgentClient<Channel>,
+    rule_id: u32,
+) -> Result<Response<FilterRulesResponse>, Error> {
+    let request = Request::new(RmFilterRuleRequest { rule_id });
+    let response = client.rm_filter_rule(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_set_rate_limit_request(
+    mut client: AgentClient<Channel>,
+    limit: RateLimit,
+) -> Result<Response<RateLimitsResponse>, Error> {
+    let request = Request::new(SetRateLimitRequest { limit: Some(limit) });
+    let response = client.set_rate_limit(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_list_rate_limits_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<RateLimitsResponse>, Error> {
+    let request = Request::new(());
+    let response = client.list_rate_limits(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_rm_rate_limit_request(
+    mut client: AgentClient<Channel>,
+    cidr: &str,
+) -> Result<Response<RateLimitsResponse>, Error> {
+    let request = Request::new(RmRateLimitRequest {
+        cidr: cidr.to_string(),
+    });
+    let response = client.rm_rate_limit(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_set_classifier_mode_request(
+    mut client: AgentClient<Channel>,
+    mode: &str,
+) -> Result<Response<ClassifierModeResponse>, Error> {
+    let mode = mode.to_string();
+    let request = Request::new(SetClassifierModeRequest { mode });
+    let response = client.set_classifier_mode(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_get_classifier_mode_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<ClassifierModeResponse>, Error> {
+    let request = Request::new(());
+    let response = client.get_classifier_mode(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_set_failure_policy_request(
+    mut client: AgentClient<Channel>,
+    policy: &str,
+) -> Result<Response<ClassifierFailurePolicyResponse>, Error> {
+    let policy = policy.to_string();
+    let request = Request::new(SetClassifierFailurePolicyRequest { policy });
+    let response = client.set_classifier_failure_policy(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_get_failure_policy_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<ClassifierFailurePolicyResponse>, Error> {
+    let request = Request::new(());
+    let response = client.get_classifier_failure_policy(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_classifier_counters_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<ClassifierCountersResponse>, Error> {
+    let request = Request::new(());
+    let response = client.get_classifier_counters(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_audit_events_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<AuditEventsResponse>, Error> {
+    let request = Request::new(());
+    let response = client.get_audit_events(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_classifier_drop_events_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<ClassifierDropEventsResponse>, Error> {
+    let request = Request::new(());
+    let response = client.get_classifier_drop_events(request).await?;
+    Ok(response)
+}
+
+#[cfg(feature = "client")]
+pub async fn send_flows_request(
+    mut client: AgentClient<Channel>,
+) -> Result<Response<FlowsResponse>, Error> {
+    let request = Request::new(());
+    let response = client.get_flows(request).await?;
+    Ok(response)
+}
@@ -7,10 +7,6 @@
 //!
 //! The consumer logic has been moved to [`crate::consumer`].
 
-use aya::maps::perf::PerfEventArrayBuffer;
-#[cfg(feature = "buffer-reader")]
-use aya::maps::{MapData, PerfEventArray};
-use aya::util::online_cpus;
 use bytemuck_derive::Zeroable;
 use bytes::BytesMut;
 use std::net::Ipv4Addr;
@@ -24,8 +20,15 @@ use std::net::Ipv4Addr;
 #[repr(u8)]
 pub enum IpProtocols {
     ICMP = 1,
+    IGMP = 2,
     TCP = 6,
     UDP = 17,
+    GRE = 47,
+    ESP = 50,
+    AH = 51,
+    ICMPv6 = 58,
+    SCTP = 132,
+    UDPLite = 136,
 }
 
 ///
@@ -39,17 +42,63 @@ impl TryFrom<u8> for IpProtocols {
     fn try_from(proto: u8) -> Result<Self, Self::Error> {
         match proto {
             1 => Ok(IpProtocols::ICMP),
+            2 => Ok(IpProtocols::IGMP),
             6 => Ok(IpProtocols::TCP),
             17 => Ok(IpProtocols::UDP),
+            47 => Ok(IpProtocols::GRE),
+            50 => Ok(IpProtocols::ESP),
+            51 => Ok(IpProtocols::AH),
+            58 => Ok(IpProtocols::ICMPv6),
+            132 => Ok(IpProtocols::SCTP),
+            136 => Ok(IpProtocols::UDPLite),
             _ => Err(()),
         }
     }
 }
 
+///
+/// Returns the name of an IP protocol number.
+/// Protocols without an [`IpProtocols`] variant are reported as `Unknown(<number>)`
+/// instead of being treated as corrupted data.
+///
+pub fn protocol_name(proto: u8) -> String {
+    match IpProtocols::try_from(proto) {
+        Ok(proto) => format!("{:?}", proto),
+        Err(_) => format!("Unknown({})", proto),
+    }
+}
+
+///
+/// Returns true for the ICMP and ICMPv6 protocol numbers.
+/// The classifier reports the ICMP type and code in the destination port
+/// field as `(type << 8) | code` (see [`icmp_type_code`]).
+///
+pub fn is_icmp(proto: u8) -> bool {
+    proto == IpProtocols::ICMP as u8 || proto == IpProtocols::ICMPv6 as u8
+}
+
+///
+/// Splits the destination port field of an ICMP/ICMPv6 event into the
+/// `(type, code)` pair.
+///
+pub fn icmp_type_code(dst_port: u16) -> (u8, u8) {
+    ((dst_port >> 8) as u8, (dst_port & 0xff) as u8)
+}
+
+pub use crate::constants::{AF_INET, AF_INET6, DIRECTION_EGRESS, DIRECTION_INGRESS};
+
 ///
 /// Structure PacketLog
 /// This structure is used to store the packet information
 ///
+/// `af` tells which address fields are populated: `src_ip`/`dst_ip` for
+/// [`AF_INET`] packets, `src_ip_v6`/`dst_ip_v6` (four host-order words, the
+/// layout expected by [`crate::formatters::format_ipv6`]) for [`AF_INET6`] packets.
+/// `direction` is [`DIRECTION_INGRESS`] or [`DIRECTION_EGRESS`] and `ifindex`
+/// is the interface the packet was seen on. `vni` is the VXLAN/Geneve network
+/// identifier of encapsulated packets (the 5-tuple is the inner one), `0` for
+/// packets that were not encapsulated.
+///
 #[cfg(feature = "network-structs")]
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable)]
@@ -60,10 +109,41 @@ pub struct PacketLog {
     pub dst_ip: u32,
     pub dst_port: u16,
     pub pid: u32,
+    pub af: u16,
+    pub src_ip_v6: [u32; 4],
+    pub dst_ip_v6: [u32; 4],
+    pub direction: u8,
+    pub ifindex: u32,
+    pub vni: u32,
 }
 #[cfg(feature = "network-structs")]
 unsafe impl aya::Pod for PacketLog {}
 
+#[cfg(feature = "network-structs")]
+impl PacketLog {
+    /// Return the source and destination addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::format_ipv6;
+
+        match self.af {
+            AF_INET6 => (format_ipv6(&self.src_ip_v6), format_ipv6(&self.dst_ip_v6)),
+            _ => (
+                reverse_be_addr(self.src_ip).to_string(),
+                reverse_be_addr(self.dst_ip).to_string(),
+            ),
+        }
+    }
+
+    /// Human readable traffic direction.
+    pub fn direction_name(&self) -> &'static str {
+        match self.direction {
+            DIRECTION_INGRESS => "ingress",
+            DIRECTION_EGRESS => "egress",
+            _ => "unknown",
+        }
+    }
+}
+
 #[cfg(feature = "network-structs")]
 #[repr(C, packed)]
 #[derive(Clone, Copy, Zeroable)]
@@ -78,6 +158,17 @@ pub struct VethLog {
 #[cfg(feature = "network-structs")]
 unsafe impl aya::Pod for VethLog {}
 
+///
+/// Structure TcpPacketRegistry
+/// Event emitted by the conntracker tcp registry kprobes (`tcp_v4_rcv`,
+/// `tcp_v6_rcv`, `tcp_v4_connect` and `tcp_v6_connect`) and by the udp socket
+/// kprobes (`udp_sendmsg`, `udp_recvmsg`, `udpv6_sendmsg` and `udpv6_recvmsg`).
+/// `proto` tells which tracer emitted the event.
+///
+/// As in [`PacketLog`], `af` tells which address fields are populated:
+/// `src_ip`/`dst_ip` for [`AF_INET`] events, `src_ip_v6`/`dst_ip_v6` for
+/// [`AF_INET6`] events. Connect events only carry the destination.
+///
 #[cfg(feature = "network-structs")]
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable)]
@@ -90,10 +181,284 @@ pub struct TcpPacketRegistry {
     pub pid: u32,
     pub command: [u8; 16],
     pub cgroup_id: u64,
+    pub af: u16,
+    pub src_ip_v6: [u32; 4],
+    pub dst_ip_v6: [u32; 4],
 }
 #[cfg(feature = "network-structs")]
 unsafe impl aya::Pod for TcpPacketRegistry {}
 
+#[cfg(feature = "network-structs")]
+impl TcpPacketRegistry {
+    /// Return the source and destination addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::format_ipv6;
+
+        match self.af {
+            AF_INET6 => (format_ipv6(&self.src_ip_v6), format_ipv6(&self.dst_ip_v6)),
+            _ => (
+                reverse_be_addr(self.src_ip).to_string(),
+                reverse_be_addr(self.dst_ip).to_string(),
+            ),
+        }
+    }
+}
+
+/// Audit reason: the source address matched a blocklist prefix.
+#[cfg(feature = "network-structs")]
+pub const AUDIT_REASON_BLOCKLIST: u8 = 1;
+/// Audit reason: the packet matched a drop or audit filtering rule.
+#[cfg(feature = "network-structs")]
+pub const AUDIT_REASON_RULE: u8 = 2;
+/// Audit reason: the source address exceeded its rate limit.
+#[cfg(feature = "network-structs")]
+pub const AUDIT_REASON_RATE_LIMIT: u8 = 3;
+
+///
+/// Structure AuditEvent
+/// "Would-have-dropped" event emitted by the classifier in audit mode.
+///
+/// Addresses are stored as four host-order words; IPv4 events use only
+/// the first word.
+///
+#[cfg(feature = "network-structs")]
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct AuditEvent {
+    pub af: u16,
+    pub proto: u8,
+    pub reason: u8,
+    pub rule_id: u32,
+    pub ifindex: u32,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+}
+#[cfg(feature = "network-structs")]
+unsafe impl aya::Pod for AuditEvent {}
+
+#[cfg(feature = "network-structs")]
+impl AuditEvent {
+    /// Return the source and destination addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::format_ip_words;
+
+        (
+            format_ip_words(self.af, &self.src_ip),
+            format_ip_words(self.af, &self.dst_ip),
+        )
+    }
+
+    /// Human readable audit reason.
+    pub fn reason_name(&self) -> &'static str {
+        match self.reason {
+            AUDIT_REASON_BLOCKLIST => "blocklist",
+            AUDIT_REASON_RULE => "rule",
+            AUDIT_REASON_RATE_LIMIT => "rate_limit",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Drop reason: the source address matched a blocklist prefix.
+#[cfg(feature = "network-structs")]
+pub const DROP_REASON_BLOCKLIST: u8 = 1;
+/// Drop reason: the packet matched a drop filtering rule.
+#[cfg(feature = "network-structs")]
+pub const DROP_REASON_RULE: u8 = 2;
+/// Drop reason: the packet headers are malformed.
+#[cfg(feature = "network-structs")]
+pub const DROP_REASON_PARSE_ERROR: u8 = 3;
+/// Drop reason: the packet is shorter than the headers the classifier reads.
+#[cfg(feature = "network-structs")]
+pub const DROP_REASON_TRUNCATED_HEADER: u8 = 4;
+/// Drop reason: the source address exceeded its rate limit. Matches the
+/// counter index, 5 and 6 are used by the other counters.
+#[cfg(feature = "network-structs")]
+pub const DROP_REASON_RATE_LIMIT: u8 = 7;
+
+///
+/// Structure DropEvent
+/// Event emitted by the classifier every time a packet is dropped.
+///
+/// Shares the [`AuditEvent`] layout. Parse errors and truncated headers
+/// carry only the address family and the ifindex.
+///
+#[cfg(feature = "network-structs")]
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct DropEvent {
+    pub af: u16,
+    pub proto: u8,
+    pub reason: u8,
+    pub rule_id: u32,
+    pub ifindex: u32,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+}
+#[cfg(feature = "network-structs")]
+unsafe impl aya::Pod for DropEvent {}
+
+#[cfg(feature = "network-structs")]
+impl DropEvent {
+    /// Return the source and destination addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::format_ip_words;
+
+        (
+            format_ip_words(self.af, &self.src_ip),
+            format_ip_words(self.af, &self.dst_ip),
+        )
+    }
+
+    /// Human readable drop reason. Also used as the `reason` metric label.
+    pub fn reason_name(&self) -> &'static str {
+        match self.reason {
+            DROP_REASON_BLOCKLIST => "blocklist",
+            DROP_REASON_RULE => "rule",
+            DROP_REASON_PARSE_ERROR => "parse_error",
+            DROP_REASON_TRUNCATED_HEADER => "truncated_header",
+            DROP_REASON_RATE_LIMIT => "rate_limit",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Connection event: the socket moved to `TCP_ESTABLISHED`.
+#[cfg(feature = "network-structs")]
+pub const CONN_EVENT_ESTABLISHED: u8 = 1;
+/// Connection event: the socket moved to `TCP_CLOSE`.
+#[cfg(feature = "network-structs")]
+pub const CONN_EVENT_CLOSED: u8 = 2;
+/// Close reason: the connection was closed with the FIN handshake.
+#[cfg(feature = "network-structs")]
+pub const CLOSE_REASON_FIN: u8 = 1;
+/// Close reason: the connection was reset.
+#[cfg(feature = "network-structs")]
+pub const CLOSE_REASON_RST: u8 = 2;
+/// Close reason: the handshake did not complete.
+#[cfg(feature = "network-structs")]
+pub const CLOSE_REASON_FAILED: u8 = 3;
+
+///
+/// Structure TcpConnEvent
+/// Event emitted by the conntracker `tcp_state_tracer` when a TCP connection
+/// is established or closed.
+///
+/// `duration_ns`, `bytes_sent`, `bytes_received` and `close_reason` are only
+/// set for [`CONN_EVENT_CLOSED`] events. `skaddr` (the kernel socket address)
+/// pairs the two events of the same connection. Addresses are stored as four
+/// host-order words; IPv4 events use only the first word.
+///
+#[cfg(feature = "network-structs")]
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct TcpConnEvent {
+    pub skaddr: u64,
+    pub ts_ns: u64,
+    pub duration_ns: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+    pub af: u16,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub direction: u8,
+    pub event_type: u8,
+    pub close_reason: u8,
+    pub _padding: [u8; 7],
+}
+#[cfg(feature = "network-structs")]
+unsafe impl aya::Pod for TcpConnEvent {}
+
+#[cfg(feature = "network-structs")]
+impl TcpConnEvent {
+    /// Return the local and remote addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::format_ip_words;
+
+        (
+            format_ip_words(self.af, &self.src_ip),
+            format_ip_words(self.af, &self.dst_ip),
+        )
+    }
+
+    /// Human readable event type.
+    pub fn event_name(&self) -> &'static str {
+        match self.event_type {
+            CONN_EVENT_ESTABLISHED => "established",
+            CONN_EVENT_CLOSED => "closed",
+            _ => "unknown",
+        }
+    }
+
+    /// Human readable close reason.
+    pub fn close_reason_name(&self) -> &'static str {
+        match self.close_reason {
+            CLOSE_REASON_FIN => "fin",
+            CLOSE_REASON_RST => "rst",
+            CLOSE_REASON_FAILED => "failed",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Listener event: the accept queue of a listening socket is full.
+#[cfg(feature = "network-structs")]
+pub const LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW: u8 = 1;
+/// Listener event: the SYN queue of a listening socket is full (SYN cookies or SYN drops).
+#[cfg(feature = "network-structs")]
+pub const LISTENER_EVENT_SYN_QUEUE_OVERFLOW: u8 = 2;
+/// Listener event: the TCP stack entered the memory pressure state.
+#[cfg(feature = "network-structs")]
+pub const LISTENER_EVENT_MEMORY_PRESSURE: u8 = 3;
+
+///
+/// Structure ListenerEvent
+/// Event emitted by the conntracker `tcp_backlog` programs when a listening
+/// socket overflows its accept or SYN queue, or when the TCP stack enters
+/// the memory pressure state.
+///
+/// `netns` is the network namespace of the socket and is used to resolve the
+/// pod, since the queue overflows are detected in the softirq context.
+/// `backlog` and `max_backlog` are the accept queue length and limit; they
+/// are zero for [`LISTENER_EVENT_MEMORY_PRESSURE`] events.
+///
+#[cfg(feature = "network-structs")]
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct ListenerEvent {
+    pub ts_ns: u64,
+    pub netns: u32,
+    pub tgid: u32,
+    pub backlog: u32,
+    pub max_backlog: u32,
+    pub comm: [u8; 16],
+    pub lport: u16,
+    pub af: u16,
+    pub kind: u8,
+    pub _padding: [u8; 3],
+}
+#[cfg(feature = "network-structs")]
+unsafe impl aya::Pod for ListenerEvent {}
+
+#[cfg(feature = "network-structs")]
+impl ListenerEvent {
+    /// Human readable event kind.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW => "accept_queue_overflow",
+            LISTENER_EVENT_SYN_QUEUE_OVERFLOW => "syn_queue_overflow",
+            LISTENER_EVENT_MEMORY_PRESSURE => "memory_pressure",
+            _ => "unknown",
+        }
+    }
+}
+
 #[cfg(feature = "monitoring-structs")]
 pub const TASK_COMM_LEN: usize = 16;
 #[cfg(feature = "monitoring-structs")]
@@ -132,6 +497,318 @@ pub struct TimeStampMetrics {
 }
 #[cfg(feature = "monitoring-structs")]
 unsafe impl aya::Pod for TimeStampMetrics {}
+/// TCP socket event: a segment was retransmitted.
+#[cfg(feature = "monitoring-structs")]
+pub const TCP_EVENT_RETRANSMIT: u8 = 1;
+/// TCP socket event: the local stack sent a reset.
+#[cfg(feature = "monitoring-structs")]
+pub const TCP_EVENT_RESET_SENT: u8 = 2;
+/// TCP socket event: a reset was received from the peer.
+#[cfg(feature = "monitoring-structs")]
+pub const TCP_EVENT_RESET_RECEIVED: u8 = 3;
+
+///
+/// Structure TcpSocketEvent
+/// Event emitted by the metrics_tracer on every TCP retransmission and on
+/// every reset sent or received.
+///
+/// Ports are in host order, `saddr_v4`/`daddr_v4` in network order (the
+/// [`TimeStampMetrics`] layout). Retransmissions and received resets usually
+/// run in softirq context, so `tgid`/`comm` are not always the socket owner:
+/// the pod is resolved from the network namespace of the socket (`netns`).
+///
+#[cfg(feature = "monitoring-structs")]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct TcpSocketEvent {
+    pub tgid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+    pub ts_us: u64,
+    pub netns: u32,
+    pub kind: u8,
+    pub state: u8,
+    pub af: u16,
+    pub lport: u16,
+    pub dport: u16,
+    pub saddr_v4: u32,
+    pub daddr_v4: u32,
+    pub saddr_v6: [u32; 4],
+    pub daddr_v6: [u32; 4],
+}
+#[cfg(feature = "monitoring-structs")]
+unsafe impl aya::Pod for TcpSocketEvent {}
+
+#[cfg(feature = "monitoring-structs")]
+impl TcpSocketEvent {
+    /// Human readable event kind.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            TCP_EVENT_RETRANSMIT => "retransmit",
+            TCP_EVENT_RESET_SENT => "reset_sent",
+            TCP_EVENT_RESET_RECEIVED => "reset_received",
+            _ => "unknown",
+        }
+    }
+
+    /// Return the local and remote addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::{format_ipv4, format_ipv6};
+
+        match self.af {
+            AF_INET6 => {
+                // copy the packed fields before taking a reference
+                let (saddr, daddr) = (self.saddr_v6, self.daddr_v6);
+                (format_ipv6(&saddr), format_ipv6(&daddr))
+            }
+            _ => (format_ipv4(self.saddr_v4), format_ipv4(self.daddr_v4)),
+        }
+    }
+}
+
+///
+/// Structure TcpHealthEvent
+/// Periodic sample of an established TCP connection emitted by the
+/// metrics_tracer (at most one sample per second and socket).
+///
+/// `srtt_us` and `mdev_us` are already converted to microseconds (the kernel
+/// stores them left shifted by 3 and 2). The sample is taken in softirq
+/// context, so the event carries the network namespace of the socket
+/// (`netns`) instead of the current task. Addresses and ports use the
+/// [`TcpSocketEvent`] layout (`s*` = local, `d*` = remote).
+///
+#[cfg(feature = "monitoring-structs")]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct TcpHealthEvent {
+    pub ts_us: u64,
+    pub netns: u32,
+    pub srtt_us: u32,
+    pub mdev_us: u32,
+    pub snd_cwnd: u32,
+    pub total_retrans: u32,
+    pub af: u16,
+    pub lport: u16,
+    pub dport: u16,
+    pub saddr_v4: u32,
+    pub daddr_v4: u32,
+    pub saddr_v6: [u32; 4],
+    pub daddr_v6: [u32; 4],
+}
+#[cfg(feature = "monitoring-structs")]
+unsafe impl aya::Pod for TcpHealthEvent {}
+
+#[cfg(feature = "monitoring-structs")]
+impl TcpHealthEvent {
+    /// Return the local and remote addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::{format_ipv4, format_ipv6};
+
+        match self.af {
+            AF_INET6 => {
+                // copy the packed fields before taking a reference
+                let (saddr, daddr) = (self.saddr_v6, self.daddr_v6);
+                (format_ipv6(&saddr), format_ipv6(&daddr))
+            }
+            _ => (format_ipv4(self.saddr_v4), format_ipv4(self.daddr_v4)),
+        }
+    }
+}
+
+/// Length of the question name copied by the metrics_tracer dns tracer.
+#[cfg(feature = "monitoring-structs")]
+pub const DNS_QNAME_LEN: usize = 64;
+/// DNS response code: the server failed to process the query.
+#[cfg(feature = "monitoring-structs")]
+pub const DNS_RCODE_SERVFAIL: u8 = 2;
+/// DNS response code: the queried name does not exist.
+#[cfg(feature = "monitoring-structs")]
+pub const DNS_RCODE_NXDOMAIN: u8 = 3;
+
+///
+/// Structure DnsEvent
+/// Event emitted by the metrics_tracer when a DNS response (UDP, source
+/// port 53) is matched with its query.
+///
+/// `tgid`/`comm` belong to the task that sent the query, `latency_ns` is the
+/// time between the query and the response. Addresses and ports are
+/// reported from the client point of view (`s*` = client, `d*` = server) with
+/// the [`TcpSocketEvent`] layout. `qname` holds the first [`DNS_QNAME_LEN`]
+/// bytes of the question name in the DNS wire format (see [`DnsEvent::qname`]).
+///
+#[cfg(feature = "monitoring-structs")]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct DnsEvent {
+    pub tgid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+    pub ts_us: u64,
+    pub latency_ns: u64,
+    pub txid: u16,
+    pub qtype: u16,
+    pub rcode: u8,
+    pub af: u16,
+    pub sport: u16,
+    pub dport: u16,
+    pub saddr_v4: u32,
+    pub daddr_v4: u32,
+    pub saddr_v6: [u32; 4],
+    pub daddr_v6: [u32; 4],
+    pub qname: [u8; DNS_QNAME_LEN],
+}
+#[cfg(feature = "monitoring-structs")]
+unsafe impl aya::Pod for DnsEvent {}
+
+#[cfg(feature = "monitoring-structs")]
+impl DnsEvent {
+    /// Decode the question name from the DNS wire format (length prefixed
+    /// labels) to the dotted notation. A name truncated by the eBPF copy ends
+    /// with `...`.
+    pub fn qname(&self) -> String {
+        let qname = self.qname;
+        let mut labels: Vec<String> = Vec::new();
+        let mut pos = 0;
+
+        while pos < DNS_QNAME_LEN {
+            let len = qname[pos] as usize;
+            if len == 0 {
+                return labels.join(".");
+            }
+            let end = (pos + 1 + len).min(DNS_QNAME_LEN);
+            labels.push(String::from_utf8_lossy(&qname[pos + 1..end]).to_string());
+            pos += len + 1;
+        }
+
+        format!("{}...", labels.join("."))
+    }
+
+    /// Human readable query type.
+    pub fn qtype_name(&self) -> String {
+        match self.qtype {
+            1 => "A".to_string(),
+            2 => "NS".to_string(),
+            5 => "CNAME".to_string(),
+            6 => "SOA".to_string(),
+            12 => "PTR".to_string(),
+            15 => "MX".to_string(),
+            16 => "TXT".to_string(),
+            28 => "AAAA".to_string(),
+            33 => "SRV".to_string(),
+            65 => "HTTPS".to_string(),
+            qtype => qtype.to_string(),
+        }
+    }
+
+    /// Human readable response code.
+    pub fn rcode_name(&self) -> String {
+        match self.rcode {
+            0 => "NOERROR".to_string(),
+            1 => "FORMERR".to_string(),
+            DNS_RCODE_SERVFAIL => "SERVFAIL".to_string(),
+            DNS_RCODE_NXDOMAIN => "NXDOMAIN".to_string(),
+            4 => "NOTIMP".to_string(),
+            5 => "REFUSED".to_string(),
+            rcode => rcode.to_string(),
+        }
+    }
+
+    /// Return the client and server addresses formatted according to `af`.
+    pub fn addresses(&self) -> (String, String) {
+        use crate::formatters::{format_ipv4, format_ipv6};
+
+        match self.af {
+            AF_INET6 => {
+                // copy the packed fields before taking a reference
+                let (saddr, daddr) = (self.saddr_v6, self.daddr_v6);
+                (format_ipv6(&saddr), format_ipv6(&daddr))
+            }
+            _ => (format_ipv4(self.saddr_v4), format_ipv4(self.daddr_v4)),
+        }
+    }
+}
+
+/// [`ProcessEvent`] kind: a process or a thread was created.
+#[cfg(feature = "monitoring-structs")]
+pub const PROCESS_EVENT_FORK: u8 = 1;
+/// [`ProcessEvent`] kind: a process executed a new program.
+#[cfg(feature = "monitoring-structs")]
+pub const PROCESS_EVENT_EXEC: u8 = 2;
+/// [`ProcessEvent`] kind: a process or a thread exited.
+#[cfg(feature = "monitoring-structs")]
+pub const PROCESS_EVENT_EXIT: u8 = 3;
+/// Length of the executable path copied by the metrics_tracer exec tracepoint.
+#[cfg(feature = "monitoring-structs")]
+pub const PROCESS_FILENAME_LEN: usize = 128;
+
+///
+/// Structure ProcessEvent
+/// Event emitted by the metrics_tracer `sched_process_fork`,
+/// `sched_process_exec` and `sched_process_exit` tracepoints.
+///
+/// For [`PROCESS_EVENT_FORK`] `tgid` and `pid` are both the child pid (a new
+/// thread cannot be told from a new process) and `comm`/`cgroup_id` are
+/// inherited from the parent. `ppid` is the tgid of the parent. `exit_code`
+/// is the raw `task->exit_code` (see [`ProcessEvent::exit_status`]) and
+/// `filename` the path passed to `execve`. `ts_ns` is the kernel monotonic
+/// clock.
+///
+#[cfg(feature = "monitoring-structs")]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Zeroable)]
+pub struct ProcessEvent {
+    pub tgid: u32,
+    pub pid: u32,
+    pub ppid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+    pub ts_ns: u64,
+    pub cgroup_id: u64,
+    pub exit_code: i32,
+    pub kind: u8,
+    pub filename: [u8; PROCESS_FILENAME_LEN],
+}
+#[cfg(feature = "monitoring-structs")]
+unsafe impl aya::Pod for ProcessEvent {}
+
+#[cfg(feature = "monitoring-structs")]
+impl ProcessEvent {
+    /// Human readable event kind.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            PROCESS_EVENT_FORK => "fork",
+            PROCESS_EVENT_EXEC => "exec",
+            PROCESS_EVENT_EXIT => "exit",
+            _ => "unknown",
+        }
+    }
+
+    /// Command of the task.
+    pub fn command(&self) -> String {
+        let comm = self.comm;
+        String::from_utf8_lossy(&comm)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    /// Path passed to `execve`, `None` for the other kinds.
+    pub fn filename(&self) -> Option<String> {
+        let filename = self.filename;
+        let end = filename
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(filename.len());
+        match end {
+            0 => None,
+            _ => Some(String::from_utf8_lossy(&filename[..end]).to_string()),
+        }
+    }
+
+    /// Decode the exit code into `(status, signal)`: the status passed to
+    /// `exit` and the number of the signal that killed the task (0 if none).
+    pub fn exit_status(&self) -> (i32, i32) {
+        let exit_code = self.exit_code;
+        ((exit_code >> 8) & 0xff, exit_code & 0x7f)
+    }
+}
+
 #[cfg(feature = "monitoring-structs")]
 #[repr(C, packed)]
 #[derive(Clone, Copy, Zeroable)]
@@ -223,11 +900,27 @@ pub enum BufferSize {
     VethEvents,
     #[cfg(feature = "network-structs")]
     TcpEvents,
+    #[cfg(feature = "network-structs")]
+    AuditEvents,
+    #[cfg(feature = "network-structs")]
+    DropEvents,
+    #[cfg(feature = "network-structs")]
+    TcpConnEvents,
+    #[cfg(feature = "network-structs")]
+    ListenerEvents,
     #[cfg(feature = "monitoring-structs")]
     NetworkMetricsEvents,
     #[cfg(feature = "monitoring-structs")]
     TimeMetricsEvents,
     #[cfg(feature = "monitoring-structs")]
+    TcpSocketEvents,
+    #[cfg(feature = "monitoring-structs")]
+    TcpHealthEvents,
+    #[cfg(feature = "monitoring-structs")]
+    DnsEvents,
+    #[cfg(feature = "monitoring-structs")]
+    ProcessEvents,
+    #[cfg(feature = "monitoring-structs")]
     CpuFrequency,
     #[cfg(feature = "monitoring-structs")]
     MemAlloc,
@@ -252,11 +945,27 @@ impl BufferSize {
             BufferSize::VethEvents => std::mem::size_of::<VethLog>(),
             #[cfg(feature = "network-structs")]
             BufferSize::TcpEvents => std::mem::size_of::<TcpPacketRegistry>(),
+            #[cfg(feature = "network-structs")]
+            BufferSize::AuditEvents => std::mem::size_of::<AuditEvent>(),
+            #[cfg(feature = "network-structs")]
+            BufferSize::DropEvents => std::mem::size_of::<DropEvent>(),
+            #[cfg(feature = "network-structs")]
+            BufferSize::TcpConnEvents => std::mem::size_of::<TcpConnEvent>(),
+            #[cfg(feature = "network-structs")]
+            BufferSize::ListenerEvents => std::mem::size_of::<ListenerEvent>(),
             #[cfg(feature = "monitoring-structs")]
             BufferSize::NetworkMetricsEvents => std::mem::size_of::<PacketLossMetrics>(),
             #[cfg(feature = "monitoring-structs")]
             BufferSize::TimeMetricsEvents => std::mem::size_of::<TimeStampMetrics>(),
             #[cfg(feature = "monitoring-structs")]
+            BufferSize::TcpSocketEvents => std::mem::size_of::<TcpSocketEvent>(),
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::TcpHealthEvents => std::mem::size_of::<TcpHealthEvent>(),
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::DnsEvents => std::mem::size_of::<DnsEvent>(),
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::ProcessEvents => std::mem::size_of::<ProcessEvent>(),
+            #[cfg(feature = "monitoring-structs")]
             BufferSize::CpuFrequency => std::mem::size_of::<CpuFrequency>(),
             #[cfg(feature = "monitoring-structs")]
             BufferSize::MemAlloc => std::mem::size_of::<MemAlloc>(),
@@ -282,77 +991,124 @@ impl BufferSize {
             #[cfg(feature = "network-structs")]
             BufferSize::ClassifierNetEvents => {
                 let capacity = self.get_size() * 200;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "network-structs")]
             BufferSize::VethEvents => {
                 let capacity = self.get_size() * 100;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "network-structs")]
             BufferSize::TcpEvents => {
                 let capacity = self.get_size() * 200;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "network-structs")]
+            BufferSize::AuditEvents => {
+                let capacity = self.get_size() * 200;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "network-structs")]
+            BufferSize::DropEvents => {
+                let capacity = self.get_size() * 200;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "network-structs")]
+            BufferSize::TcpConnEvents => {
+                let capacity = self.get_size() * 200;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "network-structs")]
+            BufferSize::ListenerEvents => {
+                let capacity = self.get_size() * 200;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::NetworkMetricsEvents => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::TimeMetricsEvents => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::TcpSocketEvents => {
+                let capacity = self.get_size() * 1024;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::TcpHealthEvents => {
+                let capacity = self.get_size() * 1024;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::DnsEvents => {
+                let capacity = self.get_size() * 1024;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
+            }
+            #[cfg(feature = "monitoring-structs")]
+            BufferSize::ProcessEvents => {
+                let capacity = self.get_size() * 1024;
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::CpuFrequency => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::MemAlloc => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::SchedStatWait => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::SchedStatRuntime => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::CpuIdle => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
             #[cfg(feature = "monitoring-structs")]
             BufferSize::SslEvents => {
                 let capacity = self.get_size() * 1024;
-                return vec![BytesMut::with_capacity(capacity); tot_cpu];
+                vec![BytesMut::with_capacity(capacity); tot_cpu]
             }
         }
     }
 }
 
-/// Open a [`PerfEventArrayBuffer`] for every online CPU and append them to `vec_of_buffers`.
-#[cfg(feature = "buffer-reader")]
-pub fn fill_buffers(
-    mut vec_of_buffers: Vec<PerfEventArrayBuffer<MapData>>,
-    mut events_array: PerfEventArray<MapData>,
-) -> Vec<PerfEventArrayBuffer<MapData>> {
-    for cpu_id in online_cpus()
-        .map_err(|e| anyhow::anyhow!("Error {:?}", e))
-        .unwrap()
-    {
-        let buf = events_array
-            .open(cpu_id, None)
-            .expect("Error during the creation of net_events_buf structure");
-
-        vec_of_buffers.push(buf);
+#[cfg(all(test, feature = "monitoring-structs"))]
+mod tests {
+    use super::*;
+
+    fn dns_event(name: &[u8]) -> DnsEvent {
+        let mut event: DnsEvent = bytemuck::Zeroable::zeroed();
+        event.qname[..name.len()].copy_from_slice(name);
+        event
+    }
+
+    #[test]
+    fn decodes_dns_wire_format_names() {
+        let event = dns_event(b"\x03api\x07example\x03com\x00");
+        assert_eq!(event.qname(), "api.example.com");
+
+        // name longer than the copied bytes
+        let mut event = dns_event(&[]);
+        event.qname[0] = 70;
+        event.qname[1..].fill(b'a');
+        assert_eq!(
+            event.qname(),
+            format!("{}...", "a".repeat(DNS_QNAME_LEN - 1))
+        );
     }
-    vec_of_buffers
 }
@@ -0,0 +1,301 @@
+//! Cgroup filter of the `metrics_tracer` eBPF programs.
+//!
+//! When the filter is enabled the programs only trace the tasks and the
+//! sockets whose cgroup v2 id is stored in the pinned `cgroup_filter` map.
+//! The traced workloads are selected with the following keys of the
+//! `cortexbrain-client-config` configmap:
+//! - `metrics_target_namespaces`: comma separated namespaces (every namespace when missing).
+//! - `metrics_target_labels`: label selector, e.g. `app=frontend,tier!=cache`.
+//!
+//! Without both keys the filter is disabled and every workload is traced.
+//! The cgroups of the selected pods are found by pod uid under
+//! `/sys/fs/cgroup` (systemd and cgroupfs drivers) and include the container
+//! cgroups nested below the pod cgroup. The filter is only enabled on the
+//! kernels that have the socket cgroup fields read by the programs.
+
+use crate::histograms::METRICS_CONFIG_MAP;
+use crate::kernel_offsets::{KernelBtf, METRICS_TRACER_CGROUP_FILTER_FIELDS, resolve_offsets};
+use anyhow::{Error, anyhow};
+use aya::maps::{Array, HashMap, Map, MapData};
+use k8s_openapi::api::core::v1::{ConfigMap, Pod};
+use kube::api::ListParams;
+use kube::{Api, Client};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use tracing::info;
+
+/// Name of the pinned hash map with the traced cgroup ids.
+pub const CGROUP_FILTER_MAP: &str = "cgroup_filter";
+
+/// Index of the cgroup filter setting in the `metrics_config` map.
+pub const CONFIG_CGROUP_FILTER: u32 = 1;
+
+/// Every cgroup is traced (default).
+pub const CGROUP_FILTER_DISABLED: u32 = 0;
+/// Only the cgroups in the `cgroup_filter` map are traced.
+pub const CGROUP_FILTER_ENABLED: u32 = 1;
+
+/// Configmap key with the comma separated namespaces of the traced pods.
+pub const TARGET_NAMESPACES_CONFIGMAP_KEY: &str = "metrics_target_namespaces";
+/// Configmap key with the label selector of the traced pods.
+pub const TARGET_LABELS_CONFIGMAP_KEY: &str = "metrics_target_labels";
+
+/// Kubernetes selector of the traced pods.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TargetSelector {
+    pub namespaces: Vec<String>,
+    pub labels: Option<String>,
+}
+
+impl TargetSelector {
+    /// Build the selector from the `cortexbrain-client-config` configmap data.
+    pub fn from_configmap_data(data: &BTreeMap<String, String>) -> Self {
+        let namespaces = data
+            .get(TARGET_NAMESPACES_CONFIGMAP_KEY)
+            .map(|namespaces| {
+                namespaces
+                    .split(',')
+                    .map(|ns| ns.trim().to_string())
+                    .filter(|ns| !ns.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let labels = data
+            .get(TARGET_LABELS_CONFIGMAP_KEY)
+            .map(|labels| labels.trim().to_string())
+            .filter(|labels| !labels.is_empty());
+
+        TargetSelector { namespaces, labels }
+    }
+
+    /// True when no namespace and no label is selected (filter disabled).
+    pub fn is_empty(&self) -> bool {
+        self.namespaces.is_empty() && self.labels.is_none()
+    }
+
+    /// Return the uids of the pods that match the selector.
+    pub async fn pod_uids(&self, client: &Client) -> Result<HashSet<String>, Error> {
+        let mut lp = ListParams::default();
+        if let Some(labels) = &self.labels {
+            lp = lp.labels(labels);
+        }
+
+        let apis: Vec<Api<Pod>> = if self.namespaces.is_empty() {
+            vec![Api::all(client.clone())]
+        } else {
+            self.namespaces
+                .iter()
+                .map(|ns| Api::namespaced(client.clone(), ns))
+                .collect()
+        };
+
+        let mut uids = HashSet::new();
+        for api in apis {
+            for pod in api.list(&lp).await? {
+                if let Some(uid) = pod.metadata.uid {
+                    uids.insert(uid);
+                }
+            }
+        }
+        Ok(uids)
+    }
+}
+
+/// Extract the pod uid from a cgroup directory name.
+///
+/// Supports the systemd driver (`kubepods-besteffort-pod<uid>.slice`, with
+/// `_` in place of `-`) and the cgroupfs driver (`pod<uid>`).
+pub fn pod_uid_from_cgroup_name(name: &str) -> Option<String> {
+    let start = name.rfind("pod")? + 3;
+    let uid = name[start..]
+        .strip_suffix(".slice")
+        .unwrap_or(&name[start..]);
+    if uid.len() != 36 {
+        return None;
+    }
+    Some(uid.replace('_', "-"))
+}
+
+/// Return the ids (directory inode numbers) of the cgroups of the pods with
+/// the given uids, including the nested container cgroups.
+pub fn pod_cgroup_ids(root: &Path, uids: &HashSet<String>) -> HashSet<u64> {
+    fn walk(dir: &Path, uids: &HashSet<String>, selected: bool, ids: &mut HashSet<u64>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_dir() {
+                continue;
+            }
+            let selected = selected
+                || pod_uid_from_cgroup_name(&entry.file_name().to_string_lossy())
+                    .is_some_and(|uid| uids.contains(&uid));
+            if selected {
+                ids.insert(metadata.ino());
+            }
+            walk(&entry.path(), uids, selected, ids);
+        }
+    }
+
+    let mut ids = HashSet::new();
+    walk(root, uids, false, &mut ids);
+    ids
+}
+
+/// Wrapper around the pinned `cgroup_filter` map and the filter setting.
+pub struct CgroupFilterMap {
+    pub cgroups: HashMap<MapData, u64, u8>,
+    pub config: Array<MapData, u32>,
+    client: Option<Client>,
+    kernel_supported: bool,
+}
+
+impl CgroupFilterMap {
+    /// Load the `cgroup_filter` and `metrics_config` maps from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin(format!("/sys/fs/bpf/trace_maps/{}", CGROUP_FILTER_MAP))
+            .map_err(|e| anyhow!("Failed to load {} map: {}", CGROUP_FILTER_MAP, e))?;
+        let cgroups = HashMap::<_, u64, u8>::try_from(Map::HashMap(mapdata))?;
+
+        let mapdata = MapData::from_pin(format!("/sys/fs/bpf/trace_maps/{}", METRICS_CONFIG_MAP))
+            .map_err(|e| anyhow!("Failed to load {} map: {}", METRICS_CONFIG_MAP, e))?;
+        let config = Array::<_, u32>::try_from(Map::Array(mapdata))?;
+
+        Ok(CgroupFilterMap {
+            cgroups,
+            config,
+            client: None,
+            kernel_supported: false,
+        })
+    }
+
+    /// Check that the running kernel has the fields read by the filter
+    /// (`sock.sk_cgrp_data.cgroup` needs Linux 5.15). The BTF is parsed until
+    /// the check succeeds once.
+    fn check_kernel_support(&mut self) -> Result<(), Error> {
+        if !self.kernel_supported {
+            let btf = KernelBtf::from_sys_fs()?;
+            resolve_offsets(&btf, METRICS_TRACER_CGROUP_FILTER_FIELDS)
+                .map_err(|e| anyhow!("The cgroup filter is not supported by the kernel: {}", e))?;
+            self.kernel_supported = true;
+        }
+        Ok(())
+    }
+
+    /// Enable or disable the filter in the programs.
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+        let value = if enabled {
+            CGROUP_FILTER_ENABLED
+        } else {
+            CGROUP_FILTER_DISABLED
+        };
+        self.config.set(CONFIG_CGROUP_FILTER, value, 0)?;
+        Ok(())
+    }
+
+    /// Replace the traced cgroups with `ids`. Returns the number of added and
+    /// removed cgroups.
+    pub fn sync(&mut self, ids: &HashSet<u64>) -> Result<(usize, usize), Error> {
+        let current = self.cgroups.keys().collect::<Result<HashSet<u64>, _>>()?;
+
+        let mut added = 0;
+        for id in ids.difference(&current) {
+            self.cgroups.insert(id, 1, 0)?;
+            added += 1;
+        }
+        let mut removed = 0;
+        for id in current.difference(ids) {
+            self.cgroups.remove(id)?;
+            removed += 1;
+        }
+        Ok((added, removed))
+    }
+
+    /// Read the selector from the `cortexbrain-client-config` configmap and
+    /// update the traced cgroups.
+    ///
+    /// The map is filled before the filter is enabled, so the selected pods
+    /// are never dropped while the filter is being set up. When the kernel
+    /// does not support the filter it stays disabled and an error is returned.
+    pub async fn refresh(&mut self) -> Result<(), Error> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let client = Client::try_default().await?;
+                self.client = Some(client.clone());
+                client
+            }
+        };
+
+        let api: Api<ConfigMap> = Api::namespaced(client.clone(), "cortexflow");
+        let configs = api.get("cortexbrain-client-config").await?;
+        let selector = TargetSelector::from_configmap_data(&configs.data.unwrap_or_default());
+
+        if selector.is_empty() {
+            self.set_enabled(false)?;
+            self.sync(&HashSet::new())?;
+            return Ok(());
+        }
+
+        if let Err(e) = self.check_kernel_support() {
+            self.set_enabled(false)?;
+            return Err(e);
+        }
+
+        let uids = selector.pod_uids(&client).await?;
+        let ids = pod_cgroup_ids(Path::new("/sys/fs/cgroup"), &uids);
+        let (added, removed) = self.sync(&ids)?;
+        if added > 0 || removed > 0 {
+            info!(
+                "Cgroup filter updated: {} pods, {} cgroups ({} added, {} removed)",
+                uids.len(),
+                ids.len(),
+                added,
+                removed
+            );
+        }
+        self.set_enabled(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pod_cgroup_ids() {
+        let uid = "231bd2d7-0f09-4781-a4e1-e4ea026342dd";
+        assert_eq!(
+            pod_uid_from_cgroup_name(
+                "kubepods-besteffort-pod231bd2d7_0f09_4781_a4e1_e4ea026342dd.slice"
+            )
+            .as_deref(),
+            Some(uid)
+        );
+        assert_eq!(
+            pod_uid_from_cgroup_name(&format!("pod{}", uid)).as_deref(),
+            Some(uid)
+        );
+        assert_eq!(pod_uid_from_cgroup_name("kubepods-besteffort.slice"), None);
+
+        let root = std::env::temp_dir().join(format!("cgroup-filter-{}", std::process::id()));
+        let pod = root.join(
+            "kubepods.slice/kubepods-besteffort-pod231bd2d7_0f09_4781_a4e1_e4ea026342dd.slice",
+        );
+        let container = pod.join("cri-containerd-abc123.scope");
+        let other = root.join("system.slice/kubelet.service");
+        fs::create_dir_all(&container).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        let ids = pod_cgroup_ids(&root, &HashSet::from([uid.to_string()]));
+        let ino = |path: &Path| fs::metadata(path).unwrap().ino();
+        assert_eq!(ids, HashSet::from([ino(&pod), ino(&container)]));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
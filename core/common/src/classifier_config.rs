@@ -0,0 +1,253 @@
+//! Runtime configuration of the `identity_classifier` eBPF program.
+//!
+//! The classifier reads its settings from the pinned `classifier_config`
+//! array map. Every setting is a `u32` stored at a fixed index; the indexes
+//! and the accepted values mirror the constants declared in the conntracker
+//! `data_structures.rs` module.
+//!
+//! Settings are loaded from the `cortexbrain-client-config` configmap at
+//! startup ([`populate_classifier_config`]) and can be changed at runtime
+//! through the agent api.
+//!
+//! The classifier also keeps per-reason counters in the pinned
+//! `classifier_counters` per-cpu array, exposed by [`ClassifierCountersMap`].
+
+use anyhow::{Error, anyhow};
+use aya::maps::{Array, Map, MapData, PerCpuArray};
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::{Api, Client};
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+
+/// Index of the enforcement mode setting.
+pub const CONFIG_ENFORCEMENT_MODE: u32 = 0;
+/// Index of the failure policy setting.
+pub const CONFIG_FAILURE_POLICY: u32 = 1;
+/// Number of slots in the `classifier_config` map. Must match the eBPF side.
+pub const MAX_CONFIG_ENTRIES: u32 = 8;
+
+/// Drop the packets matching the blocklist or a drop rule.
+pub const MODE_ENFORCE: u32 = 0;
+/// Emit an audit event for the packets that would have been dropped and let them pass.
+pub const MODE_AUDIT: u32 = 1;
+
+/// Pass the packets the classifier cannot parse (default).
+pub const FAILURE_POLICY_OPEN: u32 = 0;
+/// Drop the packets the classifier cannot parse.
+pub const FAILURE_POLICY_CLOSED: u32 = 1;
+
+/// Configmap key used to persist the enforcement mode.
+pub const MODE_CONFIGMAP_KEY: &str = "mode";
+/// Configmap key used to persist the failure policy.
+pub const FAILURE_POLICY_CONFIGMAP_KEY: &str = "failure_policy";
+
+/// Counter index: blocklist hits. Counter indexes from 1 to 4 and 7 match the drop reasons.
+pub const COUNTER_BLOCKLIST: u32 = 1;
+/// Counter index: drop rule hits.
+pub const COUNTER_RULE: u32 = 2;
+/// Counter index: malformed headers.
+pub const COUNTER_PARSE_ERROR: u32 = 3;
+/// Counter index: packets shorter than the parsed headers.
+pub const COUNTER_TRUNCATED_HEADER: u32 = 4;
+/// Counter index: packets passed because of the fail-open policy.
+pub const COUNTER_FAIL_OPEN: u32 = 5;
+/// Counter index: packets dropped by the XDP blocklist fast path.
+pub const COUNTER_XDP_BLOCKLIST: u32 = 6;
+/// Counter index: packets dropped by a rate limit. Matches the drop reason.
+pub const COUNTER_RATE_LIMIT: u32 = 7;
+/// Number of slots in the `classifier_counters` map. Must match the eBPF side.
+pub const MAX_CLASSIFIER_COUNTERS: u32 = 8;
+
+/// Parse an enforcement mode (`"enforce"` or `"audit"`).
+pub fn parse_mode(input: &str) -> Result<u32, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "enforce" | "enforcing" => Ok(MODE_ENFORCE),
+        "audit" | "dry-run" => Ok(MODE_AUDIT),
+        _ => Err(anyhow!(
+            "Unknown enforcement mode {:?}. Expected enforce or audit",
+            input
+        )),
+    }
+}
+
+/// Format an enforcement mode as returned by [`parse_mode`].
+pub fn format_mode(mode: u32) -> String {
+    match mode {
+        MODE_ENFORCE => "enforce".to_string(),
+        MODE_AUDIT => "audit".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse a failure policy (`"open"` or `"closed"`).
+pub fn parse_failure_policy(input: &str) -> Result<u32, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "open" | "fail-open" => Ok(FAILURE_POLICY_OPEN),
+        "closed" | "fail-closed" => Ok(FAILURE_POLICY_CLOSED),
+        _ => Err(anyhow!(
+            "Unknown failure policy {:?}. Expected open or closed",
+            input
+        )),
+    }
+}
+
+/// Format a failure policy as returned by [`parse_failure_policy`].
+pub fn format_failure_policy(policy: u32) -> String {
+    match policy {
+        FAILURE_POLICY_OPEN => "open".to_string(),
+        FAILURE_POLICY_CLOSED => "closed".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Name of a counter index, used as label by the agent api.
+pub fn counter_name(index: u32) -> String {
+    match index {
+        COUNTER_BLOCKLIST => "blocklist".to_string(),
+        COUNTER_RULE => "rule".to_string(),
+        COUNTER_PARSE_ERROR => "parse_error".to_string(),
+        COUNTER_TRUNCATED_HEADER => "truncated_header".to_string(),
+        COUNTER_FAIL_OPEN => "fail_open".to_string(),
+        COUNTER_XDP_BLOCKLIST => "xdp_blocklist".to_string(),
+        COUNTER_RATE_LIMIT => "rate_limit".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Wrapper around the pinned `classifier_config` array map.
+pub struct ClassifierConfigMap {
+    pub config: Array<MapData, u32>,
+}
+
+impl ClassifierConfigMap {
+    /// Load the `classifier_config` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/classifier_config")
+            .map_err(|e| anyhow!("Failed to load classifier_config map: {}", e))?;
+        let config = Array::<_, u32>::try_from(Map::Array(mapdata))?;
+        Ok(ClassifierConfigMap { config })
+    }
+
+    /// Read the value stored at `index`.
+    pub fn get(&self, index: u32) -> Result<u32, Error> {
+        Ok(self.config.get(&index, 0)?)
+    }
+
+    /// Write `value` at `index`.
+    pub fn set(&mut self, index: u32, value: u32) -> Result<(), Error> {
+        self.config.set(index, value, 0)?;
+        Ok(())
+    }
+
+    /// Current enforcement mode.
+    pub fn mode(&self) -> Result<u32, Error> {
+        self.get(CONFIG_ENFORCEMENT_MODE)
+    }
+
+    /// Change the enforcement mode. The new mode is applied to the next packet.
+    pub fn set_mode(&mut self, mode: u32) -> Result<(), Error> {
+        if mode != MODE_ENFORCE && mode != MODE_AUDIT {
+            return Err(anyhow!("Invalid enforcement mode {}", mode));
+        }
+        self.set(CONFIG_ENFORCEMENT_MODE, mode)
+    }
+
+    /// Current failure policy.
+    pub fn failure_policy(&self) -> Result<u32, Error> {
+        self.get(CONFIG_FAILURE_POLICY)
+    }
+
+    /// Change the failure policy. The new policy is applied to the next packet.
+    pub fn set_failure_policy(&mut self, policy: u32) -> Result<(), Error> {
+        if policy != FAILURE_POLICY_OPEN && policy != FAILURE_POLICY_CLOSED {
+            return Err(anyhow!("Invalid failure policy {}", policy));
+        }
+        self.set(CONFIG_FAILURE_POLICY, policy)
+    }
+}
+
+/// Wrapper around the pinned `classifier_counters` per-cpu array map.
+pub struct ClassifierCountersMap {
+    pub counters: PerCpuArray<MapData, u64>,
+}
+
+impl ClassifierCountersMap {
+    /// Load the `classifier_counters` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/classifier_counters")
+            .map_err(|e| anyhow!("Failed to load classifier_counters map: {}", e))?;
+        let counters = PerCpuArray::<_, u64>::try_from(Map::PerCpuArray(mapdata))?;
+        Ok(ClassifierCountersMap { counters })
+    }
+
+    /// Return the value of the counter at `index` summed across the cpus.
+    pub fn total(&self, index: u32) -> Result<u64, Error> {
+        let values = self.counters.get(&index, 0)?;
+        Ok(values.iter().sum())
+    }
+
+    /// Return the value of every named counter summed across the cpus.
+    pub fn totals(&self) -> Result<HashMap<String, u64>, Error> {
+        let mut totals = HashMap::new();
+        for index in COUNTER_BLOCKLIST..=COUNTER_RATE_LIMIT {
+            totals.insert(counter_name(index), self.total(index)?);
+        }
+        Ok(totals)
+    }
+}
+
+/// Load the classifier settings from the `cortexbrain-client-config` configmap.
+///
+/// Missing keys keep the default values (enforce mode, fail-open policy).
+pub async fn populate_classifier_config() -> Result<(), Error> {
+    let mut classifier_config = ClassifierConfigMap::from_pin()?;
+
+    let client = Client::try_default()
+        .await
+        .expect("Cannot connect to Kubernetes Client");
+    let namespace = "cortexflow";
+    let configmap = "cortexbrain-client-config";
+
+    let api: Api<ConfigMap> = Api::namespaced(client, namespace);
+    match api.get(configmap).await {
+        Ok(configs) => {
+            let data = configs.data.unwrap_or_default();
+            match data.get(MODE_CONFIGMAP_KEY) {
+                Some(mode) => match parse_mode(mode) {
+                    Ok(mode) => {
+                        info!("Setting classifier enforcement mode: {}", format_mode(mode));
+                        classifier_config.set_mode(mode)?;
+                    }
+                    Err(e) => {
+                        warn!("{}. Using enforce mode", e);
+                    }
+                },
+                None => {
+                    info!("No enforcement mode found in the configmap. Using enforce mode");
+                }
+            }
+            match data.get(FAILURE_POLICY_CONFIGMAP_KEY) {
+                Some(policy) => match parse_failure_policy(policy) {
+                    Ok(policy) => {
+                        info!(
+                            "Setting classifier failure policy: {}",
+                            format_failure_policy(policy)
+                        );
+                        classifier_config.set_failure_policy(policy)?;
+                    }
+                    Err(e) => {
+                        warn!("{}. Using fail-open policy", e);
+                    }
+                },
+                None => {
+                    info!("No failure policy found in the configmap. Using fail-open policy");
+                }
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("An error occured while reading configmap: {}", e);
+            Err(e.into())
+        }
+    }
+}
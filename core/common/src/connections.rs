@@ -0,0 +1,123 @@
+//! Established TCP connections tracked by the conntracker `tcp_state_tracer` eBPF program.
+//!
+//! The tracepoint inserts a [`TcpConnection`] in the pinned `tcp_connections`
+//! LRU map when a socket moves to `TCP_ESTABLISHED` and removes it when the
+//! socket moves to `TCP_CLOSE`, so the map only contains the connections that
+//! are currently open. This module contains:
+//! - The C-compatible value layout shared with the conntracker crate.
+//! - [`ConnectionTable`], a wrapper around the pinned map.
+
+use crate::constants::{DIRECTION_EGRESS, DIRECTION_INGRESS};
+use crate::formatters::{format_ip_port, format_ip_words};
+use anyhow::{Error, anyhow};
+use aya::maps::{HashMap, Map, MapData};
+use bytemuck_derive::Zeroable;
+
+/// Connection layout shared with the conntracker `TcpConnection` struct.
+///
+/// `src` is the local end of the connection and `dst` the remote end.
+/// Addresses are stored as four host-order words (IPv4 connections use only
+/// the first word). `established_ns` is a `bpf_ktime_get_ns()` value
+/// (monotonic clock, nanoseconds since boot).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Zeroable)]
+pub struct TcpConnection {
+    pub established_ns: u64,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+    pub af: u16,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub direction: u8,
+    pub _padding: u8,
+}
+unsafe impl aya::Pod for TcpConnection {}
+
+impl TcpConnection {
+    /// Local `ip:port` formatted according to `af`.
+    pub fn src(&self) -> String {
+        format_ip_port(
+            self.af,
+            &format_ip_words(self.af, &self.src_ip),
+            self.src_port,
+        )
+    }
+
+    /// Remote `ip:port` formatted according to `af`.
+    pub fn dst(&self) -> String {
+        format_ip_port(
+            self.af,
+            &format_ip_words(self.af, &self.dst_ip),
+            self.dst_port,
+        )
+    }
+
+    /// `egress` for the connections opened by the node, `ingress` for the accepted ones.
+    pub fn direction_name(&self) -> &'static str {
+        match self.direction {
+            DIRECTION_INGRESS => "ingress",
+            DIRECTION_EGRESS => "egress",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Wrapper around the pinned `tcp_connections` LRU hash map.
+///
+/// The key is the kernel address of the socket.
+pub struct ConnectionTable {
+    pub connections: HashMap<MapData, u64, TcpConnection>,
+}
+
+impl ConnectionTable {
+    /// Load the `tcp_connections` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/tcp_connections")
+            .map_err(|e| anyhow!("Failed to load tcp_connections map: {}", e))?;
+        let connections = HashMap::<_, u64, TcpConnection>::try_from(Map::LruHashMap(mapdata))?;
+        Ok(ConnectionTable { connections })
+    }
+
+    /// Return the established connections, oldest first.
+    pub fn snapshot(&self) -> Result<Vec<(u64, TcpConnection)>, Error> {
+        let mut connections = self.connections.iter().collect::<Result<Vec<_>, _>>()?;
+        connections.sort_by_key(|(_, connection)| connection.established_ns);
+        Ok(connections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{AF_INET, AF_INET6};
+
+    #[test]
+    fn test_tcp_connection_layout() {
+        // must match the conntracker TcpConnection struct
+        assert_eq!(std::mem::size_of::<TcpConnection>(), 48);
+    }
+
+    #[test]
+    fn test_tcp_connection_format() {
+        let connection = TcpConnection {
+            established_ns: 10,
+            src_ip: [0x0a000001, 0, 0, 0],
+            dst_ip: [0x0a000002, 0, 0, 0],
+            af: AF_INET,
+            src_port: 43210,
+            dst_port: 80,
+            direction: DIRECTION_EGRESS,
+            _padding: 0,
+        };
+        assert_eq!(connection.src(), "10.0.0.1:43210");
+        assert_eq!(connection.dst(), "10.0.0.2:80");
+        assert_eq!(connection.direction_name(), "egress");
+
+        let connection = TcpConnection {
+            af: AF_INET6,
+            dst_ip: [0, 0, 0, 1],
+            ..connection
+        };
+        assert_eq!(connection.dst(), "[0:0:0:0:0:0:0:1]:80");
+    }
+}
@@ -6,7 +6,45 @@ pub const BPF_PATH: &str = "BPF_PATH";
 /// Used for sharing maps between eBPF programs.
 pub const PIN_MAP_PATH: &str = "PIN_MAP_PATH";
 
+/// Suffix of the eBPF object built with perf event arrays instead of ring buffers.
+/// The object is loaded in place of `BPF_PATH` on kernels without `BPF_MAP_TYPE_RINGBUF`.
+pub const PERF_OBJECT_SUFFIX: &str = "-perf";
+
 /// Environment variable name for the OpenSSL library path used by SSL uprobes.
 /// When set, this path is used directly. When unset, the library is located
 /// by searching the default system library directories.
 pub const LIBSSL_PATH: &str = "LIBSSL_PATH";
+
+/// Environment variable name for the XDP blocklist fast path mode.
+/// Accepted values: `off` (default), `auto`, `native` and `generic`.
+pub const XDP_MODE: &str = "XDP_MODE";
+
+/// Environment variable name for the comma separated list of interfaces where
+/// the XDP blocklist fast path is attached. When unset, the interfaces
+/// monitored by the tc classifier are used.
+pub const XDP_INTERFACES: &str = "XDP_INTERFACES";
+
+/// Environment variable name for the metrics_tracer aggregation mode.
+/// Accepted values: `events` (default) and `histograms`. In `histograms`
+/// mode the scheduler and TCP latency values are aggregated in the kernel.
+pub const METRICS_AGGREGATION_MODE: &str = "METRICS_AGGREGATION_MODE";
+
+/// Number of slots of the in-kernel log2 histograms.
+/// Mirrors the `HIST_SLOTS` value of the metrics_tracer eBPF program.
+pub const HIST_SLOTS: usize = 32;
+
+/// Address family code for IPv4 events (`AF_INET`).
+/// Mirrors the value written by the eBPF programs in the `af` field.
+pub const AF_INET: u16 = 2;
+
+/// Address family code for IPv6 events (`AF_INET6`).
+/// Mirrors the value written by the eBPF programs in the `af` field.
+pub const AF_INET6: u16 = 10;
+
+/// Packet seen by the classifier attached to the tc ingress hook.
+/// Mirrors the value written by the eBPF programs in the `direction` field.
+pub const DIRECTION_INGRESS: u8 = 0;
+
+/// Packet seen by the classifier attached to the tc egress hook.
+/// Mirrors the value written by the eBPF programs in the `direction` field.
+pub const DIRECTION_EGRESS: u8 = 1;
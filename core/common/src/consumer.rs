@@ -1,7 +1,7 @@
-//! Perf-buffer consumers for eBPF events.
+//! Ring-buffer and perf-buffer consumers for eBPF events.
 //!
 //! This module provides the [`Consumer`] enum and its associated `read` methods
-//! that parse raw bytes from [`aya::maps::perf::PerfEventArrayBuffer`] into
+//! that parse raw bytes read from an [`EventSource`] into
 //! strongly-typed eBPF structs and forward them to the OpenTelemetry metrics
 //! pipeline via [`crate::otel_metrics::Metrics`].
 //!
@@ -13,15 +13,20 @@
 
 #[cfg(feature = "monitoring-structs")]
 use crate::buffer_type::{
-    CpuFrequency, CpuIdle, MemAlloc, PacketLossMetrics, SchedStatRuntime, SchedStatWait,
-    TimeStampMetrics,
+    CpuFrequency, CpuIdle, DnsEvent, MemAlloc, PROCESS_EVENT_EXEC, PROCESS_EVENT_EXIT,
+    PacketLossMetrics, ProcessEvent, SchedStatRuntime, SchedStatWait, TcpHealthEvent,
+    TcpSocketEvent, TimeStampMetrics,
 };
 #[cfg(feature = "network-structs")]
-use crate::buffer_type::{PacketLog, TcpPacketRegistry, VethLog};
+use crate::buffer_type::{ListenerEvent, PacketLog, TcpConnEvent, TcpPacketRegistry, VethLog};
+#[cfg(feature = "buffer-reader")]
+use crate::event_reader::{EventSource, spawn_event_reader};
 #[cfg(feature = "monitoring-structs")]
 use crate::metadata::Metadata;
 #[cfg(feature = "monitoring-structs")]
 use crate::otel_metrics::Metrics;
+#[cfg(feature = "monitoring-structs")]
+use crate::process_table::process_table;
 use bytes::BytesMut;
 #[cfg(feature = "monitoring-structs")]
 use std::sync::Arc;
@@ -32,6 +37,7 @@ use tracing::{error, info, warn};
 /// Each variant maps to an eBPF program output struct and a dedicated
 /// `read_*` method that knows how to parse it.
 #[cfg(feature = "buffer-reader")]
+#[derive(Clone, Copy)]
 pub enum Consumer {
     #[cfg(feature = "network-structs")]
     PacketLog,
@@ -39,6 +45,10 @@ pub enum Consumer {
     TcpPacketRegistry,
     #[cfg(feature = "network-structs")]
     VethLog,
+    #[cfg(feature = "network-structs")]
+    TcpConnEvent,
+    #[cfg(feature = "network-structs")]
+    ListenerEvent,
     #[cfg(feature = "monitoring-structs")]
     PacketLossMetrics,
     #[cfg(feature = "monitoring-structs")]
@@ -55,17 +65,26 @@ pub enum Consumer {
     CpuIdle,
     #[cfg(feature = "monitoring-structs")]
     SslEvents,
+    #[cfg(feature = "monitoring-structs")]
+    TcpSocketEvents,
+    #[cfg(feature = "monitoring-structs")]
+    TcpHealthEvents,
+    #[cfg(feature = "monitoring-structs")]
+    DnsEvents,
+    #[cfg(feature = "monitoring-structs")]
+    ProcessEvents,
 }
 
 #[cfg(feature = "buffer-reader")]
 impl Consumer {
     /// Read and log [`PacketLog`] events from the perf buffer.
     ///
-    /// Parses IPv4 addresses, ports and L4 protocol from raw eBPF bytes and
-    /// emits human-readable `tracing::info!` lines.
+    /// Parses IPv4 or IPv6 addresses (depending on the event address family),
+    /// ports and L4 protocol from raw eBPF bytes and emits human-readable
+    /// `tracing::info!` lines.
     #[cfg(feature = "network-structs")]
     pub async fn read_packet_log(buffers: &mut [BytesMut], tot_events: i32, offset: i32) {
-        use crate::buffer_type::{IpProtocols, reverse_be_addr};
+        use crate::buffer_type::{icmp_type_code, is_icmp, protocol_name};
 
         for i in offset..tot_events {
             let vec_bytes = &buffers[i as usize];
@@ -86,35 +105,166 @@ impl Consumer {
                 let pl: PacketLog =
                     unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
 
-                let src_ip = reverse_be_addr(pl.src_ip);
-                let dst_ip = reverse_be_addr(pl.dst_ip);
+                let (src_ip, dst_ip) = pl.addresses();
                 let src_port = u16::from_be(pl.src_port);
                 let dst_port = u16::from_be(pl.dst_port);
                 let event_id = pl.pid;
                 let protocol = pl.proto;
 
-                match IpProtocols::try_from(protocol) {
-                    Ok(proto) => {
-                        info!(
-                            "Event Id: {} Protocol: {:?} SRC: {}:{} -> DST: {}:{}",
-                            event_id, proto, src_ip, src_port, dst_ip, dst_port
-                        );
-                    }
-                    Err(e) => {
-                        error!("Unknown protocol. Data maybe corrupted. Reason:{:?}", e);
-                    }
+                if is_icmp(protocol) {
+                    let (icmp_type, icmp_code) = icmp_type_code(pl.dst_port);
+                    info!(
+                        "Event Id: {} Protocol: {} Family: {} Direction: {} Ifindex: {} Vni: {} SRC: {} -> DST: {} Type: {} Code: {}",
+                        event_id,
+                        protocol_name(protocol),
+                        family_name(pl.af),
+                        pl.direction_name(),
+                        pl.ifindex,
+                        pl.vni,
+                        src_ip,
+                        dst_ip,
+                        icmp_type,
+                        icmp_code
+                    );
+                } else {
+                    info!(
+                        "Event Id: {} Protocol: {} Family: {} Direction: {} Ifindex: {} Vni: {} SRC: {}:{} -> DST: {}:{}",
+                        event_id,
+                        protocol_name(protocol),
+                        family_name(pl.af),
+                        pl.direction_name(),
+                        pl.ifindex,
+                        pl.vni,
+                        src_ip,
+                        src_port,
+                        dst_ip,
+                        dst_port
+                    );
                 }
             }
         }
     }
 
+    /// Read and log [`TcpConnEvent`] events from the perf buffer.
+    ///
+    /// Established events print the connection 5-tuple, closed events also
+    /// print the duration, the bytes sent/received and the close reason.
+    #[cfg(feature = "network-structs")]
+    pub async fn read_tcp_conn_event(buffers: &mut [BytesMut], tot_events: i32, offset: i32) {
+        use crate::buffer_type::CONN_EVENT_CLOSED;
+
+        for i in offset..tot_events {
+            let vec_bytes = &buffers[i as usize];
+            if vec_bytes.len() < std::mem::size_of::<TcpConnEvent>() {
+                error!(
+                    "Corrupted TcpConnEvent data. Readed {} bytes expected {} bytes",
+                    vec_bytes.len(),
+                    std::mem::size_of::<TcpConnEvent>()
+                );
+                continue;
+            }
+            let event: TcpConnEvent =
+                unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
+
+            let (src_ip, dst_ip) = event.addresses();
+            if event.event_type == CONN_EVENT_CLOSED {
+                info!(
+                    "[TCP] Connection closed. Reason: {} Family: {} SRC: {}:{} -> DST: {}:{} Duration: {}ms Sent: {} bytes Received: {} bytes",
+                    event.close_reason_name(),
+                    family_name(event.af),
+                    src_ip,
+                    event.src_port,
+                    dst_ip,
+                    event.dst_port,
+                    event.duration_ns / 1_000_000,
+                    event.bytes_sent,
+                    event.bytes_received
+                );
+            } else {
+                info!(
+                    "[TCP] Connection {}. Family: {} SRC: {}:{} -> DST: {}:{}",
+                    event.event_name(),
+                    family_name(event.af),
+                    src_ip,
+                    event.src_port,
+                    dst_ip,
+                    event.dst_port
+                );
+            }
+        }
+    }
+
+    /// Read and log [`ListenerEvent`] events from the perf buffer.
+    ///
+    /// Accept queue and SYN queue overflows are detected in the softirq
+    /// context, so their [`Metadata`] is resolved from the network namespace
+    /// of the listening socket (cached for the whole batch). Memory pressure
+    /// events use the task that triggered the allocation. When a [`Metrics`]
+    /// handle is available the event is counted per port and pod.
+    #[cfg(feature = "network-structs")]
+    pub async fn read_listener_event(
+        buffers: &mut [BytesMut],
+        tot_events: i32,
+        offset: i32,
+        #[cfg(feature = "monitoring-structs")] metrics: Option<Arc<Metrics>>,
+    ) {
+        use crate::buffer_type::LISTENER_EVENT_MEMORY_PRESSURE;
+        use crate::metadata::Metadata;
+        use std::collections::HashMap;
+
+        let mut netns_metadata: HashMap<u32, Metadata> = HashMap::new();
+
+        for i in offset..tot_events {
+            let vec_bytes = &buffers[i as usize];
+            if vec_bytes.len() < std::mem::size_of::<ListenerEvent>() {
+                error!(
+                    "Corrupted ListenerEvent data. Readed {} bytes expected {} bytes",
+                    vec_bytes.len(),
+                    std::mem::size_of::<ListenerEvent>()
+                );
+                continue;
+            }
+            let event: ListenerEvent =
+                unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
+
+            let metadata = if event.kind == LISTENER_EVENT_MEMORY_PRESSURE {
+                Metadata::from_ebpf(Some(event.tgid), &event.comm)
+            } else {
+                netns_metadata
+                    .entry(event.netns)
+                    .or_insert_with(|| {
+                        let mut metadata = Metadata::from_netns(event.netns);
+                        metadata.enrich();
+                        metadata
+                    })
+                    .clone()
+            };
+
+            #[cfg(feature = "monitoring-structs")]
+            if let Some(metrics) = &metrics {
+                metrics.record_listener_event(&event, &metadata);
+            }
+
+            warn!(
+                "[TCP] Listener {}. Family: {} Port: {} Backlog: {}/{} Netns: {} Command: {}",
+                event.kind_name(),
+                family_name(event.af),
+                event.lport,
+                event.backlog,
+                event.max_backlog,
+                event.netns,
+                metadata.command
+            );
+        }
+    }
+
     /// Read and log [`TcpPacketRegistry`] events from the perf buffer.
     ///
     /// Similar to [`read_packet_log`] but additionally prints the command name
     /// and cgroup ID extracted from the eBPF struct.
     #[cfg(feature = "network-structs")]
     pub async fn read_tcp_registry_log(buffers: &mut [BytesMut], tot_events: i32, offset: i32) {
-        use crate::buffer_type::{IpProtocols, reverse_be_addr};
+        use crate::buffer_type::protocol_name;
 
         for i in offset..tot_events {
             let vec_bytes = &buffers[i as usize];
@@ -135,8 +285,7 @@ impl Consumer {
                 let pl: TcpPacketRegistry =
                     unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
 
-                let src = reverse_be_addr(pl.src_ip);
-                let dst = reverse_be_addr(pl.dst_ip);
+                let (src, dst) = pl.addresses();
                 let src_port = u16::from_be(pl.src_port);
                 let dst_port = u16::from_be(pl.dst_port);
                 let event_id = pl.pid;
@@ -149,17 +298,18 @@ impl Consumer {
                 let cgroup_id = pl.cgroup_id;
                 let protocol = pl.proto;
 
-                match IpProtocols::try_from(protocol) {
-                    Ok(proto) => {
-                        info!(
-                            "Event Id: {} Protocol: {:?} SRC: {}:{} -> DST: {}:{} Command: {} Cgroup_id: {}",
-                            event_id, proto, src, src_port, dst, dst_port, command_str, cgroup_id
-                        );
-                    }
-                    Err(e) => {
-                        error!("Unknown protocol. Data maybe corrupted. Reason:{:?}", e);
-                    }
-                }
+                info!(
+                    "Event Id: {} Protocol: {} Family: {} SRC: {}:{} -> DST: {}:{} Command: {} Cgroup_id: {}",
+                    event_id,
+                    protocol_name(protocol),
+                    family_name(pl.af),
+                    src,
+                    src_port,
+                    dst,
+                    dst_port,
+                    command_str,
+                    cgroup_id
+                );
             }
         }
     }
@@ -639,6 +789,246 @@ impl Consumer {
         }
     }
 
+    /// Read [`TcpSocketEvent`] events (retransmissions and resets) and record OpenTelemetry observations.
+    ///
+    /// Most of the events are raised in the softirq context, so the
+    /// [`Metadata`] of the local pod is resolved from the network namespace of
+    /// the socket (cached for the whole batch).
+    #[cfg(feature = "monitoring-structs")]
+    pub async fn read_tcp_socket_events(
+        buffers: &mut [BytesMut],
+        tot_events: i32,
+        offset: i32,
+        exporter: &str,
+        metrics: Arc<Metrics>,
+    ) {
+        use std::collections::HashMap;
+
+        let mut netns_metadata: HashMap<u32, Metadata> = HashMap::new();
+
+        for i in offset..tot_events {
+            let vec_bytes = &buffers[i as usize];
+            if vec_bytes.len() < std::mem::size_of::<TcpSocketEvent>() {
+                error!(
+                    "Corrupted TcpSocketEvent data. Raw data: {}. Readed {} bytes expected {} bytes",
+                    vec_bytes
+                        .iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    vec_bytes.len(),
+                    std::mem::size_of::<TcpSocketEvent>()
+                );
+                continue;
+            }
+            let tcp_event: TcpSocketEvent =
+                unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
+
+            match exporter {
+                "otlp" => {
+                    let metadata = netns_metadata.entry(tcp_event.netns).or_insert_with(|| {
+                        let mut metadata = Metadata::from_netns(tcp_event.netns);
+                        metadata.enrich();
+                        metadata
+                    });
+                    metrics.record_tcp_socket_event(&tcp_event, metadata);
+                }
+                _ => continue,
+            }
+
+            let tgid = tcp_event.tgid;
+            let netns = tcp_event.netns;
+            let comm = String::from_utf8_lossy(&tcp_event.comm);
+            let (src, dst) = tcp_event.addresses();
+            let lport = tcp_event.lport;
+            let dport = tcp_event.dport;
+
+            info!(
+                "TcpSocketEvent - kind: {}, netns: {}, tgid: {}, comm: {}, {}:{} -> {}:{}",
+                tcp_event.kind_name(),
+                netns,
+                tgid,
+                comm,
+                src,
+                lport,
+                dst,
+                dport
+            );
+        }
+    }
+
+    /// Read [`TcpHealthEvent`] samples and record OpenTelemetry observations.
+    ///
+    /// The samples are taken in the softirq context, so the [`Metadata`] of
+    /// the local pod is resolved from the network namespace of the socket
+    /// (cached for the whole batch).
+    #[cfg(feature = "monitoring-structs")]
+    pub async fn read_tcp_health_events(
+        buffers: &mut [BytesMut],
+        tot_events: i32,
+        offset: i32,
+        exporter: &str,
+        metrics: Arc<Metrics>,
+    ) {
+        use std::collections::HashMap;
+
+        let mut netns_metadata: HashMap<u32, Metadata> = HashMap::new();
+
+        for i in offset..tot_events {
+            let vec_bytes = &buffers[i as usize];
+            if vec_bytes.len() < std::mem::size_of::<TcpHealthEvent>() {
+                error!(
+                    "Corrupted TcpHealthEvent data. Readed {} bytes expected {} bytes",
+                    vec_bytes.len(),
+                    std::mem::size_of::<TcpHealthEvent>()
+                );
+                continue;
+            }
+            let health_event: TcpHealthEvent =
+                unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
+
+            let metadata = netns_metadata
+                .entry(health_event.netns)
+                .or_insert_with(|| {
+                    let mut metadata = Metadata::from_netns(health_event.netns);
+                    metadata.enrich();
+                    metadata
+                })
+                .clone();
+
+            match exporter {
+                "otlp" => metrics.record_tcp_health_event(&health_event, &metadata),
+                _ => continue,
+            }
+
+            let (src, dst) = health_event.addresses();
+            let lport = health_event.lport;
+            let dport = health_event.dport;
+            let srtt_us = health_event.srtt_us;
+            let mdev_us = health_event.mdev_us;
+            let snd_cwnd = health_event.snd_cwnd;
+            let total_retrans = health_event.total_retrans;
+
+            info!(
+                "TcpHealthEvent - srtt_us: {}, mdev_us: {}, cwnd: {}, retrans: {}, {}:{} -> {}:{}",
+                srtt_us, mdev_us, snd_cwnd, total_retrans, src, lport, dst, dport
+            );
+        }
+    }
+
+    /// Read [`DnsEvent`] events (DNS queries matched with their response) and record OpenTelemetry observations.
+    #[cfg(feature = "monitoring-structs")]
+    pub async fn read_dns_events(
+        buffers: &mut [BytesMut],
+        tot_events: i32,
+        offset: i32,
+        exporter: &str,
+        metrics: Arc<Metrics>,
+    ) {
+        for i in offset..tot_events {
+            let vec_bytes = &buffers[i as usize];
+            if vec_bytes.len() < std::mem::size_of::<DnsEvent>() {
+                error!(
+                    "Corrupted DnsEvent data. Readed {} bytes expected {} bytes",
+                    vec_bytes.len(),
+                    std::mem::size_of::<DnsEvent>()
+                );
+                continue;
+            }
+            let dns_event: DnsEvent =
+                unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
+
+            match exporter {
+                "otlp" => {
+                    let mut metadata = Metadata::from_ebpf(Some(dns_event.tgid), &dns_event.comm);
+                    metadata.enrich();
+                    metrics.record_dns_event(&dns_event, &metadata);
+                }
+                _ => continue,
+            }
+
+            let tgid = dns_event.tgid;
+            let comm = String::from_utf8_lossy(&dns_event.comm);
+            let (src, dst) = dns_event.addresses();
+            let sport = dns_event.sport;
+            let dport = dns_event.dport;
+            let latency_us = dns_event.latency_ns / 1_000;
+
+            info!(
+                "DnsEvent - name: {}, qtype: {}, rcode: {}, latency_us: {}, tgid: {}, comm: {}, {}:{} -> {}:{}",
+                dns_event.qname(),
+                dns_event.qtype_name(),
+                dns_event.rcode_name(),
+                latency_us,
+                tgid,
+                comm,
+                src,
+                sport,
+                dst,
+                dport
+            );
+        }
+    }
+
+    /// Read [`ProcessEvent`] events, update the process table and record OpenTelemetry observations.
+    ///
+    /// The process table is updated for every exporter, so the other consumers
+    /// can resolve the container of the processes that already exited.
+    #[cfg(feature = "monitoring-structs")]
+    pub async fn read_process_events(
+        buffers: &mut [BytesMut],
+        tot_events: i32,
+        offset: i32,
+        exporter: &str,
+        metrics: Arc<Metrics>,
+    ) {
+        for i in offset..tot_events {
+            let vec_bytes = &buffers[i as usize];
+            if vec_bytes.len() < std::mem::size_of::<ProcessEvent>() {
+                error!(
+                    "Corrupted ProcessEvent data. Readed {} bytes expected {} bytes",
+                    vec_bytes.len(),
+                    std::mem::size_of::<ProcessEvent>()
+                );
+                continue;
+            }
+            let process_event: ProcessEvent =
+                unsafe { std::ptr::read_unaligned(vec_bytes.as_ptr() as *const _) };
+
+            // forks and thread exits only update the table
+            let Some(process) = process_table().apply(&process_event) else {
+                continue;
+            };
+
+            match exporter {
+                "otlp" => {
+                    let mut metadata =
+                        Metadata::from_ebpf(Some(process_event.tgid), &process_event.comm);
+                    metadata.enrich();
+                    metrics.record_process_event(&process_event, &process, &metadata);
+                }
+                _ => continue,
+            }
+
+            let (status, signal) = process_event.exit_status();
+            match process_event.kind {
+                PROCESS_EVENT_EXEC => info!(
+                    "ProcessEvent - exec, pid: {}, ppid: {}, comm: {}, exe: {}, cgroup_id: {}",
+                    process.pid,
+                    process.ppid,
+                    process.comm,
+                    process.exe.as_deref().unwrap_or("-"),
+                    process.cgroup_id
+                ),
+                PROCESS_EVENT_EXIT => info!(
+                    "ProcessEvent - exit, pid: {}, ppid: {}, comm: {}, exit_code: {}, signal: {}",
+                    process.pid, process.ppid, process.comm, status, signal
+                ),
+                _ => {}
+            }
+        }
+    }
+
     pub async fn read_ssl_events(
         buffers: &mut [BytesMut],
         tot_events: i32,
@@ -710,155 +1100,232 @@ impl Consumer {
             }
         }
     }
+
+    /// Route `tot_events` raw events to the `read_*` method of this consumer.
+    pub async fn dispatch(
+        &self,
+        buffers: &mut [BytesMut],
+        tot_events: i32,
+        #[cfg(feature = "monitoring-structs")] metrics: &Option<Arc<Metrics>>,
+    ) {
+        let offset = 0;
+
+        match self {
+            #[cfg(feature = "network-structs")]
+            Consumer::PacketLog => Consumer::read_packet_log(buffers, tot_events, offset).await,
+            #[cfg(feature = "network-structs")]
+            Consumer::TcpPacketRegistry => {
+                Consumer::read_tcp_registry_log(buffers, tot_events, offset).await
+            }
+            #[cfg(feature = "network-structs")]
+            Consumer::VethLog => {
+                Consumer::read_and_handle_veth_log(buffers, tot_events, offset).await
+            }
+            #[cfg(feature = "network-structs")]
+            Consumer::TcpConnEvent => {
+                Consumer::read_tcp_conn_event(buffers, tot_events, offset).await
+            }
+            #[cfg(feature = "network-structs")]
+            Consumer::ListenerEvent => {
+                Consumer::read_listener_event(
+                    buffers,
+                    tot_events,
+                    offset,
+                    #[cfg(feature = "monitoring-structs")]
+                    metrics.clone(),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::PacketLossMetrics => {
+                Consumer::read_packet_loss_metrics(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics
+                        .clone()
+                        .expect("Metrics required for PacketLossMetrics"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::TimeStampMetrics => {
+                Consumer::read_timestamp_metrics(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics
+                        .clone()
+                        .expect("Metric required for TimeStampMetrics"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::CpuFrequency => {
+                Consumer::read_cpu_frequency(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for CpuFrequency"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::MemAlloc => {
+                Consumer::read_mem_alloc(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for MemAlloc"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::SchedStatWait => {
+                Consumer::read_sched_stat_wait(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for SchedStatWait"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::SchedStatRuntime => {
+                Consumer::read_sched_stat_runtime(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics
+                        .clone()
+                        .expect("Metric required for SchedStatRuntime"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::CpuIdle => {
+                Consumer::read_cpu_idle(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for CpuIdle"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::SslEvents => {
+                Consumer::read_ssl_events(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for SslEvents"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::TcpSocketEvents => {
+                Consumer::read_tcp_socket_events(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics
+                        .clone()
+                        .expect("Metric required for TcpSocketEvents"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::TcpHealthEvents => {
+                Consumer::read_tcp_health_events(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics
+                        .clone()
+                        .expect("Metric required for TcpHealthEvents"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::DnsEvents => {
+                Consumer::read_dns_events(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for DnsEvents"),
+                )
+                .await
+            }
+            #[cfg(feature = "monitoring-structs")]
+            Consumer::ProcessEvents => {
+                Consumer::read_process_events(
+                    buffers,
+                    tot_events,
+                    offset,
+                    "otlp",
+                    metrics.clone().expect("Metric required for ProcessEvents"),
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Human readable name of an address family code.
+#[cfg(feature = "network-structs")]
+fn family_name(af: u16) -> &'static str {
+    use crate::buffer_type::{AF_INET, AF_INET6};
+
+    match af {
+        AF_INET => "IPv4",
+        AF_INET6 => "IPv6",
+        _ => "Unknown",
+    }
 }
 
-/// Read perf-buffer events in a loop and dispatch to the appropriate [`Consumer`] handler.
+/// Read events from an [`EventSource`] and dispatch them to the appropriate [`Consumer`] handler.
 ///
 /// This function runs indefinitely (or until the process receives `SIGINT`).
-/// It polls every CPU buffer every 100 ms, reads available events, and routes
-/// them to the matching `Consumer::read_*` method.
+/// The ring buffer or the per-CPU perf buffers are read by the epoll-driven
+/// tasks started by [`spawn_event_reader`]; this function only waits for the
+/// batches and routes them to the matching `Consumer::read_*` method.
 ///
 /// # Arguments
-/// - `array_buffers` — per-CPU `PerfEventArrayBuffer` handles opened by [`fill_buffers`].
+/// - `source` — ring buffer or per-CPU perf buffers created by [`EventSource::from_map`].
 /// - `buffers` — pre-allocated `BytesMut` scratch space sized by [`BufferSize::set_buffer`].
 /// - `consumer` — discriminator that selects which `read_*` method to invoke.
 /// - `metrics` — optional [`Metrics`] handle; required when `consumer` is a monitoring variant.
 #[cfg(feature = "buffer-reader")]
-pub async fn read_perf_buffer<T: std::borrow::BorrowMut<aya::maps::MapData>>(
-    mut array_buffers: Vec<aya::maps::perf::PerfEventArrayBuffer<T>>,
-    mut buffers: Vec<bytes::BytesMut>,
+pub async fn read_events(
+    source: EventSource,
+    buffers: Vec<BytesMut>,
     consumer: Consumer,
     #[cfg(feature = "monitoring-structs")] metrics: Option<Arc<Metrics>>,
 ) {
-    loop {
-        for buf in array_buffers.iter_mut() {
-            match buf.read_events(&mut buffers) {
-                Ok(events) => {
-                    if events.lost > 0 {
-                        tracing::debug!("Lost events: {} ", events.lost);
-                    }
-                    if events.read > 0 {
-                        tracing::debug!("Readed events: {}", events.read);
-                        let offset = 0;
-                        let tot_events = events.read as i32;
-
-                        match consumer {
-                            #[cfg(feature = "network-structs")]
-                            Consumer::PacketLog => {
-                                Consumer::read_packet_log(&mut buffers, tot_events, offset).await
-                            }
-                            #[cfg(feature = "network-structs")]
-                            Consumer::TcpPacketRegistry => {
-                                Consumer::read_tcp_registry_log(&mut buffers, tot_events, offset)
-                                    .await
-                            }
-                            #[cfg(feature = "network-structs")]
-                            Consumer::VethLog => {
-                                Consumer::read_and_handle_veth_log(&mut buffers, tot_events, offset)
-                                    .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::PacketLossMetrics => {
-                                Consumer::read_packet_loss_metrics(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics
-                                        .clone()
-                                        .expect("Metrics required for PacketLossMetrics"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::TimeStampMetrics => {
-                                Consumer::read_timestamp_metrics(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics
-                                        .clone()
-                                        .expect("Metric required for TimeStampMetrics"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::CpuFrequency => {
-                                Consumer::read_cpu_frequency(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics.clone().expect("Metric required for CpuFrequency"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::MemAlloc => {
-                                Consumer::read_mem_alloc(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics.clone().expect("Metric required for MemAlloc"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::SchedStatWait => {
-                                Consumer::read_sched_stat_wait(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics.clone().expect("Metric required for SchedStatWait"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::SchedStatRuntime => {
-                                Consumer::read_sched_stat_runtime(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics
-                                        .clone()
-                                        .expect("Metric required for SchedStatRuntime"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::CpuIdle => {
-                                Consumer::read_cpu_idle(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics.clone().expect("Metric required for CpuIdle"),
-                                )
-                                .await
-                            }
-                            #[cfg(feature = "monitoring-structs")]
-                            Consumer::SslEvents => {
-                                Consumer::read_ssl_events(
-                                    &mut buffers,
-                                    tot_events,
-                                    offset,
-                                    "otlp",
-                                    metrics.clone().expect("Metric required for SslEvents"),
-                                )
-                                .await
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Cannot read events from buffer. Reason: {} ", e);
-                }
-            }
-        }
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    info!("Reading events from a {}", source.kind());
+    let mut rx = spawn_event_reader(source, buffers);
+
+    while let Some(mut batch) = rx.recv().await {
+        let tot_events = batch.len() as i32;
+        consumer
+            .dispatch(
+                &mut batch,
+                tot_events,
+                #[cfg(feature = "monitoring-structs")]
+                &metrics,
+            )
+            .await;
     }
+    error!("All the event readers stopped");
 }
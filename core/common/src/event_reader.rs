@@ -0,0 +1,230 @@
+//! Epoll-driven readers for eBPF event maps.
+//!
+//! The eBPF programs send their events to userspace through a
+//! `BPF_MAP_TYPE_RINGBUF` map on kernels that support it (5.8+) and through a
+//! per-CPU `BPF_MAP_TYPE_PERF_EVENT_ARRAY` otherwise (eBPF crates built
+//! without the `ringbuf` feature). Both kinds of maps are pinned under the
+//! same name, so the reader only looks at the map type to decide how to read it.
+//!
+//! - [`EventSource`] wraps either a [`RingBuf`] or the per-CPU
+//!   [`PerfEventArrayBuffer`]s of a perf event array.
+//! - [`spawn_event_reader`] registers every file descriptor of an
+//!   [`EventSource`] in the tokio reactor (epoll) and forwards the raw events
+//!   in batches through an mpsc channel. Nothing is read until the kernel
+//!   signals that data is available.
+
+use anyhow::{Error, anyhow};
+use aya::maps::perf::PerfEventArrayBuffer;
+use aya::maps::{Map, MapData, MapType, PerfEventArray, RingBuf};
+use aya::util::online_cpus;
+use bytes::BytesMut;
+use std::os::fd::AsRawFd;
+use tokio::io::Interest;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+
+/// Number of batches that can be queued before the readers wait for the consumer.
+const EVENT_CHANNEL_SIZE: usize = 64;
+
+/// An eBPF map used to send events to userspace.
+pub enum EventSource {
+    /// A single ring buffer shared by every CPU.
+    RingBuf(RingBuf<MapData>),
+    /// One perf buffer per online CPU.
+    PerfEventArray(Vec<PerfEventArrayBuffer<MapData>>),
+}
+
+impl EventSource {
+    /// Build an [`EventSource`] from a map taken from the eBPF object.
+    ///
+    /// Perf event arrays are opened on every online CPU. Returns an error if
+    /// the map is neither a ring buffer nor a perf event array.
+    pub fn from_map(map: Map) -> Result<Self, Error> {
+        match map {
+            Map::RingBuf(_) => {
+                let ring_buf = RingBuf::try_from(map).map_err(|e| {
+                    anyhow!("Cannot initialize ring buffer from map. Reason: {}", e)
+                })?;
+                Ok(EventSource::RingBuf(ring_buf))
+            }
+            Map::PerfEventArray(_) => {
+                let mut perf_event_array = PerfEventArray::try_from(map).map_err(|e| {
+                    anyhow!("Cannot initialize perf_event_array from map. Reason: {}", e)
+                })?;
+                let mut buffers = Vec::new();
+                for cpu_id in online_cpus().map_err(|e| anyhow!("Error {:?}", e))? {
+                    let buf = perf_event_array.open(cpu_id, None).map_err(|e| {
+                        anyhow!(
+                            "Cannot create perf_event_array buffer on cpu {}. Reason: {}",
+                            cpu_id,
+                            e
+                        )
+                    })?;
+                    buffers.push(buf);
+                }
+                Ok(EventSource::PerfEventArray(buffers))
+            }
+            other => Err(anyhow!(
+                "Map {:?} is not a RingBuf or a PerfEventArray",
+                other
+            )),
+        }
+    }
+
+    /// Load a pinned event map from the bpf filesystem.
+    pub fn from_pin(path: &str) -> Result<Self, Error> {
+        let map_data = MapData::from_pin(path)
+            .map_err(|e| anyhow!("Cannot load mapdata from pin {:?} .Reason: {}", path, e))?;
+        let map_type = map_data
+            .info()
+            .and_then(|info| info.map_type())
+            .map_err(|e| anyhow!("Cannot read the type of map {:?}. Reason: {}", path, e))?;
+
+        let map = match map_type {
+            MapType::RingBuf => Map::RingBuf(map_data),
+            MapType::PerfEventArray => Map::PerfEventArray(map_data),
+            other => {
+                return Err(anyhow!(
+                    "Map {:?} has type {:?}. Expected a RingBuf or a PerfEventArray",
+                    path,
+                    other
+                ));
+            }
+        };
+        Self::from_map(map)
+    }
+
+    /// Human readable name of the map type.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EventSource::RingBuf(_) => "RingBuf",
+            EventSource::PerfEventArray(_) => "PerfEventArray",
+        }
+    }
+}
+
+/// Spawn the reader tasks of an [`EventSource`] and return the receiving end of the events channel.
+///
+/// Every file descriptor (the ring buffer or one perf buffer per CPU) is
+/// wrapped in an [`AsyncFd`], so the tasks sleep until epoll reports it as
+/// readable and then drain it. Each message is a batch of at most
+/// `buffers.len()` raw events. The reader tasks stop when the receiver is dropped.
+pub fn spawn_event_reader(
+    source: EventSource,
+    buffers: Vec<BytesMut>,
+) -> mpsc::Receiver<Vec<BytesMut>> {
+    let (tx, rx) = mpsc::channel(EVENT_CHANNEL_SIZE);
+
+    match source {
+        EventSource::RingBuf(ring_buf) => {
+            tokio::spawn(async move {
+                if let Err(e) = read_ring_buf(ring_buf, buffers.len(), tx).await {
+                    error!("Ring buffer reader stopped. Reason: {}", e);
+                }
+            });
+        }
+        EventSource::PerfEventArray(perf_buffers) => {
+            for perf_buffer in perf_buffers {
+                let tx = tx.clone();
+                let buffers = buffers.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = read_perf_event_buffer(perf_buffer, buffers, tx).await {
+                        error!("Perf buffer reader stopped. Reason: {}", e);
+                    }
+                });
+            }
+        }
+    }
+
+    rx
+}
+
+// docs:
+//
+// registers the file descriptor of a ring buffer or a perf buffer in the tokio reactor (read interest only)
+//
+fn register_readable<T: AsRawFd>(inner: T) -> Result<AsyncFd<T>, Error> {
+    // SAFETY: the file descriptor is owned by the aya map (or perf buffer) moved into the AsyncFd,
+    // so it stays open and refers to the same map until the AsyncFd is dropped
+    let async_fd = unsafe { AsyncFd::register_with_interest(inner, Interest::READABLE) }
+        .map_err(std::io::Error::from)?;
+    Ok(async_fd)
+}
+
+// docs:
+//
+// drains the ring buffer every time epoll reports it as readable. The events are copied out of the ring
+// (the ring slot is released as soon as the item is dropped) and sent in batches of batch_size events
+//
+async fn read_ring_buf(
+    ring_buf: RingBuf<MapData>,
+    batch_size: usize,
+    tx: mpsc::Sender<Vec<BytesMut>>,
+) -> Result<(), Error> {
+    let batch_size = batch_size.max(1);
+    let mut async_fd = register_readable(ring_buf)?;
+    info!("Ring buffer reader started");
+
+    loop {
+        let mut guard = async_fd.readable_mut().await?;
+        let ring_buf = guard.get_inner_mut();
+
+        let mut batch = Vec::with_capacity(batch_size);
+        while let Some(item) = ring_buf.next() {
+            batch.push(BytesMut::from(&item[..]));
+            // release the ring slot before waiting for the consumer
+            drop(item);
+            if batch.len() == batch_size {
+                let full_batch = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                if tx.send(full_batch).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        // the ring is empty. Wait for the next epoll notification
+        guard.clear_ready();
+
+        if !batch.is_empty() {
+            debug!("Readed events: {}", batch.len());
+            if tx.send(batch).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// docs:
+//
+// reads a single per cpu perf buffer every time epoll reports it as readable. read_events fills at most
+// buffers.len() events, so the readiness is cleared only when the buffer has been fully drained
+//
+async fn read_perf_event_buffer(
+    perf_buffer: PerfEventArrayBuffer<MapData>,
+    mut buffers: Vec<BytesMut>,
+    tx: mpsc::Sender<Vec<BytesMut>>,
+) -> Result<(), Error> {
+    let mut async_fd = register_readable(perf_buffer)?;
+
+    loop {
+        let mut guard = async_fd.readable_mut().await?;
+        let events = guard.get_inner_mut().read_events(&mut buffers)?;
+
+        if events.lost > 0 {
+            debug!("Lost events: {} ", events.lost);
+        }
+        if events.read < buffers.len() {
+            guard.clear_ready();
+        }
+        if events.read > 0 {
+            debug!("Readed events: {}", events.read);
+            let batch: Vec<BytesMut> = buffers[..events.read]
+                .iter_mut()
+                .map(|buf| buf.split())
+                .collect();
+            if tx.send(batch).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! L4 aware filtering rules shared with the `identity_classifier` eBPF program.
+//!
+//! This module contains:
+//! - [`FilterRule`], the C-compatible rule layout stored in the pinned `FilterRules` array map.
+//! - Helpers to build rules from CIDR strings, protocol names and port ranges.
+//! - [`FilterRulesMap`], a wrapper around the pinned map used by the agent api.
+//!
+//! Rules are expressed as (src CIDR, dst CIDR, protocol, dst port range, action) and are
+//! evaluated in slot order by the classifier. The first matching rule wins.
+
+use crate::constants::{AF_INET, AF_INET6};
+use crate::map_handlers::{format_cidr, parse_cidr};
+use anyhow::{Error, anyhow};
+use aya::maps::{Array, Map, MapData};
+use bytemuck_derive::Zeroable;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Pass the packet and stop the rules evaluation.
+pub const RULE_ACTION_ALLOW: u8 = 0;
+/// Drop the packet (`TC_ACT_SHOT`).
+pub const RULE_ACTION_DROP: u8 = 1;
+/// Pass the packet and emit an audit ("would-have-dropped") event.
+pub const RULE_ACTION_AUDIT: u8 = 2;
+/// Number of slots in the `FilterRules` map. Must match the eBPF side.
+pub const MAX_FILTER_RULES: u32 = 64;
+
+/// Filtering rule layout shared with the conntracker `FilterRule` struct.
+///
+/// Addresses and masks are stored as four host-order words (IPv4 rules use
+/// only the first word). A zero mask matches every address and a zero `af`
+/// or `proto` matches every family/protocol.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Zeroable)]
+pub struct FilterRule {
+    pub rule_id: u32,
+    pub enabled: u8,
+    pub action: u8,
+    pub proto: u8,
+    pub _padding: u8,
+    pub af: u16,
+    pub dst_port_start: u16,
+    pub dst_port_end: u16,
+    pub _padding2: u16,
+    pub src_net: [u32; 4],
+    pub src_mask: [u32; 4],
+    pub dst_net: [u32; 4],
+    pub dst_mask: [u32; 4],
+}
+unsafe impl aya::Pod for FilterRule {}
+
+impl FilterRule {
+    /// Build an enabled rule from its user facing representation.
+    ///
+    /// `src` and `dst` accept single hosts or CIDR ranges; `None` (or an empty
+    /// string) matches any address. Both addresses must belong to the same
+    /// family when present.
+    pub fn new(
+        rule_id: u32,
+        src: Option<&str>,
+        dst: Option<&str>,
+        proto: u8,
+        dst_port_start: u16,
+        dst_port_end: u16,
+        action: u8,
+    ) -> Result<Self, Error> {
+        if dst_port_start > dst_port_end {
+            return Err(anyhow!(
+                "Invalid port range {}-{}",
+                dst_port_start,
+                dst_port_end
+            ));
+        }
+        if action > RULE_ACTION_AUDIT {
+            return Err(anyhow!("Invalid rule action {}", action));
+        }
+
+        let src = parse_rule_cidr(src)?;
+        let dst = parse_rule_cidr(dst)?;
+
+        let af = match (&src, &dst) {
+            (Some((src_af, _, _)), Some((dst_af, _, _))) if src_af != dst_af => {
+                return Err(anyhow!(
+                    "Source and destination addresses must belong to the same family"
+                ));
+            }
+            (Some((af, _, _)), _) | (None, Some((af, _, _))) => *af,
+            (None, None) => 0,
+        };
+
+        let (src_net, src_mask) = src.map(|(_, net, mask)| (net, mask)).unwrap_or_default();
+        let (dst_net, dst_mask) = dst.map(|(_, net, mask)| (net, mask)).unwrap_or_default();
+
+        Ok(FilterRule {
+            rule_id,
+            enabled: 1,
+            action,
+            proto,
+            _padding: 0,
+            af,
+            dst_port_start,
+            dst_port_end,
+            _padding2: 0,
+            src_net,
+            src_mask,
+            dst_net,
+            dst_mask,
+        })
+    }
+
+    /// Source CIDR of the rule (`"any"` for a zero mask).
+    pub fn src_cidr(&self) -> String {
+        words_to_cidr(self.af, &self.src_net, &self.src_mask)
+    }
+
+    /// Destination CIDR of the rule (`"any"` for a zero mask).
+    pub fn dst_cidr(&self) -> String {
+        words_to_cidr(self.af, &self.dst_net, &self.dst_mask)
+    }
+}
+
+/// Parse a protocol name or number. Empty strings and `"any"` return `0`.
+pub fn parse_protocol(input: &str) -> Result<u8, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "any" => Ok(0),
+        "icmp" => Ok(1),
+        "tcp" => Ok(6),
+        "udp" => Ok(17),
+        "icmpv6" => Ok(58),
+        other => other
+            .parse::<u8>()
+            .map_err(|_| anyhow!("Unknown protocol {:?}", input)),
+    }
+}
+
+/// Format a protocol number as returned by [`parse_protocol`].
+pub fn format_protocol(proto: u8) -> String {
+    match proto {
+        0 => "any".to_string(),
+        1 => "icmp".to_string(),
+        6 => "tcp".to_string(),
+        17 => "udp".to_string(),
+        58 => "icmpv6".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse a rule action (`"drop"`, `"allow"` or `"audit"`).
+pub fn parse_action(input: &str) -> Result<u8, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "drop" | "deny" | "block" => Ok(RULE_ACTION_DROP),
+        "allow" | "pass" => Ok(RULE_ACTION_ALLOW),
+        "audit" | "dry-run" => Ok(RULE_ACTION_AUDIT),
+        _ => Err(anyhow!("Unknown rule action {:?}", input)),
+    }
+}
+
+/// Format a rule action as returned by [`parse_action`].
+pub fn format_action(action: u8) -> String {
+    match action {
+        RULE_ACTION_DROP => "drop".to_string(),
+        RULE_ACTION_ALLOW => "allow".to_string(),
+        RULE_ACTION_AUDIT => "audit".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// (address family, network words, mask words)
+type RuleCidr = (u16, [u32; 4], [u32; 4]);
+
+// converts an optional CIDR string into a RuleCidr
+fn parse_rule_cidr(input: Option<&str>) -> Result<Option<RuleCidr>, Error> {
+    let input = match input.map(str::trim) {
+        None | Some("") | Some("any") => return Ok(None),
+        Some(input) => input,
+    };
+    match parse_cidr(input)? {
+        (IpAddr::V4(ip), prefix_len) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+            Ok(Some((AF_INET, [u32::from(ip), 0, 0, 0], [mask, 0, 0, 0])))
+        }
+        (IpAddr::V6(ip), prefix_len) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+            Ok(Some((
+                AF_INET6,
+                u128_to_words(u128::from(ip)),
+                u128_to_words(mask),
+            )))
+        }
+    }
+}
+
+fn u128_to_words(value: u128) -> [u32; 4] {
+    [
+        (value >> 96) as u32,
+        (value >> 64) as u32,
+        (value >> 32) as u32,
+        value as u32,
+    ]
+}
+
+fn words_to_cidr(af: u16, net: &[u32; 4], mask: &[u32; 4]) -> String {
+    match af {
+        AF_INET if mask[0] != 0 => {
+            format_cidr(IpAddr::V4(Ipv4Addr::from(net[0])), mask[0].count_ones())
+        }
+        AF_INET6 if mask.iter().any(|w| *w != 0) => {
+            let net = ((net[0] as u128) << 96)
+                | ((net[1] as u128) << 64)
+                | ((net[2] as u128) << 32)
+                | net[3] as u128;
+            let prefix_len = mask.iter().map(|w| w.count_ones()).sum();
+            format_cidr(IpAddr::V6(Ipv6Addr::from(net)), prefix_len)
+        }
+        _ => "any".to_string(),
+    }
+}
+
+/// Wrapper around the pinned `FilterRules` array map.
+pub struct FilterRulesMap {
+    pub rules: Array<MapData, FilterRule>,
+}
+
+impl FilterRulesMap {
+    /// Load the `FilterRules` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/FilterRules")
+            .map_err(|e| anyhow!("Failed to load FilterRules map: {}", e))?;
+        let rules = Array::<_, FilterRule>::try_from(Map::Array(mapdata))?;
+        Ok(FilterRulesMap { rules })
+    }
+
+    /// Store a rule in the first free slot and return the assigned rule id.
+    ///
+    /// The `rule_id` of `rule` is ignored: ids are assigned incrementally.
+    pub fn add(&mut self, mut rule: FilterRule) -> Result<u32, Error> {
+        let mut free_slot = None;
+        let mut next_id = 1;
+        for slot in 0..MAX_FILTER_RULES {
+            let current = self.rules.get(&slot, 0)?;
+            if current.enabled == 0 {
+                if free_slot.is_none() {
+                    free_slot = Some(slot);
+                }
+            } else if current.rule_id >= next_id {
+                next_id = current.rule_id + 1;
+            }
+        }
+        let slot = free_slot.ok_or_else(|| {
+            anyhow!(
+                "Cannot add rule. Reached the maximum number of rules ({})",
+                MAX_FILTER_RULES
+            )
+        })?;
+        rule.rule_id = next_id;
+        rule.enabled = 1;
+        self.rules.set(slot, rule, 0)?;
+        Ok(next_id)
+    }
+
+    /// Remove the rule with the given id.
+    pub fn remove(&mut self, rule_id: u32) -> Result<(), Error> {
+        for slot in 0..MAX_FILTER_RULES {
+            let current = self.rules.get(&slot, 0)?;
+            if current.enabled != 0 && current.rule_id == rule_id {
+                self.rules
+                    .set(slot, <FilterRule as bytemuck::Zeroable>::zeroed(), 0)?;
+                return Ok(());
+            }
+        }
+        Err(anyhow!("Rule {} not found", rule_id))
+    }
+
+    /// Return the active rules in evaluation order.
+    pub fn list(&self) -> Result<Vec<FilterRule>, Error> {
+        let mut rules = Vec::new();
+        for slot in 0..MAX_FILTER_RULES {
+            let current = self.rules.get(&slot, 0)?;
+            if current.enabled != 0 {
+                rules.push(current);
+            }
+        }
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_rule_roundtrip() {
+        let rule = FilterRule::new(
+            1,
+            Some("10.1.0.0/16"),
+            None,
+            parse_protocol("tcp").unwrap(),
+            6379,
+            6379,
+            RULE_ACTION_DROP,
+        )
+        .unwrap();
+        assert_eq!(rule.af, AF_INET);
+        assert_eq!(rule.src_cidr(), "10.1.0.0/16");
+        assert_eq!(rule.dst_cidr(), "any");
+        assert_eq!(format_protocol(rule.proto), "tcp");
+        assert_eq!(format_action(rule.action), "drop");
+
+        let rule = FilterRule::new(
+            2,
+            None,
+            Some("fd00::/64"),
+            0,
+            0,
+            u16::MAX,
+            RULE_ACTION_ALLOW,
+        )
+        .unwrap();
+        assert_eq!(rule.af, AF_INET6);
+        assert_eq!(rule.dst_cidr(), "fd00::/64");
+    }
+
+    #[test]
+    fn test_filter_rule_invalid() {
+        assert!(FilterRule::new(1, Some("10.0.0.0/8"), Some("fd00::/8"), 0, 0, 0, 1).is_err());
+        assert!(FilterRule::new(1, None, None, 6, 100, 10, RULE_ACTION_DROP).is_err());
+        assert!(parse_protocol("foo").is_err());
+        assert!(parse_action("reject").is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! Per-flow counters aggregated by the `identity_classifier` eBPF program.
+//!
+//! The classifier keeps one [`FlowStats`] entry per cpu for every [`FlowKey`]
+//! in the pinned `flow_table` LRU map. This module contains:
+//! - The C-compatible key and value layouts shared with the conntracker crate.
+//! - [`FlowTable`], a wrapper around the pinned map that merges the per-cpu values.
+//! - [`FlowTracker`], which diffs two consecutive scrapes so the counters can
+//!   be exported as monotonic increments.
+
+use crate::constants::{DIRECTION_EGRESS, DIRECTION_INGRESS};
+use crate::formatters::{format_ip_port, format_ip_words};
+use anyhow::{Error, anyhow};
+use aya::maps::{Map, MapData, PerCpuHashMap};
+use bytemuck_derive::Zeroable;
+use std::collections::HashMap;
+
+/// Flow key layout shared with the conntracker `FlowKey` struct.
+///
+/// Addresses are stored as four host-order words (IPv4 flows use only the
+/// first word).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Zeroable)]
+pub struct FlowKey {
+    pub af: u16,
+    pub proto: u8,
+    pub direction: u8,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+}
+unsafe impl aya::Pod for FlowKey {}
+
+impl FlowKey {
+    /// Source `ip:port` formatted according to `af`.
+    pub fn src(&self) -> String {
+        format_ip_port(
+            self.af,
+            &format_ip_words(self.af, &self.src_ip),
+            self.src_port,
+        )
+    }
+
+    /// Destination `ip:port` formatted according to `af`.
+    pub fn dst(&self) -> String {
+        format_ip_port(
+            self.af,
+            &format_ip_words(self.af, &self.dst_ip),
+            self.dst_port,
+        )
+    }
+
+    /// Human readable traffic direction.
+    pub fn direction_name(&self) -> &'static str {
+        match self.direction {
+            DIRECTION_INGRESS => "ingress",
+            DIRECTION_EGRESS => "egress",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Per-cpu flow counters layout shared with the conntracker `FlowStats` struct.
+///
+/// Timestamps are `bpf_ktime_get_ns()` values (monotonic clock, nanoseconds since boot).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Zeroable)]
+pub struct FlowStats {
+    pub packets: u64,
+    pub bytes: u64,
+    pub first_seen_ns: u64,
+    pub last_seen_ns: u64,
+    pub ifindex: u32,
+    pub _padding: u32,
+}
+unsafe impl aya::Pod for FlowStats {}
+
+impl FlowStats {
+    /// Merge the values of another cpu into `self`.
+    pub fn merge(&mut self, other: &FlowStats) {
+        if other.packets == 0 {
+            return;
+        }
+        if self.packets == 0 || other.first_seen_ns < self.first_seen_ns {
+            self.first_seen_ns = other.first_seen_ns;
+        }
+        if other.last_seen_ns >= self.last_seen_ns {
+            self.last_seen_ns = other.last_seen_ns;
+            self.ifindex = other.ifindex;
+        }
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Packets and bytes observed for a flow since the previous scrape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlowDelta {
+    pub key: FlowKey,
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+/// Wrapper around the pinned `flow_table` per-cpu LRU hash map.
+pub struct FlowTable {
+    pub flows: PerCpuHashMap<MapData, FlowKey, FlowStats>,
+}
+
+impl FlowTable {
+    /// Load the `flow_table` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/flow_table")
+            .map_err(|e| anyhow!("Failed to load flow_table map: {}", e))?;
+        let flows =
+            PerCpuHashMap::<_, FlowKey, FlowStats>::try_from(Map::PerCpuLruHashMap(mapdata))?;
+        Ok(FlowTable { flows })
+    }
+
+    /// Return every flow in the map with the per-cpu values merged.
+    pub fn snapshot(&self) -> Result<HashMap<FlowKey, FlowStats>, Error> {
+        let mut snapshot = HashMap::new();
+        for item in self.flows.iter() {
+            let (key, values) = item?;
+            let mut stats = FlowStats::default();
+            for value in values.iter() {
+                stats.merge(value);
+            }
+            snapshot.insert(key, stats);
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Diffs consecutive [`FlowTable::snapshot`] results.
+///
+/// Flows evicted from the LRU map are forgotten. A flow that is evicted and
+/// seen again starts from zero, so its first delta is its full count.
+#[derive(Default)]
+pub struct FlowTracker {
+    previous: HashMap<FlowKey, FlowStats>,
+}
+
+impl FlowTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the increments since the previous call and remember `snapshot`.
+    ///
+    /// Flows without new packets are not returned.
+    pub fn diff(&mut self, snapshot: HashMap<FlowKey, FlowStats>) -> Vec<FlowDelta> {
+        let mut deltas = Vec::new();
+        for (key, stats) in snapshot.iter() {
+            let (packets, bytes) = match self.previous.get(key) {
+                // the counters went backwards: the entry was evicted and created again
+                Some(prev) if stats.packets >= prev.packets && stats.bytes >= prev.bytes => {
+                    (stats.packets - prev.packets, stats.bytes - prev.bytes)
+                }
+                _ => (stats.packets, stats.bytes),
+            };
+            if packets > 0 {
+                deltas.push(FlowDelta {
+                    key: *key,
+                    packets,
+                    bytes,
+                });
+            }
+        }
+        self.previous = snapshot;
+        deltas
+    }
+
+    /// Number of flows seen in the last snapshot.
+    pub fn active_flows(&self) -> usize {
+        self.previous.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::AF_INET;
+
+    fn key(src_port: u16) -> FlowKey {
+        FlowKey {
+            af: AF_INET,
+            proto: 6,
+            direction: DIRECTION_INGRESS,
+            src_port,
+            dst_port: 80,
+            src_ip: [0x0a000001, 0, 0, 0],
+            dst_ip: [0x0a000002, 0, 0, 0],
+        }
+    }
+
+    fn stats(packets: u64, bytes: u64, first_seen_ns: u64, last_seen_ns: u64) -> FlowStats {
+        FlowStats {
+            packets,
+            bytes,
+            first_seen_ns,
+            last_seen_ns,
+            ifindex: 3,
+            _padding: 0,
+        }
+    }
+
+    #[test]
+    fn test_flow_stats_merge() {
+        let mut merged = FlowStats::default();
+        merged.merge(&stats(2, 200, 50, 80));
+        merged.merge(&FlowStats::default());
+        merged.merge(&stats(1, 60, 10, 90));
+        assert_eq!(merged.packets, 3);
+        assert_eq!(merged.bytes, 260);
+        assert_eq!(merged.first_seen_ns, 10);
+        assert_eq!(merged.last_seen_ns, 90);
+    }
+
+    #[test]
+    fn test_flow_tracker_diff() {
+        let mut tracker = FlowTracker::new();
+
+        let first = HashMap::from([(key(1000), stats(3, 300, 0, 10))]);
+        assert_eq!(
+            tracker.diff(first),
+            vec![FlowDelta {
+                key: key(1000),
+                packets: 3,
+                bytes: 300
+            }]
+        );
+
+        // no new packets for the first flow, a new flow appears
+        let second = HashMap::from([
+            (key(1000), stats(3, 300, 0, 10)),
+            (key(1001), stats(1, 60, 20, 20)),
+        ]);
+        let deltas = tracker.diff(second);
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].key, key(1001));
+
+        // the first flow was evicted and created again
+        let third = HashMap::from([(key(1000), stats(1, 40, 30, 30))]);
+        assert_eq!(
+            tracker.diff(third),
+            vec![FlowDelta {
+                key: key(1000),
+                packets: 1,
+                bytes: 40
+            }]
+        );
+        assert_eq!(tracker.active_flows(), 1);
+    }
+}
@@ -13,3 +13,19 @@ pub fn format_ipv6(ip: &[u32; 4]) -> String {
         (ip[3] >> 16) & 0xFFFF, ip[3] & 0xFFFF
     )
 }
+
+/// Format an `ip:port` pair, wrapping IPv6 addresses in brackets.
+pub fn format_ip_port(af: u16, ip: &str, port: u16) -> String {
+    match af {
+        crate::constants::AF_INET6 => format!("[{}]:{}", ip, port),
+        _ => format!("{}:{}", ip, port),
+    }
+}
+
+/// Format an address stored as four host-order words (IPv4 uses only the first word).
+pub fn format_ip_words(af: u16, ip: &[u32; 4]) -> String {
+    match af {
+        crate::constants::AF_INET6 => format_ipv6(ip),
+        _ => Ipv4Addr::from(ip[0]).to_string(),
+    }
+}
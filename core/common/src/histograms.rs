@@ -0,0 +1,244 @@
+//! Log2 histograms aggregated in the kernel by the `metrics_tracer` eBPF programs.
+//!
+//! When the aggregation mode is enabled the scheduler tracepoints and the TCP
+//! connect latency monitor stop sending one event per occurrence and record
+//! the values in the pinned `log2_histograms` per-cpu map, keyed by cgroup
+//! and histogram kind. This module contains:
+//! - The C-compatible key and value layouts shared with the metrics_tracer crate.
+//! - [`set_aggregation_mode`], which writes the mode in the `metrics_config` map.
+//! - [`HistogramTable`], a wrapper around the pinned map that merges the
+//!   per-cpu values and resets the histograms after every read.
+
+use crate::constants::HIST_SLOTS;
+use anyhow::{Error, anyhow};
+use aya::maps::{Array, Map, MapData, PerCpuHashMap};
+use bytemuck_derive::Zeroable;
+use std::path::Path;
+use tracing::info;
+
+/// Name of the pinned array map with the metrics_tracer settings.
+pub const METRICS_CONFIG_MAP: &str = "metrics_config";
+
+/// Index of the aggregation mode in the `metrics_config` map.
+pub const CONFIG_AGGREGATION_MODE: u32 = 0;
+
+/// One event per occurrence is sent to the userspace (default).
+pub const AGGREGATION_MODE_EVENTS: u32 = 0;
+/// The values are recorded in the `log2_histograms` map.
+pub const AGGREGATION_MODE_HISTOGRAMS: u32 = 1;
+
+/// Scheduler wait time, in nanoseconds.
+pub const HIST_SCHED_WAIT: u32 = 1;
+/// Scheduler runtime, in nanoseconds.
+pub const HIST_SCHED_RUNTIME: u32 = 2;
+/// TCP connect latency, in microseconds.
+pub const HIST_TCP_LATENCY: u32 = 3;
+
+/// Histogram key layout shared with the metrics_tracer `HistogramKey` struct.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Zeroable)]
+pub struct HistogramKey {
+    pub cgroup_id: u64,
+    pub kind: u32,
+    pub _padding: u32,
+}
+unsafe impl aya::Pod for HistogramKey {}
+
+/// Histogram layout shared with the metrics_tracer `Log2Histogram` struct.
+///
+/// `slots[i]` counts the values with bit length `i`: slot 0 is the value 0
+/// and slot `i` covers `[2^(i-1), 2^i - 1]`. The last slot also counts the
+/// larger values.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Zeroable)]
+pub struct Log2Histogram {
+    pub count: u64,
+    pub sum: u64,
+    pub slots: [u64; HIST_SLOTS],
+}
+unsafe impl aya::Pod for Log2Histogram {}
+
+impl Default for Log2Histogram {
+    fn default() -> Self {
+        Log2Histogram {
+            count: 0,
+            sum: 0,
+            slots: [0; HIST_SLOTS],
+        }
+    }
+}
+
+impl Log2Histogram {
+    /// Merge the histogram of another cpu into `self`.
+    pub fn merge(&mut self, other: &Log2Histogram) {
+        self.count += other.count;
+        self.sum += other.sum;
+        for (slot, count) in self.slots.iter_mut().zip(other.slots.iter()) {
+            *slot += count;
+        }
+    }
+
+    /// Return `(value, count)` pairs that reproduce the histogram: every
+    /// value falls in its slot, the counts add up to the slot counts and the
+    /// values add up to `sum`. The kernel only keeps the total sum, so the
+    /// part of `sum` above the slot lower bounds is assigned to the largest
+    /// slots first.
+    pub fn samples(&self) -> Vec<(u64, u64)> {
+        let base: u64 = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(slot, count)| slot_lower_bound(slot).saturating_mul(*count))
+            .fold(0, u64::saturating_add);
+        let mut residual = self.sum.saturating_sub(base);
+
+        let mut samples = Vec::new();
+        for (slot, count) in self.slots.iter().enumerate().rev() {
+            let count = *count;
+            if count == 0 {
+                continue;
+            }
+            let lower = slot_lower_bound(slot);
+            let width = slot_upper_bound(slot).unwrap_or(u64::MAX) - lower;
+            let value = lower + (residual / count).min(width);
+            residual -= (value - lower) * count;
+            // the remainder of the division goes to a single value
+            let last = value + residual.min(width - (value - lower));
+            residual -= last - value;
+            if count > 1 {
+                samples.push((value, count - 1));
+            }
+            samples.push((last, 1));
+        }
+        samples
+    }
+}
+
+/// Smallest value counted in a slot.
+pub fn slot_lower_bound(slot: usize) -> u64 {
+    match slot {
+        0 => 0,
+        _ => 1u64 << (slot - 1),
+    }
+}
+
+/// Largest value counted in a slot, `None` for the last (unbounded) slot.
+pub fn slot_upper_bound(slot: usize) -> Option<u64> {
+    match slot {
+        0 => Some(0),
+        _ if slot >= HIST_SLOTS - 1 => None,
+        _ => Some((1u64 << slot) - 1),
+    }
+}
+
+/// Parse the aggregation mode (`"events"` or `"histograms"`).
+pub fn parse_aggregation_mode(input: &str) -> Result<u32, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "events" | "off" => Ok(AGGREGATION_MODE_EVENTS),
+        "histograms" | "histogram" | "on" => Ok(AGGREGATION_MODE_HISTOGRAMS),
+        _ => Err(anyhow!(
+            "Unknown aggregation mode {:?}. Expected events or histograms",
+            input
+        )),
+    }
+}
+
+/// Write the aggregation mode in the pinned `metrics_config` map.
+///
+/// Must be called before the programs are attached: the events already in
+/// flight are not converted.
+pub fn set_aggregation_mode(pin_path: &Path, mode: u32) -> Result<(), Error> {
+    let map_path = pin_path.join(METRICS_CONFIG_MAP);
+    let mapdata = MapData::from_pin(&map_path)
+        .map_err(|e| anyhow!("Failed to load {:?} map: {}", map_path, e))?;
+    let mut map = Array::<_, u32>::try_from(Map::Array(mapdata))?;
+    map.set(CONFIG_AGGREGATION_MODE, mode, 0)?;
+
+    match mode {
+        AGGREGATION_MODE_HISTOGRAMS => info!("Aggregation mode: in-kernel log2 histograms"),
+        _ => info!("Aggregation mode: events"),
+    }
+    Ok(())
+}
+
+/// Wrapper around the pinned `log2_histograms` per-cpu hash map.
+pub struct HistogramTable {
+    pub histograms: PerCpuHashMap<MapData, HistogramKey, Log2Histogram>,
+}
+
+impl HistogramTable {
+    /// Load the `log2_histograms` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/trace_maps/log2_histograms")
+            .map_err(|e| anyhow!("Failed to load log2_histograms map: {}", e))?;
+        let histograms =
+            PerCpuHashMap::<_, HistogramKey, Log2Histogram>::try_from(Map::PerCpuHashMap(mapdata))?;
+        Ok(HistogramTable { histograms })
+    }
+
+    /// Return every histogram with the per-cpu values merged and remove it
+    /// from the map.
+    ///
+    /// The values recorded between the read and the removal of an entry are
+    /// lost.
+    pub fn drain(&mut self) -> Result<Vec<(HistogramKey, Log2Histogram)>, Error> {
+        let mut histograms = Vec::new();
+        for item in self.histograms.iter() {
+            let (key, values) = item?;
+            let mut hist = Log2Histogram::default();
+            for value in values.iter() {
+                hist.merge(value);
+            }
+            histograms.push((key, hist));
+        }
+        for (key, _) in histograms.iter() {
+            self.histograms.remove(key)?;
+        }
+        Ok(histograms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log2_histogram_merge_and_samples() {
+        let mut slots = [0; HIST_SLOTS];
+        slots[1] = 1; // 1
+        slots[3] = 2; // 4 and 7
+        let cpu0 = Log2Histogram {
+            count: 3,
+            sum: 12,
+            slots,
+        };
+        let mut slots = [0; HIST_SLOTS];
+        slots[3] = 1; // 5
+        let cpu1 = Log2Histogram {
+            count: 1,
+            sum: 5,
+            slots,
+        };
+
+        let mut hist = Log2Histogram::default();
+        hist.merge(&cpu0);
+        hist.merge(&cpu1);
+        assert_eq!((hist.count, hist.sum), (4, 17));
+        // slot 3 covers [4, 7]: 3 values at the lower bound leave 4 to assign
+        assert_eq!(hist.samples(), vec![(5, 2), (6, 1), (1, 1)]);
+        // a sum below the lower bounds (values recorded between two reads) keeps the lower bounds
+        hist.sum = 0;
+        assert_eq!(hist.samples(), vec![(4, 2), (4, 1), (1, 1)]);
+
+        assert_eq!(slot_lower_bound(0), 0);
+        assert_eq!(slot_lower_bound(HIST_SLOTS - 1), 1 << 30);
+        assert_eq!(slot_upper_bound(0), Some(0));
+        assert_eq!(slot_upper_bound(HIST_SLOTS - 2), Some((1 << 30) - 1));
+        assert_eq!(slot_upper_bound(HIST_SLOTS - 1), None);
+        assert_eq!(
+            parse_aggregation_mode("Histograms").unwrap(),
+            AGGREGATION_MODE_HISTOGRAMS
+        );
+        assert!(parse_aggregation_mode("sampled").is_err());
+    }
+}
@@ -0,0 +1,700 @@
+//! Kernel struct field offsets resolved from the running kernel's BTF.
+//!
+//! The conntracker and metrics_tracer eBPF programs read kernel structs
+//! (`net_device`, `sk_buff`, `sock`, ...) with `bpf_probe_read_kernel`. The
+//! field offsets change across kernel versions, so instead of hardcoding them
+//! the user space resolves them at load time from `/sys/kernel/btf/vmlinux`
+//! and writes them in the `kernel_offsets` array map of the eBPF object.
+//!
+//! - [`KernelBtf`] is a minimal BTF parser that only understands what is needed
+//!   to compute the byte offset of a (possibly nested) struct field.
+//! - [`KernelField`] describes a field required by an eBPF program and the map
+//!   index where its offset is stored. The indexes mirror the `OFFSET_*`
+//!   constants of the eBPF crates. Fields whose type changed across kernel
+//!   versions also store whether the field is a pointer
+//!   ([`KernelFieldValue::IsPointer`]).
+//! - [`populate_kernel_offsets`] resolves every required field and fills the
+//!   pinned map. It fails if a field is missing, so the programs are never
+//!   attached with wrong offsets.
+//! - [`TracepointField`] and [`populate_tracepoint_offsets`] do the same for
+//!   the fields of the tracepoint records, read from the tracepoint `format`
+//!   file in tracefs.
+
+use anyhow::{Error, anyhow};
+use aya::maps::{Array, Map, MapData};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::Path;
+use tracing::info;
+
+/// Path of the running kernel's BTF.
+pub const VMLINUX_BTF_PATH: &str = "/sys/kernel/btf/vmlinux";
+/// Mount points of tracefs, in lookup order.
+pub const TRACEFS_PATHS: &[&str] = &["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+/// Name of the array map that stores the offsets. Must match the eBPF side.
+pub const KERNEL_OFFSETS_MAP: &str = "kernel_offsets";
+/// Number of slots in the largest `kernel_offsets` map (metrics_tracer).
+pub const MAX_KERNEL_OFFSETS: u32 = 64;
+
+const BTF_MAGIC: u16 = 0xeb9f;
+const BTF_HEADER_LEN: usize = 24;
+
+const BTF_KIND_INT: u32 = 1;
+const BTF_KIND_PTR: u32 = 2;
+const BTF_KIND_ARRAY: u32 = 3;
+const BTF_KIND_STRUCT: u32 = 4;
+const BTF_KIND_UNION: u32 = 5;
+const BTF_KIND_ENUM: u32 = 6;
+const BTF_KIND_TYPEDEF: u32 = 8;
+const BTF_KIND_VOLATILE: u32 = 9;
+const BTF_KIND_CONST: u32 = 10;
+const BTF_KIND_RESTRICT: u32 = 11;
+const BTF_KIND_FUNC_PROTO: u32 = 13;
+const BTF_KIND_VAR: u32 = 14;
+const BTF_KIND_DATASEC: u32 = 15;
+const BTF_KIND_DECL_TAG: u32 = 17;
+const BTF_KIND_TYPE_TAG: u32 = 18;
+const BTF_KIND_ENUM64: u32 = 19;
+
+/// Value stored in the `kernel_offsets` map for a [`KernelField`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelFieldValue {
+    /// Byte offset of the field.
+    Offset,
+    /// 1 if the field is a pointer, 0 otherwise.
+    IsPointer,
+}
+
+/// A kernel struct field required by an eBPF program.
+#[derive(Clone, Copy, Debug)]
+pub struct KernelField {
+    /// Index of the value in the `kernel_offsets` map.
+    pub index: u32,
+    /// Name of the kernel struct.
+    pub btf_struct: &'static str,
+    /// Dot separated path of the field inside the struct (e.g. `"nd_net.net"`).
+    /// Anonymous structs and unions are traversed automatically.
+    pub path: &'static str,
+    pub value: KernelFieldValue,
+}
+
+impl KernelField {
+    pub const fn new(index: u32, btf_struct: &'static str, path: &'static str) -> Self {
+        KernelField {
+            index,
+            btf_struct,
+            path,
+            value: KernelFieldValue::Offset,
+        }
+    }
+
+    /// A field stored as a pointer flag instead of an offset.
+    pub const fn is_pointer(index: u32, btf_struct: &'static str, path: &'static str) -> Self {
+        KernelField {
+            index,
+            btf_struct,
+            path,
+            value: KernelFieldValue::IsPointer,
+        }
+    }
+}
+
+/// A field of a tracepoint record required by an eBPF program.
+#[derive(Clone, Copy, Debug)]
+pub struct TracepointField {
+    /// Index of the offset in the `kernel_offsets` map.
+    pub index: u32,
+    /// Tracepoint category (e.g. `"sched"`).
+    pub category: &'static str,
+    /// Tracepoint name (e.g. `"sched_process_fork"`).
+    pub tracepoint: &'static str,
+    /// Name of the field in the tracepoint record.
+    pub field: &'static str,
+}
+
+impl TracepointField {
+    pub const fn new(
+        index: u32,
+        category: &'static str,
+        tracepoint: &'static str,
+        field: &'static str,
+    ) -> Self {
+        TracepointField {
+            index,
+            category,
+            tracepoint,
+            field,
+        }
+    }
+}
+
+/// Fields read by the conntracker programs. Indexes mirror the conntracker `OFFSET_*` constants.
+pub const CONNTRACKER_KERNEL_FIELDS: &[KernelField] = &[
+    KernelField::new(0, "net_device", "name"),
+    KernelField::new(1, "net_device", "state"),
+    KernelField::new(2, "net_device", "dev_addr"),
+    KernelField::new(3, "net_device", "nd_net.net"),
+    KernelField::new(4, "net", "ns.inum"),
+    KernelField::new(5, "sk_buff", "data"),
+    KernelField::new(6, "tcp_sock", "bytes_acked"),
+    KernelField::new(7, "tcp_sock", "bytes_received"),
+    KernelField::new(8, "sock", "__sk_common.skc_num"),
+    KernelField::new(9, "sock", "__sk_common.skc_family"),
+    KernelField::new(10, "sock", "__sk_common.skc_net.net"),
+    KernelField::new(11, "sock", "sk_ack_backlog"),
+    KernelField::new(12, "sock", "sk_max_ack_backlog"),
+    KernelField::new(13, "sk_buff", "head"),
+    KernelField::new(14, "sk_buff", "network_header"),
+    KernelField::new(15, "sk_buff", "transport_header"),
+    KernelField::new(16, "sock", "__sk_common.skc_daddr"),
+    KernelField::new(17, "sock", "__sk_common.skc_rcv_saddr"),
+    KernelField::new(18, "sock", "__sk_common.skc_dport"),
+    KernelField::new(19, "sock", "__sk_common.skc_v6_daddr"),
+    KernelField::new(20, "sock", "__sk_common.skc_v6_rcv_saddr"),
+    KernelField::new(21, "msghdr", "msg_name"),
+    // inline array before 5.17, `const unsigned char *` since
+    KernelField::is_pointer(22, "net_device", "dev_addr"),
+];
+
+/// Tracepoint record fields read by the conntracker programs. Indexes mirror
+/// the conntracker `OFFSET_*` constants.
+pub const CONNTRACKER_TRACEPOINT_FIELDS: &[TracepointField] = &[
+    TracepointField::new(23, "sock", "inet_sock_set_state", "skaddr"),
+    TracepointField::new(24, "sock", "inet_sock_set_state", "oldstate"),
+    TracepointField::new(25, "sock", "inet_sock_set_state", "newstate"),
+    TracepointField::new(26, "sock", "inet_sock_set_state", "sport"),
+    TracepointField::new(27, "sock", "inet_sock_set_state", "dport"),
+    TracepointField::new(28, "sock", "inet_sock_set_state", "family"),
+    TracepointField::new(29, "sock", "inet_sock_set_state", "protocol"),
+    TracepointField::new(30, "sock", "inet_sock_set_state", "saddr"),
+    TracepointField::new(31, "sock", "inet_sock_set_state", "daddr"),
+    TracepointField::new(32, "sock", "inet_sock_set_state", "saddr_v6"),
+    TracepointField::new(33, "sock", "inet_sock_set_state", "daddr_v6"),
+];
+
+/// Fields read by the metrics_tracer programs. Indexes mirror the metrics_tracer `OFFSET_*` constants.
+pub const METRICS_TRACER_KERNEL_FIELDS: &[KernelField] = &[
+    KernelField::new(0, "sock", "sk_err"),
+    KernelField::new(1, "sock", "sk_err_soft"),
+    KernelField::new(2, "sock", "sk_backlog.len"),
+    KernelField::new(3, "sock", "sk_wmem_queued"),
+    KernelField::new(4, "sock", "sk_rcvbuf"),
+    KernelField::new(5, "sock", "sk_ack_backlog"),
+    KernelField::new(6, "sock", "sk_drops"),
+    KernelField::new(7, "sock", "__sk_common.skc_daddr"),
+    KernelField::new(8, "sock", "__sk_common.skc_rcv_saddr"),
+    KernelField::new(9, "sock", "__sk_common.skc_dport"),
+    KernelField::new(10, "sock", "__sk_common.skc_num"),
+    KernelField::new(11, "sock", "__sk_common.skc_family"),
+    KernelField::new(12, "sock", "__sk_common.skc_state"),
+    KernelField::new(13, "sock", "__sk_common.skc_v6_daddr"),
+    KernelField::new(14, "sock", "__sk_common.skc_v6_rcv_saddr"),
+    KernelField::new(15, "sk_buff", "head"),
+    KernelField::new(16, "sk_buff", "network_header"),
+    KernelField::new(17, "sk_buff", "transport_header"),
+    KernelField::new(18, "tcp_sock", "srtt_us"),
+    KernelField::new(19, "tcp_sock", "mdev_us"),
+    KernelField::new(20, "tcp_sock", "snd_cwnd"),
+    KernelField::new(21, "tcp_sock", "total_retrans"),
+    KernelField::new(22, "sock", "__sk_common.skc_net.net"),
+    KernelField::new(23, "net", "ns.inum"),
+    KernelField::new(24, "sock", "__sk_common.skc_cookie"),
+    // cgroup id of the sched_stat_wait task, also read by the cgroup filter
+    KernelField::new(26, "cgroup", "kn"),
+    KernelField::new(27, "kernfs_node", "id"),
+    KernelField::new(28, "task_struct", "exit_code"),
+    KernelField::new(29, "task_struct", "real_parent"),
+    KernelField::new(30, "task_struct", "tgid"),
+    KernelField::new(31, "task_struct", "comm"),
+    KernelField::new(32, "task_struct", "cgroups"),
+    KernelField::new(33, "css_set", "dfl_cgrp"),
+];
+
+/// Tracepoint record fields read by the metrics_tracer programs. Indexes
+/// mirror the metrics_tracer `OFFSET_*` constants.
+pub const METRICS_TRACER_TRACEPOINT_FIELDS: &[TracepointField] = &[
+    TracepointField::new(34, "sched", "sched_process_fork", "child_comm"),
+    TracepointField::new(35, "sched", "sched_process_fork", "child_pid"),
+    TracepointField::new(36, "sched", "sched_process_exec", "filename"),
+];
+
+/// Fields read by the cgroup filter of the metrics_tracer programs.
+///
+/// `sock.sk_cgrp_data.cgroup` exists since Linux 5.15, so these fields are
+/// not part of the startup self-check: they are resolved when available and
+/// checked again before the filter is enabled.
+pub const METRICS_TRACER_CGROUP_FILTER_FIELDS: &[KernelField] = &[
+    KernelField::new(25, "sock", "sk_cgrp_data.cgroup"),
+    KernelField::new(26, "cgroup", "kn"),
+    KernelField::new(27, "kernfs_node", "id"),
+];
+
+#[derive(Debug)]
+struct BtfMember {
+    name_off: u32,
+    type_id: u32,
+    bit_offset: u32,
+}
+
+#[derive(Debug)]
+struct BtfType {
+    name_off: u32,
+    kind: u32,
+    // size for structs and unions, referenced type for typedefs and modifiers
+    size_or_type: u32,
+    members: Vec<BtfMember>,
+}
+
+/// The types and strings of a BTF blob.
+pub struct KernelBtf {
+    // types[0] is the void type
+    types: Vec<BtfType>,
+    strings: Vec<u8>,
+    structs: HashMap<String, u32>,
+}
+
+impl KernelBtf {
+    /// Parse the running kernel's BTF.
+    pub fn from_sys_fs() -> Result<Self, Error> {
+        let data = std::fs::read(VMLINUX_BTF_PATH)
+            .map_err(|e| anyhow!("Cannot read {}. Reason: {}", VMLINUX_BTF_PATH, e))?;
+        Self::parse(&data)
+    }
+
+    /// Parse a raw BTF blob in the host byte order.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < BTF_HEADER_LEN {
+            return Err(anyhow!("BTF data is too short"));
+        }
+        let magic = u16::from_ne_bytes([data[0], data[1]]);
+        if magic != BTF_MAGIC {
+            return Err(anyhow!("Invalid BTF magic {:#x}", magic));
+        }
+        let hdr_len = read_u32(data, 4)? as usize;
+        let type_off = read_u32(data, 8)? as usize;
+        let type_len = read_u32(data, 12)? as usize;
+        let str_off = read_u32(data, 16)? as usize;
+        let str_len = read_u32(data, 20)? as usize;
+
+        let types_start = hdr_len + type_off;
+        let types_end = types_start + type_len;
+        let strings_start = hdr_len + str_off;
+        let strings_end = strings_start + str_len;
+        if types_end > data.len() || strings_end > data.len() {
+            return Err(anyhow!("BTF sections are out of bounds"));
+        }
+
+        let mut types = vec![BtfType {
+            name_off: 0,
+            kind: 0,
+            size_or_type: 0,
+            members: Vec::new(),
+        }];
+        let mut pos = types_start;
+        while pos < types_end {
+            let name_off = read_u32(data, pos)?;
+            let info = read_u32(data, pos + 4)?;
+            let size_or_type = read_u32(data, pos + 8)?;
+            pos += 12;
+
+            let vlen = (info & 0xffff) as usize;
+            let kind = (info >> 24) & 0x1f;
+            let kind_flag = info >> 31 == 1;
+            let mut members = Vec::new();
+
+            match kind {
+                BTF_KIND_INT | BTF_KIND_VAR | BTF_KIND_DECL_TAG => pos += 4,
+                BTF_KIND_ARRAY => pos += 12,
+                BTF_KIND_STRUCT | BTF_KIND_UNION => {
+                    for _ in 0..vlen {
+                        let offset = read_u32(data, pos + 8)?;
+                        members.push(BtfMember {
+                            name_off: read_u32(data, pos)?,
+                            type_id: read_u32(data, pos + 4)?,
+                            // with kind_flag set the upper 8 bits hold the bitfield size
+                            bit_offset: if kind_flag { offset & 0xffffff } else { offset },
+                        });
+                        pos += 12;
+                    }
+                }
+                BTF_KIND_ENUM | BTF_KIND_FUNC_PROTO => pos += vlen * 8,
+                BTF_KIND_DATASEC | BTF_KIND_ENUM64 => pos += vlen * 12,
+                _ => {}
+            }
+
+            types.push(BtfType {
+                name_off,
+                kind,
+                size_or_type,
+                members,
+            });
+        }
+
+        let strings = data[strings_start..strings_end].to_vec();
+        let mut btf = KernelBtf {
+            types,
+            strings,
+            structs: HashMap::new(),
+        };
+        for (id, ty) in btf.types.iter().enumerate() {
+            if ty.kind == BTF_KIND_STRUCT && ty.name_off != 0 {
+                let name = btf.name(ty.name_off).to_string();
+                // keep the first definition, like libbpf does
+                btf.structs.entry(name).or_insert(id as u32);
+            }
+        }
+        Ok(btf)
+    }
+
+    /// Byte offset of `path` (dot separated) inside the struct `btf_struct`.
+    ///
+    /// Returns `None` if the struct or one of the fields does not exist.
+    pub fn field_offset(&self, btf_struct: &str, path: &str) -> Option<u32> {
+        self.find_field(btf_struct, path)
+            .map(|(bit_offset, _)| bit_offset / 8)
+    }
+
+    /// True if the field `path` of the struct `btf_struct` is a pointer
+    /// (typedefs and type modifiers are skipped).
+    ///
+    /// Returns `None` if the struct or one of the fields does not exist.
+    pub fn field_is_pointer(&self, btf_struct: &str, path: &str) -> Option<bool> {
+        let (_, type_id) = self.find_field(btf_struct, path)?;
+        let ty = self.types.get(self.resolve(type_id) as usize)?;
+        Some(ty.kind == BTF_KIND_PTR)
+    }
+
+    // returns the bit offset and the type id of a (possibly nested) field
+    fn find_field(&self, btf_struct: &str, path: &str) -> Option<(u32, u32)> {
+        let mut type_id = *self.structs.get(btf_struct)?;
+        let mut bit_offset = 0;
+        for field in path.split('.') {
+            let (offset, member_type) = self.find_member(type_id, field)?;
+            bit_offset += offset;
+            type_id = member_type;
+        }
+        Some((bit_offset, type_id))
+    }
+
+    // docs:
+    //
+    // looks for a member named field in a struct or union. Anonymous structs and unions are searched
+    // recursively (e.g. skc_daddr lives in an anonymous struct inside sock_common)
+    //
+    // Returns the bit offset of the member and its type id
+    //
+    fn find_member(&self, type_id: u32, field: &str) -> Option<(u32, u32)> {
+        let ty = self.types.get(self.resolve(type_id) as usize)?;
+        if ty.kind != BTF_KIND_STRUCT && ty.kind != BTF_KIND_UNION {
+            return None;
+        }
+        for member in ty.members.iter() {
+            if member.name_off == 0 {
+                if let Some((offset, member_type)) = self.find_member(member.type_id, field) {
+                    return Some((member.bit_offset + offset, member_type));
+                }
+            } else if self.name(member.name_off) == field {
+                return Some((member.bit_offset, member.type_id));
+            }
+        }
+        None
+    }
+
+    // skips typedefs and type modifiers
+    fn resolve(&self, mut type_id: u32) -> u32 {
+        while let Some(ty) = self.types.get(type_id as usize) {
+            match ty.kind {
+                BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT
+                | BTF_KIND_TYPE_TAG => type_id = ty.size_or_type,
+                _ => break,
+            }
+        }
+        type_id
+    }
+
+    fn name(&self, name_off: u32) -> &str {
+        let start = name_off as usize;
+        if start >= self.strings.len() {
+            return "";
+        }
+        let end = self.strings[start..]
+            .iter()
+            .position(|b| *b == 0)
+            .map(|len| start + len)
+            .unwrap_or(self.strings.len());
+        std::str::from_utf8(&self.strings[start..end]).unwrap_or("")
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, Error> {
+    let bytes = data
+        .get(pos..pos + 4)
+        .ok_or_else(|| anyhow!("BTF data truncated at byte {}", pos))?;
+    Ok(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Resolve the value (offset or pointer flag) of every field.
+///
+/// This is the startup self-check: returns an error listing all the missing
+/// fields instead of stopping at the first one.
+pub fn resolve_offsets(btf: &KernelBtf, fields: &[KernelField]) -> Result<Vec<(u32, u32)>, Error> {
+    let mut offsets = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for field in fields {
+        let value = match field.value {
+            KernelFieldValue::Offset => btf.field_offset(field.btf_struct, field.path),
+            KernelFieldValue::IsPointer => btf
+                .field_is_pointer(field.btf_struct, field.path)
+                .map(u32::from),
+        };
+        match value {
+            Some(value) => offsets.push((field.index, value)),
+            None => missing.push(format!("{}.{}", field.btf_struct, field.path)),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "Required kernel fields not found in the kernel BTF: {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(offsets)
+}
+
+/// Offset of `field` in a tracepoint record, parsed from the content of the
+/// tracepoint `format` file. The lines look like:
+///
+/// ```text
+///     field:pid_t child_pid;  offset:44;  size:4; signed:1;
+/// ```
+pub fn tracepoint_field_offset(format: &str, field: &str) -> Option<u32> {
+    for line in format.lines() {
+        let mut declaration = None;
+        let mut offset = None;
+        for part in line.split(';') {
+            let part = part.trim();
+            if let Some(value) = part.strip_prefix("field:") {
+                declaration = Some(value);
+            } else if let Some(value) = part.strip_prefix("offset:") {
+                offset = value.parse::<u32>().ok();
+            }
+        }
+        // the name is the last word of the declaration, without the array size
+        let name = declaration
+            .and_then(|declaration| declaration.split_whitespace().last())
+            .map(|name| name.split('[').next().unwrap_or(name));
+        if name == Some(field) {
+            return offset;
+        }
+    }
+    None
+}
+
+fn read_tracepoint_format(category: &str, tracepoint: &str) -> Result<String, Error> {
+    for tracefs in TRACEFS_PATHS {
+        let path = format!("{}/events/{}/{}/format", tracefs, category, tracepoint);
+        if let Ok(format) = std::fs::read_to_string(&path) {
+            return Ok(format);
+        }
+    }
+    Err(anyhow!(
+        "Cannot read the format of the {}:{} tracepoint in {}",
+        category,
+        tracepoint,
+        TRACEFS_PATHS.join(", ")
+    ))
+}
+
+/// Resolve the offset of every tracepoint field from the tracefs `format`
+/// files. Returns an error listing all the missing fields.
+pub fn resolve_tracepoint_offsets(fields: &[TracepointField]) -> Result<Vec<(u32, u32)>, Error> {
+    let mut formats: HashMap<(&str, &str), String> = HashMap::new();
+    let mut offsets = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for field in fields {
+        let format = match formats.entry((field.category, field.tracepoint)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(read_tracepoint_format(field.category, field.tracepoint)?)
+            }
+        };
+        match tracepoint_field_offset(format, field.field) {
+            Some(offset) => offsets.push((field.index, offset)),
+            None => missing.push(format!(
+                "{}:{}.{}",
+                field.category, field.tracepoint, field.field
+            )),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "Required tracepoint fields not found in the tracepoint formats: {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(offsets)
+}
+
+/// Resolve `fields` from the running kernel's BTF and write the offsets in
+/// the `kernel_offsets` map pinned under `pin_path`.
+///
+/// Must be called before attaching the programs: an error means that the
+/// running kernel does not have one of the fields read by the programs.
+pub fn populate_kernel_offsets(pin_path: &Path, fields: &[KernelField]) -> Result<(), Error> {
+    let btf = KernelBtf::from_sys_fs()?;
+    let offsets = resolve_offsets(&btf, fields)?;
+    write_offsets(pin_path, &offsets)?;
+    info!("Resolved {} kernel field offsets from BTF", fields.len());
+    Ok(())
+}
+
+/// Resolve `fields` from the tracepoint formats and write the offsets in the
+/// `kernel_offsets` map pinned under `pin_path`.
+///
+/// Must be called before attaching the tracepoint programs: an error means
+/// that tracefs is not mounted or that a tracepoint record changed.
+pub fn populate_tracepoint_offsets(
+    pin_path: &Path,
+    fields: &[TracepointField],
+) -> Result<(), Error> {
+    let offsets = resolve_tracepoint_offsets(fields)?;
+    write_offsets(pin_path, &offsets)?;
+    info!(
+        "Resolved {} tracepoint field offsets from tracefs",
+        fields.len()
+    );
+    Ok(())
+}
+
+fn write_offsets(pin_path: &Path, offsets: &[(u32, u32)]) -> Result<(), Error> {
+    let map_path = pin_path.join(KERNEL_OFFSETS_MAP);
+    let mapdata = MapData::from_pin(&map_path)
+        .map_err(|e| anyhow!("Failed to load {:?} map: {}", map_path, e))?;
+    let mut map = Array::<_, u32>::try_from(Map::Array(mapdata))?;
+
+    for &(index, offset) in offsets {
+        if index >= MAX_KERNEL_OFFSETS {
+            return Err(anyhow!("Kernel offset index {} out of bounds", index));
+        }
+        map.set(index, offset, 0)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // builds a BTF blob with:
+    //   [1] int
+    //   [2] struct { int skc_daddr; int skc_rcv_saddr; }   (anonymous)
+    //   [3] struct sock_common { [2]; int skc_family; }
+    //   [4] typedef sock_common_t -> [3]
+    //   [5] struct sock { sock_common_t __sk_common; int sk_err; }
+    //   [6] int *
+    //   [7] struct net_device { int *dev_addr; }
+    fn test_btf() -> Vec<u8> {
+        let strings =
+            b"\0int\0skc_daddr\0skc_rcv_saddr\0sock_common\0skc_family\0sock_common_t\0sock\0__sk_common\0sk_err\0net_device\0dev_addr\0";
+        let name = |s: &str| -> u32 {
+            let needle = format!("\0{}\0", s);
+            strings
+                .windows(needle.len())
+                .position(|w| w == needle.as_bytes())
+                .unwrap() as u32
+                + 1
+        };
+        let info = |kind: u32, vlen: u32| (kind << 24) | vlen;
+
+        let mut types: Vec<u32> = Vec::new();
+        types.extend([name("int"), info(BTF_KIND_INT, 0), 4, 32]);
+        types.extend([0, info(BTF_KIND_STRUCT, 2), 8]);
+        types.extend([name("skc_daddr"), 1, 0]);
+        types.extend([name("skc_rcv_saddr"), 1, 32]);
+        types.extend([name("sock_common"), info(BTF_KIND_STRUCT, 2), 12]);
+        types.extend([0, 2, 0]);
+        types.extend([name("skc_family"), 1, 64]);
+        types.extend([name("sock_common_t"), info(BTF_KIND_TYPEDEF, 0), 3]);
+        types.extend([name("sock"), info(BTF_KIND_STRUCT, 2), 16]);
+        types.extend([name("__sk_common"), 4, 0]);
+        types.extend([name("sk_err"), 1, 96]);
+        types.extend([0, info(BTF_KIND_PTR, 0), 1]);
+        types.extend([name("net_device"), info(BTF_KIND_STRUCT, 1), 8]);
+        types.extend([name("dev_addr"), 6, 0]);
+        let types: Vec<u8> = types.iter().flat_map(|v| v.to_ne_bytes()).collect();
+
+        let mut data = Vec::new();
+        data.extend(BTF_MAGIC.to_ne_bytes());
+        data.extend([1u8, 0u8]);
+        for v in [
+            BTF_HEADER_LEN as u32,
+            0,
+            types.len() as u32,
+            types.len() as u32,
+            strings.len() as u32,
+        ] {
+            data.extend(v.to_ne_bytes());
+        }
+        data.extend(types);
+        data.extend(strings);
+        data
+    }
+
+    #[test]
+    fn test_field_offset() {
+        let btf = KernelBtf::parse(&test_btf()).unwrap();
+        assert_eq!(btf.field_offset("sock", "sk_err"), Some(12));
+        assert_eq!(btf.field_offset("sock", "__sk_common.skc_family"), Some(8));
+        assert_eq!(
+            btf.field_offset("sock", "__sk_common.skc_rcv_saddr"),
+            Some(4)
+        );
+        assert_eq!(btf.field_offset("sock_common", "skc_daddr"), Some(0));
+        assert_eq!(btf.field_offset("sock", "sk_drops"), None);
+        assert_eq!(btf.field_offset("net_device", "name"), None);
+
+        assert_eq!(btf.field_is_pointer("net_device", "dev_addr"), Some(true));
+        assert_eq!(btf.field_is_pointer("sock", "__sk_common"), Some(false));
+        assert_eq!(btf.field_is_pointer("sock", "sk_drops"), None);
+    }
+
+    #[test]
+    fn test_resolve_offsets_reports_missing_fields() {
+        let btf = KernelBtf::parse(&test_btf()).unwrap();
+        let fields = [
+            KernelField::new(0, "sock", "sk_err"),
+            KernelField::new(1, "sock", "sk_drops"),
+            KernelField::new(2, "net", "ns.inum"),
+        ];
+        let err = resolve_offsets(&btf, &fields).unwrap_err().to_string();
+        assert!(err.contains("sock.sk_drops"));
+        assert!(err.contains("net.ns.inum"));
+
+        let offsets = resolve_offsets(&btf, &fields[..1]).unwrap();
+        assert_eq!(offsets, vec![(0, 12)]);
+    }
+
+    #[test]
+    fn test_tracepoint_field_offset() {
+        let format = "name: sched_process_fork
+ID: 318
+format:
+\tfield:unsigned short common_type;\toffset:0;\tsize:2;\tsigned:0;
+\tfield:int common_pid;\toffset:4;\tsize:4;\tsigned:1;
+
+\tfield:char parent_comm[16];\toffset:8;\tsize:16;\tsigned:0;
+\tfield:pid_t parent_pid;\toffset:24;\tsize:4;\tsigned:1;
+\tfield:char child_comm[16];\toffset:28;\tsize:16;\tsigned:0;
+\tfield:pid_t child_pid;\toffset:44;\tsize:4;\tsigned:1;
+\tfield:__data_loc char[] filename;\toffset:48;\tsize:4;\tsigned:0;
+
+print fmt: \"comm=%s pid=%d\", REC->parent_comm, REC->parent_pid
+";
+        assert_eq!(tracepoint_field_offset(format, "child_comm"), Some(28));
+        assert_eq!(tracepoint_field_offset(format, "child_pid"), Some(44));
+        assert_eq!(tracepoint_field_offset(format, "filename"), Some(48));
+        assert_eq!(tracepoint_field_offset(format, "common_pid"), Some(4));
+        assert_eq!(tracepoint_field_offset(format, "child"), None);
+        assert_eq!(tracepoint_field_offset(format, "comm"), None);
+    }
+}
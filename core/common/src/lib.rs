@@ -4,15 +4,37 @@
     feature = "monitoring-structs",
 ))]
 pub mod buffer_type;
+#[cfg(feature = "map-handlers")]
+pub mod cgroup_filter;
+#[cfg(feature = "map-handlers")]
+pub mod classifier_config;
+#[cfg(feature = "map-handlers")]
+pub mod connections;
 pub mod constants;
+#[cfg(feature = "buffer-reader")]
+pub mod event_reader;
+#[cfg(feature = "map-handlers")]
+pub mod filter_rules;
+#[cfg(feature = "map-handlers")]
+pub mod flows;
 pub mod formatters;
+#[cfg(feature = "map-handlers")]
+pub mod histograms;
+#[cfg(feature = "map-handlers")]
+pub mod kernel_offsets;
 pub mod logger;
 #[cfg(feature = "map-handlers")]
 pub mod map_handlers;
 #[cfg(feature = "monitoring-structs")]
 pub mod otel_metrics;
+#[cfg(feature = "monitoring-structs")]
+pub mod process_table;
 #[cfg(feature = "program-handlers")]
 pub mod program_handlers;
+#[cfg(feature = "map-handlers")]
+pub mod rate_limits;
+#[cfg(feature = "map-handlers")]
+pub mod throughput;
 pub mod semantic;
 pub mod metadata;
 #[cfg(feature = "buffer-reader")]
@@ -40,9 +40,10 @@ pub fn init_logger_without_time() {
 }
 
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
-use opentelemetry_otlp::{LogExporter, WithExportConfig};
+use opentelemetry_otlp::{LogExporter, MetricExporter, WithExportConfig};
 use opentelemetry_sdk::Resource;
 use opentelemetry_sdk::logs::SdkLoggerProvider;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
 
 const OTEL_SERVICE_NAME: &str = "OTEL_SERVICE_NAME";
 const OTEL_EXPORTER_OTLP_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
@@ -131,3 +132,41 @@ pub fn otlp_logger_init(service_name: String) -> SdkLoggerProvider {
 
     provider
 }
+
+/// Initialize an OTLP meter provider and register it as the global meter provider.
+///
+/// Uses the same endpoint, protocol and service name resolution as
+/// [`otlp_logger_init`]. Metrics are pushed to the collector every 5 seconds.
+/// Call `shutdown()` on the returned provider to flush the pending metrics.
+pub fn otlp_meter_init(service_name: String) -> SdkMeterProvider {
+    let otlp_endpoint = resolved_otlp_endpoint();
+    let otlp_protocol = resolved_otlp_protocol();
+
+    let exporter = match otlp_protocol.as_str() {
+        "http/protobuf" | "http/json" => MetricExporter::builder()
+            .with_http()
+            .with_endpoint(otlp_endpoint)
+            .build()
+            .expect("Failed to create OTLP HTTP metric exporter"),
+        _ => MetricExporter::builder()
+            .with_tonic()
+            .with_endpoint(otlp_endpoint)
+            .build()
+            .expect("Failed to create OTLP gRPC metric exporter"),
+    };
+
+    let reader = PeriodicReader::builder(exporter)
+        .with_interval(std::time::Duration::from_secs(5))
+        .build();
+
+    let service_name = resolved_service_name(service_name);
+
+    let provider = SdkMeterProvider::builder()
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .with_reader(reader)
+        .build();
+
+    opentelemetry::global::set_meter_provider(provider.clone());
+
+    provider
+}
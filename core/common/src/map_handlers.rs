@@ -1,15 +1,17 @@
 use anyhow::Error;
 use anyhow::Ok;
 use aya::Ebpf;
-use aya::maps::HashMap;
 use aya::maps::Map;
+use aya::maps::MapData;
+use aya::maps::lpm_trie::{Key, LpmTrie};
 use k8s_openapi::api::core::v1::ConfigMap;
 use kube::{Api, Client};
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
 use tracing::warn;
 use tracing::{error, info};
 
@@ -91,16 +93,275 @@ pub fn map_pinner(maps: BpfMapsData, path: &PathBuf) -> Result<BpfMapsData, Erro
     Ok(owned_bpf_maps_data) // return a BpfMapsData type 
 }
 
+// docs:
+//
+// parses a blocklist entry. Accepts single hosts ("10.0.0.1", "fd00::1") and CIDR notation ("10.0.0.0/8", "fd00::/64")
+// Single hosts are converted to a full length prefix (/32 for ipv4, /128 for ipv6) and the host bits of a CIDR
+// are zeroed so the same range always produces the same LPM key
+//
+// Returns the network address and the prefix length or an error if the entry is not valid
+
 #[cfg(feature = "map-handlers")]
-pub async fn populate_blocklist() -> Result<(), Error> {
-    use aya::maps::MapData;
-    // load mapdata from path
+pub fn parse_cidr(input: &str) -> Result<(IpAddr, u32), Error> {
+    let input = input.trim();
+    let (addr, prefix_len) = match input.split_once('/') {
+        Some((addr, prefix_len)) => {
+            let addr = IpAddr::from_str(addr.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid address {:?}. Reason: {}", input, e))?;
+            let prefix_len = u32::from_str(prefix_len.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid prefix length {:?}. Reason: {}", input, e))?;
+            (addr, prefix_len)
+        }
+        None => {
+            let addr = IpAddr::from_str(input)
+                .map_err(|e| anyhow::anyhow!("Invalid address {:?}. Reason: {}", input, e))?;
+            let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+            (addr, prefix_len)
+        }
+    };
+
+    match addr {
+        IpAddr::V4(ip) => {
+            if prefix_len > 32 {
+                return Err(anyhow::anyhow!("Invalid ipv4 prefix length {}", prefix_len));
+            }
+            let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+            let network = Ipv4Addr::from(u32::from(ip) & mask);
+            Ok((IpAddr::V4(network), prefix_len))
+        }
+        IpAddr::V6(ip) => {
+            if prefix_len > 128 {
+                return Err(anyhow::anyhow!("Invalid ipv6 prefix length {}", prefix_len));
+            }
+            let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+            let network = Ipv6Addr::from(u128::from(ip) & mask);
+            Ok((IpAddr::V6(network), prefix_len))
+        }
+    }
+}
+
+// docs:
+//
+// formats a network address and a prefix length. Full length prefixes are returned as a plain address
+// to match the way single hosts are inserted by the users
+
+#[cfg(feature = "map-handlers")]
+pub fn format_cidr(addr: IpAddr, prefix_len: u32) -> String {
+    match addr {
+        IpAddr::V4(_) if prefix_len == 32 => addr.to_string(),
+        IpAddr::V6(_) if prefix_len == 128 => addr.to_string(),
+        _ => format!("{}/{}", addr, prefix_len),
+    }
+}
+
+// docs:
+//
+// returns the current time as unix timestamp in seconds. Used for the blocklist insertion time and expiry
+
+#[cfg(feature = "map-handlers")]
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// docs:
+//
+// loads a pinned map with from_pin, retrying every interval until it succeeds. The errors are logged,
+// so the periodic tasks never exit while the maps are not pinned yet
+
+#[cfg(feature = "map-handlers")]
+pub async fn from_pin_with_retry<T>(
+    map_name: &str,
+    interval: std::time::Duration,
+    from_pin: impl Fn() -> Result<T, anyhow::Error>,
+) -> T {
+    loop {
+        match from_pin() {
+            std::result::Result::Ok(map) => return map,
+            Err(e) => error!("Cannot load the {} map: {}", map_name, e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// docs:
+//
+// BlocklistEntry is the value stored in the blocklist tries. Mirrors the conntracker BlocklistEntry struct
+//
+// added_at: insertion time as unix timestamp in seconds
+// ttl_secs: lifetime of the entry in seconds (0 = the entry never expires)
+// rule_id: identifier of the entry, assigned on insert from an atomic counter (see BlocklistMaps::insert)
+// added_by: who inserted the entry, zero padded
+
+#[cfg(feature = "map-handlers")]
+pub const BLOCKLIST_ADDED_BY_LEN: usize = 32;
+
+#[cfg(feature = "map-handlers")]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck_derive::Zeroable)]
+pub struct BlocklistEntry {
+    pub added_at: u64,
+    pub ttl_secs: u64,
+    pub rule_id: u32,
+    pub _padding: u32,
+    pub added_by: [u8; BLOCKLIST_ADDED_BY_LEN],
+}
+#[cfg(feature = "map-handlers")]
+unsafe impl aya::Pod for BlocklistEntry {}
+
+#[cfg(feature = "map-handlers")]
+impl BlocklistEntry {
+    // builds an entry inserted now. The author is truncated to BLOCKLIST_ADDED_BY_LEN bytes
+    pub fn new(rule_id: u32, ttl_secs: u64, added_by: &str, now: u64) -> Self {
+        let mut author = [0u8; BLOCKLIST_ADDED_BY_LEN];
+        let len = added_by.len().min(BLOCKLIST_ADDED_BY_LEN);
+        author[..len].copy_from_slice(&added_by.as_bytes()[..len]);
+        BlocklistEntry {
+            added_at: now,
+            ttl_secs,
+            rule_id,
+            _padding: 0,
+            added_by: author,
+        }
+    }
+
+    // expiry time as unix timestamp in seconds. None for the permanent entries
+    pub fn expires_at(&self) -> Option<u64> {
+        match self.ttl_secs {
+            0 => None,
+            ttl => Some(self.added_at.saturating_add(ttl)),
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at <= now)
+    }
+
+    pub fn added_by(&self) -> String {
+        String::from_utf8_lossy(&self.added_by)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+}
+
+// last id assigned to a blocklist entry by this process (see BlocklistMaps::insert)
+#[cfg(feature = "map-handlers")]
+static NEXT_BLOCKLIST_ID: AtomicU32 = AtomicU32::new(0);
 
-    let mapdata = MapData::from_pin("/sys/fs/bpf/maps/Blocklist")
-        .map_err(|e| anyhow::anyhow!("Failed to load blocklist_map: {}", e))?;
+// docs:
+//
+// BlocklistMaps wraps the pinned ipv4 ("Blocklist") and ipv6 ("BlocklistV6") LPM tries used by the
+// identity_classifier and exposes a CIDR aware interface for the agent api and the configmap loader
+
+#[cfg(feature = "map-handlers")]
+pub struct BlocklistMaps {
+    pub v4: LpmTrie<MapData, [u8; 4], BlocklistEntry>,
+    pub v6: LpmTrie<MapData, [u8; 16], BlocklistEntry>,
+}
+
+#[cfg(feature = "map-handlers")]
+impl BlocklistMaps {
+    // loads both the blocklist tries from the bpf filesystem
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/Blocklist")
+            .map_err(|e| anyhow::anyhow!("Failed to load Blocklist map: {}", e))?;
+        let v4 = LpmTrie::<_, [u8; 4], BlocklistEntry>::try_from(Map::LpmTrie(mapdata))?;
+
+        let mapdata_v6 = MapData::from_pin("/sys/fs/bpf/maps/BlocklistV6")
+            .map_err(|e| anyhow::anyhow!("Failed to load BlocklistV6 map: {}", e))?;
+        let v6 = LpmTrie::<_, [u8; 16], BlocklistEntry>::try_from(Map::LpmTrie(mapdata_v6))?;
+
+        Ok(BlocklistMaps { v4, v6 })
+    }
+
+    // inserts a single host or a CIDR range in the right trie
+    // ttl_secs = 0 keeps the entry until it is removed. Returns the id assigned to the entry
+    // ids are taken from the NEXT_BLOCKLIST_ID atomic counter, raised to the highest id already in the maps (entries
+    // inserted by another process), so concurrent inserts never get the same id
+    pub fn insert(&mut self, entry: &str, ttl_secs: u64, added_by: &str) -> Result<u32, Error> {
+        let max_rule_id = self
+            .list()?
+            .iter()
+            .map(|(_, entry)| entry.rule_id)
+            .max()
+            .unwrap_or(0);
+        NEXT_BLOCKLIST_ID.fetch_max(max_rule_id, Ordering::SeqCst);
+        let rule_id = NEXT_BLOCKLIST_ID.fetch_add(1, Ordering::SeqCst) + 1;
+        let value = BlocklistEntry::new(rule_id, ttl_secs, added_by, unix_now());
+        match parse_cidr(entry)? {
+            (IpAddr::V4(ip), prefix_len) => {
+                self.v4
+                    .insert(&Key::new(prefix_len, ip.octets()), value, 0)?;
+            }
+            (IpAddr::V6(ip), prefix_len) => {
+                self.v6
+                    .insert(&Key::new(prefix_len, ip.octets()), value, 0)?;
+            }
+        }
+        Ok(rule_id)
+    }
 
-    let map = Map::HashMap(mapdata);
-    let mut blocklist_map = HashMap::<_, [u8; 4], [u8; 4]>::try_from(map)?;
+    // removes a single host or a CIDR range. The entry must match the inserted prefix
+    pub fn remove(&mut self, entry: &str) -> Result<(), Error> {
+        match parse_cidr(entry)? {
+            (IpAddr::V4(ip), prefix_len) => {
+                self.v4.remove(&Key::new(prefix_len, ip.octets()))?;
+            }
+            (IpAddr::V6(ip), prefix_len) => {
+                self.v6.remove(&Key::new(prefix_len, ip.octets()))?;
+            }
+        }
+        Ok(())
+    }
+
+    // returns every blocked prefix formatted as a string with its BlocklistEntry
+    pub fn list(&self) -> Result<Vec<(String, BlocklistEntry)>, Error> {
+        let mut entries = Vec::new();
+        for item in self.v4.iter() {
+            let (key, value) = item?;
+            let cidr = format_cidr(IpAddr::V4(Ipv4Addr::from(key.data())), key.prefix_len());
+            entries.push((cidr, value));
+        }
+        for item in self.v6.iter() {
+            let (key, value) = item?;
+            let cidr = format_cidr(IpAddr::V6(Ipv6Addr::from(key.data())), key.prefix_len());
+            entries.push((cidr, value));
+        }
+        Ok(entries)
+    }
+
+    // returns every blocked prefix formatted as a string (key and value are the same)
+    pub fn entries(&self) -> Result<std::collections::HashMap<String, String>, Error> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .map(|(cidr, _)| (cidr.clone(), cidr))
+            .collect())
+    }
+
+    // removes the entries expired at `now` (unix timestamp in seconds). A failed removal does not stop the
+    // others: returns the removed prefixes and the errors of the entries that are still in the maps
+    pub fn remove_expired(&mut self, now: u64) -> Result<(Vec<String>, Vec<Error>), Error> {
+        let mut removed = Vec::new();
+        let mut errors = Vec::new();
+        for (cidr, entry) in self.list()? {
+            if entry.is_expired(now) {
+                match self.remove(&cidr) {
+                    std::result::Result::Ok(()) => removed.push(cidr),
+                    Err(e) => errors.push(anyhow::anyhow!("Cannot remove {}: {}", cidr, e)),
+                }
+            }
+        }
+        Ok((removed, errors))
+    }
+}
+
+#[cfg(feature = "map-handlers")]
+pub async fn populate_blocklist() -> Result<(), Error> {
+    let mut blocklist = BlocklistMaps::from_pin()?;
 
     let client = Client::try_default()
         .await
@@ -114,8 +375,8 @@ pub async fn populate_blocklist() -> Result<(), Error> {
             info!("Configmap : {} loaded correctly ", configmap);
             info!("[CONFIGMAP]: {:?} ", configs);
             if let Some(data) = configs.data {
-                if let Some(blocklist) = data.get("blocklist") {
-                    let addresses: Vec<String> = blocklist
+                if let Some(blocklist_data) = data.get("blocklist") {
+                    let addresses: Vec<String> = blocklist_data
                         .lines()
                         .map(|s| s.trim().to_string())
                         .filter(|s| !s.is_empty())
@@ -126,8 +387,10 @@ pub async fn populate_blocklist() -> Result<(), Error> {
                     }
                     for item in &addresses {
                         info!("Inserting addresses: {:?}", &item);
-                        let addr = Ipv4Addr::from_str(&item)?.octets();
-                        let _ = blocklist_map.insert(addr, addr, 0);
+                        // entries can be single hosts or CIDR ranges
+                        if let Err(e) = blocklist.insert(item, 0, "configmap") {
+                            error!("Cannot insert {:?} in the blocklist. Reason: {}", item, e);
+                        }
                     }
                 }
             }
@@ -140,60 +403,31 @@ pub async fn populate_blocklist() -> Result<(), Error> {
     }
 }
 
-#[cfg(feature = "map-handlers")]
-// TODO: modify this to accept also HashMap types
-pub fn load_perf_event_array_from_mapdata(
-    path: &'static str,
-) -> Result<aya::maps::PerfEventArray<aya::maps::MapData>, Error> {
-    use aya::maps::MapData;
-    use aya::maps::PerfEventArray;
-
-    let map_data = MapData::from_pin(path)
-        .map_err(|e| anyhow::anyhow!("Cannot load mapdata from pin {:?} .Reason: {}", &path, e))?;
-
-    let map = Map::PerfEventArray(map_data);
-
-    let perf_event_array = PerfEventArray::try_from(map).map_err(|e| {
-        anyhow::anyhow!("Cannot initialize perf_event_array from map. Reason: {}", e)
-    })?;
-    Ok(perf_event_array)
-}
-
-#[cfg(feature = "map-handlers")]
+#[cfg(all(feature = "map-handlers", feature = "buffer-reader"))]
 pub fn map_manager(
     maps: BpfMapsData,
-) -> Result<
-    std::collections::HashMap<
-        String,
-        (
-            aya::maps::PerfEventArray<aya::maps::MapData>,
-            Vec<aya::maps::perf::PerfEventArrayBuffer<aya::maps::MapData>>,
-        ),
-    >,
-    Error,
-> {
-    use aya::maps::PerfEventArray;
-    use aya::maps::{MapData, perf::PerfEventArrayBuffer};
+) -> Result<std::collections::HashMap<String, crate::event_reader::EventSource>, Error> {
+    use crate::event_reader::EventSource;
     use tracing::debug;
 
     let mut map_manager = std::collections::HashMap::<
-        String, // this will store the bpf map name
-        (PerfEventArray<MapData>, Vec<PerfEventArrayBuffer<MapData>>), // this will manage the BPF_MAP_TYPE_PERF_EVENT_ARRAY and its buffer
+        String,      // this will store the bpf map name
+        EventSource, // this will manage the BPF_MAP_TYPE_RINGBUF or the BPF_MAP_TYPE_PERF_EVENT_ARRAY and its buffers
     >::new();
 
     // map_manager creates an hashmap that contains:
     // MAP NAME as String (KEY)
     //
-    // VALUES (tuple)
-    // a PERF_EVENT_ARRAY
-    // a vector of PERF_EVENT_ARRAY_BUFFER
+    // VALUE
+    // an EventSource: the RINGBUF or the PERF_EVENT_ARRAY_BUFFERS (1 per CPU) of a PERF_EVENT_ARRAY
     //
     // the map manager helps the event listener to specifically call a map by its pinned name
-    // e.g. veth_identity_map and returns the associated PERF_EVENT_ARRAY and PERF_EVENT_ARRAY_BUFFERS (1 per CPU)
+    // e.g. veth_identity_map and returns the associated EventSource. The eBPF programs use ring buffers when
+    // they are built with the ringbuf feature and perf event arrays otherwise, the map type is detected here
     // also the map manager helps to write a more complete debug context by linking map names with arrays and buffers.
     // actually i cannot return the extact information using only the Aya library
 
-    // create the PerfEventArrays and the buffers from the BpfMapsData Objects
+    // create the EventSources from the BpfMapsData Objects
     for (map, name) in maps
         .bpf_obj_map
         .into_iter()
@@ -201,14 +435,84 @@ pub fn map_manager(
     // zip two iterators at the same time for map object and map names
     {
         debug!("Debugging map type:{:?} for map name {:?}", map, &name);
-        info!("Creating PerfEventArray for map name {:?}", &name);
 
-        // save the map in a registry if is a PerfEventArray to access them by name
-        if let std::result::Result::Ok(perf_event_array) = PerfEventArray::try_from(map) {
-            map_manager.insert(name.clone(), (perf_event_array, Vec::new()));
-        } else {
-            warn!("Map {:?} is not a PerfEventArray, skipping load", &name);
+        // save the map in a registry if is a RingBuf or a PerfEventArray to access them by name
+        match EventSource::from_map(map) {
+            std::result::Result::Ok(source) => {
+                info!("Created {} for map name {:?}", source.kind(), &name);
+                map_manager.insert(name.clone(), source);
+            }
+            Err(e) => {
+                warn!("Skipping load of map {:?}. Reason: {}", &name, e);
+            }
         }
     }
     Ok(map_manager)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cidr_single_host() {
+        let (addr, prefix_len) = parse_cidr("192.168.1.10").unwrap();
+        assert_eq!(addr, IpAddr::from_str("192.168.1.10").unwrap());
+        assert_eq!(prefix_len, 32);
+
+        let (addr, prefix_len) = parse_cidr("fd00::1").unwrap();
+        assert_eq!(addr, IpAddr::from_str("fd00::1").unwrap());
+        assert_eq!(prefix_len, 128);
+    }
+
+    #[test]
+    fn test_parse_cidr_masks_host_bits() {
+        let (addr, prefix_len) = parse_cidr("10.1.2.3/8").unwrap();
+        assert_eq!(addr, IpAddr::from_str("10.0.0.0").unwrap());
+        assert_eq!(prefix_len, 8);
+
+        let (addr, prefix_len) = parse_cidr("fd00:1:2::5/32").unwrap();
+        assert_eq!(addr, IpAddr::from_str("fd00:1::").unwrap());
+        assert_eq!(prefix_len, 32);
+
+        let (addr, _) = parse_cidr("1.2.3.4/0").unwrap();
+        assert_eq!(addr, IpAddr::from_str("0.0.0.0").unwrap());
+    }
+
+    #[test]
+    fn test_parse_cidr_invalid() {
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("fd00::/129").is_err());
+        assert!(parse_cidr("not-an-ip").is_err());
+        assert!(parse_cidr("10.0.0.0/x").is_err());
+    }
+
+    #[test]
+    fn test_blocklist_entry_expiry() {
+        let entry = BlocklistEntry::new(1, 1800, "on-call", 1_000);
+        assert_eq!(entry.expires_at(), Some(2_800));
+        assert!(!entry.is_expired(2_799));
+        assert!(entry.is_expired(2_800));
+        assert_eq!(entry.added_by(), "on-call");
+
+        let entry = BlocklistEntry::new(2, 0, "configmap", 1_000);
+        assert_eq!(entry.expires_at(), None);
+        assert!(!entry.is_expired(u64::MAX));
+    }
+
+    #[test]
+    fn test_format_cidr() {
+        assert_eq!(
+            format_cidr(IpAddr::from_str("10.0.0.1").unwrap(), 32),
+            "10.0.0.1"
+        );
+        assert_eq!(
+            format_cidr(IpAddr::from_str("10.0.0.0").unwrap(), 8),
+            "10.0.0.0/8"
+        );
+        assert_eq!(
+            format_cidr(IpAddr::from_str("fd00::").unwrap(), 64),
+            "fd00::/64"
+        );
+    }
+}
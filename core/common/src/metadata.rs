@@ -1,4 +1,11 @@
+#[cfg(feature = "monitoring-structs")]
+use crate::process_table::process_table;
+use std::collections::HashMap;
 use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 /// Detected container runtime.
 #[derive(Debug, Clone, PartialEq)]
@@ -39,17 +46,61 @@ impl Metadata {
         }
     }
 
+    /// Build base metadata from a network namespace inode number.
+    ///
+    /// Used for the events raised in the softirq context (e.g. listener
+    /// overflows), where the current task is unrelated to the socket. The
+    /// process is the first one whose `/proc/<pid>/ns/net` link points to
+    /// `net:[<netns>]`; without a match only an empty metadata is returned.
+    pub fn from_netns(netns: u32) -> Self {
+        let Some(tgid) = find_netns_tgid(netns) else {
+            return Self::from_ebpf(None, &[]);
+        };
+        let command = fs::read_to_string(format!("/proc/{}/comm", tgid)).unwrap_or_default();
+        Self::from_ebpf(Some(tgid), command.trim_end().as_bytes())
+    }
+
+    /// Build base metadata from a cgroup v2 id.
+    ///
+    /// Used for the values aggregated in the kernel per cgroup (e.g. the log2
+    /// histograms), where there is no process to read `/proc/<tgid>/cgroup`
+    /// from. The container is resolved from the path of the cgroup.
+    pub fn from_cgroup_id(cgroup_id: u64) -> Self {
+        let mut metadata = Self::from_ebpf(None, &[]);
+        if let Some(cgroup_path) = find_cgroup_path(cgroup_id) {
+            metadata.resolve_container(&cgroup_path);
+        }
+        metadata
+    }
+
     /// Lookup rules: first Docker (filesystem), then Kubernetes (API).
     ///
-    /// 1. Reads `/proc/<tgid>/cgroup`.
+    /// 1. Reads the cgroup of the process from the process table, or from `/proc/<tgid>/cgroup`
+    ///    when the process is not in the table.
     /// 2. Extracts the container ID from the cgroup path.
     /// 3. Tries to resolve the container name from `/var/lib/docker/containers/<id>/config.v2.json`.
     /// 4. If Docker is not found, attempts K8s lookup.
     pub fn enrich(&mut self) {
+        #[cfg(feature = "monitoring-structs")]
+        if self.try_resolve_process_table() {
+            return;
+        }
         self.try_resolve_docker();
         // K8s lookup will be enabled later with an LRU cache.
     }
 
+    /// Resolution from the process table, which still knows the processes
+    /// that exited before their events were read.
+    #[cfg(feature = "monitoring-structs")]
+    fn try_resolve_process_table(&mut self) -> bool {
+        let Some(tgid) = self.tgid else { return false };
+        let Some(cgroup_path) = process_table().cgroup_path(tgid) else {
+            return false;
+        };
+        self.resolve_container(&cgroup_path);
+        true
+    }
+
     /// Docker resolution via local filesystem.
     ///
     // TODO: this is working for Linux, can anyone check if this works on macOs systems ?
@@ -76,6 +127,11 @@ impl Metadata {
             return;
         }
 
+        self.resolve_container(cgroup_path);
+    }
+
+    /// Resolve the container that owns a cgroup path.
+    fn resolve_container(&mut self, cgroup_path: &str) {
         // Step 2: extract container ID from the path
         if let Some(id) = extract_container_id_from_path(cgroup_path) {
             self.container_id = Some(id.clone());
@@ -127,6 +183,95 @@ fn extract_container_id_from_path(cgroup_path: &str) -> Option<String> {
     None
 }
 
+/// Minimum time between two `/proc` scans of [`find_netns_tgid`].
+const NETNS_RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Network namespace inode number -> tgid of a process in the namespace.
+#[derive(Default)]
+struct NetnsCache {
+    tgids: HashMap<u32, u32>,
+    scanned_at: Option<Instant>,
+}
+
+static NETNS_CACHE: LazyLock<Mutex<NetnsCache>> = LazyLock::new(Mutex::default);
+
+/// Find a process that lives in the network namespace with inode number `netns`.
+///
+/// The namespaces of every process are cached by a single `/proc` scan. The
+/// cache is rebuilt when a namespace is unknown or its process moved or
+/// exited, at most once every [`NETNS_RESCAN_INTERVAL`].
+fn find_netns_tgid(netns: u32) -> Option<u32> {
+    let mut cache = NETNS_CACHE.lock().unwrap();
+    if let Some(&tgid) = cache.tgids.get(&netns)
+        && read_netns(tgid) == Some(netns)
+    {
+        return Some(tgid);
+    }
+    if cache
+        .scanned_at
+        .is_some_and(|scanned_at| scanned_at.elapsed() < NETNS_RESCAN_INTERVAL)
+    {
+        return None;
+    }
+
+    let mut tgids = HashMap::new();
+    if let Ok(entries) = fs::read_dir("/proc") {
+        for tgid in
+            entries.filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        {
+            if let Some(ns) = read_netns(tgid) {
+                tgids.entry(ns).or_insert(tgid);
+            }
+        }
+    }
+    cache.tgids = tgids;
+    cache.scanned_at = Some(Instant::now());
+    cache.tgids.get(&netns).copied()
+}
+
+/// Read the network namespace inode number of a process (`/proc/<tgid>/ns/net` is `net:[<inode>]`).
+fn read_netns(tgid: u32) -> Option<u32> {
+    let link = fs::read_link(format!("/proc/{}/ns/net", tgid)).ok()?;
+    link.to_str()?
+        .strip_prefix("net:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Find the path of the cgroup v2 with id `cgroup_id`, relative to
+/// `/sys/fs/cgroup` (same format as `/proc/<tgid>/cgroup`).
+///
+/// The cgroup id returned by `bpf_get_current_cgroup_id` is the inode number
+/// of the cgroup directory.
+pub fn find_cgroup_path(cgroup_id: u64) -> Option<String> {
+    fn walk(dir: &Path, cgroup_id: u64) -> Option<String> {
+        for entry in fs::read_dir(dir).ok()?.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_dir() {
+                continue;
+            }
+            if metadata.ino() == cgroup_id {
+                let path = entry.path();
+                let relative = path.strip_prefix("/sys/fs/cgroup").ok()?;
+                return Some(format!("/{}", relative.display()));
+            }
+            if let Some(path) = walk(&entry.path(), cgroup_id) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    let root = Path::new("/sys/fs/cgroup");
+    if fs::metadata(root).ok()?.ino() == cgroup_id {
+        return Some("/".to_string());
+    }
+    walk(root, cgroup_id)
+}
+
 /// Resolve a Docker container name by reading `config.v2.json`.
 ///
 // TODO: Does this work on macOs?
@@ -178,6 +323,20 @@ mod tests {
         let path = "/sys/fs/cgroup/system.slice/systemd-journald.service";
         assert_eq!(extract_container_id_from_path(path), None);
     }
+
+    #[test]
+    fn test_find_netns_tgid_own_namespace() {
+        let link = fs::read_link("/proc/self/ns/net").expect("Cannot read the network namespace");
+        let netns = link
+            .to_string_lossy()
+            .trim_start_matches("net:[")
+            .trim_end_matches(']')
+            .parse::<u32>()
+            .expect("Cannot parse the network namespace inode");
+        assert!(find_netns_tgid(netns).is_some());
+        assert_eq!(find_netns_tgid(0), None);
+    }
+
     #[test]
     fn resolve_docker_name_test() {
         use std::process::Command;
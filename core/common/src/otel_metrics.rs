@@ -11,11 +11,29 @@
 //!   extracted from the eBPF struct via [`Metadata`].
 
 use crate::buffer_type::{
-    CpuFrequency, CpuIdle, MemAlloc, PacketLossMetrics, SchedStatRuntime, SchedStatWait, SslEvent,
-    TimeStampMetrics,
+    CpuFrequency, CpuIdle, DnsEvent, MemAlloc, PacketLossMetrics, SchedStatRuntime, SchedStatWait,
+    SslEvent, TcpHealthEvent, TcpSocketEvent, TimeStampMetrics,
+};
+use crate::buffer_type::{DNS_RCODE_NXDOMAIN, DNS_RCODE_SERVFAIL};
+#[cfg(feature = "network-structs")]
+use crate::buffer_type::{
+    LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW, LISTENER_EVENT_MEMORY_PRESSURE,
+    LISTENER_EVENT_SYN_QUEUE_OVERFLOW, ListenerEvent,
+};
+use crate::buffer_type::{PROCESS_EVENT_EXEC, PROCESS_EVENT_EXIT, ProcessEvent};
+use crate::buffer_type::{TCP_EVENT_RESET_RECEIVED, TCP_EVENT_RESET_SENT, TCP_EVENT_RETRANSMIT};
+use crate::constants::HIST_SLOTS;
+#[cfg(feature = "map-handlers")]
+use crate::flows::FlowDelta;
+#[cfg(feature = "map-handlers")]
+use crate::histograms::{
+    HIST_SCHED_RUNTIME, HIST_SCHED_WAIT, HIST_TCP_LATENCY, HistogramKey, Log2Histogram,
 };
 use crate::metadata::{ContainerRuntime, Metadata};
+use crate::process_table::ProcessInfo;
 use crate::semantic::Semantic;
+#[cfg(feature = "map-handlers")]
+use crate::throughput::ThroughputDelta;
 use opentelemetry::KeyValue;
 use opentelemetry::metrics::{Counter, Gauge, Histogram, Meter};
 
@@ -66,6 +84,93 @@ pub struct Metrics {
 
     pub ssl_read_bytes: Gauge<i64>,
     pub ssl_write_bytes: Gauge<i64>,
+
+    /// Total number of packets dropped by the `identity_classifier`,
+    /// labelled by drop reason.
+    pub classifier_drops_total: Counter<u64>,
+
+    /// Packets seen by the `identity_classifier`, aggregated per flow in the
+    /// `flow_table` map.
+    pub flow_packets_total: Counter<u64>,
+
+    /// Bytes seen by the `identity_classifier`, aggregated per flow in the
+    /// `flow_table` map.
+    pub flow_bytes_total: Counter<u64>,
+
+    /// Number of flows currently stored in the `flow_table` map.
+    pub active_flows: Gauge<i64>,
+
+    /// Retransmitted TCP segments (`tcp_retransmit_skb`).
+    pub tcp_retransmits_total: Counter<u64>,
+
+    /// TCP resets sent by the local stack (`tcp_send_active_reset`).
+    pub tcp_resets_sent_total: Counter<u64>,
+
+    /// TCP resets received from the peers (`tcp_reset`).
+    pub tcp_resets_received_total: Counter<u64>,
+
+    /// Connections dropped because the accept queue of the listener was full.
+    pub tcp_accept_queue_overflows_total: Counter<u64>,
+
+    /// SYNs received while the SYN queue of the listener was full.
+    pub tcp_syn_queue_overflows_total: Counter<u64>,
+
+    /// Transitions of the TCP stack to the memory pressure state.
+    pub tcp_memory_pressure_total: Counter<u64>,
+
+    /// Distribution of the DNS query latencies in microseconds.
+    pub dns_latency_us: Histogram<u64>,
+
+    /// DNS responses with the NXDOMAIN response code.
+    pub dns_nxdomain_total: Counter<u64>,
+
+    /// DNS responses with the SERVFAIL response code.
+    pub dns_servfail_total: Counter<u64>,
+
+    /// Distribution of the smoothed RTT of the sampled TCP connections in
+    /// microseconds.
+    pub tcp_srtt_us: Histogram<u64>,
+
+    /// Distribution of the RTT mean deviation of the sampled TCP connections
+    /// in microseconds.
+    pub tcp_rtt_mdev_us: Histogram<u64>,
+
+    /// Distribution of the congestion window (segments) of the sampled TCP
+    /// connections.
+    pub tcp_snd_cwnd: Histogram<u64>,
+
+    /// Distribution of the retransmitted segments of the sampled TCP
+    /// connections.
+    pub tcp_total_retrans: Histogram<u64>,
+
+    /// Bytes sent on the TCP sockets, accumulated in the `tcp_throughput`
+    /// map from the return value of `tcp_sendmsg`.
+    pub tcp_bytes_sent: Counter<u64>,
+
+    /// Bytes received on the TCP sockets, accumulated in the
+    /// `tcp_throughput` map by `tcp_cleanup_rbuf`.
+    pub tcp_bytes_received: Counter<u64>,
+
+    /// Scheduler wait time in nanoseconds, aggregated per cgroup in the
+    /// `log2_histograms` map.
+    pub sched_stat_wait_log2_ns: Histogram<u64>,
+
+    /// Scheduler runtime in nanoseconds, aggregated per cgroup in the
+    /// `log2_histograms` map.
+    pub sched_stat_runtime_log2_ns: Histogram<u64>,
+
+    /// TCP connect latency in microseconds, aggregated per cgroup in the
+    /// `log2_histograms` map.
+    pub tcp_latency_log2_us: Histogram<u64>,
+
+    /// Programs started, from the `sched_process_exec` tracepoint.
+    pub process_starts_total: Counter<u64>,
+
+    /// Process exits, from the `sched_process_exit` tracepoint.
+    pub process_exits_total: Counter<u64>,
+
+    /// Distribution of the process lifetimes in milliseconds.
+    pub process_lifetime_ms: Histogram<u64>,
 }
 
 // TODO: add identity metrics with TC classifier packet counts
@@ -180,6 +285,137 @@ impl Metrics {
             .with_description(Semantic::SslWriteBytes.description())
             .build();
 
+        // packets dropped by the identity classifier
+        let classifier_drops_total = meter
+            .u64_counter(Semantic::ClassifierDrops.title())
+            .with_description(Semantic::ClassifierDrops.description())
+            .build();
+
+        // per-flow packet and byte counters
+        let flow_packets_total = meter
+            .u64_counter(Semantic::FlowPackets.title())
+            .with_description(Semantic::FlowPackets.description())
+            .build();
+
+        let flow_bytes_total = meter
+            .u64_counter(Semantic::FlowBytes.title())
+            .with_description(Semantic::FlowBytes.description())
+            //.with_unit("bytes")
+            .build();
+
+        let active_flows = meter
+            .i64_gauge(Semantic::ActiveFlows.title())
+            .with_description(Semantic::ActiveFlows.description())
+            .build();
+
+        // tcp retransmissions and resets
+        let tcp_retransmits_total = meter
+            .u64_counter(Semantic::TcpRetransmits.title())
+            .with_description(Semantic::TcpRetransmits.description())
+            .build();
+
+        let tcp_resets_sent_total = meter
+            .u64_counter(Semantic::TcpResetsSent.title())
+            .with_description(Semantic::TcpResetsSent.description())
+            .build();
+
+        let tcp_resets_received_total = meter
+            .u64_counter(Semantic::TcpResetsReceived.title())
+            .with_description(Semantic::TcpResetsReceived.description())
+            .build();
+
+        // listening sockets overload and tcp memory pressure
+        let tcp_accept_queue_overflows_total = meter
+            .u64_counter(Semantic::TcpAcceptQueueOverflows.title())
+            .with_description(Semantic::TcpAcceptQueueOverflows.description())
+            .build();
+
+        let tcp_syn_queue_overflows_total = meter
+            .u64_counter(Semantic::TcpSynQueueOverflows.title())
+            .with_description(Semantic::TcpSynQueueOverflows.description())
+            .build();
+
+        let tcp_memory_pressure_total = meter
+            .u64_counter(Semantic::TcpMemoryPressure.title())
+            .with_description(Semantic::TcpMemoryPressure.description())
+            .build();
+
+        // dns
+        let dns_latency_us = meter
+            .u64_histogram(Semantic::DnsLatency.title())
+            .with_description(Semantic::DnsLatency.description())
+            .build();
+        let dns_nxdomain_total = meter
+            .u64_counter(Semantic::DnsNxdomain.title())
+            .with_description(Semantic::DnsNxdomain.description())
+            .build();
+        let dns_servfail_total = meter
+            .u64_counter(Semantic::DnsServfail.title())
+            .with_description(Semantic::DnsServfail.description())
+            .build();
+
+        // tcp connection health
+        let tcp_srtt_us = meter
+            .u64_histogram(Semantic::TcpSrtt.title())
+            .with_description(Semantic::TcpSrtt.description())
+            .build();
+        let tcp_rtt_mdev_us = meter
+            .u64_histogram(Semantic::TcpRttVariance.title())
+            .with_description(Semantic::TcpRttVariance.description())
+            .build();
+        let tcp_snd_cwnd = meter
+            .u64_histogram(Semantic::TcpSndCwnd.title())
+            .with_description(Semantic::TcpSndCwnd.description())
+            .build();
+        let tcp_total_retrans = meter
+            .u64_histogram(Semantic::TcpTotalRetrans.title())
+            .with_description(Semantic::TcpTotalRetrans.description())
+            .build();
+
+        // tcp throughput
+        let tcp_bytes_sent = meter
+            .u64_counter(Semantic::TcpBytesSent.title())
+            .with_description(Semantic::TcpBytesSent.description())
+            .build();
+        let tcp_bytes_received = meter
+            .u64_counter(Semantic::TcpBytesReceived.title())
+            .with_description(Semantic::TcpBytesReceived.description())
+            .build();
+
+        // in-kernel log2 histograms: the bucket of slot i ends at 2^i - 1
+        let log2_boundaries: Vec<f64> = (0..HIST_SLOTS - 1)
+            .map(|slot| ((1u64 << slot) - 1) as f64)
+            .collect();
+        let sched_stat_wait_log2_ns = meter
+            .u64_histogram(Semantic::SchedStatWaitLog2.title())
+            .with_description(Semantic::SchedStatWaitLog2.description())
+            .with_boundaries(log2_boundaries.clone())
+            .build();
+        let sched_stat_runtime_log2_ns = meter
+            .u64_histogram(Semantic::SchedStatRuntimeLog2.title())
+            .with_description(Semantic::SchedStatRuntimeLog2.description())
+            .with_boundaries(log2_boundaries.clone())
+            .build();
+        let tcp_latency_log2_us = meter
+            .u64_histogram(Semantic::TcpLatencyLog2.title())
+            .with_description(Semantic::TcpLatencyLog2.description())
+            .with_boundaries(log2_boundaries)
+            .build();
+
+        // processes
+        let process_starts_total = meter
+            .u64_counter(Semantic::ProcessStarts.title())
+            .with_description(Semantic::ProcessStarts.description())
+            .build();
+        let process_exits_total = meter
+            .u64_counter(Semantic::ProcessExits.title())
+            .with_description(Semantic::ProcessExits.description())
+            .build();
+        let process_lifetime_ms = meter
+            .u64_histogram(Semantic::ProcessLifetime.title())
+            .with_description(Semantic::ProcessLifetime.description())
+            .build();
+
         Self {
             events_total,
             socket_events_total,
@@ -197,6 +433,31 @@ impl Metrics {
             cpu_idle_state,
             ssl_read_bytes,
             ssl_write_bytes,
+            classifier_drops_total,
+            flow_packets_total,
+            flow_bytes_total,
+            active_flows,
+            tcp_retransmits_total,
+            tcp_resets_sent_total,
+            tcp_resets_received_total,
+            tcp_accept_queue_overflows_total,
+            tcp_syn_queue_overflows_total,
+            tcp_memory_pressure_total,
+            dns_latency_us,
+            dns_nxdomain_total,
+            dns_servfail_total,
+            tcp_srtt_us,
+            tcp_rtt_mdev_us,
+            tcp_snd_cwnd,
+            tcp_total_retrans,
+            tcp_bytes_sent,
+            tcp_bytes_received,
+            sched_stat_wait_log2_ns,
+            sched_stat_runtime_log2_ns,
+            tcp_latency_log2_us,
+            process_starts_total,
+            process_exits_total,
+            process_lifetime_ms,
         }
     }
 
@@ -262,6 +523,96 @@ impl Metrics {
         self.tcp_latency_us.record(m.delta_us, attrs);
     }
 
+    /// Record a single [`TcpSocketEvent`].
+    ///
+    /// Increments `events_total` and the counter that matches the event kind
+    /// (`tcp_retransmits_total`, `tcp_resets_sent_total` or
+    /// `tcp_resets_received_total`).
+    pub fn record_tcp_socket_event(&self, m: &TcpSocketEvent, metadata: &Metadata) {
+        let attrs = &self.build_attrs(metadata);
+
+        self.events_total.add(1, attrs);
+        match m.kind {
+            TCP_EVENT_RETRANSMIT => self.tcp_retransmits_total.add(1, attrs),
+            TCP_EVENT_RESET_SENT => self.tcp_resets_sent_total.add(1, attrs),
+            TCP_EVENT_RESET_RECEIVED => self.tcp_resets_received_total.add(1, attrs),
+            _ => {}
+        }
+    }
+
+    /// Record a single [`DnsEvent`].
+    ///
+    /// Observes the query latency in `dns_latency_us` (labelled with the
+    /// query type) and increments `dns_nxdomain_total` or
+    /// `dns_servfail_total` for the matching response codes. The query name
+    /// is only logged.
+    pub fn record_dns_event(&self, m: &DnsEvent, metadata: &Metadata) {
+        let mut attrs = self.build_attrs(metadata);
+        attrs.push(KeyValue::new("qtype", m.qtype_name()));
+
+        self.events_total.add(1, &attrs);
+        self.dns_latency_us.record(m.latency_ns / 1_000, &attrs);
+        match m.rcode {
+            DNS_RCODE_NXDOMAIN => self.dns_nxdomain_total.add(1, &attrs),
+            DNS_RCODE_SERVFAIL => self.dns_servfail_total.add(1, &attrs),
+            _ => {}
+        }
+    }
+
+    /// Record a [`ProcessEvent`] after the process table update.
+    ///
+    /// Increments `process_starts_total` for the exec events. For the exits
+    /// of a process increments `process_exits_total` (labelled with the exit
+    /// code and signal) and observes the lifetime when the start of the
+    /// process is known. Forks and thread exits are not recorded.
+    pub fn record_process_event(
+        &self,
+        m: &ProcessEvent,
+        process: &ProcessInfo,
+        metadata: &Metadata,
+    ) {
+        let mut attrs = self.build_attrs(metadata);
+        let ts_ns = m.ts_ns;
+
+        match m.kind {
+            PROCESS_EVENT_EXEC => {
+                self.events_total.add(1, &attrs);
+                self.process_starts_total.add(1, &attrs);
+            }
+            PROCESS_EVENT_EXIT => {
+                self.events_total.add(1, &attrs);
+                if process.start_time_ns < ts_ns {
+                    self.process_lifetime_ms
+                        .record((ts_ns - process.start_time_ns) / 1_000_000, &attrs);
+                }
+                let (status, signal) = m.exit_status();
+                attrs.push(KeyValue::new("exit.code", status as i64));
+                attrs.push(KeyValue::new("exit.signal", signal as i64));
+                self.process_exits_total.add(1, &attrs);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record a single [`TcpHealthEvent`].
+    ///
+    /// Observes the smoothed RTT, the RTT deviation, the congestion window
+    /// and the retransmissions of the connection. Observations are keyed by
+    /// the local pod metadata (resolved from the network namespace of the
+    /// socket). The remote address is unbounded, so as the ports it is only
+    /// logged.
+    pub fn record_tcp_health_event(&self, m: &TcpHealthEvent, metadata: &Metadata) {
+        let mut attrs = self.build_attrs(metadata);
+        attrs.push(KeyValue::new("af", m.af as i64));
+
+        self.events_total.add(1, &attrs);
+        self.tcp_srtt_us.record(m.srtt_us as u64, &attrs);
+        self.tcp_rtt_mdev_us.record(m.mdev_us as u64, &attrs);
+        self.tcp_snd_cwnd.record(m.snd_cwnd as u64, &attrs);
+        self.tcp_total_retrans
+            .record(m.total_retrans as u64, &attrs);
+    }
+
     /// Record a single [`CpuFrequency`] event.
     pub fn record_cpu_bytes_alloc(&self, m: &CpuFrequency, metadata: &Metadata) {
         let bytes_allocated = m.bytes_alloc;
@@ -336,4 +687,103 @@ impl Metrics {
         self.events_total.add(1, &attrs);
         self.ssl_write_bytes.record(m.size as i64, &attrs);
     }
+
+    /// Record the packets dropped by the classifier since the previous
+    /// scrape of the `classifier_counters` map.
+    ///
+    /// Adds to `classifier_drops_total`, labelled by drop reason.
+    #[cfg(feature = "map-handlers")]
+    pub fn record_classifier_drops(&self, reason: &str, count: u64) {
+        let attrs = [KeyValue::new("reason", reason.to_string())];
+
+        self.classifier_drops_total.add(count, &attrs);
+    }
+
+    /// Record a single [`ListenerEvent`].
+    ///
+    /// Increments the counter that matches the event kind. Observations are
+    /// labelled with the local (listening) port and the pod metadata resolved
+    /// from the network namespace of the socket.
+    #[cfg(feature = "network-structs")]
+    pub fn record_listener_event(&self, m: &ListenerEvent, metadata: &Metadata) {
+        let mut attrs = self.build_attrs(metadata);
+        attrs.push(KeyValue::new("port", m.lport as i64));
+        attrs.push(KeyValue::new("af", m.af as i64));
+
+        self.events_total.add(1, &attrs);
+        match m.kind {
+            LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW => {
+                self.tcp_accept_queue_overflows_total.add(1, &attrs)
+            }
+            LISTENER_EVENT_SYN_QUEUE_OVERFLOW => self.tcp_syn_queue_overflows_total.add(1, &attrs),
+            LISTENER_EVENT_MEMORY_PRESSURE => self.tcp_memory_pressure_total.add(1, &attrs),
+            _ => {}
+        }
+    }
+
+    /// Record the increments of a single flow since the previous scrape.
+    ///
+    /// Adds to `flow_packets_total` and `flow_bytes_total`. Addresses and
+    /// ports are not used as labels.
+    #[cfg(feature = "map-handlers")]
+    pub fn record_flow_delta(&self, d: &FlowDelta) {
+        let attrs = [
+            KeyValue::new("af", d.key.af as i64),
+            KeyValue::new("protocol", d.key.proto as i64),
+            KeyValue::new("direction", d.key.direction_name()),
+        ];
+
+        self.flow_packets_total.add(d.packets, &attrs);
+        self.flow_bytes_total.add(d.bytes, &attrs);
+    }
+
+    /// Record the bytes sent and received by a TCP socket since the previous
+    /// scrape.
+    ///
+    /// Adds to `tcp_bytes_sent` and `tcp_bytes_received`. As for the health
+    /// events, observations are keyed by the local pod metadata (resolved
+    /// from the network namespace of the socket); the remote address is not
+    /// used as a label.
+    #[cfg(feature = "map-handlers")]
+    pub fn record_tcp_throughput_delta(&self, d: &ThroughputDelta, metadata: &Metadata) {
+        let mut attrs = self.build_attrs(metadata);
+        attrs.push(KeyValue::new("af", d.socket.af as i64));
+
+        self.tcp_bytes_sent.add(d.bytes_sent, &attrs);
+        self.tcp_bytes_received.add(d.bytes_received, &attrs);
+    }
+
+    /// Record a log2 histogram read from the `log2_histograms` map.
+    ///
+    /// The instrument boundaries match the log2 slots, so every slot is
+    /// converted into the same OpenTelemetry bucket. The recorded values are
+    /// the [`Log2Histogram::samples`]: the bucket counts and the sum match
+    /// the kernel histogram.
+    #[cfg(feature = "map-handlers")]
+    pub fn record_log2_histogram(
+        &self,
+        key: &HistogramKey,
+        hist: &Log2Histogram,
+        metadata: &Metadata,
+    ) {
+        let instrument = match key.kind {
+            HIST_SCHED_WAIT => &self.sched_stat_wait_log2_ns,
+            HIST_SCHED_RUNTIME => &self.sched_stat_runtime_log2_ns,
+            HIST_TCP_LATENCY => &self.tcp_latency_log2_us,
+            _ => return,
+        };
+        let mut attrs = self.build_attrs(metadata);
+        attrs.push(KeyValue::new("cgroup.id", key.cgroup_id as i64));
+
+        for (value, count) in hist.samples() {
+            for _ in 0..count {
+                instrument.record(value, &attrs);
+            }
+        }
+    }
+
+    /// Record the number of flows currently tracked in the `flow_table` map.
+    pub fn record_active_flows(&self, count: usize) {
+        self.active_flows.record(count as i64, &[]);
+    }
 }
@@ -0,0 +1,292 @@
+//! User-space process table fed by the `metrics_tracer` process lifecycle tracepoints.
+//!
+//! The `sched_process_fork`, `sched_process_exec` and `sched_process_exit`
+//! tracepoints send a [`ProcessEvent`] for every new task, new program and
+//! exit. The [`ProcessTable`] keeps the processes seen so far (seeded from
+//! `/proc` at startup) with their cgroup, so [`Metadata::enrich`] can resolve
+//! the container of a short-lived process after `/proc/<tgid>` is gone.
+//!
+//! The exited processes are kept for [`EXITED_PROCESS_TTL`], because the
+//! events of a process can be read from the other buffers after its exit
+//! event, and then removed by [`ProcessTable::reap`].
+//!
+//! [`Metadata::enrich`]: crate::metadata::Metadata::enrich
+
+use crate::buffer_type::{
+    PROCESS_EVENT_EXEC, PROCESS_EVENT_EXIT, PROCESS_EVENT_FORK, ProcessEvent,
+};
+use crate::metadata::find_cgroup_path;
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long an exited process stays in the table.
+pub const EXITED_PROCESS_TTL: Duration = Duration::from_secs(60);
+
+/// Clock ticks per second of the `/proc/<pid>/stat` times (`USER_HZ`, 100 on
+/// every supported architecture).
+const USER_HZ: u64 = 100;
+
+/// Cached cgroup paths above which the cache is reset.
+const MAX_CGROUP_PATHS: usize = 4096;
+
+static PROCESS_TABLE: LazyLock<ProcessTable> = LazyLock::new(ProcessTable::new);
+
+/// Process table shared by the event consumers.
+pub fn process_table() -> &'static ProcessTable {
+    &PROCESS_TABLE
+}
+
+/// A process in the [`ProcessTable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub comm: String,
+    /// Path of the executable: the path passed to `execve`, or `/proc/<pid>/exe`
+    /// for the processes found at startup. Inherited from the parent after a fork.
+    pub exe: Option<String>,
+    pub cgroup_id: u64,
+    /// Start time in nanoseconds since boot (kernel monotonic clock).
+    pub start_time_ns: u64,
+    /// When the exit event was processed, `None` while the process runs.
+    pub exited_at: Option<Instant>,
+}
+
+/// Processes by tgid, updated from the [`ProcessEvent`]s.
+#[derive(Default)]
+pub struct ProcessTable {
+    processes: RwLock<HashMap<u32, ProcessInfo>>,
+    cgroup_paths: Mutex<HashMap<u64, Option<String>>>,
+}
+
+impl ProcessTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the processes already running, read from `proc_root` (the host
+    /// `/proc`). Returns the number of added processes.
+    pub fn seed_from_proc(&self, proc_root: &Path) -> usize {
+        let Ok(entries) = fs::read_dir(proc_root) else {
+            return 0;
+        };
+
+        let running: Vec<ProcessInfo> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+                self.read_proc(&entry.path(), pid)
+            })
+            .collect();
+
+        // the processes already added by an event are more recent
+        let mut processes = self.processes.write().unwrap();
+        let before = processes.len();
+        for process in running {
+            processes.entry(process.pid).or_insert(process);
+        }
+        processes.len() - before
+    }
+
+    /// Build a [`ProcessInfo`] from `/proc/<pid>`. The cgroup path is cached.
+    fn read_proc(&self, dir: &Path, pid: u32) -> Option<ProcessInfo> {
+        // format: pid (comm) state ppid ... starttime (field 22)
+        let stat = fs::read_to_string(dir.join("stat")).ok()?;
+        let comm_start = stat.find('(')?;
+        let comm_end = stat.rfind(')')?;
+        let comm = stat.get(comm_start + 1..comm_end)?.to_string();
+        let fields: Vec<&str> = stat.get(comm_end + 1..)?.split_whitespace().collect();
+        let ppid = fields.get(1)?.parse::<u32>().ok()?;
+        let start_ticks = fields.get(19)?.parse::<u64>().ok()?;
+
+        let exe = fs::read_link(dir.join("exe"))
+            .ok()
+            .map(|exe| exe.to_string_lossy().to_string());
+
+        let cgroup_id = fs::read_to_string(dir.join("cgroup"))
+            .ok()
+            .and_then(|cgroup| {
+                let cgroup_path = cgroup.lines().find_map(|line| line.strip_prefix("0::"))?;
+                let id = fs::metadata(format!("/sys/fs/cgroup{}", cgroup_path))
+                    .ok()?
+                    .ino();
+                self.cache_cgroup_path(id, Some(cgroup_path.to_string()));
+                Some(id)
+            })
+            .unwrap_or(0);
+
+        Some(ProcessInfo {
+            pid,
+            ppid,
+            comm,
+            exe,
+            cgroup_id,
+            start_time_ns: start_ticks * (1_000_000_000 / USER_HZ),
+            exited_at: None,
+        })
+    }
+
+    /// Update the table with an event.
+    ///
+    /// Returns the process after the update, or `None` for the thread exits
+    /// and the unknown kinds. An exited process is marked and kept until
+    /// [`ProcessTable::reap`] removes it.
+    pub fn apply(&self, event: &ProcessEvent) -> Option<ProcessInfo> {
+        let (tgid, pid, ppid) = (event.tgid, event.pid, event.ppid);
+        let new_process = || ProcessInfo {
+            pid: tgid,
+            ppid,
+            comm: event.command(),
+            exe: None,
+            cgroup_id: event.cgroup_id,
+            start_time_ns: event.ts_ns,
+            exited_at: None,
+        };
+
+        let mut processes = self.processes.write().unwrap();
+        match event.kind {
+            PROCESS_EVENT_FORK => {
+                let mut process = new_process();
+                process.exe = processes.get(&ppid).and_then(|parent| parent.exe.clone());
+                processes.insert(tgid, process.clone());
+                Some(process)
+            }
+            PROCESS_EVENT_EXEC => {
+                let process = processes.entry(tgid).or_insert_with(new_process);
+                process.comm = event.command();
+                process.exe = event.filename();
+                process.cgroup_id = event.cgroup_id;
+                if ppid != 0 {
+                    process.ppid = ppid;
+                }
+                Some(process.clone())
+            }
+            PROCESS_EVENT_EXIT if pid != tgid => {
+                // the fork event of a thread created an entry keyed by the thread id
+                processes.remove(&pid);
+                None
+            }
+            PROCESS_EVENT_EXIT => {
+                let process = processes.entry(tgid).or_insert_with(new_process);
+                process.exited_at = Some(Instant::now());
+                Some(process.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the process with the given tgid.
+    pub fn get(&self, tgid: u32) -> Option<ProcessInfo> {
+        self.processes.read().unwrap().get(&tgid).cloned()
+    }
+
+    /// Return the cgroup path (relative to `/sys/fs/cgroup`, the format of
+    /// `/proc/<tgid>/cgroup`) of the process with the given tgid.
+    ///
+    /// The path is resolved from the cgroup id once per cgroup.
+    pub fn cgroup_path(&self, tgid: u32) -> Option<String> {
+        let cgroup_id = self.get(tgid)?.cgroup_id;
+        if cgroup_id == 0 {
+            return None;
+        }
+        if let Some(cgroup_path) = self.cgroup_paths.lock().unwrap().get(&cgroup_id) {
+            return cgroup_path.clone();
+        }
+
+        // walk the cgroup tree without holding the lock
+        let cgroup_path = find_cgroup_path(cgroup_id);
+        self.cache_cgroup_path(cgroup_id, cgroup_path.clone());
+        cgroup_path
+    }
+
+    fn cache_cgroup_path(&self, cgroup_id: u64, cgroup_path: Option<String>) {
+        let mut cgroup_paths = self.cgroup_paths.lock().unwrap();
+        if cgroup_paths.len() >= MAX_CGROUP_PATHS {
+            cgroup_paths.clear();
+        }
+        cgroup_paths.insert(cgroup_id, cgroup_path);
+    }
+
+    /// Remove the processes that exited more than `ttl` ago. Returns the
+    /// number of removed processes.
+    pub fn reap(&self, ttl: Duration) -> usize {
+        let mut processes = self.processes.write().unwrap();
+        let before = processes.len();
+        processes.retain(|_, process| match process.exited_at {
+            Some(exited_at) => exited_at.elapsed() < ttl,
+            None => true,
+        });
+        before - processes.len()
+    }
+
+    /// Number of processes in the table, exited processes included.
+    pub fn len(&self) -> usize {
+        self.processes.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: u8, tgid: u32, pid: u32, ppid: u32, comm: &[u8]) -> ProcessEvent {
+        let mut event: ProcessEvent = bytemuck::Zeroable::zeroed();
+        event.kind = kind;
+        event.tgid = tgid;
+        event.pid = pid;
+        event.ppid = ppid;
+        event.comm[..comm.len()].copy_from_slice(comm);
+        event.cgroup_id = 42;
+        event.ts_ns = 1_000;
+        event
+    }
+
+    #[test]
+    fn test_process_lifecycle() {
+        let table = ProcessTable::new();
+
+        let mut exec = event(PROCESS_EVENT_EXEC, 100, 100, 1, b"sh");
+        exec.filename[..7].copy_from_slice(b"/bin/sh");
+        table.apply(&exec);
+
+        // fork of a process and of a thread, then exec of a short-lived command
+        let child = table
+            .apply(&event(PROCESS_EVENT_FORK, 101, 101, 100, b"sh"))
+            .unwrap();
+        assert_eq!(child.exe.as_deref(), Some("/bin/sh"));
+        table.apply(&event(PROCESS_EVENT_FORK, 102, 102, 100, b"sh"));
+        let mut exec = event(PROCESS_EVENT_EXEC, 101, 101, 0, b"curl");
+        exec.filename[..13].copy_from_slice(b"/usr/bin/curl");
+        let child = table.apply(&exec).unwrap();
+        assert_eq!(child.comm, "curl");
+        assert_eq!(child.ppid, 100);
+        assert_eq!(child.start_time_ns, 1_000);
+
+        // the thread 102 of the process 100 exits, then the child exits
+        assert!(
+            table
+                .apply(&event(PROCESS_EVENT_EXIT, 100, 102, 1, b"sh"))
+                .is_none()
+        );
+        let mut exit = event(PROCESS_EVENT_EXIT, 101, 101, 100, b"curl");
+        exit.exit_code = 7 << 8;
+        assert_eq!(exit.exit_status(), (7, 0));
+        assert!(table.apply(&exit).unwrap().exited_at.is_some());
+        assert_eq!(table.len(), 2);
+
+        // the exited process is still resolved until it is reaped
+        assert_eq!(table.get(101).unwrap().cgroup_id, 42);
+        assert_eq!(table.reap(EXITED_PROCESS_TTL), 0);
+        assert_eq!(table.reap(Duration::ZERO), 1);
+        assert!(table.get(101).is_none());
+        assert!(table.get(100).is_some());
+    }
+}
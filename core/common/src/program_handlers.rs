@@ -1,10 +1,24 @@
+use crate::constants::PERF_OBJECT_SUFFIX;
 use aya::{
     Ebpf,
-    programs::{KProbe, TracePoint, UProbe},
+    programs::{KProbe, RawTracePoint, TracePoint, UProbe},
 };
 use std::convert::TryInto;
 use std::sync::{Arc, Mutex};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+const BPF_MAP_CREATE: libc::c_long = 0;
+const BPF_MAP_TYPE_RINGBUF: u32 = 27;
+
+// leading fields of the BPF_MAP_CREATE variant of union bpf_attr
+#[repr(C)]
+struct MapCreateAttr {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+}
 
 #[cfg(feature = "program-handlers")]
 pub fn load_program(
@@ -99,6 +113,52 @@ pub fn load_tracepoint_program(
     Ok(())
 }
 
+#[cfg(feature = "program-handlers")]
+pub fn load_raw_tracepoint_program(
+    bpf: Arc<Mutex<Ebpf>>,
+    program_name: &str,
+    tracepoint_name: &str,
+) -> Result<(), anyhow::Error> {
+    let mut bpf_new = bpf
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Cannot get value from lock. Reason: {}", e))?;
+
+    // Load and attach the eBPF program
+    let program: &mut RawTracePoint = bpf_new
+        .program_mut(program_name)
+        .ok_or_else(|| anyhow::anyhow!("Program {} not found", program_name))?
+        .try_into()
+        .map_err(|e| anyhow::anyhow!("Failed to convert program: {:?}", e))?;
+
+    // STEP 1: load program
+
+    program
+        .load()
+        .map_err(|e| anyhow::anyhow!("Cannot load program: {}. Error: {}", &program_name, e))?;
+
+    // STEP 2: Attach the loaded program to the raw tracepoint
+    match program.attach(tracepoint_name) {
+        Ok(_) => info!(
+            "{} program attached successfully to raw tracepoint {}",
+            &program_name, &tracepoint_name
+        ),
+        Err(e) => {
+            error!(
+                "Error attaching {} program to raw tracepoint {}. Reason: {:?}",
+                &program_name, &tracepoint_name, e
+            );
+            return Err(anyhow::anyhow!(
+                "Failed to attach program {} to raw tracepoint {}. Reason {:?}",
+                &program_name,
+                &tracepoint_name,
+                e
+            ));
+        }
+    };
+
+    Ok(())
+}
+
 #[cfg(feature = "program-handlers")]
 pub fn load_uprobe_program(
     bpf: Arc<Mutex<Ebpf>>,
@@ -146,3 +206,53 @@ pub fn load_uprobe_program(
 
     Ok(())
 }
+
+/// Probes the running kernel for `BPF_MAP_TYPE_RINGBUF` support (Linux 5.8+)
+/// by creating a one page ring buffer and closing it right away.
+#[cfg(feature = "program-handlers")]
+pub fn ringbuf_supported() -> bool {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    let attr = MapCreateAttr {
+        map_type: BPF_MAP_TYPE_RINGBUF,
+        key_size: 0,
+        value_size: 0,
+        max_entries: page_size.max(4096) as u32,
+        map_flags: 0,
+    };
+
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_MAP_CREATE,
+            &attr as *const MapCreateAttr,
+            std::mem::size_of::<MapCreateAttr>() as libc::c_uint,
+        )
+    };
+    if fd < 0 {
+        warn!(
+            "Cannot create a BPF ring buffer. Reason: {}",
+            std::io::Error::last_os_error()
+        );
+        return false;
+    }
+
+    unsafe { libc::close(fd as libc::c_int) };
+    true
+}
+
+/// Returns the eBPF object to load for the running kernel: `bpf_path` itself
+/// (ring buffer maps) or its perf event array variant (`bpf_path` + `-perf`).
+#[cfg(feature = "program-handlers")]
+pub fn resolve_bpf_object_path(bpf_path: &str) -> String {
+    if ringbuf_supported() {
+        info!("BPF ring buffers supported, loading {}", bpf_path);
+        return bpf_path.to_string();
+    }
+
+    let perf_path = format!("{}{}", bpf_path, PERF_OBJECT_SUFFIX);
+    info!(
+        "BPF ring buffers not supported, loading the perf event array object {}",
+        perf_path
+    );
+    perf_path
+}
@@ -0,0 +1,210 @@
+//! Token bucket rate limits shared with the `identity_classifier` eBPF program.
+//!
+//! This module contains:
+//! - [`RateLimit`], the C-compatible value stored in the pinned `RateLimits`/`RateLimitsV6` LPM tries.
+//! - Helpers to parse and format the rate limit scope.
+//! - [`RateLimitMaps`], a wrapper around the pinned tries used by the agent api.
+//!
+//! A rate limit is expressed as (CIDR, packets per second, burst, scope). The classifier
+//! checks the remote address (the source address on ingress) against the tries after the
+//! blocklist and drops the packets that exceed the token bucket of the matching prefix.
+
+use crate::constants::{AF_INET, AF_INET6};
+use crate::map_handlers::{format_cidr, parse_cidr};
+use anyhow::{Error, anyhow};
+use aya::maps::lpm_trie::{Key, LpmTrie};
+use aya::maps::{Map, MapData};
+use bytemuck_derive::Zeroable;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// One token bucket for every source address in the prefix.
+pub const RATE_LIMIT_SCOPE_SOURCE: u8 = 0;
+/// One token bucket shared by the whole prefix.
+pub const RATE_LIMIT_SCOPE_CIDR: u8 = 1;
+
+/// Rate limit layout shared with the conntracker `RateLimit` struct.
+///
+/// `rate` is the refill rate in packets per second and `burst` the size of
+/// the bucket in packets. The network address is stored as four host-order
+/// words (IPv4 limits use only the first word) so the classifier can key the
+/// per-CIDR buckets without the LPM key.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Zeroable)]
+pub struct RateLimit {
+    pub rate: u32,
+    pub burst: u32,
+    pub af: u16,
+    pub scope: u8,
+    pub prefix_len: u8,
+    pub net: [u32; 4],
+}
+unsafe impl aya::Pod for RateLimit {}
+
+impl RateLimit {
+    /// Build a rate limit for a single host or a CIDR range.
+    ///
+    /// A zero `burst` defaults to one second worth of packets (`rate`).
+    pub fn new(cidr: &str, rate: u32, burst: u32, scope: u8) -> Result<Self, Error> {
+        if rate == 0 {
+            return Err(anyhow!(
+                "The rate must be greater than 0 packets per second"
+            ));
+        }
+        if scope > RATE_LIMIT_SCOPE_CIDR {
+            return Err(anyhow!("Invalid rate limit scope {}", scope));
+        }
+        let burst = if burst == 0 { rate } else { burst };
+
+        let (af, net, prefix_len) = match parse_cidr(cidr)? {
+            (IpAddr::V4(ip), prefix_len) => (AF_INET, [u32::from(ip), 0, 0, 0], prefix_len),
+            (IpAddr::V6(ip), prefix_len) => {
+                let value = u128::from(ip);
+                let net = [
+                    (value >> 96) as u32,
+                    (value >> 64) as u32,
+                    (value >> 32) as u32,
+                    value as u32,
+                ];
+                (AF_INET6, net, prefix_len)
+            }
+        };
+
+        Ok(RateLimit {
+            rate,
+            burst,
+            af,
+            scope,
+            prefix_len: prefix_len as u8,
+            net,
+        })
+    }
+
+    /// Network address of the rate limit.
+    pub fn addr(&self) -> IpAddr {
+        match self.af {
+            AF_INET => IpAddr::V4(Ipv4Addr::from(self.net[0])),
+            _ => {
+                let net = ((self.net[0] as u128) << 96)
+                    | ((self.net[1] as u128) << 64)
+                    | ((self.net[2] as u128) << 32)
+                    | self.net[3] as u128;
+                IpAddr::V6(Ipv6Addr::from(net))
+            }
+        }
+    }
+
+    /// Rate limited prefix formatted as a single host or a CIDR range.
+    pub fn cidr(&self) -> String {
+        format_cidr(self.addr(), self.prefix_len as u32)
+    }
+}
+
+/// Parse a rate limit scope (`"source"` or `"cidr"`).
+pub fn parse_scope(input: &str) -> Result<u8, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "source" | "per-source" | "ip" => Ok(RATE_LIMIT_SCOPE_SOURCE),
+        "cidr" | "per-cidr" | "prefix" => Ok(RATE_LIMIT_SCOPE_CIDR),
+        _ => Err(anyhow!(
+            "Unknown rate limit scope {:?}. Expected source or cidr",
+            input
+        )),
+    }
+}
+
+/// Format a rate limit scope as returned by [`parse_scope`].
+pub fn format_scope(scope: u8) -> String {
+    match scope {
+        RATE_LIMIT_SCOPE_SOURCE => "source".to_string(),
+        RATE_LIMIT_SCOPE_CIDR => "cidr".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Wrapper around the pinned `RateLimits` (IPv4) and `RateLimitsV6` LPM tries.
+pub struct RateLimitMaps {
+    pub v4: LpmTrie<MapData, [u8; 4], RateLimit>,
+    pub v6: LpmTrie<MapData, [u8; 16], RateLimit>,
+}
+
+impl RateLimitMaps {
+    /// Load both the rate limit tries from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/maps/RateLimits")
+            .map_err(|e| anyhow!("Failed to load RateLimits map: {}", e))?;
+        let v4 = LpmTrie::<_, [u8; 4], RateLimit>::try_from(Map::LpmTrie(mapdata))?;
+
+        let mapdata_v6 = MapData::from_pin("/sys/fs/bpf/maps/RateLimitsV6")
+            .map_err(|e| anyhow!("Failed to load RateLimitsV6 map: {}", e))?;
+        let v6 = LpmTrie::<_, [u8; 16], RateLimit>::try_from(Map::LpmTrie(mapdata_v6))?;
+
+        Ok(RateLimitMaps { v4, v6 })
+    }
+
+    /// Insert or replace the rate limit of a prefix.
+    ///
+    /// The buckets already created by the classifier keep their credit and
+    /// are refilled with the new rate.
+    pub fn insert(&mut self, limit: RateLimit) -> Result<(), Error> {
+        let prefix_len = limit.prefix_len as u32;
+        match limit.addr() {
+            IpAddr::V4(ip) => {
+                self.v4
+                    .insert(&Key::new(prefix_len, ip.octets()), limit, 0)?;
+            }
+            IpAddr::V6(ip) => {
+                self.v6
+                    .insert(&Key::new(prefix_len, ip.octets()), limit, 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the rate limit of a prefix. The entry must match the inserted prefix.
+    pub fn remove(&mut self, cidr: &str) -> Result<(), Error> {
+        match parse_cidr(cidr)? {
+            (IpAddr::V4(ip), prefix_len) => {
+                self.v4.remove(&Key::new(prefix_len, ip.octets()))?;
+            }
+            (IpAddr::V6(ip), prefix_len) => {
+                self.v6.remove(&Key::new(prefix_len, ip.octets()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return every configured rate limit, IPv4 first.
+    pub fn list(&self) -> Result<Vec<RateLimit>, Error> {
+        let mut limits = Vec::new();
+        for item in self.v4.iter() {
+            let (_, limit) = item?;
+            limits.push(limit);
+        }
+        for item in self.v6.iter() {
+            let (_, limit) = item?;
+            limits.push(limit);
+        }
+        Ok(limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_new() {
+        let limit = RateLimit::new("10.1.2.3/16", 100, 0, RATE_LIMIT_SCOPE_CIDR).unwrap();
+        assert_eq!(limit.af, AF_INET);
+        assert_eq!(limit.burst, 100);
+        assert_eq!(limit.cidr(), "10.1.0.0/16");
+        assert_eq!(format_scope(limit.scope), "cidr");
+
+        let limit = RateLimit::new("fd00::1", 10, 50, parse_scope("source").unwrap()).unwrap();
+        assert_eq!(limit.af, AF_INET6);
+        assert_eq!(limit.prefix_len, 128);
+        assert_eq!(limit.cidr(), "fd00::1");
+
+        assert!(RateLimit::new("10.0.0.0/8", 0, 10, RATE_LIMIT_SCOPE_SOURCE).is_err());
+        assert!(parse_scope("host").is_err());
+    }
+}
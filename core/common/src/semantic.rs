@@ -17,6 +17,31 @@ pub enum Semantic {
     CpuIdleState,
     SslReadBytes,
     SslWriteBytes,
+    ClassifierDrops,
+    FlowPackets,
+    FlowBytes,
+    ActiveFlows,
+    TcpRetransmits,
+    TcpResetsSent,
+    TcpResetsReceived,
+    TcpAcceptQueueOverflows,
+    TcpSynQueueOverflows,
+    TcpMemoryPressure,
+    DnsLatency,
+    DnsNxdomain,
+    DnsServfail,
+    TcpSrtt,
+    TcpRttVariance,
+    TcpSndCwnd,
+    TcpTotalRetrans,
+    TcpBytesSent,
+    TcpBytesReceived,
+    SchedStatWaitLog2,
+    SchedStatRuntimeLog2,
+    TcpLatencyLog2,
+    ProcessStarts,
+    ProcessExits,
+    ProcessLifetime,
 }
 
 impl Semantic {
@@ -38,6 +63,31 @@ impl Semantic {
             Semantic::CpuIdleState => "cpu_idle_state",
             Semantic::SslReadBytes => "ssl_read_bytes",
             Semantic::SslWriteBytes => "ssl_write_bytes",
+            Semantic::ClassifierDrops => "classifier_drops_total",
+            Semantic::FlowPackets => "flow_packets_total",
+            Semantic::FlowBytes => "flow_bytes_total",
+            Semantic::ActiveFlows => "active_flows",
+            Semantic::TcpRetransmits => "tcp_retransmits_total",
+            Semantic::TcpResetsSent => "tcp_resets_sent_total",
+            Semantic::TcpResetsReceived => "tcp_resets_received_total",
+            Semantic::TcpAcceptQueueOverflows => "tcp_accept_queue_overflows_total",
+            Semantic::TcpSynQueueOverflows => "tcp_syn_queue_overflows_total",
+            Semantic::TcpMemoryPressure => "tcp_memory_pressure_total",
+            Semantic::DnsLatency => "dns_latency_us",
+            Semantic::DnsNxdomain => "dns_nxdomain_total",
+            Semantic::DnsServfail => "dns_servfail_total",
+            Semantic::TcpSrtt => "tcp_srtt_us",
+            Semantic::TcpRttVariance => "tcp_rtt_mdev_us",
+            Semantic::TcpSndCwnd => "tcp_snd_cwnd",
+            Semantic::TcpTotalRetrans => "tcp_total_retrans",
+            Semantic::TcpBytesSent => "tcp_bytes_sent_total",
+            Semantic::TcpBytesReceived => "tcp_bytes_received_total",
+            Semantic::SchedStatWaitLog2 => "sched_stat_wait_log2_ns",
+            Semantic::SchedStatRuntimeLog2 => "sched_stat_runtime_log2_ns",
+            Semantic::TcpLatencyLog2 => "tcp_latency_log2_us",
+            Semantic::ProcessStarts => "process_starts_total",
+            Semantic::ProcessExits => "process_exits_total",
+            Semantic::ProcessLifetime => "process_lifetime_ms",
         }
     }
     pub fn description(&self) -> &'static str {
@@ -72,6 +122,59 @@ impl Semantic {
             }
             Semantic::SslReadBytes => "Total bytes requested by the ssl_read function",
             Semantic::SslWriteBytes => "Total bytes requested by the ssl_write function",
+            Semantic::ClassifierDrops => {
+                "Total number of packets dropped by the identity classifier, labelled by reason"
+            }
+            Semantic::FlowPackets => "Total number of packets seen by the identity classifier",
+            Semantic::FlowBytes => "Total number of bytes seen by the identity classifier",
+            Semantic::ActiveFlows => "Number of flows tracked in the flow_table map",
+            Semantic::TcpRetransmits => "Total number of retransmitted TCP segments",
+            Semantic::TcpResetsSent => "Total number of TCP resets sent by the local stack",
+            Semantic::TcpResetsReceived => "Total number of TCP resets received from the peers",
+            Semantic::TcpAcceptQueueOverflows => {
+                "Total number of connections dropped because the listener accept queue was full"
+            }
+            Semantic::TcpSynQueueOverflows => {
+                "Total number of SYNs received with a full listener SYN queue (SYN cookies or drops)"
+            }
+            Semantic::TcpMemoryPressure => {
+                "Total number of times the TCP stack entered the memory pressure state"
+            }
+            Semantic::DnsLatency => "Distribution of the DNS query latencies (query to response)",
+            Semantic::DnsNxdomain => {
+                "Total number of DNS responses with the NXDOMAIN response code"
+            }
+            Semantic::DnsServfail => {
+                "Total number of DNS responses with the SERVFAIL response code"
+            }
+            Semantic::TcpSrtt => {
+                "Distribution of the smoothed RTT of the established TCP connections"
+            }
+            Semantic::TcpRttVariance => {
+                "Distribution of the RTT mean deviation of the established TCP connections"
+            }
+            Semantic::TcpSndCwnd => {
+                "Distribution of the congestion window (in segments) of the established TCP connections"
+            }
+            Semantic::TcpTotalRetrans => {
+                "Distribution of the retransmitted segments per established TCP connection"
+            }
+            Semantic::TcpBytesSent => "Bytes sent on the TCP sockets, keyed by local pod",
+            Semantic::TcpBytesReceived => "Bytes received on the TCP sockets, keyed by local pod",
+            Semantic::SchedStatWaitLog2 => {
+                "Distribution of the scheduler wait time per cgroup, aggregated in the kernel"
+            }
+            Semantic::SchedStatRuntimeLog2 => {
+                "Distribution of the scheduler runtime per cgroup, aggregated in the kernel"
+            }
+            Semantic::TcpLatencyLog2 => {
+                "Distribution of the TCP connect latency per cgroup, aggregated in the kernel"
+            }
+            Semantic::ProcessStarts => "Total number of programs started (execve)",
+            Semantic::ProcessExits => {
+                "Total number of process exits, keyed by exit code and signal"
+            }
+            Semantic::ProcessLifetime => "Distribution of the process lifetimes (start to exit)",
         }
     }
 }
@@ -0,0 +1,226 @@
+//! Per-socket TCP throughput accumulated by the `metrics_tracer` eBPF programs.
+//!
+//! The `tcp_sendmsg_ret_tracer` kretprobe (bytes queued by `tcp_sendmsg`)
+//! and the `tcp_cleanup_rbuf_tracer` kprobe add the bytes sent and received
+//! by every socket to the pinned `tcp_throughput` per-cpu LRU map, keyed by
+//! the socket cookie. This module contains:
+//! - [`TcpThroughput`], the C-compatible value layout shared with the metrics_tracer crate.
+//! - [`ThroughputTable`], a wrapper around the pinned map that merges the per-cpu values.
+//! - [`ThroughputTracker`], which diffs two consecutive scrapes so the
+//!   counters can be exported as monotonic increments.
+
+use crate::formatters::{format_ip_port, format_ip_words};
+use anyhow::{Error, anyhow};
+use aya::maps::{Map, MapData, PerCpuHashMap};
+use bytemuck_derive::Zeroable;
+use std::collections::HashMap;
+
+/// Throughput layout shared with the metrics_tracer `TcpThroughput` struct.
+///
+/// `saddr` is the local end of the socket and `daddr` the remote end, both
+/// stored as four host-order words (IPv4 sockets use only the first word).
+/// `netns` is the network namespace inode of the socket, used to resolve the
+/// local pod.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Zeroable)]
+pub struct TcpThroughput {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub saddr: [u32; 4],
+    pub daddr: [u32; 4],
+    pub netns: u32,
+    pub af: u16,
+    pub lport: u16,
+    pub dport: u16,
+    pub _padding: [u8; 6],
+}
+unsafe impl aya::Pod for TcpThroughput {}
+
+impl TcpThroughput {
+    /// Merge the values of another cpu into `self`.
+    ///
+    /// Only the cpu that created the entry fills the socket fields, the
+    /// other cpus start from a zeroed value.
+    pub fn merge(&mut self, other: &TcpThroughput) {
+        if self.af == 0 {
+            self.saddr = other.saddr;
+            self.daddr = other.daddr;
+            self.netns = other.netns;
+            self.af = other.af;
+            self.lport = other.lport;
+            self.dport = other.dport;
+        }
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+    }
+
+    /// True if both values belong to the same connection (same family and
+    /// 4-tuple). A socket freed and reused by a new connection keeps its key.
+    pub fn same_connection(&self, other: &TcpThroughput) -> bool {
+        self.af == other.af
+            && self.lport == other.lport
+            && self.dport == other.dport
+            && self.saddr == other.saddr
+            && self.daddr == other.daddr
+    }
+
+    /// Remote address formatted according to `af`.
+    pub fn peer(&self) -> String {
+        format_ip_words(self.af, &self.daddr)
+    }
+
+    /// Local `ip:port` formatted according to `af`.
+    pub fn src(&self) -> String {
+        format_ip_port(self.af, &format_ip_words(self.af, &self.saddr), self.lport)
+    }
+
+    /// Remote `ip:port` formatted according to `af`.
+    pub fn dst(&self) -> String {
+        format_ip_port(self.af, &self.peer(), self.dport)
+    }
+}
+
+/// Bytes sent and received by a socket since the previous scrape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThroughputDelta {
+    pub socket: TcpThroughput,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// Wrapper around the pinned `tcp_throughput` per-cpu LRU hash map.
+pub struct ThroughputTable {
+    pub sockets: PerCpuHashMap<MapData, u64, TcpThroughput>,
+}
+
+impl ThroughputTable {
+    /// Load the `tcp_throughput` map from the bpf filesystem.
+    pub fn from_pin() -> Result<Self, Error> {
+        let mapdata = MapData::from_pin("/sys/fs/bpf/trace_maps/tcp_throughput")
+            .map_err(|e| anyhow!("Failed to load tcp_throughput map: {}", e))?;
+        let sockets =
+            PerCpuHashMap::<_, u64, TcpThroughput>::try_from(Map::PerCpuLruHashMap(mapdata))?;
+        Ok(ThroughputTable { sockets })
+    }
+
+    /// Return every socket in the map with the per-cpu values merged.
+    pub fn snapshot(&self) -> Result<HashMap<u64, TcpThroughput>, Error> {
+        let mut snapshot = HashMap::new();
+        for item in self.sockets.iter() {
+            let (cookie, values) = item?;
+            let mut stats = TcpThroughput::default();
+            for value in values.iter() {
+                stats.merge(value);
+            }
+            snapshot.insert(cookie, stats);
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Diffs consecutive [`ThroughputTable::snapshot`] results.
+///
+/// Sockets evicted from the LRU map are forgotten. A socket that is evicted
+/// and seen again starts from zero, so its first delta is its full count. The
+/// same applies to a key reused by a new connection (different 4-tuple).
+#[derive(Default)]
+pub struct ThroughputTracker {
+    previous: HashMap<u64, TcpThroughput>,
+}
+
+impl ThroughputTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the increments since the previous call and remember `snapshot`.
+    ///
+    /// Sockets without new traffic are not returned.
+    pub fn diff(&mut self, snapshot: HashMap<u64, TcpThroughput>) -> Vec<ThroughputDelta> {
+        let mut deltas = Vec::new();
+        for (cookie, stats) in snapshot.iter() {
+            let (bytes_sent, bytes_received) = match self.previous.get(cookie) {
+                // the counters went backwards or the connection changed: the entry was evicted
+                // or replaced and created again
+                Some(prev)
+                    if prev.same_connection(stats)
+                        && stats.bytes_sent >= prev.bytes_sent
+                        && stats.bytes_received >= prev.bytes_received =>
+                {
+                    (
+                        stats.bytes_sent - prev.bytes_sent,
+                        stats.bytes_received - prev.bytes_received,
+                    )
+                }
+                _ => (stats.bytes_sent, stats.bytes_received),
+            };
+            if bytes_sent > 0 || bytes_received > 0 {
+                deltas.push(ThroughputDelta {
+                    socket: *stats,
+                    bytes_sent,
+                    bytes_received,
+                });
+            }
+        }
+        self.previous = snapshot;
+        deltas
+    }
+
+    /// Number of sockets seen in the last snapshot.
+    pub fn active_sockets(&self) -> usize {
+        self.previous.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::AF_INET;
+
+    fn socket(bytes_sent: u64, bytes_received: u64) -> TcpThroughput {
+        TcpThroughput {
+            bytes_sent,
+            bytes_received,
+            saddr: [0x0a000001, 0, 0, 0],
+            daddr: [0x0a000002, 0, 0, 0],
+            netns: 4026531840,
+            af: AF_INET,
+            lport: 40000,
+            dport: 6379,
+            _padding: [0; 6],
+        }
+    }
+
+    #[test]
+    fn test_throughput_merge_and_diff() {
+        // the second cpu has no socket fields
+        let mut merged = TcpThroughput::default();
+        merged.merge(&TcpThroughput {
+            bytes_sent: 10,
+            ..TcpThroughput::default()
+        });
+        merged.merge(&socket(100, 50));
+        assert_eq!(merged.bytes_sent, 110);
+        assert_eq!(merged.bytes_received, 50);
+        assert_eq!(merged.dst(), "10.0.0.2:6379");
+
+        let mut tracker = ThroughputTracker::new();
+        let deltas = tracker.diff(HashMap::from([(1, socket(100, 50))]));
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].bytes_sent, 100);
+
+        // only the received bytes changed, then the entry was evicted and created again
+        let deltas = tracker.diff(HashMap::from([(1, socket(100, 80))]));
+        assert_eq!((deltas[0].bytes_sent, deltas[0].bytes_received), (0, 30));
+        let deltas = tracker.diff(HashMap::from([(1, socket(20, 0))]));
+        assert_eq!((deltas[0].bytes_sent, deltas[0].bytes_received), (20, 0));
+        assert!(tracker.diff(HashMap::from([(1, socket(20, 0))])).is_empty());
+        assert_eq!(tracker.active_sockets(), 1);
+
+        // the socket memory was reused by a connection to another peer
+        let mut reused = socket(500, 0);
+        reused.dport = 443;
+        let deltas = tracker.diff(HashMap::from([(1, reused)]));
+        assert_eq!((deltas[0].bytes_sent, deltas[0].bytes_received), (500, 0));
+    }
+}
@@ -15,6 +15,11 @@ fi
 
 bindgen vmlinux.h -o src/bindings.rs --use-core --allowlist-type 'sk_buff' --opaque-type 'ieee80211_sband_iftype_data'
 
+# BPF ring buffers are available since kernel 5.8. Build the perf event array variant too,
+# the userspace service picks the object supported by the running kernel at load time
+cargo +nightly build -Z build-std=core --target bpfel-unknown-none --release --bin conntracker --no-default-features
+cp ../../../target/bpfel-unknown-none/release/conntracker ../../../target/bpfel-unknown-none/release/conntracker-perf
+
 cargo +nightly build -Z build-std=core --target bpfel-unknown-none --release --bin conntracker
 
 rm -f vmlinux.h
\ No newline at end of file
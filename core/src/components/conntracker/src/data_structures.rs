@@ -1,17 +1,47 @@
 use aya_ebpf::{
+    bindings::BPF_F_NO_PREALLOC,
     macros::map,
-    maps::{HashMap, LruPerCpuHashMap, PerfEventArray},
+    maps::{
+        Array, HashMap, LpmTrie, LruHashMap, LruPerCpuHashMap, PerCpuArray, PerfEventArray, RingBuf,
+    },
+    EbpfContext,
 };
 
+pub const AF_INET: u16 = 2;
+pub const AF_INET6: u16 = 10;
+
+// traffic direction of a classified packet. Set by the tc hook (ingress or egress) that ran the classifier
+pub const DIRECTION_INGRESS: u8 = 0;
+pub const DIRECTION_EGRESS: u8 = 1;
+
+// L4 protocol numbers (include/uapi/linux/in.h)
+//
+// only TCP, UDP, UDP-Lite and SCTP carry ports. For ICMP and ICMPv6 the type and the code are reported in the
+// dst_port field as (type << 8) | code (the netflow/ipfix convention), src_port is zero. Every other protocol is
+// reported with both ports set to zero
+pub const IPPROTO_ICMP: u8 = 1;
+pub const IPPROTO_TCP: u8 = 6;
+pub const IPPROTO_UDP: u8 = 17;
+pub const IPPROTO_ICMPV6: u8 = 58;
+pub const IPPROTO_SCTP: u8 = 132;
+pub const IPPROTO_UDPLITE: u8 = 136;
+
 // docs:
 // PacketLog structure used to track an incoming network packet
 //
 // proto: packet protol (ex. TCP,UDP,ICMP)
-// src_ip: source address ip
+// src_ip: source address ip (ipv4 packets only)
 // src_port: source address port
-// dst_ip: destination ip
+// dst_ip: destination ip (ipv4 packets only)
 // dst_port: destination port
 // pid: kernel process ID
+// af: address family (AF_INET=2, AF_INET6=10)
+// src_ip_v6: source address ip as four host order words (ipv6 packets only)
+// dst_ip_v6: destination address ip as four host order words (ipv6 packets only)
+// direction: DIRECTION_INGRESS or DIRECTION_EGRESS
+// ifindex: index of the interface the packet was seen on
+// vni: VXLAN/Geneve network identifier of the tunnel that carried the packet, 0 for not encapsulated packets.
+//      For encapsulated packets the addresses, the ports and the protocol are read from the inner headers
 //
 
 #[repr(C)]
@@ -23,17 +53,57 @@ pub struct PacketLog {
     pub dst_ip: u32,
     pub dst_port: u16,
     pub pid: u32,
+    pub af: u16,
+    pub src_ip_v6: [u32; 4],
+    pub dst_ip_v6: [u32; 4],
+    pub direction: u8,
+    pub ifindex: u32,
+    pub vni: u32,
 }
 
-// This structure is only for active connections (TODO: investigate if this is really useful)
+// docs:
+// FlowKey structure used as key in the FLOW_TABLE map. Identifies a flow by its 5-tuple and direction
+//
+// af: address family (AF_INET, AF_INET6)
+// proto: L4 protocol
+// direction: DIRECTION_INGRESS or DIRECTION_EGRESS
+// src_port: source port
+// dst_port: destination port (ICMP type and code for ICMP/ICMPv6, see the IPPROTO_* constants)
+// src_ip: source address as four host order words (ipv4 uses only the first word)
+// dst_ip: destination address as four host order words (ipv4 uses only the first word)
+//
+
 #[repr(C)]
 #[derive(Clone, Copy)]
-pub struct ConnArray {
-    pub src_ip: u32,
-    pub dst_ip: u32,
+pub struct FlowKey {
+    pub af: u16,
+    pub proto: u8,
+    pub direction: u8,
     pub src_port: u16,
     pub dst_port: u16,
-    pub proto: u8,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+}
+
+// docs:
+// FlowStats structure used as value in the FLOW_TABLE map. Aggregates the packets of a flow seen on one cpu
+//
+// packets: number of packets
+// bytes: number of bytes (skb len, L2 header included)
+// first_seen_ns: bpf_ktime_get_ns() of the first packet
+// last_seen_ns: bpf_ktime_get_ns() of the last packet
+// ifindex: index of the interface the last packet was seen on
+//
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FlowStats {
+    pub packets: u64,
+    pub bytes: u64,
+    pub first_seen_ns: u64,
+    pub last_seen_ns: u64,
+    pub ifindex: u32,
+    pub _padding: u32,
 }
 
 // docs:
@@ -58,7 +128,23 @@ pub struct VethLog {
     pub pid: u32,          // 4 bytes: PID that triggered the event
 }
 
-// TODO: write documentation about this structure
+// docs:
+// TcpPacketRegistry structure emitted by the tcp registry kprobes (tcp_v4_rcv, tcp_v6_rcv, tcp_v4_connect and
+// tcp_v6_connect, see tcp_analyzer.rs) and by the udp socket kprobes (udp_sendmsg, udp_recvmsg, udpv6_sendmsg and
+// udpv6_recvmsg, see udp_tracer.rs)
+//
+// proto: L4 protocol (IPPROTO_TCP or IPPROTO_UDP)
+// src_ip/dst_ip: ipv4 source and destination addresses in network order (AF_INET only)
+// src_port/dst_port: source and destination ports
+// pid: thread group id of the current task
+// command: command of the current task
+// cgroup_id: cgroup id of the current task
+// af: address family (AF_INET, AF_INET6)
+// src_ip_v6/dst_ip_v6: ipv6 source and destination addresses as four host order words (AF_INET6 only)
+//
+// the af, src_ip_v6 and dst_ip_v6 fields are appended to the original ipv4 layout, like in PacketLog
+//
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct TcpPacketRegistry {
@@ -70,36 +156,606 @@ pub struct TcpPacketRegistry {
     pub pid: u32,
     pub command: [u8; 16],
     pub cgroup_id: u64,
+    pub af: u16,
+    pub src_ip_v6: [u32; 4],
+    pub dst_ip_v6: [u32; 4],
+}
+
+// docs:
+// TcpConnection structure used as value in the TCP_CONNECTIONS map. Describes an established tcp connection
+// The key of the map is the kernel address of the socket (struct sock *)
+//
+// established_ns: bpf_ktime_get_ns() of the transition to TCP_ESTABLISHED
+// src_ip: local address as four host order words (ipv4 uses only the first word)
+// dst_ip: remote address as four host order words (ipv4 uses only the first word)
+// af: address family (AF_INET, AF_INET6)
+// src_port: local port
+// dst_port: remote port
+// direction: DIRECTION_EGRESS for the connections opened by the node (SYN_SENT -> ESTABLISHED),
+//            DIRECTION_INGRESS for the accepted connections (SYN_RECV -> ESTABLISHED)
+//
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TcpConnection {
+    pub established_ns: u64,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+    pub af: u16,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub direction: u8,
+    pub _padding: u8,
+}
+
+// docs:
+// TcpConnEvent structure emitted by the tcp_state_tracer when a tcp connection is established or closed
+//
+// skaddr: kernel address of the socket. Pairs the established and the closed events of the same connection
+// ts_ns: bpf_ktime_get_ns() of the state transition
+// duration_ns: time spent since the transition to TCP_ESTABLISHED (closed events only)
+// bytes_sent: bytes acked by the peer (tcp_sock->bytes_acked, closed events only)
+// bytes_received: bytes received from the peer (tcp_sock->bytes_received, closed events only)
+// src_ip/dst_ip/af/src_port/dst_port/direction: see TcpConnection
+// event_type: CONN_EVENT_ESTABLISHED or CONN_EVENT_CLOSED
+// close_reason: CLOSE_REASON_FIN, CLOSE_REASON_RST or CLOSE_REASON_FAILED (closed events only)
+//
+// the close reason is inferred from the state that precedes TCP_CLOSE: the FIN handshake always goes through
+// FIN_WAIT*, CLOSING, LAST_ACK or TIME_WAIT while a reset moves the socket to TCP_CLOSE from any other state
+//
+
+pub const CONN_EVENT_ESTABLISHED: u8 = 1;
+pub const CONN_EVENT_CLOSED: u8 = 2;
+pub const CLOSE_REASON_FIN: u8 = 1;
+pub const CLOSE_REASON_RST: u8 = 2;
+pub const CLOSE_REASON_FAILED: u8 = 3; // the handshake did not complete
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TcpConnEvent {
+    pub skaddr: u64,
+    pub ts_ns: u64,
+    pub duration_ns: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+    pub af: u16,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub direction: u8,
+    pub event_type: u8,
+    pub close_reason: u8,
+    pub _padding: [u8; 7],
+}
+
+// docs:
+// ListenerEvent structure emitted by the tcp_backlog programs when a listening socket is overloaded or the tcp
+// stack enters the memory pressure state
+//
+// ts_ns: bpf_ktime_get_ns() of the event
+// netns: network namespace inode number of the socket. Used by the userspace to resolve the pod
+// tgid: thread group id of the current task. Only meaningful for the memory pressure events, the listener events
+//       are raised in the softirq context
+// backlog: accept queue length (sock->sk_ack_backlog)
+// max_backlog: accept queue limit (sock->sk_max_ack_backlog), the backlog argument of listen()
+// comm: command of the current task
+// lport: local port of the socket (listening port for the listener events)
+// af: address family (AF_INET, AF_INET6)
+// kind: LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW, LISTENER_EVENT_SYN_QUEUE_OVERFLOW or LISTENER_EVENT_MEMORY_PRESSURE
+//
+// LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW: the handshake completed but the accept queue is full, the connection is
+//                                       dropped (the client retransmits until it times out)
+// LISTENER_EVENT_SYN_QUEUE_OVERFLOW: the SYN queue is full. The listener answers with SYN cookies if
+//                                    net.ipv4.tcp_syncookies is enabled, otherwise the SYN is dropped
+// LISTENER_EVENT_MEMORY_PRESSURE: the tcp stack entered the memory pressure state (tcp_mem limits)
+//
+
+pub const LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW: u8 = 1;
+pub const LISTENER_EVENT_SYN_QUEUE_OVERFLOW: u8 = 2;
+pub const LISTENER_EVENT_MEMORY_PRESSURE: u8 = 3;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ListenerEvent {
+    pub ts_ns: u64,
+    pub netns: u32,
+    pub tgid: u32,
+    pub backlog: u32,
+    pub max_backlog: u32,
+    pub comm: [u8; 16],
+    pub lport: u16,
+    pub af: u16,
+    pub kind: u8,
+    pub _padding: [u8; 3],
+}
+
+// docs:
+// BlocklistEntry structure stored as value in the BLOCKLIST and BLOCKLIST_V6 tries
+// The same layout is mirrored in cortexbrain-common (map_handlers.rs) and written by the agent api. The classifier
+// and the identity_xdp program only check if the key is in the trie: the expiry is enforced by the userspace reaper
+// that removes the expired entries
+//
+// added_at: insertion time as unix timestamp in seconds
+// ttl_secs: lifetime of the entry in seconds (0 = the entry never expires)
+// rule_id: identifier of the entry assigned by the userspace
+// added_by: who inserted the entry (e.g. the cli user or "configmap"), zero padded
+//
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct BlocklistEntry {
+    pub added_at: u64,
+    pub ttl_secs: u64,
+    pub rule_id: u32,
+    pub _padding: u32,
+    pub added_by: [u8; 32],
+}
+
+// docs:
+// FilterRule structure used to express L4 aware filtering rules evaluated by the identity_classifier
+// The same layout is mirrored in cortexbrain-common (filter_rules.rs) and written by the agent api
+//
+// rule_id: rule identifier assigned by the userspace
+// enabled: 1 if the slot contains a rule, 0 if the slot is empty
+// action: RULE_ACTION_ALLOW, RULE_ACTION_DROP or RULE_ACTION_AUDIT (emit an AuditEvent without dropping)
+// proto: L4 protocol number (0 = any protocol)
+// af: address family (AF_INET, AF_INET6 or 0 = any family)
+// dst_port_start: first destination port of the range (inclusive). ICMP/ICMPv6 rules match on
+//                 (type << 8) | code (see the IPPROTO_* constants). "any" protocol rules with a port range
+//                 never match ICMP/ICMPv6 packets (see rule_matches in rules.rs)
+// dst_port_end: last destination port of the range (inclusive)
+// src_net/src_mask: source network and mask as four host order words (ipv4 uses only the first word)
+// dst_net/dst_mask: destination network and mask as four host order words (ipv4 uses only the first word)
+//
+// A zero mask matches every address, so a rule without a CIDR matches any source/destination
+//
+
+pub const RULE_ACTION_ALLOW: u8 = 0;
+pub const RULE_ACTION_DROP: u8 = 1;
+pub const RULE_ACTION_AUDIT: u8 = 2;
+pub const MAX_FILTER_RULES: u32 = 64;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FilterRule {
+    pub rule_id: u32,
+    pub enabled: u8,
+    pub action: u8,
+    pub proto: u8,
+    pub _padding: u8,
+    pub af: u16,
+    pub dst_port_start: u16,
+    pub dst_port_end: u16,
+    pub _padding2: u16,
+    pub src_net: [u32; 4],
+    pub src_mask: [u32; 4],
+    pub dst_net: [u32; 4],
+    pub dst_mask: [u32; 4],
+}
+
+// docs:
+// RateLimit structure used to express a token bucket rate limit evaluated by the identity_classifier
+// The same layout is mirrored in cortexbrain-common (rate_limits.rs) and written by the agent api. It is stored as
+// the value of the RATE_LIMITS/RATE_LIMITS_V6 tries, keyed by the rate limited prefix
+//
+// rate: refill rate of the bucket in packets per second
+// burst: size of the bucket in packets (how many packets can be sent back to back after an idle period)
+// af: address family of the prefix (AF_INET, AF_INET6)
+// scope: RATE_LIMIT_SCOPE_SOURCE (one bucket for every address in the prefix) or RATE_LIMIT_SCOPE_CIDR (one
+//        bucket shared by the whole prefix)
+// prefix_len: prefix length of the rate limited range
+// net: network address of the prefix as four host order words (ipv4 uses only the first word). Used as the bucket
+//      key for the RATE_LIMIT_SCOPE_CIDR scope since the LPM lookup returns only the value
+//
+
+pub const RATE_LIMIT_SCOPE_SOURCE: u8 = 0;
+pub const RATE_LIMIT_SCOPE_CIDR: u8 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RateLimit {
+    pub rate: u32,
+    pub burst: u32,
+    pub af: u16,
+    pub scope: u8,
+    pub prefix_len: u8,
+    pub net: [u32; 4],
+}
+
+// docs:
+// RateLimitKey structure used as key in the RATE_LIMIT_BUCKETS map
+//
+// af: address family (AF_INET, AF_INET6)
+// prefix_len: 32/128 for the per source buckets, the prefix length of the range for the per CIDR buckets
+// addr: source address (or network address of the range) as four host order words
+//
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RateLimitKey {
+    pub af: u16,
+    pub prefix_len: u16,
+    pub addr: [u32; 4],
+}
+
+// docs:
+// TokenBucket structure stored in the RATE_LIMIT_BUCKETS map
+//
+// credit_ns: available credit in nanoseconds. Every packet costs 1s / rate and the credit grows with the elapsed
+//            time up to burst * (1s / rate). Storing the tokens as time avoids the multiplications in the refill
+// last_ns: timestamp of the last refill (bpf_ktime_get_ns)
+//
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TokenBucket {
+    pub credit_ns: u64,
+    pub last_ns: u64,
+}
+
+// docs:
+// AuditEvent structure emitted by the identity_classifier when a packet would have been dropped but the
+// classifier is running in audit mode (or the matching rule has the RULE_ACTION_AUDIT action)
+//
+// af: address family (AF_INET, AF_INET6)
+// proto: L4 protocol
+// reason: AUDIT_REASON_BLOCKLIST, AUDIT_REASON_RULE or AUDIT_REASON_RATE_LIMIT
+// rule_id: id of the matching rule (0 for blocklist and rate limit hits)
+// ifindex: index of the interface that received the packet
+// src_port: source port
+// dst_port: destination port
+// src_ip: source address as four host order words (ipv4 uses only the first word)
+// dst_ip: destination address as four host order words (ipv4 uses only the first word)
+//
+
+pub const AUDIT_REASON_BLOCKLIST: u8 = 1;
+pub const AUDIT_REASON_RULE: u8 = 2;
+pub const AUDIT_REASON_RATE_LIMIT: u8 = 3;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AuditEvent {
+    pub af: u16,
+    pub proto: u8,
+    pub reason: u8,
+    pub rule_id: u32,
+    pub ifindex: u32,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+}
+
+// docs:
+// DropEvent structure emitted by the identity_classifier every time a packet is dropped (TC_ACT_SHOT)
+//
+// af: address family (AF_INET, AF_INET6). 0 if the packet was dropped before reading the ethertype
+// proto: L4 protocol
+// reason: DROP_REASON_BLOCKLIST, DROP_REASON_RULE, DROP_REASON_PARSE_ERROR, DROP_REASON_TRUNCATED_HEADER or
+//         DROP_REASON_RATE_LIMIT
+// rule_id: id of the matching rule (0 for every other reason)
+// ifindex: index of the interface that received the packet
+// src_port: source port
+// dst_port: destination port
+// src_ip: source address as four host order words (ipv4 uses only the first word)
+// dst_ip: destination address as four host order words (ipv4 uses only the first word)
+//
+// parse errors and truncated headers are reported with the address family only because the
+// 5-tuple is not available when the packet cannot be parsed
+//
+
+pub const DROP_REASON_BLOCKLIST: u8 = 1;
+pub const DROP_REASON_RULE: u8 = 2;
+pub const DROP_REASON_PARSE_ERROR: u8 = 3;
+pub const DROP_REASON_TRUNCATED_HEADER: u8 = 4;
+// 5 and 6 are already used by the COUNTER_* indexes: the drop reason is also the counter index
+pub const DROP_REASON_RATE_LIMIT: u8 = 7;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct DropEvent {
+    pub af: u16,
+    pub proto: u8,
+    pub reason: u8,
+    pub rule_id: u32,
+    pub ifindex: u32,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub src_ip: [u32; 4],
+    pub dst_ip: [u32; 4],
+}
+
+// docs:
+// classifier configuration indexes used in the CLASSIFIER_CONFIG array map
+//
+// CONFIG_ENFORCEMENT_MODE: MODE_ENFORCE (drop the packets) or MODE_AUDIT (emit an AuditEvent and pass the packets)
+// CONFIG_FAILURE_POLICY: FAILURE_POLICY_OPEN (pass the packets that cannot be parsed) or FAILURE_POLICY_CLOSED
+//                        (drop them). Fail-open is the default
+//
+
+pub const CONFIG_ENFORCEMENT_MODE: u32 = 0;
+pub const MODE_ENFORCE: u32 = 0;
+pub const MODE_AUDIT: u32 = 1;
+pub const CONFIG_FAILURE_POLICY: u32 = 1;
+pub const FAILURE_POLICY_OPEN: u32 = 0;
+pub const FAILURE_POLICY_CLOSED: u32 = 1;
+pub const MAX_CONFIG_ENTRIES: u32 = 8;
+
+// docs:
+// per reason counters indexes used in the CLASSIFIER_COUNTERS per cpu array map
+//
+// the indexes from 1 to 4 and 7 are the drop reasons (DROP_REASON_*) and count how many times each path fires,
+// regardless of the failure policy. Rate limited packets (7) are counted apart from the blocklist hits (1)
+// COUNTER_FAIL_OPEN: packets passed because of the fail-open policy (parse errors and truncated headers)
+// COUNTER_XDP_BLOCKLIST: packets dropped by the identity_xdp program (blocklist hits at the driver level)
+//
+
+pub const COUNTER_FAIL_OPEN: u32 = 5;
+pub const COUNTER_XDP_BLOCKLIST: u32 = 6;
+pub const MAX_CLASSIFIER_COUNTERS: u32 = 8;
+
+// docs:
+// kernel struct field offsets indexes used in the KERNEL_OFFSETS array map
+//
+// the offsets are resolved by the userspace from the running kernel BTF (/sys/kernel/btf/vmlinux) before the
+// programs are attached. The indexes must match the CONNTRACKER_KERNEL_FIELDS table in the common crate
+//
+// OFFSET_NET_DEVICE_NAME: net_device->name
+// OFFSET_NET_DEVICE_STATE: net_device->state
+// OFFSET_NET_DEVICE_DEV_ADDR: net_device->dev_addr
+// OFFSET_NET_DEVICE_ND_NET: net_device->nd_net.net (pointer to the network namespace)
+// OFFSET_NET_NS_INUM: net->ns.inum (network namespace inode number)
+// OFFSET_SKB_DATA: sk_buff->data
+// OFFSET_TCP_BYTES_ACKED: tcp_sock->bytes_acked
+// OFFSET_TCP_BYTES_RECEIVED: tcp_sock->bytes_received
+// OFFSET_SKC_NUM: sock->__sk_common.skc_num (local port, host order)
+// OFFSET_SKC_FAMILY: sock->__sk_common.skc_family
+// OFFSET_SKC_NET: sock->__sk_common.skc_net.net (pointer to the network namespace)
+// OFFSET_SK_ACK_BACKLOG: sock->sk_ack_backlog
+// OFFSET_SK_MAX_ACK_BACKLOG: sock->sk_max_ack_backlog
+// OFFSET_SKB_HEAD: sk_buff->head
+// OFFSET_SKB_NETWORK_HEADER: sk_buff->network_header (offset of the ip header from head)
+// OFFSET_SKB_TRANSPORT_HEADER: sk_buff->transport_header (offset of the tcp/udp header from head)
+// OFFSET_SKC_DADDR: sock->__sk_common.skc_daddr (remote ipv4 address, network order)
+// OFFSET_SKC_RCV_SADDR: sock->__sk_common.skc_rcv_saddr (local ipv4 address, network order)
+// OFFSET_SKC_DPORT: sock->__sk_common.skc_dport (remote port, network order)
+// OFFSET_SKC_V6_DADDR: sock->__sk_common.skc_v6_daddr (remote ipv6 address)
+// OFFSET_SKC_V6_RCV_SADDR: sock->__sk_common.skc_v6_rcv_saddr (local ipv6 address)
+// OFFSET_MSGHDR_MSG_NAME: msghdr->msg_name (destination sockaddr of sendmsg, NULL for connected sockets)
+// OFFSET_NET_DEVICE_DEV_ADDR_IS_PTR: 1 if net_device->dev_addr is a pointer to the address (kernel >= 5.17),
+//                                    0 if it is an inline array. This slot stores a flag, not an offset
+//
+// OFFSET_INET_SOCK_SET_STATE_*: offsets of the sock:inet_sock_set_state tracepoint record fields, resolved from
+//                               the tracepoint format file (/sys/kernel/tracing/events/sock/inet_sock_set_state/format)
+//                               instead of BTF. The indexes must match the CONNTRACKER_TRACEPOINT_FIELDS table
+//
+// request_sock starts with a sock_common too (__req_common), so the OFFSET_SKC_* offsets are valid for the
+// request sockets
+//
+
+pub const OFFSET_NET_DEVICE_NAME: u32 = 0;
+pub const OFFSET_NET_DEVICE_STATE: u32 = 1;
+pub const OFFSET_NET_DEVICE_DEV_ADDR: u32 = 2;
+pub const OFFSET_NET_DEVICE_ND_NET: u32 = 3;
+pub const OFFSET_NET_NS_INUM: u32 = 4;
+pub const OFFSET_SKB_DATA: u32 = 5;
+pub const OFFSET_TCP_BYTES_ACKED: u32 = 6;
+pub const OFFSET_TCP_BYTES_RECEIVED: u32 = 7;
+pub const OFFSET_SKC_NUM: u32 = 8;
+pub const OFFSET_SKC_FAMILY: u32 = 9;
+pub const OFFSET_SKC_NET: u32 = 10;
+pub const OFFSET_SK_ACK_BACKLOG: u32 = 11;
+pub const OFFSET_SK_MAX_ACK_BACKLOG: u32 = 12;
+pub const OFFSET_SKB_HEAD: u32 = 13;
+pub const OFFSET_SKB_NETWORK_HEADER: u32 = 14;
+pub const OFFSET_SKB_TRANSPORT_HEADER: u32 = 15;
+pub const OFFSET_SKC_DADDR: u32 = 16;
+pub const OFFSET_SKC_RCV_SADDR: u32 = 17;
+pub const OFFSET_SKC_DPORT: u32 = 18;
+pub const OFFSET_SKC_V6_DADDR: u32 = 19;
+pub const OFFSET_SKC_V6_RCV_SADDR: u32 = 20;
+pub const OFFSET_MSGHDR_MSG_NAME: u32 = 21;
+pub const OFFSET_NET_DEVICE_DEV_ADDR_IS_PTR: u32 = 22;
+pub const OFFSET_INET_SOCK_SET_STATE_SKADDR: u32 = 23;
+pub const OFFSET_INET_SOCK_SET_STATE_OLDSTATE: u32 = 24;
+pub const OFFSET_INET_SOCK_SET_STATE_NEWSTATE: u32 = 25;
+pub const OFFSET_INET_SOCK_SET_STATE_SPORT: u32 = 26;
+pub const OFFSET_INET_SOCK_SET_STATE_DPORT: u32 = 27;
+pub const OFFSET_INET_SOCK_SET_STATE_FAMILY: u32 = 28;
+pub const OFFSET_INET_SOCK_SET_STATE_PROTOCOL: u32 = 29;
+pub const OFFSET_INET_SOCK_SET_STATE_SADDR: u32 = 30;
+pub const OFFSET_INET_SOCK_SET_STATE_DADDR: u32 = 31;
+pub const OFFSET_INET_SOCK_SET_STATE_SADDR_V6: u32 = 32;
+pub const OFFSET_INET_SOCK_SET_STATE_DADDR_V6: u32 = 33;
+pub const MAX_KERNEL_OFFSETS: u32 = 64;
+
+// docs:
+//
+// Event maps
+//
+// The events are sent to the userspace through a BPF ring buffer (RingBuf) when the crate is built with the
+// "ringbuf" feature (default, requires kernel >= 5.8). Building with --no-default-features falls back to per cpu
+// perf event arrays for older kernels. The map names are the same in both cases and the userspace detects the
+// map type when it opens the pinned maps
+//
+// EventOutput hides the map type to the programs: call EVENTS.emit(ctx, &event) instead of output()
+//
+
+pub const RINGBUF_BYTE_SIZE: u32 = 256 * 1024; // must be a power of 2 multiple of the page size
+
+pub trait EventOutput<T> {
+    fn emit<C: EbpfContext>(&self, ctx: &C, event: &T);
+}
+
+impl<T> EventOutput<T> for PerfEventArray<T> {
+    #[inline(always)]
+    fn emit<C: EbpfContext>(&self, ctx: &C, event: &T) {
+        self.output(ctx, event, 0);
+    }
+}
+
+impl<T> EventOutput<T> for RingBuf {
+    #[inline(always)]
+    fn emit<C: EbpfContext>(&self, _ctx: &C, event: &T) {
+        // the event is lost if the ring buffer is full (same behaviour of a full perf buffer)
+        let _ = self.output(event, 0);
+    }
 }
 
 // docs:
 //
 // BPF maps used in the conntracker programs
 //
-// VETH_EVENTS: PerfEventArray used in the veth_tracer functions (veth_tracer.rs module)
+// EVENTS, VETH_EVENTS, AUDIT_EVENTS, DROP_EVENTS, PACKET_REGISTRY, TCP_CONN_EVENTS and LISTENER_EVENTS are event maps
+// (RingBuf or PerfEventArray, see EventOutput)
+//
+// VETH_EVENTS: event map used in the veth_tracer functions (veth_tracer.rs module)
+//
+// FLOW_TABLE: a per cpu LRU hash map that aggregates packets and bytes for every flow (FlowKey) seen by the
+//             identity_classifier. The userspace merges the per cpu values and scrapes the map periodically.
+//             The least recently used flows are evicted when the map is full
+//
+// BLOCKLIST: a longest prefix match trie used to block addresses and CIDR ranges. The key is the prefix length
+//            plus the network address in network order (a single host is a /32 prefix). The value is a
+//            BlocklistEntry with the insertion time, the ttl, the entry id and the author
+//
+// BLOCKLIST_V6: same as BLOCKLIST but for ipv6 addresses and ranges. Keys are the prefix length plus the 16 bytes
+//               of the address in network order (a single host is a /128 prefix)
+//
+// LPM tries require the BPF_F_NO_PREALLOC flag
+//
+// FILTER_RULES: an array of FilterRule evaluated in order by the identity_classifier. The first matching rule wins.
+//               Empty slots have enabled = 0
+//
+// RATE_LIMITS: a longest prefix match trie with the rate limited ipv4 prefixes. Same key as BLOCKLIST, the value is the
+//              RateLimit configuration of the prefix
+//
+// RATE_LIMITS_V6: same as RATE_LIMITS but for ipv6 prefixes
+//
+// RATE_LIMIT_BUCKETS: an LRU hash map with the token bucket state (TokenBucket) of every rate limited source or
+//                     range (RateLimitKey). The map is shared by every cpu: concurrent updates of the same bucket
+//                     can lose a few tokens, which is acceptable for a rate limit. Idle buckets are evicted first
+//
+// AUDIT_EVENTS: event map used to send the "would-have-dropped" events to the userspace
 //
-// BLOCKLIST: an hashmap used to block addresses -----> TODO: key and values are the same for semplicity but we need to
-//            investigate the possibility to save the service name or the timestamp registered when the command was executed or a simple int index
+// DROP_EVENTS: event map used to send the dropped packets (with the drop reason) to the userspace
+//
+// CLASSIFIER_CONFIG: an array of u32 values written by the userspace to configure the identity_classifier
+//                    (see the CONFIG_* indexes)
+//
+// CLASSIFIER_COUNTERS: a per cpu array of u64 counters incremented by the identity_classifier
+//                      (see the COUNTER_* indexes). The userspace sums the values of every cpu
+//
+// XDP_MODE: a hash map written by the userspace with the blocklist enforcement mode of every interface where the
+//           identity_xdp program has been requested ("native", "generic" or "tc" if XDP cannot be attached).
+//           The key is the interface name. Only read by the userspace (agent api)
+//
+// KERNEL_OFFSETS: an array of u32 kernel struct field offsets written by the userspace (see the OFFSET_* indexes)
+//
+// TCP_CONNECTIONS: an LRU hash map with the established tcp connections (TcpConnection) keyed by the socket
+//                  address. Written by the tcp_state_tracer and read by the agent api to list the active connections
+//
+// TCP_CONN_EVENTS: event map used to send the connection established/closed events (TcpConnEvent) to the userspace
+//
+// LISTENER_EVENTS: event map used to send the accept queue, SYN queue and memory pressure events (ListenerEvent)
+//                  to the userspace
 //
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "events_map", pinning = "by_name")]
+pub static mut EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "events_map", pinning = "by_name")]
 pub static mut EVENTS: PerfEventArray<PacketLog> = PerfEventArray::new(0);
 
-// FIXME: this might be useless
-#[map(name = "ConnectionTrackerMap")]
-pub static mut CONNTRACKER: LruPerCpuHashMap<ConnArray, u8> =
+#[map(name = "flow_table", pinning = "by_name")]
+pub static mut FLOW_TABLE: LruPerCpuHashMap<FlowKey, FlowStats> =
     LruPerCpuHashMap::with_max_entries(65536, 0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "veth_identity_map", pinning = "by_name")]
+pub static mut VETH_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "veth_identity_map", pinning = "by_name")]
 pub static mut VETH_EVENTS: PerfEventArray<VethLog> = PerfEventArray::new(0);
 
 #[map(name = "Blocklist", pinning = "by_name")]
-pub static mut BLOCKLIST: HashMap<[u8; 4], [u8; 4]> = HashMap::with_max_entries(1024, 0);
-//here i need to pass an address like this: [135,171,168,192]
+pub static mut BLOCKLIST: LpmTrie<[u8; 4], BlocklistEntry> =
+    LpmTrie::with_max_entries(1024, BPF_F_NO_PREALLOC);
+//here i need to pass a key like this: Key::new(24, [192,168,171,0]) (network order)
+
+#[map(name = "BlocklistV6", pinning = "by_name")]
+pub static mut BLOCKLIST_V6: LpmTrie<[u8; 16], BlocklistEntry> =
+    LpmTrie::with_max_entries(1024, BPF_F_NO_PREALLOC);
+
+#[map(name = "FilterRules", pinning = "by_name")]
+pub static mut FILTER_RULES: Array<FilterRule> = Array::with_max_entries(MAX_FILTER_RULES, 0);
+
+#[map(name = "RateLimits", pinning = "by_name")]
+pub static mut RATE_LIMITS: LpmTrie<[u8; 4], RateLimit> =
+    LpmTrie::with_max_entries(1024, BPF_F_NO_PREALLOC);
 
+#[map(name = "RateLimitsV6", pinning = "by_name")]
+pub static mut RATE_LIMITS_V6: LpmTrie<[u8; 16], RateLimit> =
+    LpmTrie::with_max_entries(1024, BPF_F_NO_PREALLOC);
+
+#[map(name = "rate_limit_buckets", pinning = "by_name")]
+pub static mut RATE_LIMIT_BUCKETS: LruHashMap<RateLimitKey, TokenBucket> =
+    LruHashMap::with_max_entries(65536, 0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "audit_events", pinning = "by_name")]
+pub static mut AUDIT_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "audit_events", pinning = "by_name")]
+pub static mut AUDIT_EVENTS: PerfEventArray<AuditEvent> = PerfEventArray::new(0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "drop_events", pinning = "by_name")]
+pub static mut DROP_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "drop_events", pinning = "by_name")]
+pub static mut DROP_EVENTS: PerfEventArray<DropEvent> = PerfEventArray::new(0);
+
+#[map(name = "classifier_config", pinning = "by_name")]
+pub static mut CLASSIFIER_CONFIG: Array<u32> = Array::with_max_entries(MAX_CONFIG_ENTRIES, 0);
+
+#[map(name = "classifier_counters", pinning = "by_name")]
+pub static mut CLASSIFIER_COUNTERS: PerCpuArray<u64> =
+    PerCpuArray::with_max_entries(MAX_CLASSIFIER_COUNTERS, 0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "TcpPacketRegistry", pinning = "by_name")]
+pub static mut PACKET_REGISTRY: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "TcpPacketRegistry", pinning = "by_name")]
 pub static mut PACKET_REGISTRY: PerfEventArray<TcpPacketRegistry> = PerfEventArray::new(0);
 
+#[map(name = "xdp_mode", pinning = "by_name")]
+pub static mut XDP_MODE: HashMap<[u8; 16], [u8; 8]> = HashMap::with_max_entries(1024, 0);
+
+#[map(name = "kernel_offsets", pinning = "by_name")]
+pub static mut KERNEL_OFFSETS: Array<u32> = Array::with_max_entries(MAX_KERNEL_OFFSETS, 0);
+
+#[map(name = "tcp_connections", pinning = "by_name")]
+pub static mut TCP_CONNECTIONS: LruHashMap<u64, TcpConnection> =
+    LruHashMap::with_max_entries(65536, 0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "tcp_conn_events", pinning = "by_name")]
+pub static mut TCP_CONN_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "tcp_conn_events", pinning = "by_name")]
+pub static mut TCP_CONN_EVENTS: PerfEventArray<TcpConnEvent> = PerfEventArray::new(0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "listener_events", pinning = "by_name")]
+pub static mut LISTENER_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "listener_events", pinning = "by_name")]
+pub static mut LISTENER_EVENTS: PerfEventArray<ListenerEvent> = PerfEventArray::new(0);
+
 #[map(name = "tracked_veth", pinning = "by_name")]
 // This map takes a registry of tracked veth interfaces
 // The maximum number of characters is 16 of type u8
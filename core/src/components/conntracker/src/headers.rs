@@ -0,0 +1,330 @@
+use core::mem;
+
+use aya_ebpf::{ maps::lpm_trie::Key, programs::{ TcContext, XdpContext } };
+
+use crate::data_structures::{ BLOCKLIST, BLOCKLIST_V6, IPPROTO_UDP };
+use crate::data_structures::{ DROP_REASON_PARSE_ERROR, DROP_REASON_TRUNCATED_HEADER };
+use crate::offsets::OFFSETS;
+
+// docs:
+//
+// This module contains the packet headers parsing shared by the identity_classifier (tc.rs) and the XDP blocklist
+// fast path (xdp.rs): VLAN tags, ipv4 options, ipv6 extension headers and VXLAN/Geneve encapsulation are decoded by
+// the same code, so both programs check the blocklist against the same addresses
+//
+
+// docs:
+//
+// Read access to the packet data of a tc or an xdp program
+//
+// load_at returns the value at the given offset or DROP_REASON_TRUNCATED_HEADER as i64 when the packet is shorter
+// than the expected headers
+
+pub trait PacketData {
+    fn load_at<T: Copy>(&self, offset: usize) -> Result<T, i64>;
+}
+
+impl PacketData for TcContext {
+    #[inline(always)]
+    fn load_at<T: Copy>(&self, offset: usize) -> Result<T, i64> {
+        self.load::<T>(offset).map_err(|_| DROP_REASON_TRUNCATED_HEADER as i64)
+    }
+}
+
+impl PacketData for XdpContext {
+    // the bounds check against data_end is required by the verifier
+    #[inline(always)]
+    fn load_at<T: Copy>(&self, offset: usize) -> Result<T, i64> {
+        let start = self.data();
+        let end = self.data_end();
+        let len = mem::size_of::<T>();
+
+        if start + offset + len > end {
+            return Err(DROP_REASON_TRUNCATED_HEADER as i64);
+        }
+        Ok(unsafe { core::ptr::read_unaligned((start + offset) as *const T) })
+    }
+}
+
+// docs:
+//
+// This is an helper function to load a value from the packet at the given offset.
+// A failed load means that the packet is shorter than the expected headers
+//
+// Returns the loaded value or DROP_REASON_TRUNCATED_HEADER as i64
+
+#[inline(always)]
+pub fn load<T: Copy>(ctx: &impl PacketData, offset: usize) -> Result<T, i64> {
+    ctx.load_at::<T>(offset)
+}
+
+// docs:
+//
+// Headers structure returned by locate_headers
+//
+// ethertype: ethertype of the packet to classify (after the vlan tags)
+// l3_offset: offset of the ip header to classify
+// vni: VXLAN/Geneve network identifier, 0 for not encapsulated packets
+// tunneled: true if the packet to classify is the inner frame of a VXLAN/Geneve datagram
+// outer_ethertype/outer_l3_offset: ethertype and ip header offset of the outer (node) packet. Same as ethertype and
+//                                  l3_offset for not encapsulated packets
+//
+
+pub struct Headers {
+    pub ethertype: u16,
+    pub l3_offset: usize,
+    pub vni: u32,
+    pub tunneled: bool,
+    pub outer_ethertype: u16,
+    pub outer_l3_offset: usize,
+}
+
+// docs:
+//
+// This is an helper function to find the headers to classify
+// Skips the VLAN tags of the frame. When the packet is a VXLAN (udp/4789) or a Geneve (udp/6081) datagram that
+// carries an ethernet frame, moves to the inner frame: the classifier, the blocklist and the filtering rules see the
+// pod addresses instead of the node tunnel addresses. Only one level of encapsulation is decoded
+//
+// The destination port alone does not make a datagram a tunnel: the VXLAN header must have the I flag set (valid
+// VNI), the Geneve header must have version 0 and the transparent ethernet bridging protocol, and the inner frame
+// must carry an ipv4 or ipv6 packet. Every other datagram (including the ones with a truncated tunnel header) is
+// classified as a plain udp datagram
+//
+// Returns a Result with the Headers or an error code as i64
+
+#[inline(always)]
+pub fn locate_headers(ctx: &impl PacketData) -> Result<Headers, i64> {
+    let (ethertype, l3_offset) = skip_vlan_tags(ctx, 0)?;
+    let outer = Headers {
+        ethertype,
+        l3_offset,
+        vni: 0,
+        tunneled: false,
+        outer_ethertype: ethertype,
+        outer_l3_offset: l3_offset,
+    };
+
+    // offset of the outer udp header
+    let udp_offset = match ethertype {
+        OFFSETS::IPV4_ETHERTYPE => {
+            if load::<u8>(ctx, l3_offset + OFFSETS::IPV4_PROTOCOL_OFFSET)? != IPPROTO_UDP {
+                return Ok(outer);
+            }
+            // the non-first fragments do not carry the udp header
+            if is_ipv4_later_fragment(ctx, l3_offset)? {
+                return Ok(outer);
+            }
+            l3_offset + ipv4_header_len(ctx, l3_offset)?
+        }
+        OFFSETS::IPV6_ETHERTYPE => {
+            let (proto, l4_offset, later_fragment) = walk_ipv6_extension_headers(ctx, l3_offset)?;
+            if proto != IPPROTO_UDP || later_fragment {
+                return Ok(outer);
+            }
+            l4_offset
+        }
+        _ => {
+            return Ok(outer);
+        }
+    };
+
+    let tunnel_offset = udp_offset + OFFSETS::UDP_HEADER_LEN;
+    let dst_port = u16::from_be(load::<u16>(ctx, udp_offset + OFFSETS::DST_PORT_OFFSET_FROM_IP_HEADER)?);
+    let inner_offset = match dst_port {
+        OFFSETS::VXLAN_PORT => {
+            let Ok(flags) = load::<u8>(ctx, tunnel_offset) else {
+                return Ok(outer);
+            };
+            if flags & OFFSETS::VXLAN_FLAG_VNI == 0 {
+                return Ok(outer);
+            }
+            tunnel_offset + OFFSETS::VXLAN_HEADER_LEN
+        }
+        OFFSETS::GENEVE_PORT => {
+            let Ok(ver_opt_len) = load::<u8>(ctx, tunnel_offset) else {
+                return Ok(outer);
+            };
+            let Ok(protocol) = load::<u16>(ctx, tunnel_offset + OFFSETS::GENEVE_PROTOCOL_OFFSET) else {
+                return Ok(outer);
+            };
+            if ver_opt_len >> 6 != OFFSETS::GENEVE_VERSION || u16::from_be(protocol) != OFFSETS::GENEVE_ETHERNET_PROTOCOL {
+                return Ok(outer);
+            }
+            let opt_len = (ver_opt_len & 0x3f) as usize;
+            tunnel_offset + OFFSETS::GENEVE_HEADER_LEN + opt_len * 4
+        }
+        _ => {
+            return Ok(outer);
+        }
+    };
+
+    let Ok(vni) = load::<[u8; 3]>(ctx, tunnel_offset + OFFSETS::TUNNEL_VNI_OFFSET) else {
+        return Ok(outer);
+    };
+    let (inner_ethertype, inner_l3_offset) = match skip_vlan_tags(ctx, inner_offset) {
+        Ok((OFFSETS::IPV4_ETHERTYPE, inner_l3_offset)) => (OFFSETS::IPV4_ETHERTYPE, inner_l3_offset),
+        Ok((OFFSETS::IPV6_ETHERTYPE, inner_l3_offset)) => (OFFSETS::IPV6_ETHERTYPE, inner_l3_offset),
+        _ => {
+            return Ok(outer);
+        }
+    };
+
+    Ok(Headers {
+        ethertype: inner_ethertype,
+        l3_offset: inner_l3_offset,
+        vni: ((vni[0] as u32) << 16) | ((vni[1] as u32) << 8) | (vni[2] as u32),
+        tunneled: true,
+        outer_ethertype: ethertype,
+        outer_l3_offset: l3_offset,
+    })
+}
+
+// docs:
+//
+// This is an helper function to skip the 802.1Q/802.1ad VLAN tags of an ethernet frame. Takes the offset of the
+// ethernet header. The tags stripped by the driver (hardware vlan offload) are not in the packet data
+// The walk is bounded by OFFSETS::VLAN_MAX_TAGS iterations (Q-in-Q)
+//
+// Returns a Result with the ethertype and the L3 header offset or an error code as i64
+
+#[inline(always)]
+fn skip_vlan_tags(ctx: &impl PacketData, eth_offset: usize) -> Result<(u16, usize), i64> {
+    let mut ethertype_offset = eth_offset + OFFSETS::ETHERTYPE_OFFSET;
+    let mut ethertype = u16::from_be(load::<u16>(ctx, ethertype_offset)?);
+
+    for _ in 0..OFFSETS::VLAN_MAX_TAGS {
+        if ethertype != OFFSETS::VLAN_ETHERTYPE && ethertype != OFFSETS::QINQ_ETHERTYPE {
+            break;
+        }
+        ethertype_offset += OFFSETS::VLAN_HEADER_LEN;
+        ethertype = u16::from_be(load::<u16>(ctx, ethertype_offset)?);
+    }
+
+    Ok((ethertype, ethertype_offset + OFFSETS::ETHERTYPE_BYTES))
+}
+
+// docs:
+//
+// This is an helper function to read the length of an ipv4 header (options included). Takes the offset of the
+// ipv4 header
+//
+// Returns a Result with the header length in bytes or an error code as i64
+
+#[inline(always)]
+pub fn ipv4_header_len(ctx: &impl PacketData, l3_offset: usize) -> Result<usize, i64> {
+    // read if the packets has Options
+    let first_ipv4_byte = u8::from_be(load::<u8>(ctx, l3_offset)?);
+    let ihl = (first_ipv4_byte &
+        0x0f) as usize; /* 0x0F=00001111 &=AND bit a bit operator to extract the last 4 bit*/
+    // the minimum ipv4 header is 5 words (20 bytes)
+    if ihl < 5 {
+        return Err(DROP_REASON_PARSE_ERROR as i64);
+    }
+    Ok(ihl * 4) //returns the header lenght in bytes
+}
+
+// docs:
+//
+// This is an helper function to check if an ipv4 packet is a non-first fragment. Takes the offset of the ipv4
+// header. Only the first fragment (fragment offset 0, with or without the MF flag) carries the L4 header: the
+// payload of the other fragments starts in the middle of the datagram and cannot be parsed as a L4 header
+//
+// Returns a Result with true if the fragment offset is not zero or an error code as i64
+
+#[inline(always)]
+pub fn is_ipv4_later_fragment(ctx: &impl PacketData, l3_offset: usize) -> Result<bool, i64> {
+    let frag_off = u16::from_be(load::<u16>(ctx, l3_offset + OFFSETS::IPV4_FRAGMENT_OFFSET)?);
+    Ok(frag_off & OFFSETS::IPV4_FRAGMENT_OFFSET_MASK != 0)
+}
+
+// docs:
+//
+// This is an helper function to walk the ipv6 extension headers chain. Takes the offset of the ipv6 header
+// Starts from the "Next Header" field of the fixed ipv6 header and skips every known extension header
+// (hop-by-hop, routing, fragment, destination options, authentication) until an upper layer header is found.
+// The walk is bounded by OFFSETS::IPV6_MAX_EXT_HEADERS iterations to satisfy the verifier
+// The walk stops at the fragment header of a non-first fragment (fragment offset != 0): its payload starts in the
+// middle of the datagram and does not contain the L4 header
+//
+// Returns a Result with the L4 protocol, the L4 header offset and true for a non-first fragment, or an error code
+// as i64
+
+pub fn walk_ipv6_extension_headers(ctx: &impl PacketData, l3_offset: usize) -> Result<(u8, usize, bool), i64> {
+    let mut next_header = load::<u8>(ctx, l3_offset + OFFSETS::IPV6_NEXT_HEADER_OFFSET)?;
+    let mut offset = l3_offset + OFFSETS::IPV6_HEADER_LEN;
+
+    for _ in 0..OFFSETS::IPV6_MAX_EXT_HEADERS {
+        match next_header {
+            OFFSETS::IPV6_EXT_HOP_BY_HOP | OFFSETS::IPV6_EXT_ROUTING | OFFSETS::IPV6_EXT_DEST_OPTS => {
+                // Hdr Ext Len is expressed in 8 bytes units, not including the first 8 bytes
+                let hdr_ext_len = load::<u8>(ctx, offset + 1)? as usize;
+                next_header = load::<u8>(ctx, offset)?;
+                offset += (hdr_ext_len + 1) * 8;
+            }
+            OFFSETS::IPV6_EXT_FRAGMENT => {
+                let frag_off = u16::from_be(load::<u16>(ctx, offset + OFFSETS::IPV6_FRAGMENT_OFFSET)?);
+                next_header = load::<u8>(ctx, offset)?;
+                offset += OFFSETS::IPV6_FRAGMENT_HEADER_LEN;
+                if frag_off & OFFSETS::IPV6_FRAGMENT_OFFSET_MASK != 0 {
+                    return Ok((next_header, offset, true));
+                }
+            }
+            OFFSETS::IPV6_EXT_AUTH => {
+                // Payload Len is expressed in 4 bytes units, minus 2
+                let payload_len = load::<u8>(ctx, offset + 1)? as usize;
+                next_header = load::<u8>(ctx, offset)?;
+                offset += (payload_len + 2) * 4;
+            }
+            _ => {
+                return Ok((next_header, offset, false));
+            }
+        }
+    }
+
+    // the chain is longer than the supported maximum
+    Err(DROP_REASON_PARSE_ERROR as i64)
+}
+
+// docs:
+//
+// This is an helper function to check an address of the ip header at l3_offset against the BLOCKLIST (ipv4) or
+// BLOCKLIST_V6 (ipv6) map. Takes the ethertype of the header and the address to check (source = true for the
+// source address, false for the destination address)
+// The lookup uses a full length prefix: the trie returns the longest stored prefix that contains the address
+//
+// Returns a Result with true if the address is blocklisted or an error code as i64
+
+#[inline(always)]
+pub fn is_blocklisted(ctx: &impl PacketData, ethertype: u16, l3_offset: usize, source: bool) -> Result<bool, i64> {
+    match ethertype {
+        OFFSETS::IPV4_ETHERTYPE => {
+            let addr_offset = if source { OFFSETS::SRC_BYTE_OFFSET } else { OFFSETS::DST_BYTE_OFFSET };
+            let addr = load::<[u8; 4]>(ctx, l3_offset + addr_offset)?;
+            Ok(unsafe { BLOCKLIST.get(&Key::new(32, addr)).is_some() })
+        }
+        OFFSETS::IPV6_ETHERTYPE => {
+            let addr_offset = if source { OFFSETS::IPV6_SRC_BYTE_OFFSET } else { OFFSETS::IPV6_DST_BYTE_OFFSET };
+            let addr = load::<[u8; 16]>(ctx, l3_offset + addr_offset)?;
+            Ok(unsafe { BLOCKLIST_V6.get(&Key::new(128, addr)).is_some() })
+        }
+        // only ipv4 and ipv6 protocols are checked
+        _ => Ok(false),
+    }
+}
+
+// docs:
+//
+// This is an helper function to check the outer (node) address of a VXLAN/Geneve datagram against the blocklist,
+// so a blocklisted node cannot reach the pods by encapsulating the traffic. Takes the address to check (see
+// is_blocklisted)
+//
+// Returns a Result with true if the packet is tunneled and the outer address is blocklisted or an error code as i64
+
+#[inline(always)]
+pub fn is_outer_blocklisted(ctx: &impl PacketData, headers: &Headers, source: bool) -> Result<bool, i64> {
+    if !headers.tunneled {
+        return Ok(false);
+    }
+    is_blocklisted(ctx, headers.outer_ethertype, headers.outer_l3_offset, source)
+}
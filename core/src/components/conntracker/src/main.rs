@@ -5,8 +5,12 @@
 //      1. Creates a PacketLog structure to track incoming packets
 //      2. Creates a VethLog structure to track veth creation and veth eletetion events
 //      3. VethLog Tracking Parameters: NAME,STATE,DEVICE_ADDRESS,EVENT_TYPE,NETNS INUM.
-//      4. PacketLog Tracking Parameters: SRC_IP.SRC_PORT,DST_IP,DST_PORT,PROTOCOL,PID(HOOK)
+//      4. PacketLog Tracking Parameters: SRC_IP.SRC_PORT,DST_IP,DST_PORT,PROTOCOL,PID(HOOK),DIRECTION,IFINDEX
 //      5. Store CONNECTION_ID in a BPF LRU HASHMAP and pass PID to the user space to identify ACTIVE CONNECTIONS
+//      6. Optional XDP fast path that drops the blocklisted sources at the driver level
+//      7. Tracks the TCP connections lifecycle (established/closed events with duration, bytes and close reason)
+//      8. Detects the accept queue and SYN queue overflows of the listening sockets and the tcp memory pressure
+//      9. Traces the UDP sockets (sendmsg/recvmsg) to report the UDP flows (DNS, QUIC, ...) with their PID
 //
 
 #![no_std]
@@ -17,24 +21,74 @@
 
 mod bindings;
 mod data_structures;
+mod headers;
 mod offsets;
+mod rate_limit;
+mod rules;
 mod tc;
 mod tcp_analyzer;
+mod tcp_backlog;
+mod tcp_state;
+mod udp_tracer;
 mod veth_tracer;
+mod xdp;
 
 use aya_ebpf::{
-    bindings::{TC_ACT_OK, TC_ACT_SHOT},
-    macros::{classifier, kprobe},
-    programs::{ProbeContext, TcContext},
+    bindings::{xdp_action, TC_ACT_OK, TC_ACT_SHOT},
+    macros::{classifier, kprobe, tracepoint, xdp},
+    programs::{ProbeContext, TcContext, TracePointContext, XdpContext},
 };
 
+use crate::data_structures::{AF_INET, AF_INET6, DIRECTION_EGRESS, DIRECTION_INGRESS};
 use crate::tc::try_identity_classifier;
-use crate::tcp_analyzer::try_tcp_analyzer;
+use crate::tcp_analyzer::{try_tcp_analyzer, try_tcp_connect_analyzer};
+use crate::tcp_backlog::{try_accept_queue_tracer, try_memory_pressure_tracer, try_syn_flood_tracer};
+use crate::tcp_state::try_tcp_state_tracer;
+use crate::udp_tracer::try_udp_sock_tracer;
 use crate::veth_tracer::try_veth_tracer;
+use crate::xdp::try_xdp_blocklist;
 
-// TODO: add function to track
-// 1. kprobe:tcp_enter_memory_pressure
-// 2. kprobe:tcp_create_openreq_child (https://elixir.bootlin.com/linux/v6.18.6/source/net/ipv4/tcp_ipv4.c#L1776) [function: *tcp_v4_syn_recv_sock]
+// docs:
+//
+// listening sockets overload tracers (see tcp_backlog.rs):
+//      - tcp_accept_queue_tracer / tcp_accept_queue_tracer_v6: attached to tcp_v4_syn_recv_sock and
+//        tcp_v6_syn_recv_sock (https://elixir.bootlin.com/linux/v6.18.6/source/net/ipv4/tcp_ipv4.c#L1776).
+//        Triggered when the accept queue of a listener is full
+//      - tcp_syn_flood_tracer: attached to tcp_syn_flood_action. Triggered when the SYN queue of a listener is full
+//      - tcp_memory_pressure_tracer: attached to tcp_enter_memory_pressure
+//
+
+#[kprobe]
+pub fn tcp_accept_queue_tracer(ctx: ProbeContext) -> u32 {
+    match try_accept_queue_tracer(&ctx, AF_INET) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn tcp_accept_queue_tracer_v6(ctx: ProbeContext) -> u32 {
+    match try_accept_queue_tracer(&ctx, AF_INET6) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn tcp_syn_flood_tracer(ctx: ProbeContext) -> u32 {
+    match try_syn_flood_tracer(&ctx) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn tcp_memory_pressure_tracer(ctx: ProbeContext) -> u32 {
+    match try_memory_pressure_tracer(&ctx) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
 
 // docs:
 //
@@ -87,27 +141,55 @@ pub fn veth_deletion_trace(ctx: ProbeContext) -> u32 {
 
 #[classifier]
 pub fn identity_classifier(ctx: TcContext) -> i32 {
-    match try_identity_classifier(ctx) {
+    match try_identity_classifier(ctx, DIRECTION_INGRESS) {
+        Ok(_) => TC_ACT_OK,
+        Err(_) => TC_ACT_SHOT, // block packets that returns errors
+    }
+}
+
+// docs:
+//
+// egress counterpart of the identity_classifier. Attached to the egress hook of the same interfaces to observe
+// and block the traffic leaving the pods
+
+#[classifier]
+pub fn identity_classifier_egress(ctx: TcContext) -> i32 {
+    match try_identity_classifier(ctx, DIRECTION_EGRESS) {
         Ok(_) => TC_ACT_OK,
         Err(_) => TC_ACT_SHOT, // block packets that returns errors
     }
 }
 
+// docs:
+//
+// XDP fast path for the blocklist drops. Optionally attached by the userspace to the node interfaces (native or
+// generic mode) to drop the blocklisted sources before the kernel allocates an skb. The identity_classifier
+// keeps enforcing the blocklist on the interfaces where XDP is not available (see xdp.rs)
+
+#[xdp]
+pub fn identity_xdp(ctx: XdpContext) -> u32 {
+    match try_xdp_blocklist(&ctx) {
+        Ok(action) => action,
+        Err(_) => xdp_action::XDP_PASS, // the failure policy is applied by the identity_classifier
+    }
+}
+
 // docs:
 //
 // this kprobe retrieves pid data and task id of an incoming packet
 
 // this kprobe separation is needed because every kprobe program can be attached only one time.
 // if you try to attach the same program the kernel returns this error: "Program is already attached"
-// this is the reason why we have tcp_message_tracer_connect and tcp_message_tracer_rcv that are essentially the same functions
-// but in the kernel space one is attached to the tcp_v4_connect kprobe and one to the tcp_v4_rcv kprobe
+// this is the reason why we have tcp_message_tracer_connect and tcp_message_tracer_rcv (and their _v6 variants)
+// that are essentially the same functions but in the kernel space they are attached to the tcp_v4_connect,
+// tcp_v4_rcv, tcp_v6_connect and tcp_v6_rcv kprobes
 // TODO: a good addition to the library will be a function that check if the program is already attached:
 // if the program is attached it creates a safe copy of the program to attach a second kernel symbol (kprobes)
 // if the program is not attached we have the traditional behaviour (load the program + attach the program to the kernel symbol (kprobes))
 
 #[kprobe]
 pub fn tcp_message_tracer_connect(ctx: ProbeContext) -> u32 {
-    match try_tcp_analyzer(ctx) {
+    match try_tcp_connect_analyzer(ctx, AF_INET) {
         Ok(ret_val) => ret_val,
         Err(ret_val) => ret_val.try_into().unwrap_or(1),
     }
@@ -115,7 +197,77 @@ pub fn tcp_message_tracer_connect(ctx: ProbeContext) -> u32 {
 
 #[kprobe]
 pub fn tcp_message_tracer_rcv(ctx: ProbeContext) -> u32 {
-    match try_tcp_analyzer(ctx) {
+    match try_tcp_analyzer(ctx, AF_INET) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn tcp_message_tracer_connect_v6(ctx: ProbeContext) -> u32 {
+    match try_tcp_connect_analyzer(ctx, AF_INET6) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn tcp_message_tracer_rcv_v6(ctx: ProbeContext) -> u32 {
+    match try_tcp_analyzer(ctx, AF_INET6) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+// docs:
+//
+// udp socket tracers (see udp_tracer.rs):
+//      - udp_sendmsg_tracer / udpv6_sendmsg_tracer: attached to udp_sendmsg and udpv6_sendmsg
+//      - udp_recvmsg_tracer / udpv6_recvmsg_tracer: attached to udp_recvmsg and udpv6_recvmsg
+// The events are sent in the PACKET_REGISTRY buffer with proto = IPPROTO_UDP
+//
+
+#[kprobe]
+pub fn udp_sendmsg_tracer(ctx: ProbeContext) -> u32 {
+    match try_udp_sock_tracer(ctx, AF_INET, true) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn udp_recvmsg_tracer(ctx: ProbeContext) -> u32 {
+    match try_udp_sock_tracer(ctx, AF_INET, false) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn udpv6_sendmsg_tracer(ctx: ProbeContext) -> u32 {
+    match try_udp_sock_tracer(ctx, AF_INET6, true) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+#[kprobe]
+pub fn udpv6_recvmsg_tracer(ctx: ProbeContext) -> u32 {
+    match try_udp_sock_tracer(ctx, AF_INET6, false) {
+        Ok(ret_val) => ret_val,
+        Err(ret_val) => ret_val.try_into().unwrap_or(1),
+    }
+}
+
+// docs:
+//
+// tcp connection lifecycle tracer:
+// This tracepoint is triggered on every socket state transition (sock:inet_sock_set_state). It keeps the
+// TCP_CONNECTIONS map of the established connections and sends the established/closed events (see tcp_state.rs)
+
+#[tracepoint]
+pub fn tcp_state_tracer(ctx: TracePointContext) -> u32 {
+    match try_tcp_state_tracer(&ctx) {
         Ok(ret_val) => ret_val,
         Err(ret_val) => ret_val.try_into().unwrap_or(1),
     }
@@ -1,6 +1,12 @@
 pub mod bindings;
 pub mod data_structures;
+pub mod headers;
 pub mod offsets;
+pub mod rate_limit;
+pub mod rules;
 pub mod tc;
 pub mod tcp_analyzer;
+pub mod tcp_backlog;
+pub mod tcp_state;
+pub mod udp_tracer;
 pub mod veth_tracer;
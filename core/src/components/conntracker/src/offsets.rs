@@ -1,3 +1,5 @@
+use crate::data_structures::KERNEL_OFFSETS;
+
 pub struct OFFSETS;
 
 /*
@@ -42,13 +44,93 @@ pub struct OFFSETS;
 
 */
 
+/*
+    * Ipv6 stack reference:
+    * https://en.wikipedia.org/wiki/IPv6_packet#Fixed_header
+    *
+    * The IPv6 fixed header is always 40 bytes long:
+
+   |Version| Traffic Class |           Flow Label                  |     4 bytes            0
+   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+   |         Payload Length        |  Next Header  |   Hop Limit   |     4 bytes            4
+   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+   |                         Source Address                        |     16 bytes           8
+   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+   |                      Destination Address                      |     16 bytes           24
+   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+    * The L4 header is not at a fixed offset: the "Next Header" field can point to a chain of
+    * extension headers (hop-by-hop, routing, fragment, destination options, authentication)
+    * that must be walked before reaching the TCP/UDP ports.
+    * reference: https://en.wikipedia.org/wiki/IPv6_packet#Extension_headers
+
+*/
+
+/*
+    * VLAN and UDP tunnels reference:
+    * 802.1Q: https://en.wikipedia.org/wiki/IEEE_802.1Q
+    * VXLAN: https://datatracker.ietf.org/doc/html/rfc7348#section-5
+    * Geneve: https://datatracker.ietf.org/doc/html/rfc8926#section-3.4
+    *
+    * A VLAN tag (4 bytes: TPID 0x8100 or 0x88A8 + TCI) is inserted between the source mac address and the
+    * ethertype. Q-in-Q frames carry two tags.
+    *
+    * VXLAN and Geneve carry a whole ethernet frame in a UDP datagram:
+    *
+    *   outer ethernet | outer ip | udp (8 bytes) | tunnel header | inner ethernet | inner ip | inner L4
+    *
+    * VXLAN header (8 bytes):  flags (1) | reserved (3) | VNI (3) | reserved (1), the "I" flag (0x08) must be set
+    * Geneve header (8 bytes + options): ver/opt len (1) | flags (1) | protocol type (2) | VNI (3) | reserved (1)
+    * the Geneve "Opt Len" field (low 6 bits of the first byte) is expressed in 4 bytes units
+
+*/
+
 impl OFFSETS {
     pub const IPV4_ETHERTYPE: u16 = 0x0800;
+    pub const IPV6_ETHERTYPE: u16 = 0x86DD;
+    pub const ETHERTYPE_OFFSET: usize = 12; // ethertype offset in the ethernet frame
+
+    //VLAN
+    pub const VLAN_ETHERTYPE: u16 = 0x8100; // 802.1Q tag
+    pub const QINQ_ETHERTYPE: u16 = 0x88A8; // 802.1ad (Q-in-Q) outer tag
+    pub const VLAN_HEADER_LEN: usize = 4; // TPID + TCI
+    pub const VLAN_MAX_TAGS: usize = 2; // upper bound for the vlan tags walk (keeps the verifier happy)
+
+    //UDP TUNNELS
+    pub const UDP_HEADER_LEN: usize = 8; // fixed udp header length
+    pub const VXLAN_PORT: u16 = 4789; // IANA vxlan udp port
+    pub const GENEVE_PORT: u16 = 6081; // IANA geneve udp port
+    pub const VXLAN_HEADER_LEN: usize = 8; // fixed vxlan header length
+    pub const VXLAN_FLAG_VNI: u8 = 0x08; // vxlan "I" flag, set when the VNI is valid
+    pub const GENEVE_HEADER_LEN: usize = 8; // geneve header length without options
+    pub const GENEVE_VERSION: u8 = 0; // geneve version (high 2 bits of the first byte)
+    pub const GENEVE_PROTOCOL_OFFSET: usize = 2; // geneve protocol type offset
+    pub const GENEVE_ETHERNET_PROTOCOL: u16 = 0x6558; // transparent ethernet bridging
+    pub const TUNNEL_VNI_OFFSET: usize = 4; // the 24 bit VNI has the same offset in the vxlan and geneve headers
 
     //IPV4 STACK
     pub const SRC_BYTE_OFFSET: usize = 12; // source address offset for ipv4 addresses
     pub const DST_BYTE_OFFSET: usize = 16; // destination address offset for ipv4 addresses
     pub const IPV4_PROTOCOL_OFFSET: usize = 9; // ipv4 protocol offset
+    pub const IPV4_FRAGMENT_OFFSET: usize = 6; // flags (3 bits) + fragment offset (13 bits) offset
+    pub const IPV4_FRAGMENT_OFFSET_MASK: u16 = 0x1fff; // fragment offset bits, in 8 bytes units
+
+    //IPV6 STACK
+    pub const IPV6_HEADER_LEN: usize = 40; // fixed ipv6 header length
+    pub const IPV6_NEXT_HEADER_OFFSET: usize = 6; // next header offset for ipv6 addresses
+    pub const IPV6_SRC_BYTE_OFFSET: usize = 8; // source address offset for ipv6 addresses
+    pub const IPV6_DST_BYTE_OFFSET: usize = 24; // destination address offset for ipv6 addresses
+    pub const IPV6_MAX_EXT_HEADERS: usize = 6; // upper bound for the extension headers walk (keeps the verifier happy)
+
+    //IPV6 EXTENSION HEADERS (next header values)
+    pub const IPV6_EXT_HOP_BY_HOP: u8 = 0;
+    pub const IPV6_EXT_ROUTING: u8 = 43;
+    pub const IPV6_EXT_FRAGMENT: u8 = 44;
+    pub const IPV6_EXT_AUTH: u8 = 51;
+    pub const IPV6_EXT_DEST_OPTS: u8 = 60;
+    pub const IPV6_FRAGMENT_HEADER_LEN: usize = 8; // the fragment header has a fixed length
+    pub const IPV6_FRAGMENT_OFFSET: usize = 2; // fragment offset (13 bits) + res (2 bits) + M flag offset
+    pub const IPV6_FRAGMENT_OFFSET_MASK: u16 = 0xfff8; // fragment offset bits, in 8 bytes units
 
     //ETHERNET STACK
     pub const SRC_MAC: usize = 6; // source mac address offset
@@ -66,6 +148,28 @@ impl OFFSETS {
     pub const SRC_T0TAL_BYTES_OFFSET: usize = OFFSETS::ETH_STACK_BYTES + OFFSETS::SRC_BYTE_OFFSET; // source total bytes offset
     pub const PROTOCOL_T0TAL_BYTES_OFFSET: usize =
         OFFSETS::ETH_STACK_BYTES + OFFSETS::IPV4_PROTOCOL_OFFSET; // total bytes offset
+    pub const IPV6_SRC_T0TAL_BYTES_OFFSET: usize =
+        OFFSETS::ETH_STACK_BYTES + OFFSETS::IPV6_SRC_BYTE_OFFSET; // ipv6 source total bytes offset
+    pub const IPV6_DST_T0TAL_BYTES_OFFSET: usize =
+        OFFSETS::ETH_STACK_BYTES + OFFSETS::IPV6_DST_BYTE_OFFSET; // ipv6 destination total bytes offset
+    pub const IPV6_NEXT_HEADER_T0TAL_BYTES_OFFSET: usize =
+        OFFSETS::ETH_STACK_BYTES + OFFSETS::IPV6_NEXT_HEADER_OFFSET; // ipv6 next header total bytes offset
+}
+
+// docs:
+//
+// This is an helper function to read a kernel struct field offset from the KERNEL_OFFSETS map
+// Takes an OFFSET_* index (see data_structures.rs)
+//
+// The offsets are resolved by the userspace from the kernel BTF before the programs are attached, the userspace
+// refuses to attach the programs if a field is missing
+//
+// Returns a Result type with the offset as usize or an error code as i64
 
-    pub const SKB_DATA_POINTER: usize = 208; // sk_buff structure data pointer
+#[inline(always)]
+pub fn kernel_offset(index: u32) -> Result<usize, i64> {
+    match unsafe { KERNEL_OFFSETS.get(index) } {
+        Some(offset) => Ok(*offset as usize),
+        None => Err(1),
+    }
 }
@@ -0,0 +1,71 @@
+use aya_ebpf::helpers::bpf_ktime_get_ns;
+
+use crate::data_structures::{ RateLimit, RateLimitKey, TokenBucket, RATE_LIMIT_BUCKETS };
+use crate::data_structures::{ AF_INET, RATE_LIMIT_SCOPE_CIDR };
+
+// docs:
+//
+// This module contains the token bucket rate limit used by the identity_classifier
+// The rate limits are stored in the RATE_LIMITS/RATE_LIMITS_V6 LPM tries by the agent api and are checked against
+// the remote address (the source address on ingress) after the blocklist. Every rate limited source (or every
+// rate limited range, see RATE_LIMIT_SCOPE_CIDR) has its own bucket in the RATE_LIMIT_BUCKETS map
+//
+// The bucket credit is stored in nanoseconds: every packet costs 1s / rate and the credit grows with the elapsed
+// time, capped at burst packets. A packet is rate limited when the credit is lower than its cost
+
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+// docs:
+//
+// checks the token bucket of a packet and consumes a token if the packet is allowed
+// remote_ip is the remote address as four host order words (ipv4 uses only the first word)
+//
+// Returns true if the packet exceeds the rate limit and must be dropped
+
+pub fn is_rate_limited(af: u16, remote_ip: &[u32; 4], limit: &RateLimit) -> bool {
+    if limit.rate == 0 {
+        return true;
+    }
+
+    let key = if limit.scope == RATE_LIMIT_SCOPE_CIDR {
+        RateLimitKey {
+            af,
+            prefix_len: limit.prefix_len as u16,
+            addr: limit.net,
+        }
+    } else {
+        RateLimitKey {
+            af,
+            prefix_len: if af == AF_INET { 32 } else { 128 },
+            addr: *remote_ip,
+        }
+    };
+
+    let cost = NSEC_PER_SEC / (limit.rate as u64);
+    let max_credit = cost * (limit.burst as u64);
+    let now = unsafe { bpf_ktime_get_ns() };
+
+    match unsafe { RATE_LIMIT_BUCKETS.get_ptr_mut(&key) } {
+        Some(bucket) => {
+            let bucket = unsafe { &mut *bucket };
+            let credit = bucket.credit_ns.saturating_add(now.saturating_sub(bucket.last_ns));
+            let credit = if credit > max_credit { max_credit } else { credit };
+            bucket.last_ns = now;
+            if credit < cost {
+                bucket.credit_ns = credit;
+                return true;
+            }
+            bucket.credit_ns = credit - cost;
+            false
+        }
+        None => {
+            // first packet of the source: the bucket starts full
+            let bucket = TokenBucket {
+                credit_ns: max_credit.saturating_sub(cost),
+                last_ns: now,
+            };
+            let _ = unsafe { RATE_LIMIT_BUCKETS.insert(&key, &bucket, 0) };
+            false
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::data_structures::{ FilterRule, FILTER_RULES, IPPROTO_ICMP, IPPROTO_ICMPV6, MAX_FILTER_RULES };
+
+// docs:
+//
+// This module contains the L4 aware filtering rules evaluation used by the identity_classifier.
+// Every rule is expressed as (src CIDR, dst CIDR, protocol, dst port range, action) and is stored in the
+// FILTER_RULES array map by the agent api. Rules are evaluated in slot order and the first matching rule wins
+//
+// Addresses are passed as four host order words (ipv4 packets use only the first word) so the same code
+// can evaluate both ipv4 and ipv6 packets
+
+// docs:
+//
+// evaluates the FILTER_RULES map against a packet
+//
+// Returns the first matching rule or None if no rule matches the packet
+
+pub fn evaluate_rules(
+    af: u16,
+    src_ip: &[u32; 4],
+    dst_ip: &[u32; 4],
+    proto: u8,
+    dst_port: u16
+) -> Option<&'static FilterRule> {
+    for slot in 0..MAX_FILTER_RULES {
+        let rule = match unsafe { FILTER_RULES.get(slot) } {
+            Some(rule) => rule,
+            None => {
+                return None;
+            }
+        };
+        if rule.enabled == 0 {
+            continue;
+        }
+        if rule_matches(rule, af, src_ip, dst_ip, proto, dst_port) {
+            return Some(rule);
+        }
+    }
+    None
+}
+
+// docs:
+//
+// checks a single rule against the packet fields. A zero value for af or proto in the rule means "any"
+//
+// ICMP and ICMPv6 packets carry the type and the code in dst_port: they are matched on the port range only by
+// the ICMP/ICMPv6 rules. An "any" protocol rule matches them only when it does not restrict the ports
+
+#[inline(always)]
+fn rule_matches(
+    rule: &FilterRule,
+    af: u16,
+    src_ip: &[u32; 4],
+    dst_ip: &[u32; 4],
+    proto: u8,
+    dst_port: u16
+) -> bool {
+    if rule.af != 0 && rule.af != af {
+        return false;
+    }
+    if rule.proto != 0 && rule.proto != proto {
+        return false;
+    }
+    if proto == IPPROTO_ICMP || proto == IPPROTO_ICMPV6 {
+        // dst_port holds (type << 8) | code: only the ICMP rules match on it, while a port range of an
+        // "any" protocol rule never matches an ICMP packet
+        let any_port = rule.dst_port_start == 0 && rule.dst_port_end == u16::MAX;
+        if rule.proto == 0 && !any_port {
+            return false;
+        }
+    }
+    if dst_port < rule.dst_port_start || dst_port > rule.dst_port_end {
+        return false;
+    }
+    addr_matches(src_ip, &rule.src_net, &rule.src_mask) &&
+        addr_matches(dst_ip, &rule.dst_net, &rule.dst_mask)
+}
+
+#[inline(always)]
+fn addr_matches(addr: &[u32; 4], net: &[u32; 4], mask: &[u32; 4]) -> bool {
+    (addr[0] & mask[0]) == net[0] &&
+        (addr[1] & mask[1]) == net[1] &&
+        (addr[2] & mask[2]) == net[2] &&
+        (addr[3] & mask[3]) == net[3]
+}
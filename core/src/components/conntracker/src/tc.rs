@@ -1,14 +1,58 @@
-use core::net::Ipv4Addr;
+use core::net::{ Ipv4Addr, Ipv6Addr };
 
 use aya_ebpf::{
-    helpers::{bpf_get_current_pid_tgid},
+    helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns},
+    maps::lpm_trie::Key,
     programs::{TcContext},
 };
 use aya_log_ebpf::info;
 
-use crate::data_structures::{ ConnArray, PacketLog };
-use crate::data_structures::{ EVENTS,BLOCKLIST };
+use crate::data_structures::{ AuditEvent, DropEvent, FlowKey, FlowStats, PacketLog, AF_INET, AF_INET6 };
+use crate::data_structures::{
+    IPPROTO_ICMP,
+    IPPROTO_ICMPV6,
+    IPPROTO_SCTP,
+    IPPROTO_TCP,
+    IPPROTO_UDP,
+    IPPROTO_UDPLITE,
+};
+use crate::data_structures::DIRECTION_EGRESS;
+use crate::data_structures::{ RULE_ACTION_AUDIT, RULE_ACTION_DROP, AUDIT_REASON_BLOCKLIST, AUDIT_REASON_RULE };
+use crate::data_structures::AUDIT_REASON_RATE_LIMIT;
+use crate::data_structures::{ CONFIG_ENFORCEMENT_MODE, MODE_AUDIT, CONFIG_FAILURE_POLICY, FAILURE_POLICY_CLOSED };
+use crate::data_structures::COUNTER_FAIL_OPEN;
+use crate::data_structures::EventOutput;
+use crate::data_structures::{
+    DROP_REASON_BLOCKLIST,
+    DROP_REASON_RULE,
+    DROP_REASON_PARSE_ERROR,
+    DROP_REASON_TRUNCATED_HEADER,
+    DROP_REASON_RATE_LIMIT,
+};
+use crate::data_structures::{
+    EVENTS,
+    FLOW_TABLE,
+    BLOCKLIST,
+    BLOCKLIST_V6,
+    RATE_LIMITS,
+    RATE_LIMITS_V6,
+    AUDIT_EVENTS,
+    DROP_EVENTS,
+    CLASSIFIER_CONFIG,
+    CLASSIFIER_COUNTERS,
+};
+use crate::headers::{
+    is_ipv4_later_fragment,
+    is_outer_blocklisted,
+    ipv4_header_len,
+    load,
+    locate_headers,
+    walk_ipv6_extension_headers,
+    Headers,
+};
 use crate::offsets::OFFSETS;
+use crate::rate_limit::is_rate_limited;
+use crate::rules::evaluate_rules;
 
 // docs:
 //
@@ -19,82 +63,520 @@ use crate::offsets::OFFSETS;
 //      - source ip (src_ip)
 //      - destination ip (dst_ip)
 //      - source port (src_port)
-//      - destination port (dst_port)
+//      - destination port (dst_port). ICMP and ICMPv6 packets report the type and the code (see load_l4_ports)
+//        The non-first ip fragments do not carry the L4 header and report zero ports
 //      - protocol (proto)
 //      - kernel PID (pid)
 //
-// Features: 
-// Users can directly block one ip or a list of ips. The ip addresses goes directly into the blocklist hashmap 
-// and allows users to block the ips before entering into the userspace
+// Both ipv4 and ipv6 packets are classified. Every other ethertype is passed through without being logged
+// 802.1Q/802.1ad VLAN tags are skipped. VXLAN and Geneve packets are classified using the inner headers (pod
+// addresses instead of the node tunnel addresses) and the tunnel VNI is reported in the PacketLog (see
+// locate_headers in headers.rs). The blocklist is checked against both the inner and the outer addresses
+// The classifier is attached to both the ingress and the egress hooks. The direction (DIRECTION_INGRESS or
+// DIRECTION_EGRESS) is passed by the entrypoint and reported in the PacketLog together with the interface ifindex
+// Every accepted packet is aggregated in the FLOW_TABLE map (packets, bytes, first/last seen). Only the first packet
+// of a flow produces a PacketLog in the EVENTS map
+//
+// Features:
+// Users can directly block one ip, a list of ips or whole CIDR ranges. The prefixes goes directly into the blocklist
+// LPM trie and allows users to block the ips before entering into the userspace
+// The blocklist is checked against the remote address: the source address on ingress and the destination
+// address on egress. The lookup is done as soon as the addresses are read, so the packets of a blocklisted address
+// are dropped even when the rest of the headers cannot be parsed
+// Remote addresses and CIDR ranges can be rate limited with a token bucket (packets per second plus burst) stored in
+// the RATE_LIMITS LPM tries. The rate limit is checked after the blocklist (see rate_limit.rs)
+// Users can also define L4 filtering rules (src CIDR, dst CIDR, protocol, dst port range, action) that are
+// evaluated after the blocklist and the rate limits (see rules.rs)
+// When the classifier runs in audit mode (CLASSIFIER_CONFIG[CONFIG_ENFORCEMENT_MODE] == MODE_AUDIT) or a rule has the
+// audit action, the packet is not dropped and an AuditEvent is sent to the userspace instead
+// Every dropped packet produces a DropEvent in the DROP_EVENTS map. Blocklist, rate limit and rule drops are reported
+// by the ipv4/ipv6 classifiers with the full 5-tuple, parse errors and truncated headers are reported here
+//
+// Failure policy:
+// packets that cannot be parsed (parse errors, truncated headers, e.g. a TCP packet cut before the ports) are passed
+// by default (fail-open). Setting CLASSIFIER_CONFIG[CONFIG_FAILURE_POLICY] to FAILURE_POLICY_CLOSED drops them instead
+// Every error path increments its counter in the CLASSIFIER_COUNTERS map
+//
+// Returns a Result with a unit type () and a i64 error code. The error code is the drop reason (DROP_REASON_*)
+
+pub fn try_identity_classifier(ctx: TcContext, direction: u8) -> Result<(), i64> {
+    let (af, result) = match locate_headers(&ctx) {
+        Ok(headers) =>
+            match headers.ethertype {
+                OFFSETS::IPV4_ETHERTYPE => (AF_INET, try_ipv4_classifier(&ctx, direction, &headers)),
+                OFFSETS::IPV6_ETHERTYPE => (AF_INET6, try_ipv6_classifier(&ctx, direction, &headers)),
+                // only ipv4 and ipv6 protocols allowed
+                _ => {
+                    return Ok(());
+                }
+            }
+        Err(e) => (0, Err(e)),
+    };
+
+    if let Err(reason) = result {
+        increment_counter(reason as u32);
+        if reason == (DROP_REASON_PARSE_ERROR as i64) || reason == (DROP_REASON_TRUNCATED_HEADER as i64) {
+            if !is_fail_closed() {
+                increment_counter(COUNTER_FAIL_OPEN);
+                return Ok(());
+            }
+            let event = DropEvent {
+                af,
+                proto: 0,
+                reason: reason as u8,
+                rule_id: 0,
+                ifindex: unsafe { (*ctx.skb.skb).ifindex },
+                src_port: 0,
+                dst_port: 0,
+                src_ip: [0; 4],
+                dst_ip: [0; 4],
+            };
+            unsafe {
+                DROP_EVENTS.emit(&ctx, &event);
+            }
+        }
+    }
+
+    result
+}
+
+// docs:
+//
+// ipv4 classifier. Reads the ipv4 header (options included) and the L4 ports, checks the remote address
+// against the BLOCKLIST map and sends a PacketLog to the userspace
+// The ipv4 header starts at headers.l3_offset (after the vlan tags, or the inner header of a tunnel)
+// The blocklist lookup is done before the ipv4 options and the L4 header are parsed: a blocklisted address cannot
+// get through the fail-open policy with truncated or malformed packets
+// For VXLAN/Geneve packets both the inner (pod) and the outer (node) remote addresses are checked. The events report
+// the inner addresses
 //
 // Returns a Result with a unit type () and a i64 error code
 
-pub fn try_identity_classifier(ctx: TcContext) -> Result<(), i64> {
-    let eth_proto = u16::from_be(ctx.load::<u16>(12).map_err(|_| 1)?);
+fn try_ipv4_classifier(ctx: &TcContext, direction: u8, headers: &Headers) -> Result<(), i64> {
+    let l3_offset = headers.l3_offset;
+
+    // get the source ip,destination ip and connection id
+    let src_ip = load::<u32>(ctx, l3_offset + OFFSETS::SRC_BYTE_OFFSET)?; // L3+SOURCE_ADDRESS
+    let dst_ip = load::<u32>(ctx, l3_offset + OFFSETS::DST_BYTE_OFFSET)?; // L3+DESTINATION_ADDRESS
+    let proto = u8::from_be(load::<u8>(ctx, l3_offset + OFFSETS::IPV4_PROTOCOL_OFFSET)?);
+
+    // check if the remote address is in the blocklist
+    // the addresses are loaded with the raw packet bytes, so the native byte representation is already in network order
+    // full length prefix: the trie returns the longest stored prefix that contains the address
+    let remote_ip = if direction == DIRECTION_EGRESS { dst_ip } else { src_ip };
+    let remote_ip_be_bytes: [u8; 4] = remote_ip.to_ne_bytes();
+    let lpm_key = Key::new(32, remote_ip_be_bytes);
+    let blocklisted =
+        unsafe { BLOCKLIST.get(&lpm_key).is_some() } || is_outer_blocklisted(ctx, headers, direction != DIRECTION_EGRESS)?;
 
-    // only ipv4 protcol allowed
-    if eth_proto != OFFSETS::IPV4_ETHERTYPE {
+    // the parsing errors of a blocklisted packet are reported after the blocklist logic (zero ports in the events)
+    // the non-first fragments do not carry the L4 header: their ports are reported as zero
+    let l4_ports = ipv4_header_len(ctx, l3_offset).and_then(|ip_header_len| {
+        if is_ipv4_later_fragment(ctx, l3_offset)? {
+            return Ok((0, 0));
+        }
+        load_l4_ports(ctx, proto, l3_offset + ip_header_len) // L3+IHL-Lenght
+    });
+    let (src_port, dst_port) = match l4_ports {
+        Ok(ports) => ports,
+        Err(e) if !blocklisted => {
+            return Err(e);
+        }
+        Err(_) => (0, 0),
+    };
+
+    let pid: u32 = bpf_get_current_pid_tgid() as u32;
+
+    let src_words: [u32; 4] = [u32::from_be(src_ip), 0, 0, 0];
+    let dst_words: [u32; 4] = [u32::from_be(dst_ip), 0, 0, 0];
+    let mut audit_event = AuditEvent {
+        af: AF_INET,
+        proto,
+        reason: 0,
+        rule_id: 0,
+        ifindex: unsafe { (*ctx.skb.skb).ifindex },
+        src_port,
+        dst_port,
+        src_ip: src_words,
+        dst_ip: dst_words,
+    };
+
+    // blocklist logic
+    if blocklisted {
+        if is_audit_mode() {
+            audit_event.reason = AUDIT_REASON_BLOCKLIST;
+            emit_audit_event(ctx, &audit_event);
+        } else {
+            info!(
+                ctx,
+                "Blocking address: {}. Reason: Address is in a BLOCKLIST",
+                Ipv4Addr::from(remote_ip_be_bytes)
+            );
+            emit_drop_event(ctx, &audit_event, DROP_REASON_BLOCKLIST, 0);
+            return Err(DROP_REASON_BLOCKLIST as i64);
+        }
+    }
+    // audit mode: the blocklisted packets that cannot be parsed follow the failure policy
+    l4_ports?;
+
+    // rate limit logic. The same full length key is used for the RATE_LIMITS trie
+    if let Some(limit) = unsafe { RATE_LIMITS.get(&lpm_key) } {
+        let remote_words = if direction == DIRECTION_EGRESS { &dst_words } else { &src_words };
+        if is_rate_limited(AF_INET, remote_words, limit) {
+            if is_audit_mode() {
+                audit_event.reason = AUDIT_REASON_RATE_LIMIT;
+                emit_audit_event(ctx, &audit_event);
+            } else {
+                emit_drop_event(ctx, &audit_event, DROP_REASON_RATE_LIMIT, 0);
+                return Err(DROP_REASON_RATE_LIMIT as i64);
+            }
+        }
+    }
+
+    // L4 filtering rules
+    if let Some(rule) = evaluate_rules(AF_INET, &src_words, &dst_words, proto, dst_port) {
+        if rule.action == RULE_ACTION_AUDIT || (rule.action == RULE_ACTION_DROP && is_audit_mode()) {
+            audit_event.reason = AUDIT_REASON_RULE;
+            audit_event.rule_id = rule.rule_id;
+            emit_audit_event(ctx, &audit_event);
+        } else if rule.action == RULE_ACTION_DROP {
+            info!(
+                ctx,
+                "Blocking address: {}. Reason: matched filter rule {}",
+                Ipv4Addr::from(remote_ip_be_bytes),
+                rule.rule_id
+            );
+            emit_drop_event(ctx, &audit_event, DROP_REASON_RULE, rule.rule_id);
+            return Err(DROP_REASON_RULE as i64);
+        }
+    }
+
+    // per flow counters. The PacketLog is sent only for the first packet of a flow
+    let flow_key = FlowKey {
+        af: AF_INET,
+        proto,
+        direction,
+        src_port,
+        dst_port,
+        src_ip: src_words,
+        dst_ip: dst_words,
+    };
+    if !update_flow(ctx, &flow_key, audit_event.ifindex) {
         return Ok(());
     }
 
-    // read if the packets has Options
-    let first_ipv4_byte = u8::from_be(ctx.load::<u8>(OFFSETS::ETH_STACK_BYTES).map_err(|_| 1)?);
-    let ihl = (first_ipv4_byte &
-        0x0f) as usize; /* 0x0F=00001111 &=AND bit a bit operator to extract the last 4 bit*/
-    let ip_header_len = ihl * 4; //returns the header lenght in bytes
+    let log = PacketLog {
+        proto,
+        src_ip,
+        src_port,
+        dst_ip,
+        dst_port,
+        pid,
+        af: AF_INET,
+        src_ip_v6: [0; 4],
+        dst_ip_v6: [0; 4],
+        direction,
+        ifindex: audit_event.ifindex,
+        vni: headers.vni,
+    };
+    unsafe {
+        EVENTS.emit(ctx, &log); //output to userspace
+    }
+    Ok(())
+}
 
-    // get the source ip,destination ip and connection id
-    let src_ip = ctx.load::<u32>(OFFSETS::SRC_T0TAL_BYTES_OFFSET).map_err(|_| 1)?; // ETH+SOURCE_ADDRESS
-    let src_port = u16::from_be(
-        ctx
-            .load::<u16>(
-                OFFSETS::ETH_STACK_BYTES + ip_header_len + OFFSETS::SRC_PORT_OFFSET_FROM_IP_HEADER
-            )
-            .map_err(|_| 1)?
-    ); // 14+IHL-Lenght+0
-    let dst_ip = ctx.load::<u32>(OFFSETS::DST_T0TAL_BYTES_OFFSET).map_err(|_| 1)?; // ETH+ DESTINATION_ADDRESS
-    let dst_port = u16::from_be(
-        ctx
-            .load::<u16>(
-                OFFSETS::ETH_STACK_BYTES + ip_header_len + OFFSETS::DST_PORT_OFFSET_FROM_IP_HEADER
-            )
-            .map_err(|_| 1)?
-    ); // 14+IHL-Lenght+0
-    let proto = u8::from_be(ctx.load::<u8>(OFFSETS::PROTOCOL_T0TAL_BYTES_OFFSET).map_err(|_| 1)?);
+// docs:
+//
+// ipv6 classifier. Reads the source and destination addresses from the fixed ipv6 header, walks the
+// extension headers chain to reach the L4 header, checks the remote address against the BLOCKLIST_V6 map
+// and sends a PacketLog to the userspace
+// The ipv6 header starts at headers.l3_offset (after the vlan tags, or the inner header of a tunnel)
+// The blocklist lookup is done before the extension headers and the L4 header are parsed: a blocklisted address
+// cannot get through the fail-open policy with truncated packets or long extension headers chains
+// For VXLAN/Geneve packets both the inner (pod) and the outer (node) remote addresses are checked. The events report
+// the inner addresses
+//
+// Returns a Result with a unit type () and a i64 error code
+
+fn try_ipv6_classifier(ctx: &TcContext, direction: u8, headers: &Headers) -> Result<(), i64> {
+    let l3_offset = headers.l3_offset;
+    let src_ip = load::<[u8; 16]>(ctx, l3_offset + OFFSETS::IPV6_SRC_BYTE_OFFSET)?; // L3+SOURCE_ADDRESS
+    let dst_ip = load::<[u8; 16]>(ctx, l3_offset + OFFSETS::IPV6_DST_BYTE_OFFSET)?; // L3+DESTINATION_ADDRESS
+
+    // check if the remote address is in the blocklist
+    let remote_ip = if direction == DIRECTION_EGRESS { dst_ip } else { src_ip };
+    let lpm_key = Key::new(128, remote_ip);
+    let blocklisted =
+        unsafe { BLOCKLIST_V6.get(&lpm_key).is_some() } ||
+        is_outer_blocklisted(ctx, headers, direction != DIRECTION_EGRESS)?;
+
+    // the parsing errors of a blocklisted packet are reported after the blocklist logic (zero protocol and ports in
+    // the events)
+    // the non-first fragments do not carry the L4 header: their ports are reported as zero
+    let l4_header = walk_ipv6_extension_headers(ctx, l3_offset).and_then(|(proto, l4_offset, later_fragment)| {
+        if later_fragment {
+            return Ok((proto, (0, 0)));
+        }
+        load_l4_ports(ctx, proto, l4_offset).map(|ports| (proto, ports))
+    });
+    let (proto, (src_port, dst_port)) = match l4_header {
+        Ok(l4_header) => l4_header,
+        Err(e) if !blocklisted => {
+            return Err(e);
+        }
+        Err(_) => (0, (0, 0)),
+    };
 
     let pid: u32 = bpf_get_current_pid_tgid() as u32;
 
-    // check if the address is in the blocklist
-    let src_ip_be_bytes: [u8; 4] = src_ip.to_be_bytes(); // transforming the src_ip in big endian bytes
+    let src_words = ipv6_to_words(&src_ip);
+    let dst_words = ipv6_to_words(&dst_ip);
+    let mut audit_event = AuditEvent {
+        af: AF_INET6,
+        proto,
+        reason: 0,
+        rule_id: 0,
+        ifindex: unsafe { (*ctx.skb.skb).ifindex },
+        src_port,
+        dst_port,
+        src_ip: src_words,
+        dst_ip: dst_words,
+    };
 
     // blocklist logic
-    if unsafe { BLOCKLIST.get(&src_ip_be_bytes).is_some() } {
-        info!(
-            &ctx,
-            "Blocking address: {}. Reason: Address is in a BLOCKLIST",
-            Ipv4Addr::from(src_ip_be_bytes)
-        );
-        return Err(1);
-    } else {
-        let key = ConnArray {
-            src_ip,
-            dst_ip,
-            src_port,
-            dst_port,
-            proto,
-        };
-
-        let log = PacketLog {
-            proto,
-            src_ip,
-            src_port,
-            dst_ip,
-            dst_port,
-            pid,
-        };
-        unsafe {
-            EVENTS.output(&ctx, &log, 0); //output to userspace
+    if blocklisted {
+        if is_audit_mode() {
+            audit_event.reason = AUDIT_REASON_BLOCKLIST;
+            emit_audit_event(ctx, &audit_event);
+        } else {
+            info!(
+                ctx,
+                "Blocking address: {}. Reason: Address is in a BLOCKLIST",
+                Ipv6Addr::from(remote_ip)
+            );
+            emit_drop_event(ctx, &audit_event, DROP_REASON_BLOCKLIST, 0);
+            return Err(DROP_REASON_BLOCKLIST as i64);
+        }
+    }
+    // audit mode: the blocklisted packets that cannot be parsed follow the failure policy
+    l4_header?;
+
+    // rate limit logic
+    if let Some(limit) = unsafe { RATE_LIMITS_V6.get(&lpm_key) } {
+        let remote_words = if direction == DIRECTION_EGRESS { &dst_words } else { &src_words };
+        if is_rate_limited(AF_INET6, remote_words, limit) {
+            if is_audit_mode() {
+                audit_event.reason = AUDIT_REASON_RATE_LIMIT;
+                emit_audit_event(ctx, &audit_event);
+            } else {
+                emit_drop_event(ctx, &audit_event, DROP_REASON_RATE_LIMIT, 0);
+                return Err(DROP_REASON_RATE_LIMIT as i64);
+            }
         }
     }
+
+    // L4 filtering rules
+    if let Some(rule) = evaluate_rules(AF_INET6, &src_words, &dst_words, proto, dst_port) {
+        if rule.action == RULE_ACTION_AUDIT || (rule.action == RULE_ACTION_DROP && is_audit_mode()) {
+            audit_event.reason = AUDIT_REASON_RULE;
+            audit_event.rule_id = rule.rule_id;
+            emit_audit_event(ctx, &audit_event);
+        } else if rule.action == RULE_ACTION_DROP {
+            info!(
+                ctx,
+                "Blocking address: {}. Reason: matched filter rule {}",
+                Ipv6Addr::from(remote_ip),
+                rule.rule_id
+            );
+            emit_drop_event(ctx, &audit_event, DROP_REASON_RULE, rule.rule_id);
+            return Err(DROP_REASON_RULE as i64);
+        }
+    }
+
+    // per flow counters. The PacketLog is sent only for the first packet of a flow
+    let flow_key = FlowKey {
+        af: AF_INET6,
+        proto,
+        direction,
+        src_port,
+        dst_port,
+        src_ip: src_words,
+        dst_ip: dst_words,
+    };
+    if !update_flow(ctx, &flow_key, audit_event.ifindex) {
+        return Ok(());
+    }
+
+    let log = PacketLog {
+        proto,
+        src_ip: 0,
+        src_port,
+        dst_ip: 0,
+        dst_port,
+        pid,
+        af: AF_INET6,
+        src_ip_v6: src_words,
+        dst_ip_v6: dst_words,
+        direction,
+        ifindex: audit_event.ifindex,
+        vni: headers.vni,
+    };
+    unsafe {
+        EVENTS.emit(ctx, &log); //output to userspace
+    }
+
     Ok(())
 }
+
+// docs:
+//
+// This is an helper function to update the FLOW_TABLE counters of a flow.
+// The map is a per cpu map so the counters can be updated without atomic operations. The userspace merges the
+// values of every cpu
+//
+// Returns true if the flow was not in the map (first packet of the flow seen on this cpu)
+
+#[inline(always)]
+fn update_flow(ctx: &TcContext, key: &FlowKey, ifindex: u32) -> bool {
+    let now = unsafe { bpf_ktime_get_ns() };
+    let len = ctx.len() as u64;
+
+    match unsafe { FLOW_TABLE.get_ptr_mut(key) } {
+        Some(stats) => {
+            unsafe {
+                (*stats).packets += 1;
+                (*stats).bytes += len;
+                (*stats).last_seen_ns = now;
+                (*stats).ifindex = ifindex;
+            }
+            false
+        }
+        None => {
+            let stats = FlowStats {
+                packets: 1,
+                bytes: len,
+                first_seen_ns: now,
+                last_seen_ns: now,
+                ifindex,
+                _padding: 0,
+            };
+            let _ = unsafe { FLOW_TABLE.insert(key, &stats, 0) };
+            true
+        }
+    }
+}
+
+// docs:
+//
+// This is an helper function to read the enforcement mode from the CLASSIFIER_CONFIG map
+// A missing value is treated as MODE_ENFORCE
+
+#[inline(always)]
+pub fn is_audit_mode() -> bool {
+    match unsafe { CLASSIFIER_CONFIG.get(CONFIG_ENFORCEMENT_MODE) } {
+        Some(mode) => *mode == MODE_AUDIT,
+        None => false,
+    }
+}
+
+// docs:
+//
+// This is an helper function to read the failure policy from the CLASSIFIER_CONFIG map
+// A missing value is treated as FAILURE_POLICY_OPEN
+
+#[inline(always)]
+fn is_fail_closed() -> bool {
+    match unsafe { CLASSIFIER_CONFIG.get(CONFIG_FAILURE_POLICY) } {
+        Some(policy) => *policy == FAILURE_POLICY_CLOSED,
+        None => false,
+    }
+}
+
+// docs:
+//
+// This is an helper function to increment a counter in the CLASSIFIER_COUNTERS map
+// The map is a per cpu array so the increment does not need atomic operations
+
+#[inline(always)]
+pub fn increment_counter(index: u32) {
+    if let Some(counter) = unsafe { CLASSIFIER_COUNTERS.get_ptr_mut(index) } {
+        unsafe {
+            *counter += 1;
+        }
+    }
+}
+
+// docs:
+//
+// This is an helper function to send a "would-have-dropped" event to the userspace using the AUDIT_EVENTS map
+
+#[inline(always)]
+fn emit_audit_event(ctx: &TcContext, event: &AuditEvent) {
+    unsafe {
+        AUDIT_EVENTS.emit(ctx, event);
+    }
+}
+
+// docs:
+//
+// This is an helper function to send a DropEvent to the userspace using the DROP_EVENTS map.
+// The 5-tuple and the ifindex are copied from the AuditEvent built by the classifiers
+
+#[inline(always)]
+fn emit_drop_event(ctx: &TcContext, packet: &AuditEvent, reason: u8, rule_id: u32) {
+    let event = DropEvent {
+        af: packet.af,
+        proto: packet.proto,
+        reason,
+        rule_id,
+        ifindex: packet.ifindex,
+        src_port: packet.src_port,
+        dst_port: packet.dst_port,
+        src_ip: packet.src_ip,
+        dst_ip: packet.dst_ip,
+    };
+    unsafe {
+        DROP_EVENTS.emit(ctx, &event);
+    }
+}
+
+// docs:
+//
+// This is an helper function to read the L4 ports of a packet. Takes the protocol and the offset of the L4 header
+//      - TCP, UDP, UDP-Lite and SCTP: source and destination ports (the first 4 bytes of the header)
+//      - ICMP and ICMPv6: src_port is zero and dst_port is (type << 8) | code
+//      - every other protocol: both ports are zero
+//
+// Returns the (src_port, dst_port) pair or DROP_REASON_TRUNCATED_HEADER as i64
+
+#[inline(always)]
+fn load_l4_ports(ctx: &TcContext, proto: u8, l4_offset: usize) -> Result<(u16, u16), i64> {
+    match proto {
+        IPPROTO_TCP | IPPROTO_UDP | IPPROTO_UDPLITE | IPPROTO_SCTP => {
+            let src_port = u16::from_be(load::<u16>(ctx, l4_offset + OFFSETS::SRC_PORT_OFFSET_FROM_IP_HEADER)?);
+            let dst_port = u16::from_be(load::<u16>(ctx, l4_offset + OFFSETS::DST_PORT_OFFSET_FROM_IP_HEADER)?);
+            Ok((src_port, dst_port))
+        }
+        IPPROTO_ICMP | IPPROTO_ICMPV6 => {
+            let icmp_type = load::<u8>(ctx, l4_offset)?;
+            let icmp_code = load::<u8>(ctx, l4_offset + 1)?;
+            Ok((0, ((icmp_type as u16) << 8) | (icmp_code as u16)))
+        }
+        _ => Ok((0, 0)),
+    }
+}
+
+// docs:
+//
+// This is an helper function to convert a 16 bytes ipv6 address (network order) into four host order words
+// The same layout is used by the userspace formatters (format_ipv6)
+
+#[inline(always)]
+pub fn ipv6_to_words(addr: &[u8; 16]) -> [u32; 4] {
+    [
+        u32::from_be_bytes([addr[0], addr[1], addr[2], addr[3]]),
+        u32::from_be_bytes([addr[4], addr[5], addr[6], addr[7]]),
+        u32::from_be_bytes([addr[8], addr[9], addr[10], addr[11]]),
+        u32::from_be_bytes([addr[12], addr[13], addr[14], addr[15]]),
+    ]
+}
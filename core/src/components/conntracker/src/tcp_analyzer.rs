@@ -6,78 +6,163 @@ use aya_ebpf::helpers::{
 };
 
 use crate::bindings::{ sk_buff };
-use crate::offsets::OFFSETS;
-use crate::data_structures::{ PACKET_REGISTRY, TcpPacketRegistry };
+use crate::offsets::{ kernel_offset, OFFSETS };
+use crate::data_structures::{ EventOutput, PACKET_REGISTRY, TcpPacketRegistry };
+use crate::data_structures::{
+    AF_INET,
+    AF_INET6,
+    IPPROTO_TCP,
+    OFFSET_SKB_HEAD,
+    OFFSET_SKB_NETWORK_HEADER,
+    OFFSET_SKB_TRANSPORT_HEADER,
+};
+use crate::tc::ipv6_to_words;
 use crate::veth_tracer::{ read_linux_inner_struct, read_linux_inner_value };
 
-// docs:
-// TODO: add function documentation
+// sockaddr_in / sockaddr_in6 layouts (include/uapi/linux/in.h, include/uapi/linux/in6.h)
+pub const SOCKADDR_PORT_OFFSET: usize = 2;
+pub const SOCKADDR_IN_ADDR_OFFSET: usize = 4;
+pub const SOCKADDR_IN6_ADDR_OFFSET: usize = 8;
 
 // docs:
 //
 // how skb works? http://oldvger.kernel.org/~davem/skb_data.html
 //
 // ref: https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv4/tcp_ipv4.c#L2195
+//      https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv6/tcp_ipv6.c#L1731
+//
+// This function is attached to tcp_v4_rcv (af = AF_INET) and tcp_v6_rcv (af = AF_INET6)
+// When the tcp receive functions are called the ip header has already been pulled: skb->data points to the tcp
+// header. The ip and tcp headers are read from skb->head plus the network_header and transport_header offsets
 //
+// Returns a Result type with the value as u32 or an error code as i64
 
-//in tcp_v4_recv skb->data
-pub fn try_tcp_analyzer(ctx: ProbeContext) -> Result<u32, i64> {
+pub fn try_tcp_analyzer(ctx: ProbeContext, af: u16) -> Result<u32, i64> {
     let sk_buff_pointer: *const sk_buff = ctx.arg(0).ok_or(1i64)?;
     // first control: i'm, verifying that the pointer is not null
     if sk_buff_pointer.is_null() {
         return Err(1);
     }
 
-    let skb_data_pointer = read_linux_inner_struct::<u8>(
+    let skb_head_pointer = read_linux_inner_struct::<u8>(
         sk_buff_pointer as *const u8,
-        OFFSETS::SKB_DATA_POINTER
+        kernel_offset(OFFSET_SKB_HEAD)?
     )?;
-    let first_ipv4_byte = read_linux_inner_value::<u8>(skb_data_pointer as *const u8, 0)?;
-    let ihl = (first_ipv4_byte & 0x0f) as usize; // 0x0F=00001111 &=AND bit a bit operator to extract the last 4 bit
-    let ip_header_len = ihl * 4; //returns the header lenght in bytes
-
-    let proto = read_linux_inner_struct::<u8>(
-        skb_data_pointer,
-        OFFSETS::IPV4_PROTOCOL_OFFSET
-    )? as u8;
-
-    if proto != 6 {
-        return Ok(0);
-    } else {
-        // get the source ip,destination ip and connection id
-        let src_ip = read_linux_inner_value::<u32>(skb_data_pointer, OFFSETS::SRC_BYTE_OFFSET)?;
-        let dst_ip = read_linux_inner_value::<u32>(skb_data_pointer, OFFSETS::DST_BYTE_OFFSET)?;
-        let src_port = u16::from_be(
-            read_linux_inner_value(
-                skb_data_pointer,
-                ip_header_len + OFFSETS::SRC_PORT_OFFSET_FROM_IP_HEADER
-            )?
-        );
-        let dst_port = u16::from_be(
-            read_linux_inner_value(
-                skb_data_pointer,
-                ip_header_len + OFFSETS::DST_PORT_OFFSET_FROM_IP_HEADER
-            )?
-        );
-
-        let command = bpf_get_current_comm()?;
-        let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
-        let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
-
-        let log = TcpPacketRegistry {
-            proto,
-            src_ip,
-            dst_ip,
-            src_port,
-            dst_port,
-            pid,
-            command,
-            cgroup_id,
-        };
-        unsafe {
-            PACKET_REGISTRY.output(&ctx, &log, 0);
+    let network_header = read_linux_inner_value::<u16>(
+        sk_buff_pointer as *const u8,
+        kernel_offset(OFFSET_SKB_NETWORK_HEADER)?
+    )? as usize;
+    let transport_header = read_linux_inner_value::<u16>(
+        sk_buff_pointer as *const u8,
+        kernel_offset(OFFSET_SKB_TRANSPORT_HEADER)?
+    )? as usize;
+
+    let ip_header_pointer = unsafe { skb_head_pointer.add(network_header) };
+    let tcp_header_pointer = unsafe { skb_head_pointer.add(transport_header) };
+
+    let mut log = new_registry_log(IPPROTO_TCP, af)?;
+
+    match af {
+        AF_INET => {
+            let proto = read_linux_inner_value::<u8>(ip_header_pointer, OFFSETS::IPV4_PROTOCOL_OFFSET)?;
+            if proto != IPPROTO_TCP {
+                return Ok(0);
+            }
+            log.src_ip = read_linux_inner_value::<u32>(ip_header_pointer, OFFSETS::SRC_BYTE_OFFSET)?;
+            log.dst_ip = read_linux_inner_value::<u32>(ip_header_pointer, OFFSETS::DST_BYTE_OFFSET)?;
+        }
+        AF_INET6 => {
+            // tcp_v6_rcv is only called for tcp segments, the extension headers (if any) are already parsed
+            let src_ip = read_linux_inner_value::<[u8; 16]>(
+                ip_header_pointer,
+                OFFSETS::IPV6_SRC_BYTE_OFFSET
+            )?;
+            let dst_ip = read_linux_inner_value::<[u8; 16]>(
+                ip_header_pointer,
+                OFFSETS::IPV6_DST_BYTE_OFFSET
+            )?;
+            log.src_ip_v6 = ipv6_to_words(&src_ip);
+            log.dst_ip_v6 = ipv6_to_words(&dst_ip);
+        }
+        _ => {
+            return Err(1);
         }
     }
 
+    log.src_port = u16::from_be(
+        read_linux_inner_value(tcp_header_pointer, OFFSETS::SRC_PORT_OFFSET_FROM_IP_HEADER)?
+    );
+    log.dst_port = u16::from_be(
+        read_linux_inner_value(tcp_header_pointer, OFFSETS::DST_PORT_OFFSET_FROM_IP_HEADER)?
+    );
+
+    unsafe {
+        PACKET_REGISTRY.emit(&ctx, &log);
+    }
+
     Ok(0)
 }
+
+// docs:
+//
+// ref: https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv4/tcp_ipv4.c#L221
+//      https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv6/tcp_ipv6.c#L140
+//
+// This function is attached to tcp_v4_connect (af = AF_INET) and tcp_v6_connect (af = AF_INET6)
+// The destination is read from the sockaddr passed to connect() (second argument, already copied in the kernel
+// memory). The source address and port are chosen by the connect function itself, so they are zero in the event
+//
+// Returns a Result type with the value as u32 or an error code as i64
+
+pub fn try_tcp_connect_analyzer(ctx: ProbeContext, af: u16) -> Result<u32, i64> {
+    let uaddr: *const u8 = ctx.arg(1).ok_or(1i64)?;
+
+    let mut log = new_registry_log(IPPROTO_TCP, af)?;
+
+    match af {
+        AF_INET => {
+            log.dst_ip = read_linux_inner_value::<u32>(uaddr, SOCKADDR_IN_ADDR_OFFSET)?;
+        }
+        AF_INET6 => {
+            let dst_ip = read_linux_inner_value::<[u8; 16]>(uaddr, SOCKADDR_IN6_ADDR_OFFSET)?;
+            log.dst_ip_v6 = ipv6_to_words(&dst_ip);
+        }
+        _ => {
+            return Err(1);
+        }
+    }
+    log.dst_port = u16::from_be(read_linux_inner_value(uaddr, SOCKADDR_PORT_OFFSET)?);
+
+    unsafe {
+        PACKET_REGISTRY.emit(&ctx, &log);
+    }
+
+    Ok(0)
+}
+
+// docs:
+//
+// This is an helper function to compose an empty TcpPacketRegistry with the current task informations
+// Also used by the udp socket tracers (udp_tracer.rs)
+//
+// Returns a Result type with the TcpPacketRegistry or an error code as i64
+
+pub fn new_registry_log(proto: u8, af: u16) -> Result<TcpPacketRegistry, i64> {
+    let command = bpf_get_current_comm()?;
+    let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+
+    Ok(TcpPacketRegistry {
+        proto,
+        src_ip: 0,
+        dst_ip: 0,
+        src_port: 0,
+        dst_port: 0,
+        pid,
+        command,
+        cgroup_id,
+        af,
+        src_ip_v6: [0; 4],
+        dst_ip_v6: [0; 4],
+    })
+}
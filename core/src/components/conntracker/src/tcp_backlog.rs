@@ -0,0 +1,131 @@
+use aya_ebpf::{
+    helpers::{ bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_ktime_get_ns },
+    programs::ProbeContext,
+};
+
+use crate::bindings::net;
+use crate::data_structures::{ EventOutput, ListenerEvent, LISTENER_EVENTS };
+use crate::data_structures::{
+    LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW,
+    LISTENER_EVENT_MEMORY_PRESSURE,
+    LISTENER_EVENT_SYN_QUEUE_OVERFLOW,
+    OFFSET_NET_NS_INUM,
+    OFFSET_SKC_FAMILY,
+    OFFSET_SKC_NET,
+    OFFSET_SKC_NUM,
+    OFFSET_SK_ACK_BACKLOG,
+    OFFSET_SK_MAX_ACK_BACKLOG,
+};
+use crate::offsets::kernel_offset;
+use crate::veth_tracer::{ read_linux_inner_struct, read_linux_inner_value };
+
+// docs:
+//
+// This function is attached to tcp_v4_syn_recv_sock and tcp_v6_syn_recv_sock. The kernel calls them on the
+// listening socket when the last ACK of the handshake is received, to create the child socket.
+// They refuse to create the child (and increment the ListenOverflows counter) when the accept queue is full
+// (sk_acceptq_is_full: sk_ack_backlog > sk_max_ack_backlog), the same check is replicated here
+//
+// The ipv6 function calls the ipv4 one for the ipv4-mapped connections: the address family of the request socket
+// (third argument) is checked to count every overflow once
+//
+// Returns a Result type with the value as u32 or an error code as i64
+
+pub fn try_accept_queue_tracer(ctx: &ProbeContext, af: u16) -> Result<u32, i64> {
+    let sk: *const u8 = ctx.arg(0).ok_or(1i64)?;
+    let req: *const u8 = ctx.arg(2).ok_or(1i64)?;
+
+    let req_af = read_linux_inner_value::<u16>(req, kernel_offset(OFFSET_SKC_FAMILY)?)?;
+    if req_af != af {
+        return Ok(0);
+    }
+
+    let backlog = read_linux_inner_value::<u32>(sk, kernel_offset(OFFSET_SK_ACK_BACKLOG)?)?;
+    let max_backlog = read_linux_inner_value::<u32>(sk, kernel_offset(OFFSET_SK_MAX_ACK_BACKLOG)?)?;
+    if backlog <= max_backlog {
+        return Ok(0);
+    }
+
+    let mut event = new_listener_event(sk, LISTENER_EVENT_ACCEPT_QUEUE_OVERFLOW)?;
+    event.backlog = backlog;
+    event.max_backlog = max_backlog;
+
+    unsafe {
+        LISTENER_EVENTS.emit(ctx, &event);
+    }
+
+    Ok(0)
+}
+
+// docs:
+//
+// This function is attached to tcp_syn_flood_action. The kernel calls it on the listening socket when a SYN is
+// received and the SYN queue is full: the listener answers with SYN cookies (net.ipv4.tcp_syncookies = 1) or drops
+// the SYN (net.ipv4.tcp_syncookies = 0)
+//
+// Returns a Result type with the value as u32 or an error code as i64
+
+pub fn try_syn_flood_tracer(ctx: &ProbeContext) -> Result<u32, i64> {
+    let sk: *const u8 = ctx.arg(0).ok_or(1i64)?;
+
+    let mut event = new_listener_event(sk, LISTENER_EVENT_SYN_QUEUE_OVERFLOW)?;
+    event.backlog = read_linux_inner_value::<u32>(sk, kernel_offset(OFFSET_SK_ACK_BACKLOG)?)?;
+    event.max_backlog = read_linux_inner_value::<u32>(sk, kernel_offset(OFFSET_SK_MAX_ACK_BACKLOG)?)?;
+
+    unsafe {
+        LISTENER_EVENTS.emit(ctx, &event);
+    }
+
+    Ok(0)
+}
+
+// docs:
+//
+// This function is attached to tcp_enter_memory_pressure. The kernel calls it when the memory allocated by the tcp
+// sockets exceeds the net.ipv4.tcp_mem pressure threshold. The socket passed as argument is the one that
+// triggered the allocation
+//
+// Returns a Result type with the value as u32 or an error code as i64
+
+pub fn try_memory_pressure_tracer(ctx: &ProbeContext) -> Result<u32, i64> {
+    let sk: *const u8 = ctx.arg(0).ok_or(1i64)?;
+
+    let event = new_listener_event(sk, LISTENER_EVENT_MEMORY_PRESSURE)?;
+
+    unsafe {
+        LISTENER_EVENTS.emit(ctx, &event);
+    }
+
+    Ok(0)
+}
+
+// docs:
+//
+// This is an helper function to compose a ListenerEvent from a socket pointer
+// Reads the local port, the address family and the network namespace of the socket plus the current task
+//
+// Returns a Result type with the ListenerEvent or an error code as i64
+
+fn new_listener_event(sk: *const u8, kind: u8) -> Result<ListenerEvent, i64> {
+    let lport = read_linux_inner_value::<u16>(sk, kernel_offset(OFFSET_SKC_NUM)?)?;
+    let af = read_linux_inner_value::<u16>(sk, kernel_offset(OFFSET_SKC_FAMILY)?)?;
+
+    let net = read_linux_inner_struct::<net>(sk, kernel_offset(OFFSET_SKC_NET)?)?;
+    let netns = read_linux_inner_value::<u32>(net as *const u8, kernel_offset(OFFSET_NET_NS_INUM)?)?;
+
+    let tgid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    let comm = bpf_get_current_comm().unwrap_or([0u8; 16]);
+
+    Ok(ListenerEvent {
+        ts_ns: unsafe { bpf_ktime_get_ns() },
+        netns,
+        tgid,
+        backlog: 0,
+        max_backlog: 0,
+        comm,
+        lport,
+        af,
+        kind,
+        _padding: [0; 3],
+    })
+}
@@ -0,0 +1,210 @@
+use aya_ebpf::{ helpers::{ bpf_ktime_get_ns, bpf_probe_read_kernel }, programs::TracePointContext };
+
+use crate::data_structures::{ EventOutput, TcpConnEvent, TcpConnection, TCP_CONNECTIONS, TCP_CONN_EVENTS };
+use crate::data_structures::{ AF_INET, AF_INET6, DIRECTION_EGRESS, DIRECTION_INGRESS };
+use crate::data_structures::{
+    CLOSE_REASON_FAILED,
+    CLOSE_REASON_FIN,
+    CLOSE_REASON_RST,
+    CONN_EVENT_CLOSED,
+    CONN_EVENT_ESTABLISHED,
+    OFFSET_TCP_BYTES_ACKED,
+    OFFSET_TCP_BYTES_RECEIVED,
+    OFFSET_INET_SOCK_SET_STATE_SKADDR,
+    OFFSET_INET_SOCK_SET_STATE_OLDSTATE,
+    OFFSET_INET_SOCK_SET_STATE_NEWSTATE,
+    OFFSET_INET_SOCK_SET_STATE_SPORT,
+    OFFSET_INET_SOCK_SET_STATE_DPORT,
+    OFFSET_INET_SOCK_SET_STATE_FAMILY,
+    OFFSET_INET_SOCK_SET_STATE_PROTOCOL,
+    OFFSET_INET_SOCK_SET_STATE_SADDR,
+    OFFSET_INET_SOCK_SET_STATE_DADDR,
+    OFFSET_INET_SOCK_SET_STATE_SADDR_V6,
+    OFFSET_INET_SOCK_SET_STATE_DADDR_V6,
+};
+use crate::offsets::kernel_offset;
+
+// docs:
+//
+// sock:inet_sock_set_state tracepoint record (stable since linux 4.16)
+// the field offsets are read from the KERNEL_OFFSETS map (see the OFFSET_INET_SOCK_SET_STATE_* indexes), resolved
+// by the userspace from /sys/kernel/tracing/events/sock/inet_sock_set_state/format
+//
+// the ports are already converted to the host byte order by the kernel, the addresses are in network order
+//
+
+// tcp states (include/net/tcp_states.h)
+const TCP_ESTABLISHED: i32 = 1;
+const TCP_SYN_SENT: i32 = 2;
+const TCP_SYN_RECV: i32 = 3;
+const TCP_FIN_WAIT1: i32 = 4;
+const TCP_FIN_WAIT2: i32 = 5;
+const TCP_TIME_WAIT: i32 = 6;
+const TCP_CLOSE: i32 = 7;
+const TCP_LAST_ACK: i32 = 9;
+const TCP_LISTEN: i32 = 10;
+const TCP_CLOSING: i32 = 11;
+
+const IPPROTO_TCP: u16 = 6;
+
+// docs:
+//
+// This is the main function of the tcp connection lifecycle tracker. Attached to the sock:inet_sock_set_state
+// tracepoint, it follows every tcp socket state transition:
+//      - X -> TCP_ESTABLISHED: the connection is saved in the TCP_CONNECTIONS map and an established event is sent
+//      - X -> TCP_CLOSE: the connection is removed from the TCP_CONNECTIONS map and a closed event is sent with the
+//                        duration, the bytes sent/received and the close reason
+//
+// Connections established before the program was attached and failed handshakes are not in the map: their closed
+// event has a zero duration
+//
+// Returns a Result type with the value as u32 or an error code as i64
+
+pub fn try_tcp_state_tracer(ctx: &TracePointContext) -> Result<u32, i64> {
+    let protocol: u16 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_PROTOCOL)?)? };
+    if protocol != IPPROTO_TCP {
+        return Ok(0);
+    }
+
+    let newstate: i32 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_NEWSTATE)?)? };
+    if newstate != TCP_ESTABLISHED && newstate != TCP_CLOSE {
+        return Ok(0);
+    }
+
+    let skaddr: u64 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_SKADDR)?)? };
+    let oldstate: i32 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_OLDSTATE)?)? };
+    let now = unsafe { bpf_ktime_get_ns() };
+
+    if newstate == TCP_ESTABLISHED {
+        let connection = read_connection(ctx, oldstate, now)?;
+        unsafe {
+            TCP_CONNECTIONS.insert(&skaddr, &connection, 0)?;
+        }
+        let event = new_event(skaddr, now, &connection, CONN_EVENT_ESTABLISHED);
+        unsafe {
+            TCP_CONN_EVENTS.emit(ctx, &event);
+        }
+        return Ok(0);
+    }
+
+    // TCP_CLOSE. Sockets that never carried a connection (listeners, bind or listen failures) are skipped
+    if oldstate == TCP_CLOSE || oldstate == TCP_LISTEN {
+        return Ok(0);
+    }
+
+    let close_reason = match oldstate {
+        TCP_FIN_WAIT1 | TCP_FIN_WAIT2 | TCP_CLOSING | TCP_LAST_ACK | TCP_TIME_WAIT => CLOSE_REASON_FIN,
+        TCP_SYN_SENT | TCP_SYN_RECV => CLOSE_REASON_FAILED,
+        _ => CLOSE_REASON_RST,
+    };
+
+    let (connection, established_ns) = match unsafe { TCP_CONNECTIONS.get(&skaddr) } {
+        Some(connection) => (*connection, connection.established_ns),
+        None => (read_connection(ctx, oldstate, now)?, now),
+    };
+
+    let mut event = new_event(skaddr, now, &connection, CONN_EVENT_CLOSED);
+    event.duration_ns = now - established_ns;
+    event.close_reason = close_reason;
+    event.bytes_sent = read_tcp_counter(skaddr, OFFSET_TCP_BYTES_ACKED).unwrap_or(0);
+    event.bytes_received = read_tcp_counter(skaddr, OFFSET_TCP_BYTES_RECEIVED).unwrap_or(0);
+
+    unsafe {
+        TCP_CONN_EVENTS.emit(ctx, &event);
+        let _ = TCP_CONNECTIONS.remove(&skaddr);
+    }
+
+    Ok(0)
+}
+
+// docs:
+//
+// This is an helper function to compose a TcpConnection from the tracepoint arguments
+// The direction is inferred from the state that precedes TCP_ESTABLISHED
+//
+// Returns a Result type with the TcpConnection or an error code as i64
+
+fn read_connection(ctx: &TracePointContext, oldstate: i32, now: u64) -> Result<TcpConnection, i64> {
+    let af: u16 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_FAMILY)?)? };
+    let src_port: u16 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_SPORT)?)? };
+    let dst_port: u16 = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_DPORT)?)? };
+
+    let mut src_ip = [0u32; 4];
+    let mut dst_ip = [0u32; 4];
+    match af {
+        AF_INET => {
+            let saddr: [u8; 4] = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_SADDR)?)? };
+            let daddr: [u8; 4] = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_DADDR)?)? };
+            src_ip[0] = u32::from_be_bytes(saddr);
+            dst_ip[0] = u32::from_be_bytes(daddr);
+        }
+        AF_INET6 => {
+            let saddr: [u8; 16] = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_SADDR_V6)?)? };
+            let daddr: [u8; 16] = unsafe { ctx.read_at(kernel_offset(OFFSET_INET_SOCK_SET_STATE_DADDR_V6)?)? };
+            for i in 0..4 {
+                src_ip[i] = u32::from_be_bytes([
+                    saddr[i * 4],
+                    saddr[i * 4 + 1],
+                    saddr[i * 4 + 2],
+                    saddr[i * 4 + 3],
+                ]);
+                dst_ip[i] = u32::from_be_bytes([
+                    daddr[i * 4],
+                    daddr[i * 4 + 1],
+                    daddr[i * 4 + 2],
+                    daddr[i * 4 + 3],
+                ]);
+            }
+        }
+        _ => {
+            return Err(1);
+        }
+    }
+
+    let direction = match oldstate {
+        TCP_SYN_SENT => DIRECTION_EGRESS,
+        _ => DIRECTION_INGRESS,
+    };
+
+    Ok(TcpConnection {
+        established_ns: now,
+        src_ip,
+        dst_ip,
+        af,
+        src_port,
+        dst_port,
+        direction,
+        _padding: 0,
+    })
+}
+
+fn new_event(skaddr: u64, now: u64, connection: &TcpConnection, event_type: u8) -> TcpConnEvent {
+    TcpConnEvent {
+        skaddr,
+        ts_ns: now,
+        duration_ns: 0,
+        bytes_sent: 0,
+        bytes_received: 0,
+        src_ip: connection.src_ip,
+        dst_ip: connection.dst_ip,
+        af: connection.af,
+        src_port: connection.src_port,
+        dst_port: connection.dst_port,
+        direction: connection.direction,
+        event_type,
+        close_reason: 0,
+        _padding: [0; 7],
+    }
+}
+
+// docs:
+//
+// This is an helper function to read a u64 counter of the tcp_sock structure
+// Takes the socket address and an OFFSET_* index (see data_structures.rs)
+//
+// Returns a Result type with the counter value as u64 or an error code as i64
+
+fn read_tcp_counter(skaddr: u64, index: u32) -> Result<u64, i64> {
+    let offset = kernel_offset(index)?;
+    unsafe { bpf_probe_read_kernel((skaddr as usize + offset) as *const u64).map_err(|_| 1) }
+}
@@ -0,0 +1,129 @@
+use aya_ebpf::programs::ProbeContext;
+
+use crate::data_structures::{ EventOutput, PACKET_REGISTRY };
+use crate::data_structures::{
+    AF_INET,
+    AF_INET6,
+    IPPROTO_UDP,
+    OFFSET_MSGHDR_MSG_NAME,
+    OFFSET_SKC_DADDR,
+    OFFSET_SKC_DPORT,
+    OFFSET_SKC_FAMILY,
+    OFFSET_SKC_NUM,
+    OFFSET_SKC_RCV_SADDR,
+    OFFSET_SKC_V6_DADDR,
+    OFFSET_SKC_V6_RCV_SADDR,
+};
+use crate::offsets::kernel_offset;
+use crate::tc::ipv6_to_words;
+use crate::tcp_analyzer::{
+    new_registry_log,
+    SOCKADDR_IN6_ADDR_OFFSET,
+    SOCKADDR_IN_ADDR_OFFSET,
+    SOCKADDR_PORT_OFFSET,
+};
+use crate::veth_tracer::{ read_linux_inner_struct, read_linux_inner_value };
+
+// docs:
+//
+// ref: https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv4/udp.c#L1270
+//      https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv4/udp.c#L2060
+//      https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv6/udp.c#L1390
+//      https://elixir.bootlin.com/linux/v6.17.7/source/net/ipv6/udp.c#L470
+//
+// This function is attached to udp_sendmsg, udp_recvmsg (af = AF_INET) and udpv6_sendmsg, udpv6_recvmsg
+// (af = AF_INET6). All of them take the socket as first argument and the msghdr as second argument
+//
+// The addresses are read from the socket: the local address/port is always available once the socket is bound,
+// the remote address/port only for connected sockets. For unconnected sockets sendmsg carries the destination in
+// msghdr->msg_name, recvmsg fills msg_name only after the datagram is dequeued so the remote side is zero in the
+// event
+//
+// Send events are reported as local -> remote, receive events as remote -> local
+//
+// udpv6_sendmsg calls udp_sendmsg with the ipv6 socket for the ipv4-mapped destinations: the socket family is
+// checked to report every datagram once
+//
+// Returns a Result type with the value as u32 or an error code as i64
+
+pub fn try_udp_sock_tracer(ctx: ProbeContext, af: u16, is_send: bool) -> Result<u32, i64> {
+    let sk: *const u8 = ctx.arg(0).ok_or(1i64)?;
+    if sk.is_null() {
+        return Err(1);
+    }
+
+    let sk_af = read_linux_inner_value::<u16>(sk, kernel_offset(OFFSET_SKC_FAMILY)?)?;
+    if sk_af != af {
+        return Ok(0);
+    }
+
+    let mut log = new_registry_log(IPPROTO_UDP, af)?;
+
+    let local_port = read_linux_inner_value::<u16>(sk, kernel_offset(OFFSET_SKC_NUM)?)?;
+    let mut remote_port = u16::from_be(
+        read_linux_inner_value::<u16>(sk, kernel_offset(OFFSET_SKC_DPORT)?)?
+    );
+
+    // destination of an unconnected socket (sendto/sendmsg with an explicit address)
+    let mut msg_name: *const u8 = core::ptr::null();
+    if is_send {
+        let msg: *const u8 = ctx.arg(1).ok_or(1i64)?;
+        msg_name = read_linux_inner_struct::<u8>(msg, kernel_offset(OFFSET_MSGHDR_MSG_NAME)?)?;
+        if !msg_name.is_null() {
+            remote_port = u16::from_be(read_linux_inner_value(msg_name, SOCKADDR_PORT_OFFSET)?);
+        }
+    }
+
+    match af {
+        AF_INET => {
+            let local_ip = read_linux_inner_value::<u32>(sk, kernel_offset(OFFSET_SKC_RCV_SADDR)?)?;
+            let remote_ip = if msg_name.is_null() {
+                read_linux_inner_value::<u32>(sk, kernel_offset(OFFSET_SKC_DADDR)?)?
+            } else {
+                read_linux_inner_value::<u32>(msg_name, SOCKADDR_IN_ADDR_OFFSET)?
+            };
+            if is_send {
+                log.src_ip = local_ip;
+                log.dst_ip = remote_ip;
+            } else {
+                log.src_ip = remote_ip;
+                log.dst_ip = local_ip;
+            }
+        }
+        AF_INET6 => {
+            let local_ip = read_linux_inner_value::<[u8; 16]>(
+                sk,
+                kernel_offset(OFFSET_SKC_V6_RCV_SADDR)?
+            )?;
+            let remote_ip = if msg_name.is_null() {
+                read_linux_inner_value::<[u8; 16]>(sk, kernel_offset(OFFSET_SKC_V6_DADDR)?)?
+            } else {
+                read_linux_inner_value::<[u8; 16]>(msg_name, SOCKADDR_IN6_ADDR_OFFSET)?
+            };
+            if is_send {
+                log.src_ip_v6 = ipv6_to_words(&local_ip);
+                log.dst_ip_v6 = ipv6_to_words(&remote_ip);
+            } else {
+                log.src_ip_v6 = ipv6_to_words(&remote_ip);
+                log.dst_ip_v6 = ipv6_to_words(&local_ip);
+            }
+        }
+        _ => {
+            return Err(1);
+        }
+    }
+
+    if is_send {
+        log.src_port = local_port;
+        log.dst_port = remote_port;
+    } else {
+        log.src_port = remote_port;
+        log.dst_port = local_port;
+    }
+
+    unsafe {
+        PACKET_REGISTRY.emit(&ctx, &log);
+    }
+
+    Ok(0)
+}
@@ -5,6 +5,12 @@ use crate::bindings::net;
 use crate::bindings::net_device;
 use crate::data_structures::VethLog;
 use crate::data_structures::VETH_EVENTS;
+use crate::data_structures::EventOutput;
+use crate::data_structures::{
+    OFFSET_NET_DEVICE_DEV_ADDR, OFFSET_NET_DEVICE_NAME, OFFSET_NET_DEVICE_ND_NET, OFFSET_NET_DEVICE_STATE,
+    OFFSET_NET_NS_INUM, OFFSET_NET_DEVICE_DEV_ADDR_IS_PTR,
+};
+use crate::offsets::kernel_offset;
 
 // docs:
 //
@@ -28,19 +34,25 @@ pub fn try_veth_tracer(ctx: ProbeContext, mode: u8) -> Result<u32, i64> {
     let mut dev_addr_buf = [0u8; 6];
 
     // name field
-    let name_field_offset = 304; // reading the name field offset
+    let name_field_offset = kernel_offset(OFFSET_NET_DEVICE_NAME)?; // reading the name field offset
 
     let name_array: [u8; 16] =
         read_linux_inner_value::<[u8; 16]>(net_device_pointer as *const u8, name_field_offset)?;
 
     // state field
-    let state_offset = 168;
+    let state_offset = kernel_offset(OFFSET_NET_DEVICE_STATE)?;
     let state: u64 = read_linux_inner_value::<u64>(net_device_pointer as *const u8, state_offset)?;
 
     // dev_addr
-    let dev_addr_offset = 1080;
-    let dev_addr_array: [u8; 6] =
-        read_linux_inner_value::<[u8; 6]>(net_device_pointer as *const u8, dev_addr_offset)?;
+    // since kernel 5.17 dev_addr is a pointer to the address instead of an inline array
+    let dev_addr_offset = kernel_offset(OFFSET_NET_DEVICE_DEV_ADDR)?;
+    let dev_addr_array: [u8; 6] = if kernel_offset(OFFSET_NET_DEVICE_DEV_ADDR_IS_PTR)? != 0 {
+        let dev_addr_pointer =
+            read_linux_inner_value::<*const u8>(net_device_pointer as *const u8, dev_addr_offset)?;
+        read_linux_inner_value::<[u8; 6]>(dev_addr_pointer, 0)?
+    } else {
+        read_linux_inner_value::<[u8; 6]>(net_device_pointer as *const u8, dev_addr_offset)?
+    };
 
     let inum: u32 = extract_netns_inum(net_device_pointer as *const u8)?;
     let pid: u32 = bpf_get_current_pid_tgid() as u32; // extracting lower 32 bit corresponding to the PID
@@ -61,7 +73,7 @@ pub fn try_veth_tracer(ctx: ProbeContext, mode: u8) -> Result<u32, i64> {
 
     // send the data to the userspace
     unsafe {
-        VETH_EVENTS.output(&ctx, &veth_data, 0);
+        VETH_EVENTS.emit(&ctx, &veth_data);
     }
 
     Ok(0)
@@ -132,13 +144,12 @@ pub fn read_linux_inner_value<T: Copy>(ptr: *const u8, offset: usize) -> Result<
 // Returns a Result type with the value as u32 or an error code as i64
 
 fn extract_netns_inum(net_device_pointer: *const u8) -> Result<u32, i64> {
-    let possible_net_t_offset = 280;
+    let possible_net_t_offset = kernel_offset(OFFSET_NET_DEVICE_ND_NET)?;
 
     let net = read_linux_inner_struct::<net>(net_device_pointer, possible_net_t_offset)?;
 
-    let ns_common_offset = 120;
-
-    let inum_offset = 16;
-    let inum_ptr = read_linux_inner_value::<u32>(net as *const u8, ns_common_offset + inum_offset)?;
+    // offset of the inum field in the ns_common struct embedded in net
+    let inum_offset = kernel_offset(OFFSET_NET_NS_INUM)?;
+    let inum_ptr = read_linux_inner_value::<u32>(net as *const u8, inum_offset)?;
     Ok(inum_ptr)
 }
@@ -0,0 +1,38 @@
+use aya_ebpf::{ bindings::xdp_action, programs::XdpContext };
+
+use crate::data_structures::COUNTER_XDP_BLOCKLIST;
+use crate::headers::{ is_blocklisted, is_outer_blocklisted, locate_headers };
+use crate::tc::{ increment_counter, is_audit_mode };
+
+// docs:
+//
+// XDP fast path for the blocklist. This program runs in the driver (native mode) or right after the driver
+// (generic mode), before the kernel allocates an skb, and drops the packets whose source address is in the
+// BLOCKLIST or BLOCKLIST_V6 maps. The maps are the same used by the identity_classifier, so the userspace
+// manages a single blocklist
+//
+// XDP only sees the ingress traffic, so the remote address is always the source address
+// The headers are decoded by the same code of the identity_classifier (see locate_headers in headers.rs): the VLAN
+// tags are skipped and the VXLAN/Geneve packets are dropped when either the inner or the outer source address is
+// blocklisted
+//
+// The fast path is intentionally minimal:
+//      - no DropEvent is emitted (a volumetric attack would flood the event maps). Every drop increments the
+//        COUNTER_XDP_BLOCKLIST counter in the CLASSIFIER_COUNTERS map
+//      - in audit mode the packets are passed and the identity_classifier reports them with an AuditEvent
+//      - packets that cannot be parsed are passed: the failure policy is applied by the identity_classifier
+//
+// Returns a Result with the XDP action as u32 or an i64 error code
+
+pub fn try_xdp_blocklist(ctx: &XdpContext) -> Result<u32, i64> {
+    let headers = locate_headers(ctx)?;
+
+    let blocked =
+        is_blocklisted(ctx, headers.ethertype, headers.l3_offset, true)? || is_outer_blocklisted(ctx, &headers, true)?;
+
+    if blocked && !is_audit_mode() {
+        increment_counter(COUNTER_XDP_BLOCKLIST);
+        return Ok(xdp_action::XDP_DROP);
+    }
+    Ok(xdp_action::XDP_PASS)
+}
@@ -12,6 +12,7 @@ popd
 
 echo "Copying connection tracker binaries"
 cp -r ../../../target/bpfel-unknown-none/release/conntracker conntracker
+cp -r ../../../target/bpfel-unknown-none/release/conntracker-perf conntracker-perf
 cp -r ../../../common common 
 
 # Run docker build
@@ -20,4 +21,5 @@ docker build -t identity:0.0.1 --provenance=false --sbom=false .
 # Cleanup
 echo "Cleaning building files"
 rm -rf conntracker
+rm -rf conntracker-perf
 rm -rf common
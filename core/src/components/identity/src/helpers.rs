@@ -1,6 +1,16 @@
+use cortexbrain_common::classifier_config::{
+    COUNTER_BLOCKLIST, COUNTER_PARSE_ERROR, COUNTER_RATE_LIMIT, COUNTER_RULE,
+    COUNTER_TRUNCATED_HEADER, COUNTER_XDP_BLOCKLIST, ClassifierConfigMap, ClassifierCountersMap,
+    FAILURE_POLICY_CLOSED, counter_name,
+};
+use cortexbrain_common::flows::{FlowTable, FlowTracker};
+use cortexbrain_common::map_handlers::{BlocklistMaps, from_pin_with_retry, unix_now};
+use cortexbrain_common::otel_metrics::Metrics;
 use nix::net::if_::if_nameindex;
+use std::collections::HashMap;
 use std::result::Result::Ok;
-use tracing::info;
+use std::sync::Arc;
+use tracing::{debug, error, info};
 
 // docs:
 // This function checks if the given interface name is in the list of ignored interfaces
@@ -36,6 +46,128 @@ pub fn get_veth_channels() -> Vec<String> {
     interfaces
 }
 
+// docs:
+// This function periodically scrapes the flow_table map filled by the identity_classifier.
+// Every time_delta seconds the per-cpu values are merged, diffed against the previous scrape with a
+// FlowTracker and exported as the flow_packets_total and flow_bytes_total metrics. The number of
+// flows in the map is exported as the active_flows gauge.
+// The function never returns: the errors (including the map not being pinned yet) are logged and retried
+// at the next interval.
+//
+pub async fn scrape_flow_table(time_delta: u64, metrics: Arc<Metrics>) {
+    let interval = std::time::Duration::from_secs(time_delta);
+    let flow_table = from_pin_with_retry("flow_table", interval, FlowTable::from_pin).await;
+    let mut tracker = FlowTracker::new();
+
+    loop {
+        match flow_table.snapshot() {
+            Ok(snapshot) => {
+                let deltas = tracker.diff(snapshot);
+                debug!(
+                    "Scraped flow_table: {} active flows, {} updated",
+                    tracker.active_flows(),
+                    deltas.len()
+                );
+                for delta in deltas.iter() {
+                    metrics.record_flow_delta(delta);
+                }
+                metrics.record_active_flows(tracker.active_flows());
+            }
+            Err(e) => error!("Cannot scrape flow_table map: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// docs:
+// This function periodically removes the expired entries from the blocklist maps.
+// Every time_delta seconds the BLOCKLIST and BLOCKLIST_V6 entries are compared with the current unix time:
+// the entries with a ttl are removed once added_at + ttl_secs is reached. The entries without a ttl are
+// never removed. The classifier has no wall clock, so an expired entry keeps dropping packets for at most
+// time_delta seconds.
+// The function never returns: the errors (including the maps not being pinned yet) are logged and retried
+// at the next interval.
+//
+pub async fn reap_blocklist(time_delta: u64) {
+    let interval = std::time::Duration::from_secs(time_delta);
+    let mut blocklist = from_pin_with_retry("blocklist", interval, BlocklistMaps::from_pin).await;
+
+    loop {
+        match blocklist.remove_expired(unix_now()) {
+            Ok((removed, errors)) => {
+                for ip in removed.iter() {
+                    info!("Removed expired blocklist entry {}", ip);
+                }
+                for e in errors.iter() {
+                    error!("Cannot reap the blocklist entry. Reason: {}", e);
+                }
+            }
+            Err(e) => error!("Cannot reap the blocklist maps: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// docs:
+// This function periodically exports the classifier drops as the classifier_drops_total metric.
+// Every time_delta seconds the per-cpu CLASSIFIER_COUNTERS values are summed and diffed against the
+// previous scrape. The blocklist, rule, rate_limit and xdp_blocklist counters only count dropped packets.
+// The parse_error and truncated_header counters also count the packets passed by the fail-open policy,
+// so they are exported only while the failure policy is fail-closed. The drop_events ring buffer is
+// consumed by the agent api, reading the counters keeps a single consumer for the event stream.
+// The function never returns: the errors (including the maps not being pinned yet) are logged and retried
+// at the next interval.
+//
+pub async fn scrape_classifier_counters(time_delta: u64, metrics: Arc<Metrics>) {
+    let interval = std::time::Duration::from_secs(time_delta);
+    let counters = from_pin_with_retry(
+        "classifier_counters",
+        interval,
+        ClassifierCountersMap::from_pin,
+    )
+    .await;
+    let config =
+        from_pin_with_retry("classifier_config", interval, ClassifierConfigMap::from_pin).await;
+    let mut previous: HashMap<u32, u64> = HashMap::new();
+
+    loop {
+        let fail_closed = match config.failure_policy() {
+            Ok(policy) => policy == FAILURE_POLICY_CLOSED,
+            Err(e) => {
+                error!("Cannot read the classifier failure policy: {}", e);
+                false
+            }
+        };
+        for index in COUNTER_BLOCKLIST..=COUNTER_RATE_LIMIT {
+            let total = match counters.total(index) {
+                Ok(total) => total,
+                Err(e) => {
+                    error!("Cannot scrape classifier_counters map: {}", e);
+                    continue;
+                }
+            };
+            // the previous value is updated for every counter, so a policy change does not export the
+            // packets counted under the previous policy
+            let delta = total.saturating_sub(previous.insert(index, total).unwrap_or(0));
+            if delta > 0 && is_drop_counter(index, fail_closed) {
+                metrics.record_classifier_drops(&counter_name(index), delta);
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// docs:
+// returns true if the counter at index only counts dropped packets under the current failure policy
+//
+fn is_drop_counter(index: u32, fail_closed: bool) -> bool {
+    match index {
+        COUNTER_BLOCKLIST | COUNTER_RULE | COUNTER_RATE_LIMIT | COUNTER_XDP_BLOCKLIST => true,
+        COUNTER_PARSE_ERROR | COUNTER_TRUNCATED_HEADER => fail_closed,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use cortexbrain_common::buffer_type::VethLog;
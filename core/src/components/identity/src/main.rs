@@ -3,20 +3,30 @@
  * Features:
  *   1. TCP events tracker
  *   2. veth creation and deletion tracker
- *   3. TC (traffic control) tracker
- *   4. [Experimental]: cgroup scanner
+ *   3. TC (traffic control) tracker (ingress and egress, VLAN tags and VXLAN/Geneve inner headers)
+ *   4. TC classifier audit (dry-run) events
+ *   5. TC classifier drop events (exported as OpenTelemetry metrics)
+ *   6. Per-flow packet and byte counters (exported as OpenTelemetry metrics)
+ *   7. Optional XDP fast path for the blocklist drops (native or generic mode)
+ *   8. TCP connection lifecycle tracker (established and closed connections)
+ *   9. Listener accept/SYN queue overflows and TCP memory pressure (exported as OpenTelemetry metrics)
+ *  10. UDP flows tracker (udp sendmsg/recvmsg) and ICMP type/code parsing in the TC classifier
+ *  11. Per source token bucket rate limits in the TC classifier
+ *  12. Temporary blocklist entries (removed by the blocklist reaper when the ttl expires)
+ *  13. [Experimental]: cgroup scanner
  *
  */
 
 mod helpers;
 mod service_discovery;
 
-use crate::helpers::get_veth_channels;
+use crate::helpers::{
+    get_veth_channels, reap_blocklist, scrape_classifier_counters, scrape_flow_table,
+};
 use aya::{
     Ebpf,
     maps::{Map, MapData},
-    programs::{SchedClassifier, TcAttachType},
-    util::online_cpus,
+    programs::{SchedClassifier, TcAttachType, Xdp, XdpFlags},
 };
 
 #[cfg(feature = "experimental")]
@@ -24,14 +34,21 @@ use crate::helpers::scan_cgroup_cronjob;
 
 use cortexbrain_common::{
     buffer_type::BufferSize,
+    classifier_config::populate_classifier_config,
     constants,
     consumer::Consumer,
-    consumer::read_perf_buffer,
+    consumer::read_events,
+    kernel_offsets::{
+        CONNTRACKER_KERNEL_FIELDS, CONNTRACKER_TRACEPOINT_FIELDS, populate_kernel_offsets,
+        populate_tracepoint_offsets,
+    },
     logger,
     map_handlers::BpfMapsData,
     map_handlers::{init_bpf_maps, map_manager, map_pinner, populate_blocklist},
-    program_handlers::load_program,
+    otel_metrics::Metrics,
+    program_handlers::{load_program, load_tracepoint_program, resolve_bpf_object_path},
 };
+use opentelemetry::metrics::MeterProvider;
 use std::{
     convert::TryInto,
     path::Path,
@@ -42,12 +59,21 @@ use anyhow::{Context, Ok, anyhow};
 
 //use std::collections::HashMap;
 use tokio::{fs, signal};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+// interval in seconds between two scrapes of the flow_table map
+const FLOW_SCRAPE_INTERVAL: u64 = 10;
+// interval in seconds between two checks of the blocklist entries ttl
+const BLOCKLIST_REAP_INTERVAL: u64 = 10;
+// interval in seconds between two scrapes of the classifier_counters map
+const CLASSIFIER_COUNTERS_SCRAPE_INTERVAL: u64 = 10;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     //init otlè tracing subscriber
     let otlp_provider = logger::otlp_logger_init("identity_service-OTLP".to_string());
+    let meter_provider = logger::otlp_meter_init("identity_service-OTLP".to_string());
+    let metrics = Arc::new(Metrics::new(&meter_provider.meter("cortexbrain-identity")));
 
     info!("Starting identity service...");
     info!("fetching data");
@@ -58,6 +84,8 @@ async fn main() -> Result<(), anyhow::Error> {
     //init conntracker data path
     let bpf_path =
         std::env::var(constants::BPF_PATH).context("BPF_PATH environment variable required")?;
+    // pick the ring buffer or the perf event array object for the running kernel
+    let bpf_path = resolve_bpf_object_path(&bpf_path);
     let data = fs::read(Path::new(&bpf_path))
         .await
         .context("failed to load file from path")?;
@@ -71,7 +99,21 @@ async fn main() -> Result<(), anyhow::Error> {
         "veth_identity_map".to_string(),
         "TcpPacketRegistry".to_string(),
         "Blocklist".to_string(),
+        "BlocklistV6".to_string(),
+        "FilterRules".to_string(),
+        "RateLimits".to_string(),
+        "RateLimitsV6".to_string(),
+        "audit_events".to_string(),
+        "drop_events".to_string(),
+        "classifier_config".to_string(),
+        "classifier_counters".to_string(),
+        "flow_table".to_string(),
         "tracked_veth".to_string(),
+        "kernel_offsets".to_string(),
+        "xdp_mode".to_string(),
+        "tcp_connections".to_string(),
+        "tcp_conn_events".to_string(),
+        "listener_events".to_string(),
     ];
     match init_bpf_maps(bpf.clone(), map_data) {
         std::result::Result::Ok(bpf_maps) => {
@@ -81,6 +123,14 @@ async fn main() -> Result<(), anyhow::Error> {
             match map_pinner(bpf_maps, &pin_path) {
                 std::result::Result::Ok(maps) => {
                     info!("maps pinned successfully");
+                    // resolve the kernel struct offsets from the kernel BTF before attaching the programs.
+                    // The programs are not attached if the running kernel misses one of the required fields
+                    {
+                        populate_kernel_offsets(&pin_path, CONNTRACKER_KERNEL_FIELDS)
+                            .context("Kernel BTF self-check failed")?;
+                        populate_tracepoint_offsets(&pin_path, CONNTRACKER_TRACEPOINT_FIELDS)
+                            .context("Tracepoint format self-check failed")?;
+                    }
                     //load veth_trace program ref veth_trace.rs
                     {
                         init_veth_tracer(bpf.clone()).await?;
@@ -94,6 +144,16 @@ async fn main() -> Result<(), anyhow::Error> {
                         populate_blocklist().await?;
                     }
 
+                    {
+                        populate_classifier_config().await?;
+                    }
+
+                    {
+                        init_xdp_blocklist(bpf.clone(), &interfaces).await.context(
+                            "An error occured during the execution of init_xdp_blocklist function",
+                        )?;
+                    }
+
                     {
                         init_tc_classifier(bpf.clone(), interfaces).await.context(
                             "An error occured during the execution of attach_bpf_program function",
@@ -104,8 +164,13 @@ async fn main() -> Result<(), anyhow::Error> {
                             "An error occured during the execution of init_tcp_registry function",
                         )?;
                     }
+                    {
+                        init_listener_tracer(bpf.clone()).await.context(
+                            "An error occured during the execution of init_listener_tracer function",
+                        )?;
+                    }
 
-                    event_listener(maps).await.map_err(|e| {
+                    event_listener(maps, metrics).await.map_err(|e| {
                         anyhow::anyhow!("Error inizializing event_listener. Reason: {}", e)
                     })?;
                 }
@@ -118,42 +183,46 @@ async fn main() -> Result<(), anyhow::Error> {
             error!("Error while loading bpf maps {}", e);
             let _ = signal::ctrl_c().await;
             let _ = otlp_provider.shutdown();
+            let _ = meter_provider.shutdown();
         }
     }
 
     Ok(())
 }
 
-//attach the tc classifier program to a vector of interfaces
+//attach the tc classifier programs to a vector of interfaces
+// identity_classifier is attached to the ingress hook and identity_classifier_egress to the egress hook
 // TODO: consider to create a load schedule classifier in the common functions
 async fn init_tc_classifier(
     bpf: Arc<Mutex<Ebpf>>,
     ifaces: Vec<String>,
 ) -> Result<(), anyhow::Error> {
-    //this funtion initialize the tc classifier program
+    //this funtion initialize the tc classifier programs
     info!("Loading programs");
 
     let mut bpf_new = bpf
         .lock()
         .map_err(|e| anyhow::anyhow!("Cannot get value from lock. Reason: {}", e))?;
 
-    let program: &mut SchedClassifier = bpf_new
-        .program_mut("identity_classifier")
-        .ok_or_else(|| anyhow::anyhow!("program 'identity_classifier' not found"))?
-        .try_into()
-        .context("Failed to init SchedClassifier program")?;
-
-    // load classifier program
-
-    program
-        .load()
-        .context("Failed to load identity_classifier program")?;
+    // load classifier programs
+    for program_name in ["identity_classifier", "identity_classifier_egress"] {
+        let program: &mut SchedClassifier = bpf_new
+            .program_mut(program_name)
+            .ok_or_else(|| anyhow::anyhow!("program '{}' not found", program_name))?
+            .try_into()
+            .context("Failed to init SchedClassifier program")?;
+
+        program
+            .load()
+            .with_context(|| format!("Failed to load {} program", program_name))?;
+    }
 
     // attach program only to desired interfaces. We can skip the dock0,tunl0,lo and eth0 interface
     // we also save the interfaces to a BPF_HASH_MAP to easily monitor the interfaces using the agent
 
     // decleare link_ids HashMap which is a shared hashmap between kernel and userspace
-    // Link_ids hashmap has type of HashMap<[u8; 16], [u8; 8]>. The key is the program name and the value is the state
+    // Link_ids hashmap has type of HashMap<[u8; 16], [u8; 8]>. The key is the interface name and the value is
+    // the attached directions ("ingress", "egress" or "both")
 
     // at this point the pinning is already successfull so we can invoque the maps from the pin
 
@@ -171,51 +240,202 @@ async fn init_tc_classifier(
         })?;
 
     for interface in ifaces {
-        match program.attach(&interface, TcAttachType::Ingress) {
-            std::result::Result::Ok(link_id) => {
-                info!(
-                    "Program 'identity_classifier' attached to interface {}",
-                    interface
+        let ingress = attach_classifier(
+            &mut bpf_new,
+            "identity_classifier",
+            &interface,
+            TcAttachType::Ingress,
+        );
+        let egress = attach_classifier(
+            &mut bpf_new,
+            "identity_classifier_egress",
+            &interface,
+            TcAttachType::Egress,
+        );
+
+        let state: &[u8] = match (ingress, egress) {
+            (true, true) => b"both",
+            (true, false) => b"ingress",
+            (false, true) => b"egress",
+            (false, false) => continue,
+        };
+
+        let interface_bytes = interface.as_bytes();
+
+        let mut if_bytes = [0u8; 16];
+
+        // to set the len compare the interface_bytes.len() with the if_bytes.len() [16] and take the minimum
+        // if we have interface_bytes.len() < than 16 we set the len
+        let len = interface_bytes.len().min(if_bytes.len());
+
+        // now we can copy the bytes from the slice into the if_bytes variable
+        if_bytes[..len].copy_from_slice(&interface_bytes[..len]);
+
+        // we compute the same process for the state_bytes
+        let mut state_bytes = [0u8; 8];
+        let state_len = state.len().min(state_bytes.len());
+        state_bytes[..state_len].copy_from_slice(&state[..state_len]);
+
+        match link_ids.insert(if_bytes, state_bytes, 0) {
+            std::result::Result::Ok(_) => {
+                info!("Veth interface {} added into map", &interface);
+            }
+            Err(e) => {
+                error!(
+                    "Cannot add Veth interface {} into map. Reason: {}",
+                    &interface, e
                 );
-                let interface_bytes = interface.as_bytes();
+            }
+        }
+    }
 
-                let mut if_bytes = [0u8; 16];
+    Ok(())
+}
+
+// attach the identity_xdp program (XDP fast path for the blocklist drops) to the interfaces listed in the
+// XDP_INTERFACES env variable (default: the interfaces monitored by the tc classifier)
+// The XDP_MODE env variable selects the attach mode:
+//      - off (default): the blocklist is enforced only by the tc classifier
+//      - auto: tries the native (driver) mode first and falls back to the generic mode
+//      - native / generic: tries only the selected mode
+// If XDP cannot be attached to an interface the blocklist is enforced by the tc classifier ("tc" mode)
+// The active mode of every interface is saved in the xdp_mode map and returned by the agent api
+async fn init_xdp_blocklist(
+    bpf: Arc<Mutex<Ebpf>>,
+    tc_ifaces: &[String],
+) -> Result<(), anyhow::Error> {
+    let xdp_mode = std::env::var(constants::XDP_MODE).unwrap_or_else(|_| "off".to_string());
+    let modes: Vec<(&str, XdpFlags)> = match xdp_mode.trim().to_lowercase().as_str() {
+        "" | "off" => {
+            info!("XDP blocklist fast path disabled");
+            return Ok(());
+        }
+        "auto" => vec![
+            ("native", XdpFlags::DRV_MODE),
+            ("generic", XdpFlags::SKB_MODE),
+        ],
+        "native" => vec![("native", XdpFlags::DRV_MODE)],
+        "generic" => vec![("generic", XdpFlags::SKB_MODE)],
+        other => {
+            return Err(anyhow!(
+                "Unknown XDP mode {:?}. Expected off, auto, native or generic",
+                other
+            ));
+        }
+    };
 
-                // to set the len compare the interface_bytes.len() with the if_bytes.len() [16] and take the minimum
-                // if we have interface_bytes.len() < than 16 we set the len
-                let len = interface_bytes.len().min(if_bytes.len());
+    let ifaces: Vec<String> = match std::env::var(constants::XDP_INTERFACES) {
+        std::result::Result::Ok(list) => list
+            .split(',')
+            .map(|iface| iface.trim().to_string())
+            .filter(|iface| !iface.is_empty())
+            .collect(),
+        Err(_) => tc_ifaces.to_vec(),
+    };
+
+    let mut bpf_new = bpf
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Cannot get value from lock. Reason: {}", e))?;
 
-                // now we can copy the bytes from the slice into the if_bytes variable
-                if_bytes[..len].copy_from_slice(&interface_bytes[..len]);
+    let program: &mut Xdp = bpf_new
+        .program_mut("identity_xdp")
+        .ok_or_else(|| anyhow::anyhow!("program 'identity_xdp' not found"))?
+        .try_into()
+        .context("Failed to init Xdp program")?;
+    program
+        .load()
+        .context("Failed to load identity_xdp program")?;
 
-                // we compute the same process for the state_bytes
-                let mut state_bytes = [0u8; 8];
-                let state = b"attached"; // prints "attached" as [u8;8] sequence of bytes
-                let state_len = state.len().min(state_bytes.len());
-                state_bytes[..state_len].copy_from_slice(&state[..state_len]);
+    let xdp_mode_mapdata = MapData::from_pin("/sys/fs/bpf/maps/xdp_mode")
+        .map_err(|e| anyhow!("Cannot return xdp_mode_mapdata. Reason: {}", e))?;
+    let mut xdp_mode_map: aya::maps::HashMap<MapData, [u8; 16], [u8; 8]> =
+        aya::maps::HashMap::try_from(Map::HashMap(xdp_mode_mapdata))
+            .map_err(|e| anyhow!("Cannot create xdp_mode HashMap. Reason: {}", e))?;
 
-                match link_ids.insert(if_bytes, state_bytes, 0) {
-                    std::result::Result::Ok(_) => {
-                        info!("Veth interface {} added into map", &interface);
-                    }
-                    Err(e) => {
-                        error!(
-                            "Cannot add Veth interface {} into map. Reason: {}",
-                            &interface, e
-                        );
-                    }
+    for interface in ifaces {
+        let mut active_mode = None;
+        for (mode, flags) in modes.iter() {
+            match program.attach(&interface, *flags) {
+                std::result::Result::Ok(_) => {
+                    info!(
+                        "Program 'identity_xdp' attached to interface {} ({} mode)",
+                        interface, mode
+                    );
+                    active_mode = Some(*mode);
+                    break;
                 }
+                Err(e) => {
+                    warn!(
+                        "Cannot attach identity_xdp to interface {} in {} mode: {:?}",
+                        interface, mode, e
+                    );
+                }
+            }
+        }
+
+        // without XDP the blocklist is enforced by the tc classifier, if attached to the interface
+        let mode = match active_mode {
+            Some(mode) => mode,
+            None if tc_ifaces.contains(&interface) => "tc",
+            None => {
+                warn!(
+                    "The blocklist is not enforced on interface {}: XDP and tc are not attached",
+                    interface
+                );
+                "none"
             }
-            Err(e) => error!(
-                "Error attaching program to interface {}: {:?}",
+        };
+
+        let mut if_bytes = [0u8; 16];
+        let len = interface.len().min(if_bytes.len());
+        if_bytes[..len].copy_from_slice(&interface.as_bytes()[..len]);
+        let mut mode_bytes = [0u8; 8];
+        mode_bytes[..mode.len()].copy_from_slice(mode.as_bytes());
+
+        if let Err(e) = xdp_mode_map.insert(if_bytes, mode_bytes, 0) {
+            error!(
+                "Cannot save the XDP mode of interface {}. Reason: {}",
                 interface, e
-            ),
+            );
         }
     }
 
     Ok(())
 }
 
+// attach a loaded tc classifier program to an interface. Returns true if the program has been attached
+fn attach_classifier(
+    bpf: &mut Ebpf,
+    program_name: &str,
+    interface: &str,
+    attach_type: TcAttachType,
+) -> bool {
+    let program: &mut SchedClassifier = match bpf.program_mut(program_name).map(|p| p.try_into()) {
+        Some(std::result::Result::Ok(program)) => program,
+        _ => {
+            error!("program '{}' not found", program_name);
+            return false;
+        }
+    };
+
+    match program.attach(interface, attach_type) {
+        std::result::Result::Ok(_) => {
+            info!(
+                "Program '{}' attached to interface {}",
+                program_name, interface
+            );
+            true
+        }
+        Err(e) => {
+            error!(
+                "Error attaching program {} to interface {}: {:?}",
+                program_name, interface, e
+            );
+            false
+        }
+    }
+}
+
 async fn init_veth_tracer(bpf: Arc<Mutex<Ebpf>>) -> Result<(), anyhow::Error> {
     //this functions init the veth_tracer used to make the InterfacesRegistry
     //creation tracer
@@ -236,7 +456,55 @@ async fn init_tcp_registry(bpf: Arc<Mutex<Ebpf>>) -> Result<(), anyhow::Error> {
 
     info!("initializing tcp tracing functions");
 
-    load_program(bpf, "tcp_message_tracer_connect", "tcp_v4_connect")?;
+    load_program(bpf.clone(), "tcp_message_tracer_connect", "tcp_v4_connect")?;
+
+    // ipv6 counterparts
+    load_program(bpf.clone(), "tcp_message_tracer_rcv_v6", "tcp_v6_rcv")?;
+    load_program(
+        bpf.clone(),
+        "tcp_message_tracer_connect_v6",
+        "tcp_v6_connect",
+    )?;
+
+    // udp flows (sent in the same packet registry buffer)
+    load_program(bpf.clone(), "udp_sendmsg_tracer", "udp_sendmsg")?;
+    load_program(bpf.clone(), "udp_recvmsg_tracer", "udp_recvmsg")?;
+    load_program(bpf.clone(), "udpv6_sendmsg_tracer", "udpv6_sendmsg")?;
+    load_program(bpf.clone(), "udpv6_recvmsg_tracer", "udpv6_recvmsg")?;
+
+    // connection lifecycle tracker (established/closed connections)
+    load_tracepoint_program(bpf, "tcp_state_tracer", "sock", "inet_sock_set_state")?;
+
+    Ok(())
+}
+
+async fn init_listener_tracer(bpf: Arc<Mutex<Ebpf>>) -> Result<(), anyhow::Error> {
+    // accept queue overflows of the ipv4 and ipv6 listeners
+    load_program(
+        bpf.clone(),
+        "tcp_accept_queue_tracer",
+        "tcp_v4_syn_recv_sock",
+    )?;
+    // tcp_v6_syn_recv_sock is missing on the kernels built without ipv6: only the ipv4 listeners are tracked
+    if let Err(e) = load_program(
+        bpf.clone(),
+        "tcp_accept_queue_tracer_v6",
+        "tcp_v6_syn_recv_sock",
+    ) {
+        warn!("IPv6 accept queue overflows are not tracked. Reason: {}", e);
+    }
+
+    load_program(
+        bpf.clone(),
+        "tcp_memory_pressure_tracer",
+        "tcp_enter_memory_pressure",
+    )?;
+
+    // tcp_syn_flood_action is a static function and can be inlined by the compiler: the SYN queue overflows
+    // are not tracked if the symbol is not available
+    if let Err(e) = load_program(bpf, "tcp_syn_flood_tracer", "tcp_syn_flood_action") {
+        warn!("SYN queue overflows are not tracked. Reason: {}", e);
+    }
 
     Ok(())
 }
@@ -244,72 +512,64 @@ async fn init_tcp_registry(bpf: Arc<Mutex<Ebpf>>) -> Result<(), anyhow::Error> {
 // this function init the event listener. Listens for veth events (creation/deletion) and network events (pod to pod communications)
 // Doc:
 //
-//   perf_net_events_array: contains is associated with the network events stored in the events_map (EventsMap)
-//   perf_veth_array: contains is associated with the network events stored in the veth_map (veth_identity_map)
+//   net_events: reads the network events stored in the events_map (EventsMap)
+//   veth_events: reads the veth events stored in the veth_map (veth_identity_map)
+//   tcp_conn_events: reads the tcp connection established/closed events stored in the tcp_conn_events map
+//   listener_events: reads the accept queue, SYN queue and memory pressure events stored in the listener_events
+//                    map. Counted in the tcp_accept_queue_overflows_total, tcp_syn_queue_overflows_total and
+//                    tcp_memory_pressure_total metrics
+//   flow_table_scraper: scrapes the flow_table map every FLOW_SCRAPE_INTERVAL seconds and exports the per-flow
+//                       counters as the flow_packets_total and flow_bytes_total metrics
+//   blocklist_reaper: removes the expired blocklist entries every BLOCKLIST_REAP_INTERVAL seconds
+//   classifier_counters_scraper: scrapes the classifier_counters map every CLASSIFIER_COUNTERS_SCRAPE_INTERVAL
+//                                seconds and exports the dropped packets as the classifier_drops_total metric.
+//                                The audit_events and drop_events maps are read by the agent api only
 //
 //
-async fn event_listener(bpf_maps: BpfMapsData) -> Result<(), anyhow::Error> {
-    info!("Preparing perf_buffers and perf_arrays");
-
-    //TODO: try to change from PerfEventArray to a RingBuffer data structure
+async fn event_listener(bpf_maps: BpfMapsData, metrics: Arc<Metrics>) -> Result<(), anyhow::Error> {
+    info!("Preparing event readers");
 
+    // the map manager opens the ring buffers (or the per cpu perf buffers on kernels without ring buffer support)
     let mut maps = map_manager(bpf_maps)?;
 
-    // fill the input buffers with data from the PerfEventArrays
-    for cpu_id in online_cpus().map_err(|e| anyhow::anyhow!("Error {:?}", e))? {
-        for (name, (perf_evt_array, perf_evt_array_buffer)) in maps.iter_mut() {
-            let buf = perf_evt_array.open(cpu_id, None).map_err(|e| {
-                anyhow!(
-                    "Cannot create perf_event_array buffer from perf_event_array. Reason: {}",
-                    e
-                )
-            })?;
-            info!(
-                "Buffer created for map {:?} on cpu_id {:?}. Buffer size: {}",
-                name,
-                cpu_id,
-                std::mem::size_of_val(&buf)
-            );
-            perf_evt_array_buffer.push(buf);
-        }
-    }
-
     info!("Listening for events...");
 
     // i need to use remove to move the values from the Map Manager to the the async tasks
-    let (perf_veth_array, perf_veth_buffers) = maps
+    let veth_events = maps
         .remove("veth_identity_map")
-        .expect("Cannot create perf_veth buffer");
-    let (perf_net_events_array, perf_net_events_buffers) = maps
+        .expect("Cannot create veth_identity_map reader");
+    let net_events = maps
         .remove("events_map")
-        .expect("Cannot create perf_net_events buffer");
-    let (tcp_registry_array, tcp_registry_buffers) = maps
+        .expect("Cannot create events_map reader");
+    let tcp_registry_events = maps
         .remove("TcpPacketRegistry")
-        .expect("Cannot create tcp_registry buffer");
+        .expect("Cannot create tcp_registry reader");
+    let tcp_conn_events = maps
+        .remove("tcp_conn_events")
+        .expect("Cannot create tcp_conn_events reader");
+    let listener_events = maps
+        .remove("listener_events")
+        .expect("Cannot create listener_events reader");
 
     // init output buffers
     let veth_buffers = BufferSize::VethEvents.set_buffer();
     let events_buffers = BufferSize::ClassifierNetEvents.set_buffer();
     let tcp_buffers = BufferSize::TcpEvents.set_buffer();
+    let tcp_conn_buffers = BufferSize::TcpConnEvents.set_buffer();
+    let listener_buffers = BufferSize::ListenerEvents.set_buffer();
 
     // spawn async tasks
     let veth_events_displayer = tokio::spawn(async move {
-        read_perf_buffer(perf_veth_buffers, veth_buffers, Consumer::VethLog, None).await;
+        read_events(veth_events, veth_buffers, Consumer::VethLog, None).await;
     });
 
     let net_events_displayer = tokio::spawn(async move {
-        read_perf_buffer(
-            perf_net_events_buffers,
-            events_buffers,
-            Consumer::PacketLog,
-            None,
-        )
-        .await;
+        read_events(net_events, events_buffers, Consumer::PacketLog, None).await;
     });
 
     let tcp_registry_events_displayer: tokio::task::JoinHandle<()> = tokio::spawn(async move {
-        read_perf_buffer(
-            tcp_registry_buffers,
+        read_events(
+            tcp_registry_events,
             tcp_buffers,
             Consumer::TcpPacketRegistry,
             None,
@@ -317,6 +577,36 @@ async fn event_listener(bpf_maps: BpfMapsData) -> Result<(), anyhow::Error> {
         .await;
     });
 
+    let tcp_conn_events_displayer: tokio::task::JoinHandle<()> = tokio::spawn(async move {
+        read_events(
+            tcp_conn_events,
+            tcp_conn_buffers,
+            Consumer::TcpConnEvent,
+            None,
+        )
+        .await;
+    });
+
+    let listener_metrics = metrics.clone();
+    let listener_events_displayer: tokio::task::JoinHandle<()> = tokio::spawn(async move {
+        read_events(
+            listener_events,
+            listener_buffers,
+            Consumer::ListenerEvent,
+            Some(listener_metrics),
+        )
+        .await;
+    });
+
+    let classifier_counters_scraper = tokio::spawn(scrape_classifier_counters(
+        CLASSIFIER_COUNTERS_SCRAPE_INTERVAL,
+        metrics.clone(),
+    ));
+
+    let flow_table_scraper = tokio::spawn(scrape_flow_table(FLOW_SCRAPE_INTERVAL, metrics));
+
+    let blocklist_reaper = tokio::spawn(reap_blocklist(BLOCKLIST_REAP_INTERVAL));
+
     #[cfg(feature = "experimental")]
     let scan_cgroup_cronjob = tokio::spawn(async move {
         let _ = scan_cgroup_cronjob(180).await;
@@ -345,6 +635,41 @@ async fn event_listener(bpf_maps: BpfMapsData) -> Result<(), anyhow::Error> {
             }
         }
 
+        result = classifier_counters_scraper => {
+            match result{
+                Err(e)=>error!("classifier_counters_scraper panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("classifier_counters scraper exited")
+            }
+        }
+
+        result = tcp_conn_events_displayer => {
+            match result{
+                Err(e)=>error!("tcp_conn_events_displayer panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("Found new tcp connection event")
+            }
+        }
+
+        result = listener_events_displayer => {
+            match result{
+                Err(e)=>error!("listener_events_displayer panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("Found new listener event")
+            }
+        }
+
+        result = flow_table_scraper => {
+            match result{
+                Err(e)=>error!("flow_table_scraper panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("flow_table scraper exited")
+            }
+        }
+
+        result = blocklist_reaper => {
+            match result{
+                Err(e)=>error!("blocklist_reaper panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("blocklist reaper exited")
+            }
+        }
+
         _= signal::ctrl_c()=>{
             info!("Triggered Exiting...");
         }
@@ -379,6 +704,41 @@ async fn event_listener(bpf_maps: BpfMapsData) -> Result<(), anyhow::Error> {
             }
         }
 
+        result = classifier_counters_scraper => {
+            match result{
+                Err(e)=>error!("classifier_counters_scraper panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("classifier_counters scraper exited")
+            }
+        }
+
+        result = tcp_conn_events_displayer => {
+            match result{
+                Err(e)=>error!("tcp_conn_events_displayer panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("Found new tcp connection event")
+            }
+        }
+
+        result = listener_events_displayer => {
+            match result{
+                Err(e)=>error!("listener_events_displayer panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("Found new listener event")
+            }
+        }
+
+        result = flow_table_scraper => {
+            match result{
+                Err(e)=>error!("flow_table_scraper panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("flow_table scraper exited")
+            }
+        }
+
+        result = blocklist_reaper => {
+            match result{
+                Err(e)=>error!("blocklist_reaper panicked {:?}",e),
+                std::result::Result::Ok(_)=>info!("blocklist reaper exited")
+            }
+        }
+
         _= signal::ctrl_c()=>{
             info!("Triggered Exiting...");
         }
@@ -8,6 +8,7 @@ popd
 
 echo "Copying metrics_tracer binaries"
 cp -r ../../../target/bpfel-unknown-none/release/metrics_tracer metrics_tracer
+cp -r ../../../target/bpfel-unknown-none/release/metrics_tracer-perf metrics_tracer-perf
 cp -r ../../../common common 
 
 # Run docker build
@@ -16,4 +17,5 @@ docker build -t metrics:0.0.1 --provenance=false --sbom=false .
 # Cleanup
 echo "Cleaning building files"
 rm -rf metrics_tracer
+rm -rf metrics_tracer-perf
 rm -rf common
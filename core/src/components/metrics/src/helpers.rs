@@ -1,17 +1,37 @@
 use anyhow::anyhow;
-use aya::util::online_cpus;
-use cortexbrain_common::map_handlers::map_manager;
+use cortexbrain_common::map_handlers::{from_pin_with_retry, map_manager};
 use cortexbrain_common::{buffer_type::BufferSize, map_handlers::BpfMapsData};
 use opentelemetry::metrics::Meter;
+use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::signal;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
+use cortexbrain_common::cgroup_filter::CgroupFilterMap;
 use cortexbrain_common::constants;
-use cortexbrain_common::consumer::{Consumer, read_perf_buffer};
+use cortexbrain_common::consumer::{Consumer, read_events};
+use cortexbrain_common::histograms::HistogramTable;
+use cortexbrain_common::metadata::Metadata;
 use cortexbrain_common::otel_metrics::Metrics;
+use cortexbrain_common::process_table::{EXITED_PROCESS_TTL, process_table};
+use cortexbrain_common::throughput::{ThroughputTable, ThroughputTracker};
+
+/// Seconds between two scrapes of the `tcp_throughput` map.
+const TCP_THROUGHPUT_SCRAPE_INTERVAL: u64 = 10;
+
+/// Seconds between two reads of the `log2_histograms` map.
+const HISTOGRAM_SCRAPE_INTERVAL: u64 = 10;
+
+/// Cached cgroup metadata above which the `log2_histograms` cache is reset.
+const MAX_CACHED_CGROUPS: usize = 4096;
+
+/// Seconds between two updates of the `cgroup_filter` map.
+const CGROUP_FILTER_SYNC_INTERVAL: u64 = 30;
+
+/// Seconds between two removals of the exited processes from the process table.
+const PROCESS_TABLE_REAP_INTERVAL: u64 = 30;
 
 /// Locate the OpenSSL shared library used for the SSL uprobes.
 ///
@@ -59,10 +79,121 @@ pub fn resolve_libssl_path() -> anyhow::Result<Option<String>> {
     Ok(None)
 }
 
-/// Listen for eBPF perf-buffer events and record OpenTelemetry metrics.
+/// Periodically scrape the `tcp_throughput` map filled by the
+/// `tcp_sendmsg_ret_tracer` and `tcp_cleanup_rbuf_tracer` programs.
+///
+/// Every `time_delta` seconds the per-cpu values are merged, diffed against
+/// the previous scrape with a [`ThroughputTracker`] and exported as the
+/// `tcp_bytes_sent_total` and `tcp_bytes_received_total` counters. The pod
+/// metadata is resolved once per network namespace and scrape. Never
+/// returns: the errors are logged and retried at the next scrape.
+pub async fn scrape_tcp_throughput(time_delta: u64, metrics: Arc<Metrics>) {
+    let interval = std::time::Duration::from_secs(time_delta);
+    let table = from_pin_with_retry("tcp_throughput", interval, ThroughputTable::from_pin).await;
+    let mut tracker = ThroughputTracker::new();
+
+    loop {
+        match table.snapshot() {
+            Ok(snapshot) => {
+                let deltas = tracker.diff(snapshot);
+                debug!(
+                    "Scraped tcp_throughput: {} active sockets, {} updated",
+                    tracker.active_sockets(),
+                    deltas.len()
+                );
+                let mut netns_metadata: HashMap<u32, Metadata> = HashMap::new();
+                for delta in deltas.iter() {
+                    let metadata = netns_metadata.entry(delta.socket.netns).or_insert_with(|| {
+                        let mut metadata = Metadata::from_netns(delta.socket.netns);
+                        metadata.enrich();
+                        metadata
+                    });
+                    metrics.record_tcp_throughput_delta(delta, metadata);
+                }
+            }
+            Err(e) => error!("Cannot scrape tcp_throughput map: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Periodically read and reset the `log2_histograms` map filled in the
+/// histogram aggregation mode.
+///
+/// Every `time_delta` seconds the per-cpu histograms are merged, removed from
+/// the map and recorded in the OpenTelemetry histograms of their kind. The
+/// container is resolved once per cgroup and kept across the scrapes, so
+/// the cgroup tree is only walked for new cgroups. In the events
+/// aggregation mode the map stays empty. Never returns: the errors are
+/// logged and retried at the next scrape.
+pub async fn scrape_histograms(time_delta: u64, metrics: Arc<Metrics>) {
+    let interval = std::time::Duration::from_secs(time_delta);
+    let mut table =
+        from_pin_with_retry("log2_histograms", interval, HistogramTable::from_pin).await;
+    let mut cgroup_metadata: HashMap<u64, Metadata> = HashMap::new();
+
+    loop {
+        match table.drain() {
+            Ok(histograms) => {
+                debug!("Scraped log2_histograms: {} histograms", histograms.len());
+                // the cgroup ids are never reused, the removed cgroups are dropped on reset
+                if cgroup_metadata.len() >= MAX_CACHED_CGROUPS {
+                    cgroup_metadata.clear();
+                }
+                for (key, hist) in histograms.iter() {
+                    let metadata = cgroup_metadata
+                        .entry(key.cgroup_id)
+                        .or_insert_with(|| Metadata::from_cgroup_id(key.cgroup_id));
+                    metrics.record_log2_histogram(key, hist, metadata);
+                }
+            }
+            Err(e) => error!("Cannot scrape log2_histograms map: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Periodically update the `cgroup_filter` map with the cgroups of the
+/// selected pods.
+///
+/// Every `time_delta` seconds the target namespaces and labels are read from
+/// the `cortexbrain-client-config` configmap and the cgroups of the matching
+/// pods are synced in the map. New pods are traced from the next sync. When
+/// no target is configured the filter is disabled and every workload is traced.
+/// Never returns: the errors are logged and retried at the next sync.
+pub async fn sync_cgroup_filter(time_delta: u64) {
+    let interval = std::time::Duration::from_secs(time_delta);
+    let mut filter =
+        from_pin_with_retry("cgroup_filter", interval, CgroupFilterMap::from_pin).await;
+
+    loop {
+        if let Err(e) = filter.refresh().await {
+            error!("Cannot update the cgroup_filter map: {}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Periodically remove the processes that exited more than
+/// [`EXITED_PROCESS_TTL`] ago from the process table.
+pub async fn reap_process_table(time_delta: u64) {
+    let interval = std::time::Duration::from_secs(time_delta);
+
+    loop {
+        tokio::time::sleep(interval).await;
+        let removed = process_table().reap(EXITED_PROCESS_TTL);
+        debug!(
+            "Process table: {} exited processes removed, {} processes",
+            removed,
+            process_table().len()
+        );
+    }
+}
+
+/// Listen for eBPF ring-buffer (or perf-buffer) events and record OpenTelemetry metrics.
 ///
-/// This function bridges the eBPF perf-buffer layer with the OpenTelemetry
-/// metrics pipeline.  It opens per-CPU buffers for the two maps of interest
+/// This function bridges the eBPF event maps with the OpenTelemetry
+/// metrics pipeline.  It opens a reader for each of the two maps of interest
 /// (`net_metrics` and `time_stamp_events`), spawns asynchronous consumers,
 /// and parks until a `Ctrl-C` signal is received or one of the consumers
 /// terminates.
@@ -78,62 +209,49 @@ pub fn resolve_libssl_path() -> anyhow::Result<Option<String>> {
 /// Returns `Err` if the map manager or CPU enumeration fails.
 ///
 pub async fn event_listener(bpf_maps: BpfMapsData, meter: Meter) -> Result<(), anyhow::Error> {
-    info!("Getting CPU count...");
+    info!("Preparing event readers...");
 
+    // the map manager opens the ring buffers (or the per cpu perf buffers on kernels without ring buffer support)
     let mut maps = map_manager(bpf_maps)?;
 
-    let cpu_count = online_cpus().map_err(|e| anyhow::anyhow!("Error {:?}", e))?;
-
-    for cpu_id in cpu_count {
-        for (name, (perf_event_array, perf_event_buffer)) in maps.iter_mut() {
-            let buf = perf_event_array.open(cpu_id, None).map_err(|e| {
-                anyhow!(
-                    "Cannot create perf_event_array buffer from perf_event_array. Reason: {}",
-                    e
-                )
-            })?;
-            info!(
-                "Buffer created for map {:?} on cpu_id {:?}. Buffer size: {}",
-                name,
-                cpu_id,
-                std::mem::size_of_val(&buf)
-            );
-            perf_event_buffer.push(buf);
-        }
-    }
-
-    info!("Perf buffers created successfully");
+    info!("Event readers created successfully");
 
-    let (_time_stamp_events_array, time_stamp_events_perf_buffer) = maps
+    let time_stamp_events_source = maps
         .remove("time_stamp_events")
-        .expect("Cannot create time_stamp_events_buffer");
-    let (_net_perf_array, net_perf_buffer) = maps
+        .expect("Cannot create time_stamp_events reader");
+    let net_metrics_source = maps
         .remove("net_metrics")
-        .expect("Cannot create net_perf_buffer");
-
-    let (_cpu_frequency_events_array, cpu_frequency_perf_buffer) = maps
+        .expect("Cannot create net_metrics reader");
+    let cpu_frequency_source = maps
         .remove("cpu_frequency")
-        .expect("Cannot create cpu_frequency_perf_buffer");
-
-    let (_cpu_idle_array, cpu_idle_perf_buffer) = maps
+        .expect("Cannot create cpu_frequency reader");
+    let cpu_idle_source = maps
         .remove("cpu_idle")
-        .expect("Cannot create cpu_idle perf buffer");
-
-    let (_mem_alloc_array, mem_alloc_perf_buffer) = maps
+        .expect("Cannot create cpu_idle reader");
+    let mem_alloc_source = maps
         .remove("mem_alloc")
-        .expect("Cannot create mem_alloc perf buffer");
-
-    let (_sched_stat_wait_array, sched_stat_wait_perf_buffer) = maps
+        .expect("Cannot create mem_alloc reader");
+    let sched_stat_wait_source = maps
         .remove("sched_stat_wait")
-        .expect("Cannot create sched_stat_wait perf buffer");
-
-    let (_sched_stat_runtime_array, sched_stat_runtime_perf_buffer) = maps
+        .expect("Cannot create sched_stat_wait reader");
+    let sched_stat_runtime_source = maps
         .remove("sched_stat_runtime")
-        .expect("Cannot create sched_stat_runtime perf buffer");
-
-    let (_ssl_events, ssl_events_perf_buffer) = maps
+        .expect("Cannot create sched_stat_runtime reader");
+    let ssl_events_source = maps
         .remove("ssl_events")
-        .expect("Cannot create ssl_events perf buffer");
+        .expect("Cannot create ssl_events reader");
+    let tcp_events_source = maps
+        .remove("tcp_events")
+        .expect("Cannot create tcp_events reader");
+    let tcp_health_events_source = maps
+        .remove("tcp_health_events")
+        .expect("Cannot create tcp_health_events reader");
+    let dns_events_source = maps
+        .remove("dns_events")
+        .expect("Cannot create dns_events reader");
+    let process_events_source = maps
+        .remove("process_events")
+        .expect("Cannot create process_events reader");
 
     // Allocate byte-buffers sized for each structure type
     let net_metrics_buffers = BufferSize::NetworkMetricsEvents.set_buffer();
@@ -144,113 +262,141 @@ pub async fn event_listener(bpf_maps: BpfMapsData, meter: Meter) -> Result<(), a
     let sched_stat_wait_buffers = BufferSize::SchedStatWait.set_buffer();
     let sched_stat_runtime_buffers = BufferSize::SchedStatRuntime.set_buffer();
     let ssl_events_buffers = BufferSize::SslEvents.set_buffer();
+    let tcp_events_buffers = BufferSize::TcpSocketEvents.set_buffer();
+    let tcp_health_events_buffers = BufferSize::TcpHealthEvents.set_buffer();
+    let dns_events_buffers = BufferSize::DnsEvents.set_buffer();
+    let process_events_buffers = BufferSize::ProcessEvents.set_buffer();
 
     let metrics = Arc::new(Metrics::new(&meter));
 
+    // the processes started before the tracepoints are only known from procfs
+    let seeded = process_table().seed_from_proc(Path::new("/proc"));
+    info!("Process table seeded with {} running processes", seeded);
+
     info!("Starting event listener tasks...");
 
     let net_metrics_handle = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = net_perf_buffer;
-        let mut buffers = net_metrics_buffers;
+        let source = net_metrics_source;
+        let buffers = net_metrics_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(
-                array_buffers,
-                buffers,
-                Consumer::PacketLossMetrics,
-                Some(metrics),
-            )
-            .await;
+            read_events(source, buffers, Consumer::PacketLossMetrics, Some(metrics)).await;
         })
     };
 
     let time_stamp_handle = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = time_stamp_events_perf_buffer;
-        let mut buffers = time_stamp_events_buffers;
+        let source = time_stamp_events_source;
+        let buffers = time_stamp_events_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(
-                array_buffers,
-                buffers,
-                Consumer::TimeStampMetrics,
-                Some(metrics),
-            )
-            .await;
+            read_events(source, buffers, Consumer::TimeStampMetrics, Some(metrics)).await;
         })
     };
 
     let cpu_frequency_metrics = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = cpu_frequency_perf_buffer;
-        let mut buffers = cpu_frequency_events_buffers;
+        let source = cpu_frequency_source;
+        let buffers = cpu_frequency_events_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(
-                array_buffers,
-                buffers,
-                Consumer::CpuFrequency,
-                Some(metrics),
-            )
-            .await;
+            read_events(source, buffers, Consumer::CpuFrequency, Some(metrics)).await;
         })
     };
 
     let cpu_idle_metrics = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = cpu_idle_perf_buffer;
-        let mut buffers = cpu_idle_buffers;
+        let source = cpu_idle_source;
+        let buffers = cpu_idle_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(array_buffers, buffers, Consumer::CpuIdle, Some(metrics)).await;
+            read_events(source, buffers, Consumer::CpuIdle, Some(metrics)).await;
         })
     };
 
     let mem_alloc_metrics = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = mem_alloc_perf_buffer;
-        let mut buffers = mem_alloc_buffers;
+        let source = mem_alloc_source;
+        let buffers = mem_alloc_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(array_buffers, buffers, Consumer::MemAlloc, Some(metrics)).await;
+            read_events(source, buffers, Consumer::MemAlloc, Some(metrics)).await;
         })
     };
 
     let sched_stat_wait_metrics = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = sched_stat_wait_perf_buffer;
-        let mut buffers = sched_stat_wait_buffers;
+        let source = sched_stat_wait_source;
+        let buffers = sched_stat_wait_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(
-                array_buffers,
-                buffers,
-                Consumer::SchedStatWait,
-                Some(metrics),
-            )
-            .await;
+            read_events(source, buffers, Consumer::SchedStatWait, Some(metrics)).await;
         })
     };
 
     let sched_stat_runtime_metrics = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = sched_stat_runtime_perf_buffer;
-        let mut buffers = sched_stat_runtime_buffers;
+        let source = sched_stat_runtime_source;
+        let buffers = sched_stat_runtime_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(
-                array_buffers,
-                buffers,
-                Consumer::SchedStatRuntime,
-                Some(metrics),
-            )
-            .await;
+            read_events(source, buffers, Consumer::SchedStatRuntime, Some(metrics)).await;
         })
     };
 
     let ssl_events_metrics = {
         let metrics = Arc::clone(&metrics);
-        let mut array_buffers = ssl_events_perf_buffer;
-        let mut buffers = ssl_events_buffers;
+        let source = ssl_events_source;
+        let buffers = ssl_events_buffers;
         tokio::spawn(async move {
-            read_perf_buffer(array_buffers, buffers, Consumer::SslEvents, Some(metrics)).await;
+            read_events(source, buffers, Consumer::SslEvents, Some(metrics)).await;
         })
     };
 
+    let tcp_events_metrics = {
+        let metrics = Arc::clone(&metrics);
+        let source = tcp_events_source;
+        let buffers = tcp_events_buffers;
+        tokio::spawn(async move {
+            read_events(source, buffers, Consumer::TcpSocketEvents, Some(metrics)).await;
+        })
+    };
+
+    let tcp_health_events_metrics = {
+        let metrics = Arc::clone(&metrics);
+        let source = tcp_health_events_source;
+        let buffers = tcp_health_events_buffers;
+        tokio::spawn(async move {
+            read_events(source, buffers, Consumer::TcpHealthEvents, Some(metrics)).await;
+        })
+    };
+
+    let dns_events_metrics = {
+        let metrics = Arc::clone(&metrics);
+        let source = dns_events_source;
+        let buffers = dns_events_buffers;
+        tokio::spawn(async move {
+            read_events(source, buffers, Consumer::DnsEvents, Some(metrics)).await;
+        })
+    };
+
+    let process_events_metrics = {
+        let metrics = Arc::clone(&metrics);
+        let source = process_events_source;
+        let buffers = process_events_buffers;
+        tokio::spawn(async move {
+            read_events(source, buffers, Consumer::ProcessEvents, Some(metrics)).await;
+        })
+    };
+
+    let process_table_reaper = tokio::spawn(reap_process_table(PROCESS_TABLE_REAP_INTERVAL));
+
+    let tcp_throughput_scraper = tokio::spawn(scrape_tcp_throughput(
+        TCP_THROUGHPUT_SCRAPE_INTERVAL,
+        Arc::clone(&metrics),
+    ));
+
+    let histogram_scraper = tokio::spawn(scrape_histograms(
+        HISTOGRAM_SCRAPE_INTERVAL,
+        Arc::clone(&metrics),
+    ));
+
+    let cgroup_filter_sync = tokio::spawn(sync_cgroup_filter(CGROUP_FILTER_SYNC_INTERVAL));
+
     info!("Event listeners started, entering main loop...");
 
     tokio::select! {
@@ -302,6 +448,54 @@ pub async fn event_listener(bpf_maps: BpfMapsData, meter: Meter) -> Result<(), a
             }
         }
 
+        result = tcp_events_metrics => {
+            if let Err(e) = result {
+                error!("Tcp events task failed: {:?}", e);
+            }
+        }
+
+        result = tcp_health_events_metrics => {
+            if let Err(e) = result {
+                error!("Tcp health events task failed: {:?}", e);
+            }
+        }
+
+        result = dns_events_metrics => {
+            if let Err(e) = result {
+                error!("Dns events task failed: {:?}", e);
+            }
+        }
+
+        result = process_events_metrics => {
+            if let Err(e) = result {
+                error!("Process events task failed: {:?}", e);
+            }
+        }
+
+        result = process_table_reaper => {
+            if let Err(e) = result {
+                error!("Process table reaper failed: {:?}", e);
+            }
+        }
+
+        result = tcp_throughput_scraper => {
+            if let Err(e) = result {
+                error!("Tcp throughput scraper failed: {:?}", e);
+            }
+        }
+
+        result = histogram_scraper => {
+            if let Err(e) = result {
+                error!("Histogram scraper failed: {:?}", e);
+            }
+        }
+
+        result = cgroup_filter_sync => {
+            if let Err(e) = result {
+                error!("Cgroup filter sync failed: {:?}", e);
+            }
+        }
+
         _ = signal::ctrl_c() => {
             info!("Ctrl-C received, shutting down...");
         }
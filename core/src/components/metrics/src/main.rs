@@ -4,10 +4,12 @@
 //!
 //! 1. Initialises an OpenTelemetry metrics pipeline (OTLP / gRPC).
 //! 2. Loads a compiled eBPF object and pins its maps to the BPF filesystem.
-//! 3. Attaches a set of kernel kprobe programs.
-//! 4. Starts asynchronous consumers that read per-CPU perf buffers and
+//! 3. Resolves the kernel struct offsets used by the programs from the kernel
+//!    BTF and refuses to start if one of them is missing.
+//! 4. Attaches a set of kernel kprobe programs.
+//! 5. Starts asynchronous consumers that read the event maps and
 //!    emit OpenTelemetry instruments for every event.
-//! 5. Blocks until `Ctrl-C` is received, then shuts down cleanly.
+//! 6. Blocks until `Ctrl-C` is received, then shuts down cleanly.
 
 use anyhow::Context;
 use aya::Ebpf;
@@ -16,7 +18,7 @@ use std::{
     path::Path,
     sync::{Arc, Mutex},
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 mod helpers;
 mod otel_init;
 use crate::helpers::{event_listener, resolve_libssl_path};
@@ -24,9 +26,17 @@ use crate::otel_init::{init_opentelemetry, shutdown_opentelemetry};
 
 use cortexbrain_common::{
     constants,
+    histograms::{parse_aggregation_mode, set_aggregation_mode},
+    kernel_offsets::{
+        METRICS_TRACER_CGROUP_FILTER_FIELDS, METRICS_TRACER_KERNEL_FIELDS,
+        METRICS_TRACER_TRACEPOINT_FIELDS, populate_kernel_offsets, populate_tracepoint_offsets,
+    },
     logger::otlp_logger_init,
     map_handlers::{init_bpf_maps, map_pinner},
-    program_handlers::{load_program, load_tracepoint_program, load_uprobe_program},
+    program_handlers::{
+        load_program, load_raw_tracepoint_program, load_tracepoint_program, load_uprobe_program,
+        resolve_bpf_object_path,
+    },
 };
 
 #[tokio::main]
@@ -41,16 +51,31 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let bpf_path =
         env::var(constants::BPF_PATH).context("BPF_PATH environment variable required")?;
+    // pick the ring buffer or the perf event array object for the running kernel
+    let bpf_path = resolve_bpf_object_path(&bpf_path);
     let data = fs::read(Path::new(&bpf_path)).context("Failed to load file from path")?;
     let bpf = Arc::new(Mutex::new(Ebpf::load(&data)?));
     let tcp_bpf = bpf.clone();
     let tcp_rev_bpf = bpf.clone();
     let tcp_v6_bpf = bpf.clone();
+    let tcp_retransmit_bpf = bpf.clone();
+    let tcp_reset_sent_bpf = bpf.clone();
+    let tcp_reset_received_bpf = bpf.clone();
+    let tcp_health_bpf = bpf.clone();
+    let tcp_sendmsg_bpf = bpf.clone();
+    let tcp_sendmsg_ret_bpf = bpf.clone();
+    let tcp_cleanup_rbuf_bpf = bpf.clone();
+    let dns_query_bpf = bpf.clone();
+    let dns_query_v6_bpf = bpf.clone();
+    let dns_response_bpf = bpf.clone();
     let cpu_frequency = bpf.clone();
     let cpu_idle_bpf = bpf.clone();
     let mem_alloc_bpf = bpf.clone();
     let sched_stat_wait_bpf = bpf.clone();
     let sched_stat_runtime_bpf = bpf.clone();
+    let process_fork_bpf = bpf.clone();
+    let process_exec_bpf = bpf.clone();
+    let process_exit_bpf = bpf.clone();
     let ssl_read_bpf = bpf.clone();
     let ssl_read_ret_bpf = bpf.clone();
     let ssl_write_bpf = bpf.clone();
@@ -71,6 +96,15 @@ async fn main() -> Result<(), anyhow::Error> {
         "sched_stat_wait".to_string(),
         "sched_stat_runtime".to_string(),
         "ssl_events".to_string(),
+        "tcp_events".to_string(),
+        "tcp_health_events".to_string(),
+        "tcp_throughput".to_string(),
+        "log2_histograms".to_string(),
+        "metrics_config".to_string(),
+        "cgroup_filter".to_string(),
+        "dns_events".to_string(),
+        "process_events".to_string(),
+        "kernel_offsets".to_string(),
     ];
 
     match init_bpf_maps(bpf.clone(), map_data) {
@@ -83,6 +117,25 @@ async fn main() -> Result<(), anyhow::Error> {
                 Ok(maps) => {
                     info!("BPF maps pinned successfully to {}", bpf_map_save_path);
 
+                    // resolve the kernel struct offsets from the kernel BTF before attaching the programs
+                    populate_kernel_offsets(&pin_path, METRICS_TRACER_KERNEL_FIELDS)
+                        .context("Kernel BTF self-check failed")?;
+                    populate_tracepoint_offsets(&pin_path, METRICS_TRACER_TRACEPOINT_FIELDS)
+                        .context("Tracepoint format self-check failed")?;
+                    // the cgroup filter fields are only required when a target is configured
+                    if let Err(e) =
+                        populate_kernel_offsets(&pin_path, METRICS_TRACER_CGROUP_FILTER_FIELDS)
+                    {
+                        warn!("The cgroup filter is not available. Reason: {}", e);
+                    }
+
+                    // the aggregation mode is read by the programs on every event
+                    let aggregation_mode = parse_aggregation_mode(
+                        &env::var(constants::METRICS_AGGREGATION_MODE).unwrap_or_default(),
+                    )?;
+                    set_aggregation_mode(&pin_path, aggregation_mode)
+                        .context("Failed to set the aggregation mode")?;
+
                     {
                         load_program(
                             bpf.clone(),
@@ -103,6 +156,67 @@ async fn main() -> Result<(), anyhow::Error> {
                             .context(
                             "An error occurred during the execution of load_program function",
                         )?;
+                        load_program(
+                            tcp_retransmit_bpf,
+                            "tcp_retransmit_tracer",
+                            "tcp_retransmit_skb",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        load_program(
+                            tcp_reset_sent_bpf,
+                            "tcp_reset_sent_tracer",
+                            "tcp_send_active_reset",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        load_program(
+                            tcp_reset_received_bpf,
+                            "tcp_reset_received_tracer",
+                            "tcp_reset",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        load_program(tcp_health_bpf, "tcp_health_tracer", "tcp_rcv_established")
+                            .context(
+                                "An error occurred during the execution of load_program function",
+                            )?;
+                        load_program(tcp_sendmsg_bpf, "tcp_sendmsg_tracer", "tcp_sendmsg")
+                            .context(
+                                "An error occurred during the execution of load_program function",
+                            )?;
+                        load_program(tcp_sendmsg_ret_bpf, "tcp_sendmsg_ret_tracer", "tcp_sendmsg")
+                            .context(
+                                "An error occurred during the execution of load_program function",
+                            )?;
+                        load_program(
+                            tcp_cleanup_rbuf_bpf,
+                            "tcp_cleanup_rbuf_tracer",
+                            "tcp_cleanup_rbuf",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        load_program(dns_query_bpf, "dns_query_tracer", "ip_send_skb").context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        // ip6_send_skb is missing on the kernels built without ipv6: only the ipv4 dns queries are traced
+                        if let Err(e) =
+                            load_program(dns_query_v6_bpf, "dns_query_tracer_v6", "ip6_send_skb")
+                        {
+                            warn!("IPv6 dns queries are not traced. Reason: {}", e);
+                        }
+                        load_program(
+                            dns_response_bpf,
+                            "dns_response_tracer",
+                            "__udp_enqueue_schedule_skb",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
                         load_tracepoint_program(
                             cpu_frequency,
                             "trace_cpu_frequency",
@@ -130,10 +244,9 @@ async fn main() -> Result<(), anyhow::Error> {
                         .context(
                             "An error occurred during the execution of load_program function",
                         )?;
-                        load_tracepoint_program(
+                        load_raw_tracepoint_program(
                             sched_stat_wait_bpf,
                             "trace_sched_stat_wait",
-                            "sched",
                             "sched_stat_wait",
                         )
                         .context(
@@ -148,6 +261,33 @@ async fn main() -> Result<(), anyhow::Error> {
                         .context(
                             "An error occurred during the execution of load_program function",
                         )?;
+                        load_tracepoint_program(
+                            process_fork_bpf,
+                            "trace_process_fork",
+                            "sched",
+                            "sched_process_fork",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        load_tracepoint_program(
+                            process_exec_bpf,
+                            "trace_process_exec",
+                            "sched",
+                            "sched_process_exec",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
+                        load_tracepoint_program(
+                            process_exit_bpf,
+                            "trace_process_exit",
+                            "sched",
+                            "sched_process_exit",
+                        )
+                        .context(
+                            "An error occurred during the execution of load_program function",
+                        )?;
                         match resolve_libssl_path()? {
                             Some(ssl_lib_path) => {
                                 load_uprobe_program(
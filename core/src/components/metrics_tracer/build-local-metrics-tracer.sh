@@ -19,6 +19,11 @@ bindgen --use-core --no-layout-tests \
     --allowlist-type 'in6_addr' \
     vmlinux.h -o src/bindings.rs
 
+# BPF ring buffers are available since kernel 5.8. Build the perf event array variant too,
+# the userspace service picks the object supported by the running kernel at load time
+cargo +nightly build -Z build-std=core --target bpfel-unknown-none --release --bin metrics_tracer --no-default-features
+cp ../../../target/bpfel-unknown-none/release/metrics_tracer ../../../target/bpfel-unknown-none/release/metrics_tracer-perf
+
 cargo +nightly build -Z build-std=core --target bpfel-unknown-none --release --bin metrics_tracer
 
 rm -f vmlinux.h
\ No newline at end of file
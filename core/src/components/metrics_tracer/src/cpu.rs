@@ -4,12 +4,19 @@
 //tracepoint:power:cpu_idle_miss
 use aya_ebpf::{
     EbpfContext,
-    helpers::{bpf_get_current_pid_tgid, generated::bpf_get_current_cgroup_id},
-    programs::TracePointContext,
+    helpers::{bpf_get_current_pid_tgid, bpf_probe_read_kernel, generated::bpf_get_current_cgroup_id},
+    programs::{RawTracePointContext, TracePointContext},
 };
 use aya_log_ebpf::info;
 
-use crate::data_structures::{CPU_FREQUENCY, CPU_IDLE, CPU_IDLE_LAST_STATE, CpuFrequency, CpuIdle};
+use crate::data_structures::{EventOutput, CPU_FREQUENCY, CPU_IDLE, CPU_IDLE_LAST_STATE, CpuFrequency, CpuIdle};
+use crate::data_structures::{OFFSET_TASK_COMM, OFFSET_TASK_TGID, TASK_COMM_LEN};
+use crate::network::kernel_offset;
+
+#[inline(always)]
+fn read_kernel<T>(base: *const u8, offset: usize) -> Result<T, i64> {
+    unsafe { bpf_probe_read_kernel::<T>(base.add(offset) as *const T).map_err(|_| 1) }
+}
 
 pub fn cpu_idle(ctx: TracePointContext) -> Result<(), i64> {
     let state_offset = 8;
@@ -31,7 +38,7 @@ pub fn cpu_idle(ctx: TracePointContext) -> Result<(), i64> {
     if emit {
         let _ = unsafe { (*map_ptr).insert(&cpu_id, &state, 0) };
         let event = CpuIdle { cpu_id, state };
-        unsafe { CPU_IDLE.output(&ctx, &event, 0) };
+        unsafe { CPU_IDLE.emit(&ctx, &event) };
     }
 
     info!(&ctx, "CPU idle: State: {} cpu_id: {}", state, cpu_id);
@@ -53,22 +60,31 @@ pub fn per_cpu_bytes_alloc(ctx: &TracePointContext) -> Result<((u32, u32, [u8; 1
     //    cpu_freq: state,
     //};
 
-    //CPU_FREQUENCY.output(&ctx, &cpu_freq_data, 0);
+    //CPU_FREQUENCY.emit(&ctx, &cpu_freq_data);
 
     Ok((bytes_alloc, tgid, command))
 }
 
-pub fn sched_stat_wait(ctx: &TracePointContext) -> Result<((u32, u64, [u8; 16])), i64> {
-    let delay_offset = 16;
+// sched_stat_wait is attached as a raw tracepoint: TP_PROTO(struct task_struct *tsk, u64 delay)
+// the wait ends when the task is picked by the scheduler, in the context of the task that is scheduled out, so the
+// tgid, command and cgroup are read from tsk and not from the current task
+pub fn sched_stat_wait(ctx: &RawTracePointContext) -> Result<((*const u8, u64)), i64> {
+    let args = ctx.as_ptr() as *const u64;
+    let task = unsafe { *args } as *const u8;
+    let delay = unsafe { *args.add(1) };
+    if task.is_null() {
+        return Err(1);
+    }
 
-    //let tgid: u32 = unsafe { ctx.read_at(tgid_offset) }?;
-    let pid_tgid: u64 = bpf_get_current_pid_tgid();
-    let tgid: u32 = (pid_tgid >> 32) as u32;
+    Ok((task, delay))
+}
 
-    let delay = unsafe { ctx.read_at(delay_offset) }?;
-    let command = ctx.command()?;
+// tgid and command of a task (task->tgid, task->comm)
+pub fn task_tgid_command(task: *const u8) -> Result<((u32, [u8; 16])), i64> {
+    let tgid = read_kernel::<i32>(task, kernel_offset(OFFSET_TASK_TGID)?)?;
+    let command = read_kernel::<[u8; TASK_COMM_LEN]>(task, kernel_offset(OFFSET_TASK_COMM)?)?;
 
-    Ok((tgid, delay, command))
+    Ok((tgid as u32, command))
 }
 
 pub fn sched_stat_runtime(ctx: &TracePointContext) -> Result<((u32, u64, [u8; 16])), i64> {
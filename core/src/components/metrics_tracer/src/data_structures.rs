@@ -1,10 +1,90 @@
 use aya_ebpf::{
+    EbpfContext,
     macros::map,
-    maps::{HashMap, LruPerCpuHashMap, PerfEventArray},
+    maps::{Array, HashMap, LruHashMap, LruPerCpuHashMap, PerCpuHashMap, PerfEventArray, RingBuf},
 };
 
 pub const TASK_COMM_LEN: usize = 16;
 
+// docs:
+//
+// kernel struct field offsets indexes used in the KERNEL_OFFSETS array map
+//
+// the offsets are resolved by the userspace from the running kernel BTF (/sys/kernel/btf/vmlinux) before the
+// programs are attached. The indexes must match the METRICS_TRACER_KERNEL_FIELDS table in the common crate.
+// The OFFSET_SKC_* fields live in the sock_common struct embedded at the beginning of sock (__sk_common)
+// The OFFSET_SKB_* fields are used by the dns tracer to locate the ip and udp headers (head + network_header and
+// head + transport_header)
+// The OFFSET_TCP_SOCK_* fields are read by the tcp health sampler (tcp_sock embeds sock as first member) and
+// OFFSET_SKC_NET/OFFSET_NET_NS_INUM resolve the network namespace of the socket
+// OFFSET_SKC_COOKIE is the socket cookie (atomic64_t, the counter is the first member) used as TCP_THROUGHPUT key
+// OFFSET_SK_CGRP_CGROUP/OFFSET_CGROUP_KN/OFFSET_KERNFS_NODE_ID resolve the cgroup v2 id of the socket owner
+// (sk->sk_cgrp_data.cgroup->kn->id) for the cgroup filter of the programs running in softirq context
+// The OFFSET_TASK_* fields are read by the process lifecycle tracepoints from the current task_struct (exit code
+// and parent tgid, current->real_parent->tgid)
+// OFFSET_TASK_COMM/OFFSET_TASK_CGROUPS/OFFSET_CSS_SET_DFL_CGRP are read by the sched_stat_wait raw tracepoint from
+// the waiting task, which is not the current task (cgroup v2 id: task->cgroups->dfl_cgrp->kn->id)
+// The OFFSET_FORK_*/OFFSET_EXEC_* fields are the offsets of the tracepoint record fields, resolved from the
+// tracepoint format files (/sys/kernel/tracing/events/sched/<tracepoint>/format) instead of BTF
+//
+
+pub const OFFSET_SK_ERR: u32 = 0;
+pub const OFFSET_SK_ERR_SOFT: u32 = 1;
+pub const OFFSET_SK_BACKLOG_LEN: u32 = 2;
+pub const OFFSET_SK_WMEM_QUEUED: u32 = 3;
+pub const OFFSET_SK_RCVBUF: u32 = 4;
+pub const OFFSET_SK_ACK_BACKLOG: u32 = 5;
+pub const OFFSET_SK_DROPS: u32 = 6;
+pub const OFFSET_SKC_DADDR: u32 = 7;
+pub const OFFSET_SKC_RCV_SADDR: u32 = 8;
+pub const OFFSET_SKC_DPORT: u32 = 9;
+pub const OFFSET_SKC_NUM: u32 = 10;
+pub const OFFSET_SKC_FAMILY: u32 = 11;
+pub const OFFSET_SKC_STATE: u32 = 12;
+pub const OFFSET_SKC_V6_DADDR: u32 = 13;
+pub const OFFSET_SKC_V6_RCV_SADDR: u32 = 14;
+pub const OFFSET_SKB_HEAD: u32 = 15;
+pub const OFFSET_SKB_NETWORK_HEADER: u32 = 16;
+pub const OFFSET_SKB_TRANSPORT_HEADER: u32 = 17;
+pub const OFFSET_TCP_SOCK_SRTT_US: u32 = 18;
+pub const OFFSET_TCP_SOCK_MDEV_US: u32 = 19;
+pub const OFFSET_TCP_SOCK_SND_CWND: u32 = 20;
+pub const OFFSET_TCP_SOCK_TOTAL_RETRANS: u32 = 21;
+pub const OFFSET_SKC_NET: u32 = 22;
+pub const OFFSET_NET_NS_INUM: u32 = 23;
+pub const OFFSET_SKC_COOKIE: u32 = 24;
+pub const OFFSET_SK_CGRP_CGROUP: u32 = 25;
+pub const OFFSET_CGROUP_KN: u32 = 26;
+pub const OFFSET_KERNFS_NODE_ID: u32 = 27;
+pub const OFFSET_TASK_EXIT_CODE: u32 = 28;
+pub const OFFSET_TASK_REAL_PARENT: u32 = 29;
+pub const OFFSET_TASK_TGID: u32 = 30;
+pub const OFFSET_TASK_COMM: u32 = 31;
+pub const OFFSET_TASK_CGROUPS: u32 = 32;
+pub const OFFSET_CSS_SET_DFL_CGRP: u32 = 33;
+pub const OFFSET_FORK_CHILD_COMM: u32 = 34;
+pub const OFFSET_FORK_CHILD_PID: u32 = 35;
+pub const OFFSET_EXEC_FILENAME: u32 = 36;
+pub const MAX_KERNEL_OFFSETS: u32 = 64;
+
+// docs:
+//
+// metrics_tracer settings indexes used in the METRICS_CONFIG array map, written by the userspace before the
+// programs are attached
+//
+// CONFIG_AGGREGATION_MODE: AGGREGATION_MODE_EVENTS (default) sends one event per occurrence to the userspace,
+// AGGREGATION_MODE_HISTOGRAMS records the scheduler and tcp latency values in the LOG2_HISTOGRAMS map instead
+// CONFIG_CGROUP_FILTER: CGROUP_FILTER_DISABLED (default) traces every cgroup, CGROUP_FILTER_ENABLED traces only
+// the cgroups in the CGROUP_FILTER map
+//
+pub const CONFIG_AGGREGATION_MODE: u32 = 0;
+pub const CONFIG_CGROUP_FILTER: u32 = 1;
+pub const MAX_METRICS_CONFIG: u32 = 8;
+pub const AGGREGATION_MODE_EVENTS: u32 = 0;
+pub const AGGREGATION_MODE_HISTOGRAMS: u32 = 1;
+pub const CGROUP_FILTER_DISABLED: u32 = 0;
+pub const CGROUP_FILTER_ENABLED: u32 = 1;
+
 #[repr(C, packed)]
 pub struct PacketLossMetrics {
     pub tgid: u32,
@@ -25,6 +105,7 @@ pub struct TimeStampStartInfo {
     pub comm: [u8; TASK_COMM_LEN],
     pub ts_ns: u64,
     pub tgid: u32,
+    pub cgroup_id: u64,
 }
 
 /// Event we send to userspace when latency is computed
@@ -87,6 +168,235 @@ pub struct CpuIdle {
     pub(crate) state: u32,
 }
 
+// docs:
+//
+// TcpSocketEvent: event sent to the userspace on every tcp retransmission and on every reset sent or received
+//
+// netns: network namespace inode number of the socket. Used by the userspace to resolve the pod
+// kind: TCP_EVENT_RETRANSMIT, TCP_EVENT_RESET_SENT or TCP_EVENT_RESET_RECEIVED
+// state: tcp state of the socket (include/net/tcp_states.h)
+// lport: local port (host order)
+// dport: remote port (host order)
+// saddr_v4/daddr_v4: local and remote ipv4 addresses (network order, AF_INET only)
+// saddr_v6/daddr_v6: local and remote ipv6 addresses (AF_INET6 only)
+//
+// retransmissions and received resets usually run in softirq context: tgid and comm belong to the task that was
+// running on the cpu, not always to the socket owner. The pod is resolved from netns
+//
+
+pub const TCP_EVENT_RETRANSMIT: u8 = 1;
+pub const TCP_EVENT_RESET_SENT: u8 = 2;
+pub const TCP_EVENT_RESET_RECEIVED: u8 = 3;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct TcpSocketEvent {
+    pub tgid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+    pub ts_us: u64,
+    pub netns: u32,
+    pub kind: u8,
+    pub state: u8,
+    pub af: u16,
+    pub lport: u16,
+    pub dport: u16,
+    pub saddr_v4: u32,
+    pub daddr_v4: u32,
+    pub saddr_v6: [u32; 4],
+    pub daddr_v6: [u32; 4],
+}
+
+// docs:
+//
+// TcpHealthEvent: periodic sample of the smoothed rtt and of the congestion window of an established tcp connection
+//
+// netns: network namespace inode number of the socket. Used by the userspace to resolve the pod (the samples are
+//        taken in softirq context, the current task is unrelated to the socket)
+// srtt_us: smoothed round trip time in microseconds (tcp_sock->srtt_us is stored << 3)
+// mdev_us: round trip time mean deviation in microseconds (tcp_sock->mdev_us is stored << 2)
+// snd_cwnd: congestion window in segments
+// total_retrans: retransmitted segments since the connection was established
+// lport/dport: local and remote ports (host order)
+// saddr_v4/daddr_v4: local and remote ipv4 addresses (network order, AF_INET only)
+// saddr_v6/daddr_v6: local and remote ipv6 addresses (AF_INET6 only)
+//
+// a connection is sampled at most once every TCP_HEALTH_SAMPLE_INTERVAL_NS (see TCP_HEALTH_LAST_SAMPLE)
+//
+
+pub const TCP_HEALTH_SAMPLE_INTERVAL_NS: u64 = 1_000_000_000;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct TcpHealthEvent {
+    pub ts_us: u64,
+    pub netns: u32,
+    pub srtt_us: u32,
+    pub mdev_us: u32,
+    pub snd_cwnd: u32,
+    pub total_retrans: u32,
+    pub af: u16,
+    pub lport: u16,
+    pub dport: u16,
+    pub saddr_v4: u32,
+    pub daddr_v4: u32,
+    pub saddr_v6: [u32; 4],
+    pub daddr_v6: [u32; 4],
+}
+
+// docs:
+//
+// TcpThroughput: bytes sent and received by a tcp socket, value of the TCP_THROUGHPUT map
+//
+// bytes_sent: bytes queued by tcp_sendmsg (return value)
+// bytes_received: bytes copied to the userspace (tcp_cleanup_rbuf copied argument)
+// saddr/daddr: local and remote addresses as four host order words (ipv4 uses only the first word)
+// netns: network namespace inode number of the socket. Used by the userspace to resolve the pod
+// lport/dport: local and remote ports (host order)
+//
+// the map is a per cpu map: the counters are updated without atomic operations and merged by the userspace
+// the 4-tuple identifies the connection: a key reused by a new connection replaces the entry (see account_tcp_throughput)
+//
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TcpThroughput {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub saddr: [u32; 4],
+    pub daddr: [u32; 4],
+    pub netns: u32,
+    pub af: u16,
+    pub lport: u16,
+    pub dport: u16,
+    pub _padding: [u8; 6],
+}
+
+// docs:
+//
+// Log2Histogram: histogram of the values recorded by a cgroup, value of the LOG2_HISTOGRAMS map
+//
+// slots[i] counts the values with bit length i: slot 0 is the value 0, slot i covers [2^(i-1), 2^i - 1]. The
+// values that do not fit are counted in the last slot
+// count/sum: number and sum of the recorded values
+//
+// HistogramKey.kind is HIST_SCHED_WAIT, HIST_SCHED_RUNTIME (nanoseconds) or HIST_TCP_LATENCY (microseconds).
+// The metrics service reads and removes the entries every scrape
+//
+
+pub const HIST_SLOTS: usize = 32;
+pub const HIST_SCHED_WAIT: u32 = 1;
+pub const HIST_SCHED_RUNTIME: u32 = 2;
+pub const HIST_TCP_LATENCY: u32 = 3;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct HistogramKey {
+    pub cgroup_id: u64,
+    pub kind: u32,
+    pub _padding: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Log2Histogram {
+    pub count: u64,
+    pub sum: u64,
+    pub slots: [u64; HIST_SLOTS],
+}
+
+// docs:
+//
+// DnsEvent: event sent to the userspace when a dns response (udp, source port 53) is matched with its query
+//
+// tgid/comm: task that sent the query (the response is received in softirq context)
+// latency_ns: time between the query and the response
+// txid: dns transaction id
+// qtype: type of the first question (1 = A, 28 = AAAA, ...), 0 when the name does not fit in qname
+// rcode: response code (0 = NOERROR, 2 = SERVFAIL, 3 = NXDOMAIN, ...)
+// sport/dport: client and server ports (host order)
+// saddr_v4/daddr_v4: client and server ipv4 addresses (network order, AF_INET only)
+// saddr_v6/daddr_v6: client and server ipv6 addresses (AF_INET6 only)
+// qname: first DNS_QNAME_LEN bytes of the question name in the dns wire format (length prefixed labels)
+//
+// queries without a response are not reported: the pending queries are evicted from the DNS_QUERIES lru map
+//
+
+pub const DNS_QNAME_LEN: usize = 64;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct DnsEvent {
+    pub tgid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+    pub ts_us: u64,
+    pub latency_ns: u64,
+    pub txid: u16,
+    pub qtype: u16,
+    pub rcode: u8,
+    pub af: u16,
+    pub sport: u16,
+    pub dport: u16,
+    pub saddr_v4: u32,
+    pub daddr_v4: u32,
+    pub saddr_v6: [u32; 4],
+    pub daddr_v6: [u32; 4],
+    pub qname: [u8; DNS_QNAME_LEN],
+}
+
+// pending dns query, keyed by client address (first word for ipv4), client port and transaction id
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct DnsQueryKey {
+    pub addr: [u32; 4],
+    pub port: u16,
+    pub txid: u16,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct DnsQueryStart {
+    pub ts_ns: u64,
+    pub tgid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+}
+
+// docs:
+//
+// ProcessEvent: event sent to the userspace by the sched_process_fork, sched_process_exec and sched_process_exit
+// tracepoints. Used by the userspace to maintain the process table
+//
+// kind: PROCESS_EVENT_FORK, PROCESS_EVENT_EXEC or PROCESS_EVENT_EXIT
+// tgid/pid: process and thread id. For PROCESS_EVENT_FORK both are the child pid: the tracepoint does not tell
+// a new process from a new thread
+// ppid: tgid of the parent (the forking process for PROCESS_EVENT_FORK, current->real_parent otherwise)
+// comm: command of the task (the child command for PROCESS_EVENT_FORK, copied from the parent, the new command for PROCESS_EVENT_EXEC)
+// ts_ns: bpf_ktime_get_ns() when the event happened
+// cgroup_id: cgroup v2 id of the task (inherited from the parent for PROCESS_EVENT_FORK)
+// exit_code: task->exit_code ((status << 8) | signal), PROCESS_EVENT_EXIT only
+// filename: first PROCESS_FILENAME_LEN bytes of the path passed to execve, PROCESS_EVENT_EXEC only
+//
+// every thread exit is reported (tgid != pid), so the userspace can remove the threads created by a fork
+//
+
+pub const PROCESS_EVENT_FORK: u8 = 1;
+pub const PROCESS_EVENT_EXEC: u8 = 2;
+pub const PROCESS_EVENT_EXIT: u8 = 3;
+pub const PROCESS_FILENAME_LEN: usize = 128;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct ProcessEvent {
+    pub tgid: u32,
+    pub pid: u32,
+    pub ppid: u32,
+    pub comm: [u8; TASK_COMM_LEN],
+    pub ts_ns: u64,
+    pub cgroup_id: u64,
+    pub exit_code: i32,
+    pub kind: u8,
+    pub filename: [u8; PROCESS_FILENAME_LEN],
+}
+
 #[repr(C, packed)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct SslEvent {
@@ -98,31 +408,116 @@ pub struct SslEvent {
     pub requested: i32, // num argument passed to SSL_read/SSL_write
 }
 
+// docs:
+//
+// Event maps
+//
+// The events are sent to the userspace through a BPF ring buffer (RingBuf) when the crate is built with the
+// "ringbuf" feature (default, requires kernel >= 5.8). Building with --no-default-features falls back to per cpu
+// perf event arrays for older kernels. The map names are the same in both cases and the userspace detects the
+// map type when it loads the maps
+//
+// EventOutput hides the map type to the programs: call MAP.emit(ctx, &event) instead of output()
+//
+
+pub const RINGBUF_BYTE_SIZE: u32 = 256 * 1024; // must be a power of 2 multiple of the page size
+
+pub trait EventOutput<T> {
+    fn emit<C: EbpfContext>(&self, ctx: &C, event: &T);
+}
+
+impl<T> EventOutput<T> for PerfEventArray<T> {
+    #[inline(always)]
+    fn emit<C: EbpfContext>(&self, ctx: &C, event: &T) {
+        self.output(ctx, event, 0);
+    }
+}
+
+impl<T> EventOutput<T> for RingBuf {
+    #[inline(always)]
+    fn emit<C: EbpfContext>(&self, _ctx: &C, event: &T) {
+        // the event is lost if the ring buffer is full (same behaviour of a full perf buffer)
+        let _ = self.output(event, 0);
+    }
+}
+
+// Map: kernel struct field offsets written by the userspace (see the OFFSET_* indexes)
+#[map(name = "kernel_offsets")]
+pub static KERNEL_OFFSETS: Array<u32> = Array::with_max_entries(MAX_KERNEL_OFFSETS, 0);
+
+// Map: metrics_tracer settings written by the userspace (see the CONFIG_* indexes)
+#[map(name = "metrics_config")]
+pub static METRICS_CONFIG: Array<u32> = Array::with_max_entries(MAX_METRICS_CONFIG, 0);
+
+// Map: cgroup v2 ids of the traced workloads, written by the userspace. Used only when CONFIG_CGROUP_FILTER is
+// CGROUP_FILTER_ENABLED
+#[map(name = "cgroup_filter")]
+pub static CGROUP_FILTER: HashMap<u64, u8> = HashMap::<u64, u8>::with_max_entries(8192, 0);
+
+// Map: log2 histograms by cgroup and kind (see Log2Histogram). Filled only in AGGREGATION_MODE_HISTOGRAMS
+#[map(name = "log2_histograms")]
+pub static mut LOG2_HISTOGRAMS: PerCpuHashMap<HistogramKey, Log2Histogram> =
+    PerCpuHashMap::<HistogramKey, Log2Histogram>::with_max_entries(4096, 0);
+
 // Map: connect-start timestamp by socket pointer
 #[map(name = "time_stamp_start")]
 pub static mut TIME_STAMP_START: HashMap<*mut core::ffi::c_void, TimeStampStartInfo> =
     HashMap::<*mut core::ffi::c_void, TimeStampStartInfo>::with_max_entries(4096, 0);
 
-// Perf event channel for emitting Event to userspace
+// Event channel for emitting Event to userspace
+#[cfg(feature = "ringbuf")]
+#[map(name = "time_stamp_events")]
+pub static TIME_STAMP_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "time_stamp_events")]
-pub static mut TIME_STAMP_EVENTS: PerfEventArray<TimeStampEvent> =
-    PerfEventArray::<TimeStampEvent>::new(0);
+pub static TIME_STAMP_EVENTS: PerfEventArray<TimeStampEvent> = PerfEventArray::new(0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "net_metrics")]
+pub static NET_METRICS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "net_metrics")]
 pub static NET_METRICS: PerfEventArray<PacketLossMetrics> = PerfEventArray::new(0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "tcp_events")]
+pub static TCP_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "tcp_events")]
+pub static TCP_EVENTS: PerfEventArray<TcpSocketEvent> = PerfEventArray::new(0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "cpu_frequency")]
+pub static CPU_FREQUENCY: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "cpu_frequency")]
 pub static CPU_FREQUENCY: PerfEventArray<CpuFrequency> = PerfEventArray::new(0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "mem_alloc")]
+pub static MEM_ALLOC: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "mem_alloc")]
 pub static MEM_ALLOC: PerfEventArray<MemAlloc> = PerfEventArray::new(0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "sched_stat_wait")]
+pub static SCHED_STAT_WAIT: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "sched_stat_wait")]
 pub static SCHED_STAT_WAIT: PerfEventArray<SchedStatWait> = PerfEventArray::new(0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "sched_stat_runtime")]
+pub static SCHED_STAT_RUNTIME: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "sched_stat_runtime")]
 pub static SCHED_STAT_RUNTIME: PerfEventArray<SchedStatRuntime> = PerfEventArray::new(0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "cpu_idle")]
+pub static CPU_IDLE: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "cpu_idle")]
 pub static CPU_IDLE: PerfEventArray<CpuIdle> = PerfEventArray::new(0);
 
@@ -130,9 +525,56 @@ pub static CPU_IDLE: PerfEventArray<CpuIdle> = PerfEventArray::new(0);
 pub static mut CPU_IDLE_LAST_STATE: HashMap<u32, u32> =
     HashMap::<u32, u32>::with_max_entries(256, 0);
 
+// Map: pending dns queries (see DnsQueryKey). Lru map: the unanswered queries are evicted
+#[map(name = "dns_queries")]
+pub static mut DNS_QUERIES: LruHashMap<DnsQueryKey, DnsQueryStart> =
+    LruHashMap::<DnsQueryKey, DnsQueryStart>::with_max_entries(8192, 0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "dns_events")]
+pub static DNS_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "dns_events")]
+pub static DNS_EVENTS: PerfEventArray<DnsEvent> = PerfEventArray::new(0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "process_events")]
+pub static PROCESS_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "process_events")]
+pub static PROCESS_EVENTS: PerfEventArray<ProcessEvent> = PerfEventArray::new(0);
+
+// Map: bytes sent and received by socket cookie (see TcpThroughput). Scraped periodically by the metrics service
+// The sockets that are not used anymore are evicted when the map is full
+#[map(name = "tcp_throughput")]
+pub static mut TCP_THROUGHPUT: LruPerCpuHashMap<u64, TcpThroughput> =
+    LruPerCpuHashMap::<u64, TcpThroughput>::with_max_entries(16384, 0);
+
+// Map: socket passed to tcp_sendmsg by pid_tgid, read by the tcp_sendmsg return probe. Lru map: an entry is left
+// behind when the return probe is missed
+#[map(name = "tcp_sendmsg_sockets")]
+pub static mut TCP_SENDMSG_SOCKETS: LruHashMap<u64, u64> =
+    LruHashMap::<u64, u64>::with_max_entries(8192, 0);
+
+// Map: timestamp of the last tcp health sample by socket pointer. Lru map: the closed sockets are evicted
+#[map(name = "tcp_health_last_sample")]
+pub static mut TCP_HEALTH_LAST_SAMPLE: LruHashMap<u64, u64> =
+    LruHashMap::<u64, u64>::with_max_entries(16384, 0);
+
+#[cfg(feature = "ringbuf")]
+#[map(name = "tcp_health_events")]
+pub static TCP_HEALTH_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
+#[map(name = "tcp_health_events")]
+pub static TCP_HEALTH_EVENTS: PerfEventArray<TcpHealthEvent> = PerfEventArray::new(0);
+
 #[map(name = "ssl_ctx_map")]
 pub static mut SSL_CTX_MAP: HashMap<u64, i32> =
     HashMap::<u64, i32>::with_max_entries(4096, 0);
 
+#[cfg(feature = "ringbuf")]
+#[map(name = "ssl_events")]
+pub static SSL_EVENTS: RingBuf = RingBuf::with_byte_size(RINGBUF_BYTE_SIZE, 0);
+#[cfg(not(feature = "ringbuf"))]
 #[map(name = "ssl_events")]
 pub static SSL_EVENTS: PerfEventArray<SslEvent> = PerfEventArray::new(0);
@@ -0,0 +1,233 @@
+// observe the dns traffic (udp, port 53)
+
+use crate::data_structures::{
+    DNS_EVENTS, DNS_QNAME_LEN, DNS_QUERIES, DnsEvent, DnsQueryKey, DnsQueryStart, EventOutput,
+    TASK_COMM_LEN,
+};
+use crate::data_structures::{
+    OFFSET_SKB_HEAD, OFFSET_SKB_NETWORK_HEADER, OFFSET_SKB_TRANSPORT_HEADER,
+};
+use crate::network::kernel_offset;
+use aya_ebpf::helpers::{
+    bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_ktime_get_ns, bpf_probe_read_kernel,
+};
+use aya_ebpf::programs::ProbeContext;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+const IPPROTO_UDP: u8 = 17;
+
+const DNS_PORT: u16 = 53;
+const DNS_HEADER_LEN: usize = 12;
+const DNS_MAX_LABELS: usize = 32;
+const UDP_HEADER_LEN: usize = 8;
+
+// ip header offsets (include/uapi/linux/ip.h, include/uapi/linux/ipv6.h)
+const IPV4_PROTOCOL_OFFSET: usize = 9;
+const IPV4_SADDR_OFFSET: usize = 12;
+const IPV4_DADDR_OFFSET: usize = 16;
+const IPV6_NEXT_HEADER_OFFSET: usize = 6;
+const IPV6_SADDR_OFFSET: usize = 8;
+const IPV6_DADDR_OFFSET: usize = 24;
+
+/// udp datagram read from a sk_buff
+struct UdpPacket {
+    af: u16,
+    saddr: [u32; 4],
+    daddr: [u32; 4],
+    sport: u16,
+    dport: u16,
+    payload: *const u8,
+}
+
+#[inline(always)]
+fn read_kernel<T>(base: *const u8, offset: usize) -> Result<T, i64> {
+    unsafe { bpf_probe_read_kernel::<T>(base.add(offset) as *const T).map_err(|_| 1) }
+}
+
+/// reads the addresses and the ports of a udp datagram
+///
+/// The headers are located from skb->head plus the network_header and transport_header offsets, both set by the
+/// ip stack before the datagram is sent and when it is queued to the socket. Returns None for non udp packets
+/// and for ipv6 packets with extension headers
+fn read_udp_packet(skb: *const u8) -> Result<Option<UdpPacket>, i64> {
+    let head = read_kernel::<*const u8>(skb, kernel_offset(OFFSET_SKB_HEAD)?)?;
+    let network_header = read_kernel::<u16>(skb, kernel_offset(OFFSET_SKB_NETWORK_HEADER)?)?;
+    let transport_header = read_kernel::<u16>(skb, kernel_offset(OFFSET_SKB_TRANSPORT_HEADER)?)?;
+
+    let ip = unsafe { head.add(network_header as usize) };
+    let udp = unsafe { head.add(transport_header as usize) };
+
+    let mut packet = UdpPacket {
+        af: 0,
+        saddr: [0; 4],
+        daddr: [0; 4],
+        sport: 0,
+        dport: 0,
+        payload: unsafe { udp.add(UDP_HEADER_LEN) },
+    };
+
+    match read_kernel::<u8>(ip, 0)? >> 4 {
+        4 => {
+            if read_kernel::<u8>(ip, IPV4_PROTOCOL_OFFSET)? != IPPROTO_UDP {
+                return Ok(None);
+            }
+            packet.af = AF_INET;
+            packet.saddr[0] = read_kernel::<u32>(ip, IPV4_SADDR_OFFSET)?;
+            packet.daddr[0] = read_kernel::<u32>(ip, IPV4_DADDR_OFFSET)?;
+        }
+        6 => {
+            if read_kernel::<u8>(ip, IPV6_NEXT_HEADER_OFFSET)? != IPPROTO_UDP {
+                return Ok(None);
+            }
+            packet.af = AF_INET6;
+            for i in 0..4 {
+                packet.saddr[i] = read_kernel::<u32>(ip, IPV6_SADDR_OFFSET + i * 4)?;
+                packet.daddr[i] = read_kernel::<u32>(ip, IPV6_DADDR_OFFSET + i * 4)?;
+            }
+        }
+        _ => return Ok(None),
+    }
+
+    packet.sport = u16::from_be(read_kernel::<u16>(udp, 0)?);
+    packet.dport = u16::from_be(read_kernel::<u16>(udp, 2)?);
+
+    Ok(Some(packet))
+}
+
+/// dns query tracer
+///
+/// Attached to ip_send_skb (the skb is the second argument) and ip6_send_skb (first argument): both are called by
+/// the udp sendmsg functions with the complete datagram in the context of the sending task. Queries (QR bit not
+/// set) sent to port 53 are stored in DNS_QUERIES with the send timestamp and the current task
+pub fn on_dns_query(ctx: &ProbeContext, skb_arg: usize) -> Result<(), i64> {
+    let skb = ctx.arg::<*const u8>(skb_arg).ok_or(1i64)?;
+    if skb.is_null() {
+        return Err(1);
+    }
+
+    let packet = match read_udp_packet(skb)? {
+        Some(packet) if packet.dport == DNS_PORT => packet,
+        _ => return Ok(()),
+    };
+
+    let flags = u16::from_be(read_kernel::<u16>(packet.payload, 2)?);
+    if flags & 0x8000 != 0 {
+        return Ok(());
+    }
+
+    let key = DnsQueryKey {
+        addr: packet.saddr,
+        port: packet.sport,
+        txid: u16::from_be(read_kernel::<u16>(packet.payload, 0)?),
+    };
+    let start = DnsQueryStart {
+        ts_ns: unsafe { bpf_ktime_get_ns() },
+        tgid: (unsafe { bpf_get_current_pid_tgid() } >> 32) as u32,
+        comm: bpf_get_current_comm().unwrap_or([0; TASK_COMM_LEN]),
+    };
+
+    unsafe {
+        let map_ptr = &raw mut DNS_QUERIES;
+        (*map_ptr).insert(&key, &start, 0).map_err(|_| 1)?;
+    }
+
+    Ok(())
+}
+
+/// dns response tracer
+///
+/// Attached to __udp_enqueue_schedule_skb (socket, skb), called for both ipv4 and ipv6 when a datagram is queued
+/// to the receiving socket. Responses (QR bit set) coming from port 53 are matched with the pending query by
+/// client address, client port and transaction id. The question name and type are read from the question
+/// section, which the server copies from the query
+pub fn on_dns_response(ctx: &ProbeContext) -> Result<(), i64> {
+    let skb = ctx.arg::<*const u8>(1).ok_or(1i64)?;
+    if skb.is_null() {
+        return Err(1);
+    }
+
+    let packet = match read_udp_packet(skb)? {
+        Some(packet) if packet.sport == DNS_PORT => packet,
+        _ => return Ok(()),
+    };
+
+    let flags = u16::from_be(read_kernel::<u16>(packet.payload, 2)?);
+    if flags & 0x8000 == 0 {
+        return Ok(());
+    }
+
+    let txid = u16::from_be(read_kernel::<u16>(packet.payload, 0)?);
+    let key = DnsQueryKey {
+        addr: packet.daddr,
+        port: packet.dport,
+        txid,
+    };
+
+    let start = unsafe {
+        let map_ptr = &raw const DNS_QUERIES;
+        (*map_ptr).get(&key).copied()
+    };
+    let start = match start {
+        Some(start) => start,
+        None => return Ok(()), // query sent before the tracer was attached or evicted
+    };
+
+    let now = unsafe { bpf_ktime_get_ns() };
+
+    // the name can be shorter than DNS_QNAME_LEN: userspace stops at the terminating zero length label
+    let qname = read_kernel::<[u8; DNS_QNAME_LEN]>(packet.payload, DNS_HEADER_LEN)
+        .unwrap_or([0; DNS_QNAME_LEN]);
+
+    // walk the labels to find the qtype that follows the name
+    let mut pos: usize = 0;
+    for _ in 0..DNS_MAX_LABELS {
+        if pos >= DNS_QNAME_LEN || qname[pos] == 0 {
+            break;
+        }
+        pos += qname[pos] as usize + 1;
+    }
+    let qtype = if pos < DNS_QNAME_LEN && qname[pos] == 0 {
+        u16::from_be(read_kernel::<u16>(
+            packet.payload,
+            DNS_HEADER_LEN + pos + 1,
+        )?)
+    } else {
+        0
+    };
+
+    let mut ev = DnsEvent {
+        tgid: start.tgid,
+        comm: start.comm,
+        ts_us: now / 1_000,
+        latency_ns: now.saturating_sub(start.ts_ns),
+        txid,
+        qtype,
+        rcode: (flags & 0x000f) as u8,
+        af: packet.af,
+        sport: packet.dport,
+        dport: packet.sport,
+        saddr_v4: 0,
+        daddr_v4: 0,
+        saddr_v6: [0; 4],
+        daddr_v6: [0; 4],
+        qname,
+    };
+
+    // the event is reported from the client point of view (client -> server)
+    if packet.af == AF_INET {
+        ev.saddr_v4 = packet.daddr[0];
+        ev.daddr_v4 = packet.saddr[0];
+    } else {
+        ev.saddr_v6 = packet.daddr;
+        ev.daddr_v6 = packet.saddr;
+    }
+
+    unsafe {
+        DNS_EVENTS.emit(ctx, &ev);
+        let map_ptr = &raw mut DNS_QUERIES;
+        let _ = (*map_ptr).remove(&key);
+    }
+
+    Ok(())
+}
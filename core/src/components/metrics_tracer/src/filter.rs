@@ -0,0 +1,101 @@
+// cgroup filter (CONFIG_CGROUP_FILTER)
+
+use crate::data_structures::{
+    CGROUP_FILTER, CGROUP_FILTER_ENABLED, CONFIG_CGROUP_FILTER, METRICS_CONFIG,
+};
+use crate::data_structures::{OFFSET_CGROUP_KN, OFFSET_KERNFS_NODE_ID, OFFSET_SK_CGRP_CGROUP};
+use crate::data_structures::{OFFSET_CSS_SET_DFL_CGRP, OFFSET_TASK_CGROUPS};
+use crate::network::kernel_offset;
+use aya_ebpf::helpers::bpf_probe_read_kernel;
+use aya_ebpf::helpers::generated::bpf_get_current_cgroup_id;
+use aya_ebpf::programs::ProbeContext;
+
+#[inline(always)]
+fn filter_enabled() -> bool {
+    match METRICS_CONFIG.get(CONFIG_CGROUP_FILTER) {
+        Some(filter) => *filter == CGROUP_FILTER_ENABLED,
+        None => false,
+    }
+}
+
+#[inline(always)]
+fn read_kernel<T>(base: *const u8, offset: usize) -> Result<T, i64> {
+    unsafe { bpf_probe_read_kernel::<T>(base.add(offset) as *const T).map_err(|_| 1) }
+}
+
+/// cgroup v2 id of a cgroup (cgroup->kn->id)
+fn cgroup_kn_id(cgroup: *const u8) -> Result<u64, i64> {
+    if cgroup.is_null() {
+        return Err(1);
+    }
+    let kn = read_kernel::<*const u8>(cgroup, kernel_offset(OFFSET_CGROUP_KN)?)?;
+    if kn.is_null() {
+        return Err(1);
+    }
+    read_kernel::<u64>(kn, kernel_offset(OFFSET_KERNFS_NODE_ID)?)
+}
+
+/// cgroup v2 id of the socket owner (sk->sk_cgrp_data.cgroup->kn->id)
+///
+/// The cgroup is assigned to the socket when it is created, so it is available in softirq context
+fn socket_cgroup_id(sk: *const u8) -> Result<u64, i64> {
+    cgroup_kn_id(read_kernel::<*const u8>(sk, kernel_offset(OFFSET_SK_CGRP_CGROUP)?)?)
+}
+
+/// cgroup v2 id of a task (task->cgroups->dfl_cgrp->kn->id), the value bpf_get_current_cgroup_id returns for the
+/// current task
+pub fn task_cgroup_id(task: *const u8) -> Result<u64, i64> {
+    let cset = read_kernel::<*const u8>(task, kernel_offset(OFFSET_TASK_CGROUPS)?)?;
+    if cset.is_null() {
+        return Err(1);
+    }
+    cgroup_kn_id(read_kernel::<*const u8>(cset, kernel_offset(OFFSET_CSS_SET_DFL_CGRP)?)?)
+}
+
+/// returns true when the cgroup must be traced: the filter is disabled or the cgroup is in the CGROUP_FILTER map
+#[inline(always)]
+pub fn is_cgroup_traced(cgroup_id: u64) -> bool {
+    if !filter_enabled() {
+        return true;
+    }
+    unsafe { CGROUP_FILTER.get(&cgroup_id).is_some() }
+}
+
+/// cgroup filter for the programs running in the context of the traced task (syscalls, scheduler, uprobes)
+#[inline(always)]
+pub fn is_current_traced() -> bool {
+    if !filter_enabled() {
+        return true;
+    }
+    is_cgroup_traced(unsafe { bpf_get_current_cgroup_id() })
+}
+
+/// cgroup filter for the programs that report a task other than the current one (sched_stat_wait). The tasks whose
+/// cgroup cannot be read are traced
+#[inline(always)]
+pub fn is_task_traced(task: *const u8) -> bool {
+    if !filter_enabled() {
+        return true;
+    }
+    match task_cgroup_id(task) {
+        Ok(cgroup_id) => is_cgroup_traced(cgroup_id),
+        Err(_) => true,
+    }
+}
+
+/// cgroup filter for the kprobes that take the socket as first argument and can run in softirq context, where
+/// the current task is unrelated to the socket. The sockets whose cgroup cannot be read are traced
+#[inline(always)]
+pub fn is_socket_traced(ctx: &ProbeContext) -> bool {
+    if !filter_enabled() {
+        return true;
+    }
+    let sk = match ctx.arg::<*const u8>(0) {
+        Some(sk) if !sk.is_null() => sk,
+        _ => return true,
+    };
+    match socket_cgroup_id(sk) {
+        Ok(cgroup_id) => is_cgroup_traced(cgroup_id),
+        Err(_) => true,
+    }
+}
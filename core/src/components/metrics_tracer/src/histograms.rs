@@ -0,0 +1,77 @@
+// in-kernel log2 histograms (AGGREGATION_MODE_HISTOGRAMS)
+
+use crate::data_structures::{
+    AGGREGATION_MODE_HISTOGRAMS, CONFIG_AGGREGATION_MODE, METRICS_CONFIG,
+};
+use crate::data_structures::{HIST_SLOTS, HistogramKey, LOG2_HISTOGRAMS, Log2Histogram};
+
+/// returns true when the userspace enabled the histogram aggregation mode
+#[inline(always)]
+pub fn histograms_enabled() -> bool {
+    match METRICS_CONFIG.get(CONFIG_AGGREGATION_MODE) {
+        Some(mode) => *mode == AGGREGATION_MODE_HISTOGRAMS,
+        None => false,
+    }
+}
+
+/// bit length of the value, capped to the last slot
+#[inline(always)]
+fn log2_slot(value: u64) -> usize {
+    if value == 0 {
+        return 0;
+    }
+    let mut v = value;
+    let mut slot: usize = 1;
+    for shift in [32, 16, 8, 4, 2, 1] {
+        if v >> shift != 0 {
+            v >>= shift;
+            slot += shift;
+        }
+    }
+    if slot >= HIST_SLOTS {
+        HIST_SLOTS - 1
+    } else {
+        slot
+    }
+}
+
+/// records a value in the histogram of the cgroup
+///
+/// The map is a per cpu map: the histogram of the current cpu is updated without atomic operations and the
+/// userspace merges the cpus. The first value of a cgroup creates the entry
+pub fn record_histogram(cgroup_id: u64, kind: u32, value: u64) -> Result<(), i64> {
+    let key = HistogramKey {
+        cgroup_id,
+        kind,
+        _padding: 0,
+    };
+    let slot = log2_slot(value);
+
+    unsafe {
+        let map_ptr = &raw const LOG2_HISTOGRAMS;
+        if let Some(hist) = (*map_ptr).get_ptr_mut(&key) {
+            let hist = &mut *hist;
+            hist.count += 1;
+            hist.sum += value;
+            if let Some(count) = hist.slots.get_mut(slot) {
+                *count += 1;
+            }
+            return Ok(());
+        }
+    }
+
+    let mut hist = Log2Histogram {
+        count: 1,
+        sum: value,
+        slots: [0; HIST_SLOTS],
+    };
+    if let Some(count) = hist.slots.get_mut(slot) {
+        *count = 1;
+    }
+    unsafe {
+        let map_ptr = &raw mut LOG2_HISTOGRAMS;
+        (*map_ptr).insert(&key, &hist, 0).map_err(|_| 1)?;
+    }
+
+    Ok(())
+}
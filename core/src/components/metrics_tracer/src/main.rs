@@ -5,33 +5,50 @@
 mod bindings;
 mod cpu;
 mod data_structures;
+mod dns;
+mod filter;
+mod histograms;
 mod memory;
 mod network;
+mod process;
 mod ssl;
 
 use crate::bindings::net_device;
-use crate::cpu::{cpu_idle, per_cpu_bytes_alloc, sched_stat_runtime, sched_stat_wait};
+use crate::cpu::{cpu_idle, per_cpu_bytes_alloc, sched_stat_runtime, sched_stat_wait, task_tgid_command};
 use crate::data_structures::CpuFrequency;
-use crate::data_structures::NET_METRICS;
+use crate::data_structures::{EventOutput, NET_METRICS};
 use crate::data_structures::{CPU_FREQUENCY, SchedStatWait};
 use crate::data_structures::{
     CPU_IDLE, PacketLossMetrics, TASK_COMM_LEN, TIME_STAMP_EVENTS, TIME_STAMP_START,
     TimeStampEvent, TimeStampStartInfo,
 };
+use crate::data_structures::{HIST_SCHED_RUNTIME, HIST_SCHED_WAIT};
 use crate::data_structures::{MEM_ALLOC, SCHED_STAT_RUNTIME, SCHED_STAT_WAIT};
 use crate::data_structures::{MemAlloc, SchedStatRuntime};
+use crate::data_structures::{
+    TCP_EVENT_RESET_RECEIVED, TCP_EVENT_RESET_SENT, TCP_EVENT_RETRANSMIT,
+};
+use crate::dns::{on_dns_query, on_dns_response};
+use crate::filter::{is_current_traced, is_socket_traced, is_task_traced, task_cgroup_id};
+use crate::histograms::{histograms_enabled, record_histogram};
 use crate::memory::enter_mmap;
-use crate::network::{detect_packet_loss, on_connect, on_rcv_state_process};
+use crate::network::{
+    detect_packet_loss, on_connect, on_rcv_state_process, on_tcp_rcv_established,
+    on_tcp_cleanup_rbuf, on_tcp_sendmsg, on_tcp_sendmsg_return, on_tcp_socket_event,
+};
+use crate::process::{on_process_exec, on_process_exit, on_process_fork};
 use crate::ssl::{try_ssl_event_end, try_ssl_start};
 use aya_ebpf::EbpfContext;
 use aya_ebpf::helpers::bpf_get_current_pid_tgid;
-use aya_ebpf::helpers::generated::{bpf_ktime_get_ns, bpf_perf_event_output};
+use aya_ebpf::helpers::generated::{
+    bpf_get_current_cgroup_id, bpf_ktime_get_ns, bpf_perf_event_output,
+};
 use aya_ebpf::helpers::{
     bpf_get_current_comm, bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes,
 };
-use aya_ebpf::macros::{kprobe, map, tracepoint, uprobe, uretprobe};
+use aya_ebpf::macros::{kprobe, kretprobe, map, raw_tracepoint, tracepoint, uprobe, uretprobe};
 use aya_ebpf::maps::{HashMap, PerfEventArray};
-use aya_ebpf::programs::{ProbeContext, RetProbeContext, TracePointContext};
+use aya_ebpf::programs::{ProbeContext, RawTracePointContext, RetProbeContext, TracePointContext};
 use core::{mem, ptr};
 
 const AF_INET: u16 = 2;
@@ -40,6 +57,9 @@ const TCP_SYN_SENT: u8 = 2;
 
 #[kprobe]
 fn packet_loss_tracer(ctx: ProbeContext) -> u32 {
+    if !is_socket_traced(&ctx) {
+        return 0;
+    }
     match try_metrics_tracer(ctx) {
         Ok(ret) => ret,
         Err(ret) => ret.try_into().unwrap_or(1),
@@ -49,30 +69,38 @@ fn packet_loss_tracer(ctx: ProbeContext) -> u32 {
 fn try_metrics_tracer(ctx: ProbeContext) -> Result<u32, i64> {
     let net_metrics = detect_packet_loss(&ctx)?;
     unsafe {
-        NET_METRICS.output(&ctx, &net_metrics, 0);
+        NET_METRICS.emit(&ctx, &net_metrics);
     }
 
     Ok(0)
 }
 
-/// Monitor on tcp_sendmsg, tcp_v6_connect
+/// Monitor on tcp_v6_connect
 #[kprobe]
 fn tcp_v6_connect(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match on_connect(ctx) {
         Ok(_) => 0,
         Err(e) => e as u32,
     }
 }
 
-/// Monitor on tcp_sendmsg, tcp_v4_connect
+/// Monitor on tcp_v4_connect
 #[kprobe]
 fn tcp_v4_connect(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match on_connect(ctx) {
         Ok(_) => 0,
         Err(e) => e as u32,
     }
 }
 
+/// Monitor on tcp_rcv_state_process (connect latency). Not filtered: the connections of the cgroups that are not
+/// traced have no TIME_STAMP_START entry
 #[kprobe]
 fn tcp_latency_monitor(ctx: ProbeContext) -> u32 {
     match on_rcv_state_process(ctx) {
@@ -81,14 +109,135 @@ fn tcp_latency_monitor(ctx: ProbeContext) -> u32 {
     }
 }
 
+/// Monitor on tcp_retransmit_skb
+#[kprobe]
+fn tcp_retransmit_tracer(ctx: ProbeContext) -> u32 {
+    if !is_socket_traced(&ctx) {
+        return 0;
+    }
+    match on_tcp_socket_event(&ctx, TCP_EVENT_RETRANSMIT) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on tcp_send_active_reset (resets sent by the local stack)
+#[kprobe]
+fn tcp_reset_sent_tracer(ctx: ProbeContext) -> u32 {
+    if !is_socket_traced(&ctx) {
+        return 0;
+    }
+    match on_tcp_socket_event(&ctx, TCP_EVENT_RESET_SENT) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on tcp_reset (resets received from the peer)
+#[kprobe]
+fn tcp_reset_received_tracer(ctx: ProbeContext) -> u32 {
+    if !is_socket_traced(&ctx) {
+        return 0;
+    }
+    match on_tcp_socket_event(&ctx, TCP_EVENT_RESET_RECEIVED) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on tcp_rcv_established (srtt, cwnd and retransmissions sampling)
+#[kprobe]
+fn tcp_health_tracer(ctx: ProbeContext) -> u32 {
+    if !is_socket_traced(&ctx) {
+        return 0;
+    }
+    match on_tcp_rcv_established(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on tcp_sendmsg (socket of the call, see tcp_sendmsg_ret_tracer)
+#[kprobe]
+fn tcp_sendmsg_tracer(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_tcp_sendmsg(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on tcp_sendmsg return (bytes sent per socket)
+#[kretprobe]
+fn tcp_sendmsg_ret_tracer(ctx: RetProbeContext) -> u32 {
+    match on_tcp_sendmsg_return(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on tcp_cleanup_rbuf (bytes received per socket)
+#[kprobe]
+fn tcp_cleanup_rbuf_tracer(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_tcp_cleanup_rbuf(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on ip_send_skb (outgoing ipv4 dns queries)
+#[kprobe]
+fn dns_query_tracer(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_dns_query(&ctx, 1) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on ip6_send_skb (outgoing ipv6 dns queries)
+#[kprobe]
+fn dns_query_tracer_v6(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_dns_query(&ctx, 0) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Monitor on __udp_enqueue_schedule_skb (dns responses queued to the client socket)
+#[kprobe]
+fn dns_response_tracer(ctx: ProbeContext) -> u32 {
+    if !is_socket_traced(&ctx) {
+        return 0;
+    }
+    match on_dns_response(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
 #[tracepoint]
 fn trace_cpu_frequency(ctx: TracePointContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match trace_cpu_metrics(&ctx) {
         Ok(_) => 0,
         Err(e) => e as u32,
     }
 }
 
+// cpu idle states are not related to a workload: not filtered
 #[tracepoint]
 fn trace_cpu_idle(ctx: TracePointContext) -> u32 {
     match cpu_idle(ctx) {
@@ -108,18 +257,20 @@ fn trace_cpu_metrics(ctx: &TracePointContext) -> Result<(), i64> {
         command,
     };
 
-    unsafe { CPU_FREQUENCY.output(ctx, &cpu_metrics, 0) };
+    unsafe { CPU_FREQUENCY.emit(ctx, &cpu_metrics) };
 
     Ok(())
 }
 
 /// Tracepoint attached to `syscalls:sys_enter_mmap`.
 ///
-/// Emits a `MemAlloc` event for every `mmap` syscall.  No PID/command filter
-/// is applied yet (see the next update), so this will generate events for every
-/// process in the system.
+/// Emits a `MemAlloc` event for every `mmap` syscall of the traced cgroups
+/// (every process in the system when the cgroup filter is disabled).
 #[tracepoint]
 fn trace_enter_mmap(ctx: TracePointContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match trace_memory_allocation(&ctx) {
         Ok(_) => 0,
         Err(e) => e as u32,
@@ -136,21 +287,30 @@ fn trace_memory_allocation(ctx: &TracePointContext) -> Result<(), i64> {
         command,
     };
 
-    unsafe { MEM_ALLOC.output(ctx, &memory_alloc_metrics, 0) };
+    unsafe { MEM_ALLOC.emit(ctx, &memory_alloc_metrics) };
 
     Ok(())
 }
 
-#[tracepoint]
-fn trace_sched_stat_wait(ctx: TracePointContext) -> u32 {
+#[raw_tracepoint(tracepoint = "sched_stat_wait")]
+fn trace_sched_stat_wait(ctx: RawTracePointContext) -> i32 {
     match sched_stat_wait_tracer(&ctx) {
         Ok(_) => 0,
-        Err(e) => e as u32,
+        Err(e) => e as i32,
     }
 }
 
-fn sched_stat_wait_tracer(ctx: &TracePointContext) -> Result<(), i64> {
-    let (tgid, delay, command) = sched_stat_wait(ctx)?;
+fn sched_stat_wait_tracer(ctx: &RawTracePointContext) -> Result<(), i64> {
+    let (task, delay) = sched_stat_wait(ctx)?;
+    if !is_task_traced(task) {
+        return Ok(());
+    }
+
+    if histograms_enabled() {
+        return record_histogram(task_cgroup_id(task)?, HIST_SCHED_WAIT, delay);
+    }
+
+    let (tgid, command) = task_tgid_command(task)?;
 
     let sched_stat_wait_data = SchedStatWait {
         tgid,
@@ -158,13 +318,16 @@ fn sched_stat_wait_tracer(ctx: &TracePointContext) -> Result<(), i64> {
         command,
     };
 
-    unsafe { SCHED_STAT_WAIT.output(ctx, &sched_stat_wait_data, 0) };
+    unsafe { SCHED_STAT_WAIT.emit(ctx, &sched_stat_wait_data) };
 
     Ok(())
 }
 
 #[tracepoint]
 fn trace_sched_stat_runtime(ctx: TracePointContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match sched_stat_runtime_tracer(&ctx) {
         Ok(_) => 0,
         Err(e) => e as u32,
@@ -174,22 +337,66 @@ fn trace_sched_stat_runtime(ctx: TracePointContext) -> u32 {
 fn sched_stat_runtime_tracer(ctx: &TracePointContext) -> Result<(), i64> {
     let (tgid, runtime, command) = sched_stat_runtime(ctx)?;
 
+    if histograms_enabled() {
+        let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+        return record_histogram(cgroup_id, HIST_SCHED_RUNTIME, runtime);
+    }
+
     let sched_stat_runtime_data = SchedStatRuntime {
         tgid,
         runtime,
         command,
     };
 
-    unsafe { SCHED_STAT_RUNTIME.output(ctx, &sched_stat_runtime_data, 0) };
+    unsafe { SCHED_STAT_RUNTIME.emit(ctx, &sched_stat_runtime_data) };
 
     Ok(())
 }
 
+/// Tracepoint attached to `sched:sched_process_fork` (new processes and threads).
+#[tracepoint]
+fn trace_process_fork(ctx: TracePointContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_process_fork(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Tracepoint attached to `sched:sched_process_exec`.
+#[tracepoint]
+fn trace_process_exec(ctx: TracePointContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_process_exec(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// Tracepoint attached to `sched:sched_process_exit` (every exiting thread).
+#[tracepoint]
+fn trace_process_exit(ctx: TracePointContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
+    match on_process_exit(&ctx) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
 const SSL_READ_DIR: u8 = 0;
 const SSL_WRITE_DIR: u8 = 1;
 
 #[uprobe]
 fn ssl_read(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match try_ssl_start(&ctx) {
         Ok(_) => 0,
         Err(_) => 0, // fail silently to avoid perturbing the application
@@ -198,6 +405,9 @@ fn ssl_read(ctx: ProbeContext) -> u32 {
 
 #[uretprobe]
 fn ssl_read_ret(ctx: RetProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match try_ssl_event_end(&ctx, SSL_READ_DIR) {
         Ok(_) => 0,
         Err(_) => 0,
@@ -208,6 +418,9 @@ fn ssl_read_ret(ctx: RetProbeContext) -> u32 {
 #[uprobe]
 //uprobe reads input data from the userspace
 fn ssl_write(ctx: ProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match try_ssl_start(&ctx) {
         Ok(_) => 0,
         Err(_) => 0,
@@ -217,6 +430,9 @@ fn ssl_write(ctx: ProbeContext) -> u32 {
 #[uretprobe]
 //uretprobe best fits for measuring returning data
 fn ssl_write_ret(ctx: RetProbeContext) -> u32 {
+    if !is_current_traced() {
+        return 0;
+    }
     match try_ssl_event_end(&ctx, SSL_WRITE_DIR) {
         Ok(_) => 0,
         Err(_) => 0,
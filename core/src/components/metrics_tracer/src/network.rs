@@ -1,23 +1,52 @@
 use crate::bindings::{self, net_device};
-use crate::data_structures::{NET_METRICS, PacketLossMetrics};
+use crate::data_structures::{EventOutput, KERNEL_OFFSETS, NET_METRICS, PacketLossMetrics};
+use crate::data_structures::{
+    HIST_TCP_LATENCY, TCP_SENDMSG_SOCKETS, TCP_THROUGHPUT, TcpThroughput,
+};
+use crate::data_structures::{
+    OFFSET_NET_NS_INUM, OFFSET_SKC_COOKIE, OFFSET_SKC_NET, OFFSET_TCP_SOCK_MDEV_US,
+    OFFSET_TCP_SOCK_SND_CWND, OFFSET_TCP_SOCK_SRTT_US, OFFSET_TCP_SOCK_TOTAL_RETRANS,
+};
+use crate::data_structures::{
+    OFFSET_SK_ACK_BACKLOG, OFFSET_SK_BACKLOG_LEN, OFFSET_SK_DROPS, OFFSET_SK_ERR,
+    OFFSET_SK_ERR_SOFT, OFFSET_SK_RCVBUF, OFFSET_SK_WMEM_QUEUED, OFFSET_SKC_DADDR,
+    OFFSET_SKC_DPORT, OFFSET_SKC_FAMILY, OFFSET_SKC_NUM, OFFSET_SKC_RCV_SADDR, OFFSET_SKC_STATE,
+    OFFSET_SKC_V6_DADDR, OFFSET_SKC_V6_RCV_SADDR,
+};
 use crate::data_structures::{
     TASK_COMM_LEN, TIME_STAMP_EVENTS, TIME_STAMP_START, TimeStampEvent, TimeStampStartInfo,
 };
+use crate::data_structures::{TCP_EVENTS, TcpSocketEvent};
+use crate::data_structures::{
+    TCP_HEALTH_EVENTS, TCP_HEALTH_LAST_SAMPLE, TCP_HEALTH_SAMPLE_INTERVAL_NS, TcpHealthEvent,
+};
+use crate::histograms::{histograms_enabled, record_histogram};
 use aya_ebpf::EbpfContext;
 use aya_ebpf::helpers::bpf_get_current_pid_tgid;
-use aya_ebpf::helpers::generated::{bpf_ktime_get_ns, bpf_perf_event_output};
+use aya_ebpf::helpers::generated::{
+    bpf_get_current_cgroup_id, bpf_ktime_get_ns, bpf_perf_event_output,
+};
 use aya_ebpf::helpers::{
     bpf_get_current_comm, bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes,
 };
 use aya_ebpf::macros::{kprobe, map, tracepoint};
 use aya_ebpf::maps::{HashMap, PerfEventArray};
-use aya_ebpf::programs::ProbeContext;
+use aya_ebpf::programs::{ProbeContext, RetProbeContext};
 use core::{mem, ptr};
 
 const AF_INET: u16 = 2;
 const AF_INET6: u16 = 10;
 const TCP_SYN_SENT: u8 = 2;
 
+// reads a kernel struct field offset resolved by the userspace from the kernel BTF (see KERNEL_OFFSETS)
+#[inline(always)]
+pub(crate) fn kernel_offset(index: u32) -> Result<usize, i64> {
+    KERNEL_OFFSETS
+        .get(index)
+        .map(|offset| *offset as usize)
+        .ok_or(1i64)
+}
+
 /// packet loss tracer
 pub fn detect_packet_loss(ctx: &ProbeContext) -> Result<PacketLossMetrics, i64> {
     let sk_pointer = ctx.arg::<*const u8>(0).ok_or(1i64)?;
@@ -29,13 +58,13 @@ pub fn detect_packet_loss(ctx: &ProbeContext) -> Result<PacketLossMetrics, i64>
     let tgid = (unsafe { bpf_get_current_pid_tgid() } >> 32) as u32;
     let comm = unsafe { bpf_get_current_comm() }.map_err(|_| 1i64)?;
     let ts_us: u64 = unsafe { bpf_ktime_get_ns() } / 1_000;
-    let sk_err_offset = 284;
-    let sk_err_soft_offset = 600;
-    let sk_backlog_len_offset = 196;
-    let sk_write_memory_queued_offset = 376;
-    let sk_receive_buffer_size_offset = 244;
-    let sk_ack_backlog_offset = 604;
-    let sk_drops_offset = 136;
+    let sk_err_offset = kernel_offset(OFFSET_SK_ERR)?;
+    let sk_err_soft_offset = kernel_offset(OFFSET_SK_ERR_SOFT)?;
+    let sk_backlog_len_offset = kernel_offset(OFFSET_SK_BACKLOG_LEN)?;
+    let sk_write_memory_queued_offset = kernel_offset(OFFSET_SK_WMEM_QUEUED)?;
+    let sk_receive_buffer_size_offset = kernel_offset(OFFSET_SK_RCVBUF)?;
+    let sk_ack_backlog_offset = kernel_offset(OFFSET_SK_ACK_BACKLOG)?;
+    let sk_drops_offset = kernel_offset(OFFSET_SK_DROPS)?;
 
     let sk_err = unsafe {
         bpf_probe_read_kernel::<i32>(sk_pointer.add(sk_err_offset) as *const i32).map_err(|_| 1)?
@@ -92,6 +121,7 @@ pub fn on_connect(ctx: ProbeContext) -> Result<(), i64> {
         comm: [0; TASK_COMM_LEN],
         ts_ns: unsafe { bpf_ktime_get_ns() },
         tgid,
+        cgroup_id: unsafe { bpf_get_current_cgroup_id() },
     };
     unsafe {
         let comm_result = bpf_get_current_comm();
@@ -119,14 +149,14 @@ pub fn on_rcv_state_process(ctx: ProbeContext) -> Result<(), i64> {
         return Err(1);
     }
 
-    let skc_daddr_off = 0;
-    let skc_rcv_saddr_off = 4;
-    let skc_dport_off = 12;
-    let skc_num_off = 14;
-    let skc_family_off = 16;
-    let skc_state_off = 18;
-    let skc_v6_daddr_off = 56;
-    let skc_v6_rcv_saddr_off = 72;
+    let skc_daddr_off = kernel_offset(OFFSET_SKC_DADDR)?;
+    let skc_rcv_saddr_off = kernel_offset(OFFSET_SKC_RCV_SADDR)?;
+    let skc_dport_off = kernel_offset(OFFSET_SKC_DPORT)?;
+    let skc_num_off = kernel_offset(OFFSET_SKC_NUM)?;
+    let skc_family_off = kernel_offset(OFFSET_SKC_FAMILY)?;
+    let skc_state_off = kernel_offset(OFFSET_SKC_STATE)?;
+    let skc_v6_daddr_off = kernel_offset(OFFSET_SKC_V6_DADDR)?;
+    let skc_v6_rcv_saddr_off = kernel_offset(OFFSET_SKC_V6_RCV_SADDR)?;
 
     let state = unsafe { bpf_probe_read_kernel::<u8>((sk as usize + skc_state_off) as *const u8) }
         .map_err(|_| 1)?;
@@ -150,6 +180,16 @@ pub fn on_rcv_state_process(ctx: ProbeContext) -> Result<(), i64> {
         return Ok(());
     }
 
+    // aggregation mode: the latency is recorded in the histogram of the cgroup that started the connection
+    if histograms_enabled() {
+        let cgroup_id = start.cgroup_id;
+        unsafe {
+            let map_ptr = &raw mut TIME_STAMP_START;
+            let _ = (*map_ptr).remove(&((sk as usize) as *mut core::ffi::c_void));
+        }
+        return record_histogram(cgroup_id, HIST_TCP_LATENCY, (delta as u64) / 1_000);
+    }
+
     let mut ev = TimeStampEvent {
         delta_us: (delta as u64) / 1_000,
         ts_us: now / 1_000,
@@ -202,16 +242,390 @@ pub fn on_rcv_state_process(ctx: ProbeContext) -> Result<(), i64> {
 
     // emit + cleanup
     unsafe {
-        bpf_perf_event_output(
-            ctx.as_ptr(),
-            &raw const TIME_STAMP_EVENTS as *const _ as *mut _,
-            0, // BPF_F_CURRENT_CPU
-            &ev as *const _ as *mut _,
-            (mem::size_of::<TimeStampEvent>() as u32).into(),
-        );
+        TIME_STAMP_EVENTS.emit(&ctx, &ev);
         let map_ptr = &raw mut TIME_STAMP_START;
         let _ = (*map_ptr).remove(&((sk as usize) as *mut core::ffi::c_void));
     }
 
     Ok(())
 }
+
+/// tcp retransmission and reset tracer
+///
+/// Attached to tcp_retransmit_skb, tcp_send_active_reset and tcp_reset. The three functions take the socket as
+/// first argument, so the event is composed from the sock_common fields resolved from the kernel BTF. The network
+/// namespace of the socket is reported because the retransmissions and the received resets run in softirq context
+pub fn on_tcp_socket_event(ctx: &ProbeContext, kind: u8) -> Result<(), i64> {
+    let sk = ctx.arg::<*const u8>(0).ok_or(1i64)?;
+    if sk.is_null() {
+        return Err(1);
+    }
+
+    let skc_daddr_off = kernel_offset(OFFSET_SKC_DADDR)?;
+    let skc_rcv_saddr_off = kernel_offset(OFFSET_SKC_RCV_SADDR)?;
+    let skc_dport_off = kernel_offset(OFFSET_SKC_DPORT)?;
+    let skc_num_off = kernel_offset(OFFSET_SKC_NUM)?;
+    let skc_family_off = kernel_offset(OFFSET_SKC_FAMILY)?;
+    let skc_state_off = kernel_offset(OFFSET_SKC_STATE)?;
+    let skc_v6_daddr_off = kernel_offset(OFFSET_SKC_V6_DADDR)?;
+    let skc_v6_rcv_saddr_off = kernel_offset(OFFSET_SKC_V6_RCV_SADDR)?;
+    let skc_net_off = kernel_offset(OFFSET_SKC_NET)?;
+    let ns_inum_off = kernel_offset(OFFSET_NET_NS_INUM)?;
+
+    let mut ev = TcpSocketEvent {
+        tgid: (unsafe { bpf_get_current_pid_tgid() } >> 32) as u32,
+        comm: bpf_get_current_comm().unwrap_or([0; TASK_COMM_LEN]),
+        ts_us: unsafe { bpf_ktime_get_ns() } / 1_000,
+        netns: 0,
+        kind,
+        state: 0,
+        af: 0,
+        lport: 0,
+        dport: 0,
+        saddr_v4: 0,
+        daddr_v4: 0,
+        saddr_v6: [0; 4],
+        daddr_v6: [0; 4],
+    };
+
+    unsafe {
+        // the current task is not always the socket owner: the pod is resolved from the socket netns
+        let net = bpf_probe_read_kernel::<*const u8>(sk.add(skc_net_off) as *const *const u8)
+            .map_err(|_| 1)?;
+        if !net.is_null() {
+            ev.netns =
+                bpf_probe_read_kernel::<u32>(net.add(ns_inum_off) as *const u32).map_err(|_| 1)?;
+        }
+
+        ev.state = bpf_probe_read_kernel::<u8>(sk.add(skc_state_off)).map_err(|_| 1)?;
+        ev.af =
+            bpf_probe_read_kernel::<u16>(sk.add(skc_family_off) as *const u16).map_err(|_| 1)?;
+        ev.lport =
+            bpf_probe_read_kernel::<u16>(sk.add(skc_num_off) as *const u16).map_err(|_| 1)?;
+        ev.dport = u16::from_be(
+            bpf_probe_read_kernel::<u16>(sk.add(skc_dport_off) as *const u16).map_err(|_| 1)?,
+        );
+    }
+
+    if ev.af == AF_INET {
+        unsafe {
+            ev.saddr_v4 = bpf_probe_read_kernel::<u32>(sk.add(skc_rcv_saddr_off) as *const u32)
+                .map_err(|_| 1)?;
+            ev.daddr_v4 =
+                bpf_probe_read_kernel::<u32>(sk.add(skc_daddr_off) as *const u32).map_err(|_| 1)?;
+        }
+    } else if ev.af == AF_INET6 {
+        for i in 0..4 {
+            unsafe {
+                ev.saddr_v6[i] = bpf_probe_read_kernel::<u32>(
+                    sk.add(skc_v6_rcv_saddr_off + i * 4) as *const u32
+                )
+                .map_err(|_| 1)?;
+                ev.daddr_v6[i] =
+                    bpf_probe_read_kernel::<u32>(sk.add(skc_v6_daddr_off + i * 4) as *const u32)
+                        .map_err(|_| 1)?;
+            }
+        }
+    } else {
+        return Ok(());
+    }
+
+    unsafe {
+        TCP_EVENTS.emit(ctx, &ev);
+    }
+
+    Ok(())
+}
+
+/// tcp health sampler
+///
+/// Attached to tcp_rcv_established (socket, skb), the fast path of the established connections. The smoothed rtt,
+/// the rtt deviation, the congestion window and the retransmissions are read from tcp_sock at most once every
+/// TCP_HEALTH_SAMPLE_INTERVAL_NS per socket. The function runs in softirq context: the pod is resolved by the
+/// userspace from the network namespace of the socket
+pub fn on_tcp_rcv_established(ctx: &ProbeContext) -> Result<(), i64> {
+    let sk = ctx.arg::<*const u8>(0).ok_or(1i64)?;
+    if sk.is_null() {
+        return Err(1);
+    }
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let sk_key = sk as u64;
+    let last_sample = unsafe {
+        let map_ptr = &raw const TCP_HEALTH_LAST_SAMPLE;
+        (*map_ptr).get(&sk_key).copied()
+    };
+    if let Some(last_sample) = last_sample {
+        if now.saturating_sub(last_sample) < TCP_HEALTH_SAMPLE_INTERVAL_NS {
+            return Ok(());
+        }
+    }
+    unsafe {
+        let map_ptr = &raw mut TCP_HEALTH_LAST_SAMPLE;
+        (*map_ptr).insert(&sk_key, &now, 0).map_err(|_| 1)?;
+    }
+
+    let srtt_off = kernel_offset(OFFSET_TCP_SOCK_SRTT_US)?;
+    let mdev_off = kernel_offset(OFFSET_TCP_SOCK_MDEV_US)?;
+    let snd_cwnd_off = kernel_offset(OFFSET_TCP_SOCK_SND_CWND)?;
+    let total_retrans_off = kernel_offset(OFFSET_TCP_SOCK_TOTAL_RETRANS)?;
+    let skc_net_off = kernel_offset(OFFSET_SKC_NET)?;
+    let ns_inum_off = kernel_offset(OFFSET_NET_NS_INUM)?;
+    let skc_daddr_off = kernel_offset(OFFSET_SKC_DADDR)?;
+    let skc_rcv_saddr_off = kernel_offset(OFFSET_SKC_RCV_SADDR)?;
+    let skc_dport_off = kernel_offset(OFFSET_SKC_DPORT)?;
+    let skc_num_off = kernel_offset(OFFSET_SKC_NUM)?;
+    let skc_family_off = kernel_offset(OFFSET_SKC_FAMILY)?;
+    let skc_v6_daddr_off = kernel_offset(OFFSET_SKC_V6_DADDR)?;
+    let skc_v6_rcv_saddr_off = kernel_offset(OFFSET_SKC_V6_RCV_SADDR)?;
+
+    let mut ev = TcpHealthEvent {
+        ts_us: now / 1_000,
+        netns: 0,
+        srtt_us: 0,
+        mdev_us: 0,
+        snd_cwnd: 0,
+        total_retrans: 0,
+        af: 0,
+        lport: 0,
+        dport: 0,
+        saddr_v4: 0,
+        daddr_v4: 0,
+        saddr_v6: [0; 4],
+        daddr_v6: [0; 4],
+    };
+
+    unsafe {
+        let srtt = bpf_probe_read_kernel::<u32>(sk.add(srtt_off) as *const u32).map_err(|_| 1)?;
+        let mdev = bpf_probe_read_kernel::<u32>(sk.add(mdev_off) as *const u32).map_err(|_| 1)?;
+        ev.srtt_us = srtt >> 3;
+        ev.mdev_us = mdev >> 2;
+        ev.snd_cwnd =
+            bpf_probe_read_kernel::<u32>(sk.add(snd_cwnd_off) as *const u32).map_err(|_| 1)?;
+        ev.total_retrans =
+            bpf_probe_read_kernel::<u32>(sk.add(total_retrans_off) as *const u32).map_err(|_| 1)?;
+
+        let net = bpf_probe_read_kernel::<*const u8>(sk.add(skc_net_off) as *const *const u8)
+            .map_err(|_| 1)?;
+        if !net.is_null() {
+            ev.netns =
+                bpf_probe_read_kernel::<u32>(net.add(ns_inum_off) as *const u32).map_err(|_| 1)?;
+        }
+
+        ev.af =
+            bpf_probe_read_kernel::<u16>(sk.add(skc_family_off) as *const u16).map_err(|_| 1)?;
+        ev.lport =
+            bpf_probe_read_kernel::<u16>(sk.add(skc_num_off) as *const u16).map_err(|_| 1)?;
+        ev.dport = u16::from_be(
+            bpf_probe_read_kernel::<u16>(sk.add(skc_dport_off) as *const u16).map_err(|_| 1)?,
+        );
+    }
+
+    if ev.af == AF_INET {
+        unsafe {
+            ev.saddr_v4 = bpf_probe_read_kernel::<u32>(sk.add(skc_rcv_saddr_off) as *const u32)
+                .map_err(|_| 1)?;
+            ev.daddr_v4 =
+                bpf_probe_read_kernel::<u32>(sk.add(skc_daddr_off) as *const u32).map_err(|_| 1)?;
+        }
+    } else if ev.af == AF_INET6 {
+        for i in 0..4 {
+            unsafe {
+                ev.saddr_v6[i] = bpf_probe_read_kernel::<u32>(
+                    sk.add(skc_v6_rcv_saddr_off + i * 4) as *const u32
+                )
+                .map_err(|_| 1)?;
+                ev.daddr_v6[i] =
+                    bpf_probe_read_kernel::<u32>(sk.add(skc_v6_daddr_off + i * 4) as *const u32)
+                        .map_err(|_| 1)?;
+            }
+        }
+    } else {
+        return Ok(());
+    }
+
+    unsafe {
+        TCP_HEALTH_EVENTS.emit(ctx, &ev);
+    }
+
+    Ok(())
+}
+
+/// tcp_sendmsg entry: stores the socket keyed by pid_tgid, the bytes are accounted by the return probe
+pub fn on_tcp_sendmsg(ctx: &ProbeContext) -> Result<(), i64> {
+    let sk = ctx.arg::<*const u8>(0).ok_or(1i64)?;
+    if sk.is_null() {
+        return Err(1);
+    }
+    let pid_tgid = bpf_get_current_pid_tgid();
+
+    unsafe {
+        let map_ptr = &raw mut TCP_SENDMSG_SOCKETS;
+        (*map_ptr)
+            .insert(&pid_tgid, &(sk as u64), 0)
+            .map_err(|_| 1i64)?;
+    }
+    Ok(())
+}
+
+/// tcp_sendmsg return: accounts the bytes actually queued (the return value), not the requested size. Errors and
+/// interrupted calls return a negative value and are not counted
+pub fn on_tcp_sendmsg_return(ctx: &RetProbeContext) -> Result<(), i64> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+
+    let map_ptr = unsafe { &raw mut TCP_SENDMSG_SOCKETS };
+    let sk = unsafe { (*map_ptr).get(&pid_tgid) }.copied().ok_or(1i64)?;
+    unsafe {
+        let _ = (*map_ptr).remove(&pid_tgid);
+    }
+
+    let sent = ctx.ret::<i32>();
+    if sent <= 0 {
+        return Ok(());
+    }
+    account_tcp_throughput(sk as *const u8, sent as u64, true)
+}
+
+/// tcp_cleanup_rbuf (socket, copied): bytes copied to the userspace
+pub fn on_tcp_cleanup_rbuf(ctx: &ProbeContext) -> Result<(), i64> {
+    let sk = ctx.arg::<*const u8>(0).ok_or(1i64)?;
+    if sk.is_null() {
+        return Err(1);
+    }
+    let copied = ctx.arg::<i32>(1).ok_or(1i64)?;
+    if copied <= 0 {
+        return Ok(());
+    }
+    account_tcp_throughput(sk, copied as u64, false)
+}
+
+/// tcp throughput accounting
+///
+/// Called by the tcp_sendmsg return probe and by the tcp_cleanup_rbuf probe, both in the context of the task that
+/// owns the socket. The bytes are accumulated in the TCP_THROUGHPUT map keyed by the socket cookie.
+/// The kernel generates the cookie lazily (SO_COOKIE, sock_diag, bpf helpers): until then the socket pointer is used
+/// as key. The userspace exports the increments of every entry, so a socket that changes key is still counted once
+///
+/// The socket memory is reused by the new connections once a socket is freed, so the key alone does not identify a
+/// connection: the 4-tuple stored in the entry is checked on every update. When it does not match, the entry of the
+/// closed connection is replaced (its bytes since the last scrape are lost) and the new connection starts from zero
+fn account_tcp_throughput(sk: *const u8, bytes: u64, is_send: bool) -> Result<(), i64> {
+    let cookie = unsafe {
+        bpf_probe_read_kernel::<u64>(sk.add(kernel_offset(OFFSET_SKC_COOKIE)?) as *const u64)
+            .map_err(|_| 1)?
+    };
+    let key = if cookie != 0 { cookie } else { sk as u64 };
+
+    let skc_net_off = kernel_offset(OFFSET_SKC_NET)?;
+    let ns_inum_off = kernel_offset(OFFSET_NET_NS_INUM)?;
+    let skc_daddr_off = kernel_offset(OFFSET_SKC_DADDR)?;
+    let skc_rcv_saddr_off = kernel_offset(OFFSET_SKC_RCV_SADDR)?;
+    let skc_dport_off = kernel_offset(OFFSET_SKC_DPORT)?;
+    let skc_num_off = kernel_offset(OFFSET_SKC_NUM)?;
+    let skc_family_off = kernel_offset(OFFSET_SKC_FAMILY)?;
+    let skc_v6_daddr_off = kernel_offset(OFFSET_SKC_V6_DADDR)?;
+    let skc_v6_rcv_saddr_off = kernel_offset(OFFSET_SKC_V6_RCV_SADDR)?;
+
+    let mut stats = TcpThroughput {
+        bytes_sent: 0,
+        bytes_received: 0,
+        saddr: [0; 4],
+        daddr: [0; 4],
+        netns: 0,
+        af: 0,
+        lport: 0,
+        dport: 0,
+        _padding: [0; 6],
+    };
+    if is_send {
+        stats.bytes_sent = bytes;
+    } else {
+        stats.bytes_received = bytes;
+    }
+
+    unsafe {
+        stats.af =
+            bpf_probe_read_kernel::<u16>(sk.add(skc_family_off) as *const u16).map_err(|_| 1)?;
+        stats.lport =
+            bpf_probe_read_kernel::<u16>(sk.add(skc_num_off) as *const u16).map_err(|_| 1)?;
+        stats.dport = u16::from_be(
+            bpf_probe_read_kernel::<u16>(sk.add(skc_dport_off) as *const u16).map_err(|_| 1)?,
+        );
+    }
+
+    // the addresses are stored as host order words
+    if stats.af == AF_INET {
+        unsafe {
+            stats.saddr[0] = u32::from_be(
+                bpf_probe_read_kernel::<u32>(sk.add(skc_rcv_saddr_off) as *const u32)
+                    .map_err(|_| 1)?,
+            );
+            stats.daddr[0] = u32::from_be(
+                bpf_probe_read_kernel::<u32>(sk.add(skc_daddr_off) as *const u32).map_err(|_| 1)?,
+            );
+        }
+    } else if stats.af == AF_INET6 {
+        for i in 0..4 {
+            unsafe {
+                let saddr_ptr = sk.add(skc_v6_rcv_saddr_off + i * 4) as *const u32;
+                let daddr_ptr = sk.add(skc_v6_daddr_off + i * 4) as *const u32;
+                stats.saddr[i] = u32::from_be(bpf_probe_read_kernel(saddr_ptr).map_err(|_| 1)?);
+                stats.daddr[i] = u32::from_be(bpf_probe_read_kernel(daddr_ptr).map_err(|_| 1)?);
+            }
+        }
+    } else {
+        return Ok(());
+    }
+
+    // the map is per cpu: the value of the current cpu is updated without atomic operations
+    let current = unsafe {
+        let map_ptr = &raw const TCP_THROUGHPUT;
+        (*map_ptr).get_ptr_mut(&key)
+    };
+    if let Some(current) = current {
+        unsafe {
+            // the values of the other cpus start zeroed: the socket fields are filled by the first update
+            if (*current).af == 0 {
+                (*current).saddr = stats.saddr;
+                (*current).daddr = stats.daddr;
+                (*current).af = stats.af;
+                (*current).lport = stats.lport;
+                (*current).dport = stats.dport;
+            }
+            if same_connection(&*current, &stats) {
+                (*current).bytes_sent += stats.bytes_sent;
+                (*current).bytes_received += stats.bytes_received;
+                return Ok(());
+            }
+            // the key belongs to a closed connection: the entry is removed from every cpu
+            let map_ptr = &raw mut TCP_THROUGHPUT;
+            let _ = (*map_ptr).remove(&key);
+        }
+    }
+
+    unsafe {
+        let net = bpf_probe_read_kernel::<*const u8>(sk.add(skc_net_off) as *const *const u8)
+            .map_err(|_| 1)?;
+        if !net.is_null() {
+            stats.netns =
+                bpf_probe_read_kernel::<u32>(net.add(ns_inum_off) as *const u32).map_err(|_| 1)?;
+        }
+    }
+
+    unsafe {
+        let map_ptr = &raw mut TCP_THROUGHPUT;
+        (*map_ptr).insert(&key, &stats, 0).map_err(|_| 1)?;
+    }
+
+    Ok(())
+}
+
+/// true if two TCP_THROUGHPUT values belong to the same connection (same family and 4-tuple)
+#[inline(always)]
+fn same_connection(a: &TcpThroughput, b: &TcpThroughput) -> bool {
+    a.af == b.af
+        && a.lport == b.lport
+        && a.dport == b.dport
+        && a.saddr == b.saddr
+        && a.daddr == b.daddr
+}
@@ -0,0 +1,102 @@
+// process lifecycle (sched_process_fork, sched_process_exec, sched_process_exit)
+
+use crate::data_structures::{
+    EventOutput, PROCESS_EVENT_EXEC, PROCESS_EVENT_EXIT, PROCESS_EVENT_FORK, PROCESS_EVENTS,
+    PROCESS_FILENAME_LEN, ProcessEvent, TASK_COMM_LEN,
+};
+use crate::data_structures::{OFFSET_EXEC_FILENAME, OFFSET_FORK_CHILD_COMM, OFFSET_FORK_CHILD_PID};
+use crate::data_structures::{OFFSET_TASK_EXIT_CODE, OFFSET_TASK_REAL_PARENT, OFFSET_TASK_TGID};
+use crate::network::kernel_offset;
+use aya_ebpf::EbpfContext;
+use aya_ebpf::helpers::generated::{bpf_get_current_cgroup_id, bpf_get_current_task};
+use aya_ebpf::helpers::{
+    bpf_get_current_pid_tgid, bpf_ktime_get_ns, bpf_probe_read_kernel,
+    bpf_probe_read_kernel_str_bytes,
+};
+use aya_ebpf::programs::TracePointContext;
+
+#[inline(always)]
+fn read_kernel<T>(base: *const u8, offset: usize) -> Result<T, i64> {
+    unsafe { bpf_probe_read_kernel::<T>(base.add(offset) as *const T).map_err(|_| 1) }
+}
+
+/// tgid of the parent of the current task (current->real_parent->tgid)
+fn current_parent_tgid() -> Result<u32, i64> {
+    let task = unsafe { bpf_get_current_task() } as *const u8;
+    let parent = read_kernel::<*const u8>(task, kernel_offset(OFFSET_TASK_REAL_PARENT)?)?;
+    if parent.is_null() {
+        return Err(1);
+    }
+    let tgid = read_kernel::<i32>(parent, kernel_offset(OFFSET_TASK_TGID)?)?;
+    Ok(tgid as u32)
+}
+
+/// exit code of the current task, set by do_exit before the sched_process_exit tracepoint
+fn current_exit_code() -> Result<i32, i64> {
+    let task = unsafe { bpf_get_current_task() } as *const u8;
+    read_kernel::<i32>(task, kernel_offset(OFFSET_TASK_EXIT_CODE)?)
+}
+
+#[inline(always)]
+fn new_event(kind: u8) -> ProcessEvent {
+    ProcessEvent {
+        tgid: 0,
+        pid: 0,
+        ppid: 0,
+        comm: [0; TASK_COMM_LEN],
+        ts_ns: unsafe { bpf_ktime_get_ns() },
+        cgroup_id: unsafe { bpf_get_current_cgroup_id() },
+        exit_code: 0,
+        kind,
+        filename: [0; PROCESS_FILENAME_LEN],
+    }
+}
+
+/// sched_process_fork runs in the context of the parent: the child inherits its cgroup
+pub fn on_process_fork(ctx: &TracePointContext) -> Result<(), i64> {
+    let child_pid: i32 = unsafe { ctx.read_at(kernel_offset(OFFSET_FORK_CHILD_PID)?) }?;
+
+    let mut event = new_event(PROCESS_EVENT_FORK);
+    event.tgid = child_pid as u32;
+    event.pid = child_pid as u32;
+    event.ppid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    event.comm = unsafe { ctx.read_at(kernel_offset(OFFSET_FORK_CHILD_COMM)?) }?;
+
+    unsafe { PROCESS_EVENTS.emit(ctx, &event) };
+    Ok(())
+}
+
+/// sched_process_exec runs in the context of the process after the new program is loaded (new comm)
+pub fn on_process_exec(ctx: &TracePointContext) -> Result<(), i64> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+
+    let mut event = new_event(PROCESS_EVENT_EXEC);
+    event.tgid = (pid_tgid >> 32) as u32;
+    event.pid = pid_tgid as u32;
+    event.ppid = current_parent_tgid().unwrap_or(0);
+    event.comm = ctx.command()?;
+
+    // __data_loc field: the low 16 bits are the offset of the string from the beginning of the record
+    let filename_loc: u32 = unsafe { ctx.read_at(kernel_offset(OFFSET_EXEC_FILENAME)?) }?;
+    let filename = unsafe { (ctx.as_ptr() as *const u8).add((filename_loc & 0xffff) as usize) };
+    // a missing filename is not an error: the event is still needed by the process table
+    let _ = unsafe { bpf_probe_read_kernel_str_bytes(filename, &mut event.filename) };
+
+    unsafe { PROCESS_EVENTS.emit(ctx, &event) };
+    Ok(())
+}
+
+/// sched_process_exit runs in the context of the exiting task, once for every thread
+pub fn on_process_exit(ctx: &TracePointContext) -> Result<(), i64> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+
+    let mut event = new_event(PROCESS_EVENT_EXIT);
+    event.tgid = (pid_tgid >> 32) as u32;
+    event.pid = pid_tgid as u32;
+    event.ppid = current_parent_tgid().unwrap_or(0);
+    event.comm = ctx.command()?;
+    event.exit_code = current_exit_code().unwrap_or(0);
+
+    unsafe { PROCESS_EVENTS.emit(ctx, &event) };
+    Ok(())
+}
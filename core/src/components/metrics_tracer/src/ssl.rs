@@ -1,6 +1,6 @@
 // observe L5 and L6 connections
 
-use crate::data_structures::{SSL_CTX_MAP, SSL_EVENTS, SslEvent};
+use crate::data_structures::{EventOutput, SSL_CTX_MAP, SSL_EVENTS, SslEvent};
 use aya_ebpf::helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_ktime_get_ns};
 use aya_ebpf::programs::{ProbeContext, RetProbeContext};
 
@@ -42,7 +42,7 @@ pub fn try_ssl_event_end(ctx: &RetProbeContext, direction: u8) -> Result<(), i64
     };
 
     unsafe {
-        SSL_EVENTS.output(ctx, &ev, 0); // emit the event
+        SSL_EVENTS.emit(ctx, &ev); // emit the event
         (*map_ptr).remove(&pid_tgid); // remove the emitted event from the MAP
     }
 